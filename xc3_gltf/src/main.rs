@@ -1,8 +1,14 @@
-use std::path::Path;
+use std::{collections::HashSet, path::Path};
 
 use anyhow::Context;
 use clap::Parser;
-use xc3_model::{gltf::GltfFile, load_model, load_model_legacy, shader_database::ShaderDatabase};
+use xc3_lib::dds::DdsExt;
+use xc3_model::{
+    gltf::{GltfFile, GltfOptions, UpAxis},
+    load_model, load_model_legacy,
+    shader_database::ShaderDatabase,
+    ImageTexture,
+};
 
 /// Convert wimdo and wismhd models to glTF for
 /// Xenoblade X, Xenoblade 1 DE, Xenoblade 2, and Xenoblade 3.
@@ -12,11 +18,31 @@ use xc3_model::{gltf::GltfFile, load_model, load_model_legacy, shader_database::
 struct Cli {
     /// The input wimdo, pcmdo, camdo, or wismhd file.
     input: String,
-    /// The output gltf file.
-    /// Images will be saved to the same directory as the output.
-    output: String,
+    /// The output gltf or glb file.
+    /// Images will be saved to the same directory as the output for gltf
+    /// and embedded in the file for glb.
+    #[arg(required_unless_present = "textures_only")]
+    output: Option<String>,
     /// The shader JSON database generated by xc3_shader.
     database: Option<String>,
+    /// Remove meshes that are initially hidden in game like alternate outfit parts.
+    #[arg(long)]
+    remove_hidden: bool,
+    /// Apply each material's texture matrix to the texture coordinate transforms.
+    #[arg(long)]
+    texture_matrices: bool,
+    /// Skip triangles with repeated indices that have zero area.
+    #[arg(long)]
+    remove_degenerates: bool,
+    /// Flip triangles that face away from their vertex normals.
+    #[arg(long)]
+    fix_winding: bool,
+    /// Rotate the scene to Z-up for applications that don't convert from glTF's Y-up.
+    #[arg(long)]
+    z_up: bool,
+    /// Save the model's textures as DDS files to this directory without creating a glTF file.
+    #[arg(long)]
+    textures_only: Option<String>,
 }
 
 fn main() -> anyhow::Result<()> {
@@ -34,43 +60,133 @@ fn main() -> anyhow::Result<()> {
         .map(|p| ShaderDatabase::from_file(&p).with_context(|| format!("{p:?}")))
         .transpose()?;
 
-    let name = std::path::Path::new(&cli.output)
+    if let Some(output_folder) = &cli.textures_only {
+        let textures = match Path::new(&cli.input).extension().unwrap().to_str().unwrap() {
+            "wimdo" | "pcmdo" => {
+                load_model(&cli.input, database.as_ref())
+                    .with_context(|| format!("failed to load model {:?}", cli.input))?
+                    .image_textures
+            }
+            "camdo" => load_model_legacy(&cli.input).image_textures,
+            e => return Err(anyhow::anyhow!("unsupported extension {e}")),
+        };
+
+        let model_name = Path::new(&cli.input)
+            .file_stem()
+            .unwrap()
+            .to_string_lossy()
+            .to_string();
+        let count = save_dds_textures(&textures, &model_name, Path::new(output_folder))?;
+
+        println!(
+            "Saved {count} textures to {output_folder:?} in {:?}",
+            start.elapsed()
+        );
+        return Ok(());
+    }
+
+    // The output is required unless only saving textures.
+    let output = cli.output.unwrap();
+
+    let name = std::path::Path::new(&output)
         .file_stem()
         .unwrap()
         .to_string_lossy()
         .to_string();
 
+    let options = GltfOptions {
+        texture_matrices: cli.texture_matrices,
+        remove_degenerate_triangles: cli.remove_degenerates,
+        fix_winding: cli.fix_winding,
+        up_axis: if cli.z_up { UpAxis::Z } else { UpAxis::Y },
+    };
+
     let gltf = match Path::new(&cli.input).extension().unwrap().to_str().unwrap() {
         "wimdo" => {
-            let root = load_model(&cli.input, database.as_ref())
+            let mut root = load_model(&cli.input, database.as_ref())
                 .with_context(|| format!("failed to load .wimdo model {:?}", cli.input))?;
-            GltfFile::from_model(&name, &[root]).with_context(|| "failed to create glTF file")
+            if cli.remove_hidden {
+                root.models.remove_hidden_meshes();
+            }
+            GltfFile::from_model_with_options(&name, &[root], &options)
+                .with_context(|| "failed to create glTF file")
         }
         "pcmdo" => {
-            let root = load_model(&cli.input, database.as_ref())
+            let mut root = load_model(&cli.input, database.as_ref())
                 .with_context(|| format!("failed to load .pcmdo model {:?}", cli.input))?;
-            GltfFile::from_model(&name, &[root]).with_context(|| "failed to create glTF file")
+            if cli.remove_hidden {
+                root.models.remove_hidden_meshes();
+            }
+            GltfFile::from_model_with_options(&name, &[root], &options)
+                .with_context(|| "failed to create glTF file")
         }
         "camdo" => {
             let root = load_model_legacy(&cli.input);
-            GltfFile::from_model(&name, &[root]).with_context(|| "failed to create glTF file")
+            GltfFile::from_model_with_options(&name, &[root], &options)
+                .with_context(|| "failed to create glTF file")
         }
         "wismhd" => {
             let roots = xc3_model::load_map(&cli.input, database.as_ref())
                 .with_context(|| format!("failed to load .wismhd map {:?}", cli.input))?;
-            GltfFile::from_map(&name, &roots).with_context(|| "failed to create glTF file")
+            GltfFile::from_map_with_options(&name, &roots, &options)
+                .with_context(|| "failed to create glTF file")
         }
         e => Err(anyhow::anyhow!("unsupported extension {e}")),
     }?;
 
-    if let Some(parent) = Path::new(&cli.output).parent() {
+    if let Some(parent) = Path::new(&output).parent() {
         std::fs::create_dir_all(parent)
             .with_context(|| format!("failed to create output directory {parent:?}"))?;
     }
 
-    gltf.save(&cli.output)
-        .with_context(|| format!("failed to save glTF file to {:?}", &cli.output))?;
+    // Embed buffers and images in a single file for .glb outputs.
+    if Path::new(&output).extension().and_then(|e| e.to_str()) == Some("glb") {
+        gltf.save_glb(&output)
+            .with_context(|| format!("failed to save GLB file to {:?}", &output))?;
+    } else {
+        gltf.save(&output)
+            .with_context(|| format!("failed to save glTF file to {:?}", &output))?;
+    }
 
     println!("Converted in {:?}", start.elapsed());
     Ok(())
 }
+
+fn save_dds_textures(
+    textures: &[ImageTexture],
+    model_name: &str,
+    output_folder: &Path,
+) -> anyhow::Result<usize> {
+    std::fs::create_dir_all(output_folder)
+        .with_context(|| format!("failed to create output directory {output_folder:?}"))?;
+
+    let mut used_names = HashSet::new();
+    for (i, texture) in textures.iter().enumerate() {
+        // Most textures don't have names, so fall back to the texture index.
+        let mut name = texture
+            .name
+            .clone()
+            .unwrap_or_else(|| format!("{model_name}.{i}"));
+        if let Some(usage) = texture.usage {
+            name = format!("{name}.{usage:?}");
+        }
+
+        // Names are not guaranteed to be unique.
+        let mut unique_name = name.clone();
+        let mut index = 1;
+        while !used_names.insert(unique_name.clone()) {
+            unique_name = format!("{name}.{index}");
+            index += 1;
+        }
+
+        let path = output_folder.join(format!("{unique_name}.dds"));
+        let dds = texture
+            .to_dds()
+            .with_context(|| format!("failed to create DDS for texture {i}"))?;
+        dds.save(&path)
+            .with_context(|| format!("failed to save DDS to {path:?}"))?;
+        println!("{path:?}");
+    }
+
+    Ok(textures.len())
+}
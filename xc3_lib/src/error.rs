@@ -12,4 +12,10 @@ pub enum DecompressStreamError {
 
     #[error("error reading stream data")]
     Binrw(#[from] binrw::Error),
+
+    #[error("legacy streaming data is not supported")]
+    LegacyStreaming,
+
+    #[error("stream index {0} is out of range")]
+    MissingStream(usize),
 }
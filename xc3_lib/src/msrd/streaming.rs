@@ -14,7 +14,7 @@ use crate::{
     mxmd::TextureUsage,
     spch::Spch,
     vertex::VertexData,
-    xbc1::{CompressionType, CreateXbc1Error},
+    xbc1::{CompressionLevel, CompressionType, CreateXbc1Error},
     ReadFileError,
 };
 
@@ -181,6 +181,36 @@ impl Msrd {
         }
     }
 
+    /// Extract only the vertex data for a `wismt` or `pcsmt` file.
+    ///
+    /// This is faster than [Msrd::extract_files] when textures are not needed
+    /// since only the stream containing the vertex data is decompressed.
+    /// Legacy streaming data returns [DecompressStreamError::LegacyStreaming].
+    pub fn extract_vertex_data(&self) -> Result<VertexData, DecompressStreamError> {
+        match &self.streaming.inner {
+            StreamingInner::StreamingLegacy(_) => Err(DecompressStreamError::LegacyStreaming),
+            StreamingInner::Streaming(data) => data.extract_vertex_data(&self.data),
+        }
+    }
+
+    /// Extract only the textures for a `wismt` file.
+    ///
+    /// This is faster than [Msrd::extract_files] when the vertex data and shaders are not needed.
+    /// High resolution textures are combined with their low resolution textures
+    /// in the same way as [Msrd::extract_files].
+    /// Legacy streaming data returns [DecompressStreamError::LegacyStreaming].
+    pub fn extract_textures(
+        &self,
+        chr_tex_nx: Option<&Path>,
+    ) -> Result<Vec<ExtractedTexture<Mibl>>, ExtractFilesError> {
+        match &self.streaming.inner {
+            StreamingInner::StreamingLegacy(_) => {
+                Err(DecompressStreamError::LegacyStreaming.into())
+            }
+            StreamingInner::Streaming(data) => data.extract_all_textures(&self.data, chr_tex_nx),
+        }
+    }
+
     /// Extract all embedded files for a `pcsmt` file.
     pub fn extract_files_pc(
         &self,
@@ -224,9 +254,30 @@ impl Msrd {
         spch: &Spch,
         textures: &[ExtractedTexture<Mibl>],
         use_chr_textures: bool,
+    ) -> Result<Self, CreateXbc1Error> {
+        Self::from_extracted_files_with_level(
+            vertex,
+            spch,
+            textures,
+            use_chr_textures,
+            CompressionLevel::Default,
+        )
+    }
+
+    /// Pack and compress the files into new archive data using the specified `level`.
+    /// See [Msrd::from_extracted_files] for details.
+    ///
+    /// Streams always use [CompressionType::Zlib] for compatibility with all games.
+    /// Use [CompressionLevel::Fast] to speed up repacking while iterating on edits.
+    pub fn from_extracted_files_with_level(
+        vertex: &VertexData,
+        spch: &Spch,
+        textures: &[ExtractedTexture<Mibl>],
+        use_chr_textures: bool,
+        level: CompressionLevel,
     ) -> Result<Self, CreateXbc1Error> {
         // TODO: This should actually be checking if the game is xenoblade 3.
-        let (mut streaming, data) = pack_files(vertex, spch, textures, use_chr_textures)?;
+        let (mut streaming, data) = pack_files(vertex, spch, textures, use_chr_textures, level)?;
 
         // HACK: We won't know the first xbc1 offset until writing the header.
         let mut writer = Cursor::new(Vec::new());
@@ -321,6 +372,32 @@ impl StreamingData {
         Ok((vertex, spch, textures))
     }
 
+    fn extract_vertex_data(&self, data: &[u8]) -> Result<VertexData, DecompressStreamError> {
+        // TODO: is this always in the first stream?
+        let stream0 = self.first_stream(data)?;
+        VertexData::from_bytes(self.entry_bytes(self.vertex_data_entry_index, &stream0))
+            .map_err(Into::into)
+    }
+
+    fn first_stream(&self, data: &[u8]) -> Result<Vec<u8>, DecompressStreamError> {
+        let stream0 = self
+            .streams
+            .first()
+            .ok_or(DecompressStreamError::MissingStream(0))?;
+        stream0.read_xbc1(data, stream0.xbc1_offset)?.decompress()
+    }
+
+    fn extract_all_textures<T: Texture>(
+        &self,
+        data: &[u8],
+        chr_tex_nx: Option<&Path>,
+    ) -> Result<Vec<ExtractedTexture<T>>, ExtractFilesError> {
+        // TODO: is this always in the first stream?
+        let stream0 = self.first_stream(data)?;
+        let low_texture_bytes = self.entry_bytes(self.low_textures_entry_index, &stream0);
+        self.extract_textures(data, low_texture_bytes, chr_tex_nx)
+    }
+
     fn extract_low_textures<T: Texture>(
         &self,
         low_texture_data: &[u8],
@@ -445,13 +522,14 @@ fn pack_files(
     spch: &Spch,
     textures: &[ExtractedTexture<Mibl>],
     use_chr_textures: bool,
+    level: CompressionLevel,
 ) -> Result<(StreamingData, Vec<u8>), CreateXbc1Error> {
     let Streams {
         stream_entries,
         streams,
         low_textures,
         data,
-    } = create_streams(vertex, spch, textures)?;
+    } = create_streams(vertex, spch, textures, level)?;
 
     let vertex_data_entry_index = stream_entry_index(&stream_entries, EntryType::Vertex);
     let shader_entry_index = stream_entry_index(&stream_entries, EntryType::Shader);
@@ -534,6 +612,7 @@ fn create_streams(
     vertex: &VertexData,
     spch: &Spch,
     textures: &[ExtractedTexture<Mibl>],
+    level: CompressionLevel,
 ) -> Result<Streams, CreateXbc1Error> {
     // Entries are in ascending order by offset and stream.
     // Data order is Vertex, Shader, LowTextures, Textures.
@@ -565,7 +644,13 @@ fn create_streams(
     let xbc1s: Vec<_> = streams_data
         .par_iter()
         .map(|data| {
-            Xbc1::from_decompressed("0000".to_string(), data, CompressionType::Zlib).unwrap()
+            Xbc1::from_decompressed_with_level(
+                "0000".to_string(),
+                data,
+                CompressionType::Zlib,
+                level,
+            )
+            .unwrap()
         })
         .collect();
 
@@ -806,6 +891,124 @@ pub fn chr_tex_nx_folder<P: AsRef<Path>>(input: P) -> Option<PathBuf> {
 mod tests {
     use super::*;
 
+    fn vertex_data() -> VertexData {
+        VertexData {
+            vertex_buffers: Vec::new(),
+            index_buffers: Vec::new(),
+            unk0: 0,
+            unk1: 0,
+            unk2: 0,
+            vertex_buffer_info: Vec::new(),
+            outline_buffers: Vec::new(),
+            vertex_morphs: None,
+            buffer: vec![1, 2, 3, 4],
+            unk_data: None,
+            weights: None,
+            unk7: None,
+            unks: [0; 5],
+        }
+    }
+
+    fn spch() -> Spch {
+        Spch {
+            version: 0,
+            slct_offsets: Vec::new(),
+            unk4s: Vec::new(),
+            slct_section: Vec::new(),
+            xv4_section: Vec::new(),
+            unk_section: Vec::new(),
+            string_section: None,
+            unk7: 0,
+            padding: [0; 4],
+        }
+    }
+
+    fn texture() -> ExtractedTexture<Mibl> {
+        let mibl = Mibl::from_surface(image_dds::Surface {
+            width: 4,
+            height: 4,
+            depth: 1,
+            layers: 1,
+            mipmaps: 1,
+            image_format: image_dds::ImageFormat::BC7RgbaUnorm,
+            data: vec![0u8; 16],
+        })
+        .unwrap();
+
+        ExtractedTexture {
+            name: "texture".to_string(),
+            usage: TextureUsage::Col,
+            low: mibl.clone(),
+            high: Some(HighTexture {
+                mid: mibl,
+                base_mip: Some(vec![0u8; 64]),
+            }),
+        }
+    }
+
+    #[test]
+    fn extract_vertex_data_skips_texture_streams() {
+        let vertex = vertex_data();
+        let mut msrd = Msrd::from_extracted_files(&vertex, &spch(), &[texture()], false).unwrap();
+
+        // Invalidate the texture streams to detect any decompression attempts.
+        let StreamingInner::Streaming(streaming) = &msrd.streaming.inner else {
+            unreachable!()
+        };
+        let first_xbc1_offset = streaming.streams[0].xbc1_offset;
+        let texture_stream_offsets: Vec<_> = streaming.streams[1..]
+            .iter()
+            .map(|s| (s.xbc1_offset - first_xbc1_offset) as usize)
+            .collect();
+        assert!(!texture_stream_offsets.is_empty());
+        for offset in texture_stream_offsets {
+            msrd.data[offset..offset + 4].copy_from_slice(&[0u8; 4]);
+        }
+
+        assert!(msrd.extract_files(None).is_err());
+        assert_eq!(vertex, msrd.extract_vertex_data().unwrap());
+    }
+
+    #[test]
+    fn extract_textures_high_and_low() {
+        let msrd =
+            Msrd::from_extracted_files(&vertex_data(), &spch(), &[texture()], false).unwrap();
+
+        let textures = msrd.extract_textures(None).unwrap();
+        assert_eq!(1, textures.len());
+
+        let expected = texture();
+        assert_eq!(expected.name, textures[0].name);
+        assert_eq!(expected.usage, textures[0].usage);
+        assert_eq!(expected.low.footer, textures[0].low.footer);
+
+        let high = textures[0].high.as_ref().unwrap();
+        let expected_high = expected.high.unwrap();
+        assert_eq!(expected_high.mid.footer, high.mid.footer);
+        assert_eq!(expected_high.base_mip, high.base_mip);
+    }
+
+    #[test]
+    fn extract_textures_missing_streams() {
+        let mut msrd =
+            Msrd::from_extracted_files(&vertex_data(), &spch(), &[texture()], false).unwrap();
+        let StreamingInner::Streaming(streaming) = &mut msrd.streaming.inner else {
+            unreachable!()
+        };
+        streaming.streams.clear();
+
+        assert!(matches!(
+            msrd.extract_textures(None),
+            Err(ExtractFilesError::Stream(
+                DecompressStreamError::MissingStream(0)
+            ))
+        ));
+        assert!(matches!(
+            msrd.extract_vertex_data(),
+            Err(DecompressStreamError::MissingStream(0))
+        ));
+    }
+
     #[test]
     fn chr_tex_nx_folders() {
         assert_eq!(None, chr_tex_nx_folder(""));
@@ -75,6 +75,14 @@ pub struct Mxmd {
     pub unk: [u32; 9],
 }
 
+impl Mxmd {
+    /// Returns `true` if the model has [Skinning] and requires a skeleton for vertex skinning.
+    /// Static models like map props typically don't have skinning.
+    pub fn has_skinning(&self) -> bool {
+        self.models.skinning.is_some()
+    }
+}
+
 // TODO: more strict alignment for xc3?
 // TODO: 108 bytes for xc2 and 112 bytes for xc3?
 /// A collection of [Material], [Sampler], and material parameters.
@@ -208,6 +216,7 @@ pub struct VertexAttribute {
 
 /// `ml::MdsMatVariableTbl` in the Xenoblade 2 binary.
 #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, BinRead, Xc3Write, Xc3WriteOffsets, PartialEq, Clone)]
 pub struct MaterialParameter {
     pub param_type: ParamType,
@@ -217,6 +226,7 @@ pub struct MaterialParameter {
 }
 
 #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, BinRead, BinWrite, Clone, Copy, PartialEq, Eq, Hash)]
 #[brw(repr(u16))]
 pub enum ParamType {
@@ -431,6 +441,7 @@ pub struct MaterialFlags {
 
 /// Flags controlling pipeline state for rasterizer and fragment state.
 #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, BinRead, BinWrite, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct StateFlags {
     pub depth_write_mode: u8, // 0, 1, 2, 7
@@ -452,6 +463,7 @@ pub struct StateFlags {
 // 3, Zero, Src Col, Add, Zero, Src Col, Add
 // 6, disabled + ???
 #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, BinRead, BinWrite, Clone, Copy, PartialEq, Eq, Hash)]
 #[brw(repr(u8))]
 pub enum BlendMode {
@@ -464,6 +476,7 @@ pub enum BlendMode {
 
 // TODO: manually test stencil values in renderdoc.
 #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, BinRead, BinWrite, Clone, Copy, PartialEq, Eq, Hash)]
 #[brw(repr(u8))]
 pub enum StencilValue {
@@ -483,6 +496,7 @@ pub enum StencilValue {
 
 // TODO: Does this flag actually disable stencil?
 #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, BinRead, BinWrite, Clone, Copy, PartialEq, Eq, Hash)]
 #[brw(repr(u8))]
 pub enum StencilMode {
@@ -496,6 +510,7 @@ pub enum StencilMode {
 }
 
 #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, BinRead, BinWrite, Clone, Copy, PartialEq, Eq, Hash)]
 #[brw(repr(u8))]
 pub enum DepthFunc {
@@ -505,6 +520,7 @@ pub enum DepthFunc {
 }
 
 #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, BinRead, BinWrite, Clone, Copy, PartialEq, Eq, Hash)]
 #[brw(repr(u8))]
 pub enum CullMode {
@@ -533,6 +549,7 @@ pub struct MaterialTechnique {
 // _zpre = 0
 // _outline = 0
 #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, BinRead, BinWrite, PartialEq, Eq, Clone, Copy, Hash)]
 #[brw(repr(u16))]
 pub enum RenderPassType {
@@ -815,6 +832,7 @@ pub struct Mesh {
 /// Flags to determine how to draw a [Mesh].
 #[bitsize(32)]
 #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(DebugBits, TryFromBits, BinRead, BinWrite, PartialEq, Clone, Copy)]
 #[br(try_map = |x: u32| x.try_into().map_err(|e| format!("{e:?}")))]
 #[bw(map = |&x| u32::from(x))]
@@ -829,6 +847,7 @@ pub struct MeshRenderFlags2 {
 /// The render pass for this draw call.
 #[bitsize(4)]
 #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, TryFromBits, PartialEq, Clone, Copy)]
 pub enum MeshRenderPass {
     /// The first opaque pass with depth writes.
@@ -1383,6 +1402,7 @@ pub struct PackedExternalTexture {
 /// Hints on how the texture is used.
 /// Actual usage is determined by the shader.
 #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, BinRead, BinWrite, Clone, Copy, PartialEq, Eq, Hash)]
 #[brw(repr(u32))]
 pub enum TextureUsage {
@@ -157,6 +157,7 @@ pub struct VertexAttribute {
 // Names are taken from shader attribute metadata.
 /// The data type, usage, and component count for a [VertexAttribute].
 #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, BinRead, BinWrite, PartialEq, Eq, Clone, Copy)]
 #[brw(repr(u16))]
 pub enum DataType {
@@ -359,6 +360,7 @@ pub struct Weights {
 /// The bone matrices for each bone index multiplied by the weights are written to the output buffer starting at [output_start_index](#structfield.output_start_index).
 /// This precomputed skinning buffer is used to select transforms in the vertex shader using [DataType::WeightIndex].
 #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, PartialEq, Clone, BinRead, Xc3Write, Xc3WriteOffsets)]
 pub struct WeightGroup {
     /// Index into the skinning buffer used in the vertex shader with bone transforms multiplied by skin weights.
@@ -386,6 +388,7 @@ pub struct WeightGroup {
 // TODO: Figure out by finding files with no more groups than pass ids?
 /// References to [WeightGroup] for each of the [RenderPassType](crate::mxmd::RenderPassType).
 #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, PartialEq, Clone, BinRead, Xc3Write, Xc3WriteOffsets)]
 pub struct WeightLod {
     /// One plus the indices pointing back to [groups](struct.Weights.html#structfield.groups).
@@ -62,6 +62,18 @@ pub enum CompressionType {
     Zstd = 3,
 }
 
+/// The tradeoff between compression speed and compressed size.
+/// Both levels produce data that can be decompressed in game.
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum CompressionLevel {
+    /// Faster compression with larger compressed sizes for faster iteration.
+    Fast,
+    /// The highest zlib level or the default zstd level.
+    #[default]
+    Default,
+}
+
 #[derive(Debug, Error)]
 pub enum CreateXbc1Error {
     #[error("error reading or writing data")]
@@ -93,16 +105,43 @@ impl Xbc1 {
         name: String,
         decompressed: &[u8],
         compression_type: CompressionType,
+    ) -> Result<Self, CreateXbc1Error> {
+        Self::from_decompressed_with_level(
+            name,
+            decompressed,
+            compression_type,
+            CompressionLevel::Default,
+        )
+    }
+
+    /// Compress the data in `decompressed` using the specified `level`.
+    /// Use [CompressionType::Zlib] for best compatibility.
+    pub fn from_decompressed_with_level(
+        name: String,
+        decompressed: &[u8],
+        compression_type: CompressionType,
+        level: CompressionLevel,
     ) -> Result<Self, CreateXbc1Error> {
         let compressed_stream = match compression_type {
             CompressionType::Uncompressed => decompressed.to_vec(),
             CompressionType::Zlib => {
-                let mut encoder = ZlibEncoder::new(decompressed, Compression::best());
+                let compression = match level {
+                    CompressionLevel::Fast => Compression::fast(),
+                    CompressionLevel::Default => Compression::best(),
+                };
+                let mut encoder = ZlibEncoder::new(decompressed, compression);
                 let mut compressed_stream = Vec::new();
                 encoder.read_to_end(&mut compressed_stream)?;
                 compressed_stream
             }
-            CompressionType::Zstd => zstd::stream::encode_all(Cursor::new(decompressed), 0)?,
+            CompressionType::Zstd => {
+                // A level of 0 uses the default zstd compression level.
+                let level = match level {
+                    CompressionLevel::Fast => 1,
+                    CompressionLevel::Default => 0,
+                };
+                zstd::stream::encode_all(Cursor::new(decompressed), level)?
+            }
         };
 
         Ok(Self {
@@ -188,3 +227,38 @@ where
         Self::read(&mut Cursor::new(bytes))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn compress_decompress(compression_type: CompressionType, level: CompressionLevel) -> Vec<u8> {
+        let data: Vec<u8> = (0..4096u32).map(|i| (i % 7 + i / 256) as u8).collect();
+
+        let xbc1 =
+            Xbc1::from_decompressed_with_level("0000".to_string(), &data, compression_type, level)
+                .unwrap();
+        assert_eq!(data.len() as u32, xbc1.decompressed_size);
+        assert_eq!(hash_crc(&data), xbc1.decompressed_hash);
+
+        let decompressed = xbc1.decompress().unwrap();
+        assert_eq!(data, decompressed);
+        decompressed
+    }
+
+    #[test]
+    fn zlib_levels_decompress_identical() {
+        assert_eq!(
+            compress_decompress(CompressionType::Zlib, CompressionLevel::Fast),
+            compress_decompress(CompressionType::Zlib, CompressionLevel::Default)
+        );
+    }
+
+    #[test]
+    fn zstd_levels_decompress_identical() {
+        assert_eq!(
+            compress_decompress(CompressionType::Zstd, CompressionLevel::Fast),
+            compress_decompress(CompressionType::Zstd, CompressionLevel::Default)
+        );
+    }
+}
@@ -0,0 +1,15 @@
+//! Log a model and its skeleton to a new Rerun viewer.
+//!
+//! cargo run -p xc3_model --example rerun_model --features rerun -- xeno3/chr/ch/ch01027000.wimdo
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let path = std::env::args()
+        .nth(1)
+        .ok_or("expected a .wimdo path as the first argument")?;
+
+    let root = xc3_model::load_model(&path, None)?;
+
+    let stream = rerun::RecordingStreamBuilder::new("xc3_model").spawn()?;
+    root.log_to_rerun(&stream)?;
+
+    Ok(())
+}
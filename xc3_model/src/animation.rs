@@ -1,8 +1,9 @@
 //! Utilities for working with animation data.
 use std::collections::{BTreeMap, HashMap};
+use std::io::Write;
 use std::ops::Bound::*;
 
-use glam::{vec4, Mat4, Quat, Vec3, Vec4, Vec4Swizzles};
+use glam::{vec4, EulerRot, Mat4, Quat, Vec3, Vec4, Vec4Swizzles};
 use log::error;
 use ordered_float::OrderedFloat;
 pub use xc3_lib::bc::anim::{BlendMode, PlayMode, SpaceMode};
@@ -112,22 +113,15 @@ impl Animation {
     /// See [Skeleton::model_space_transforms] for the transforms without animations applied.
     pub fn model_space_transforms(&self, skeleton: &Skeleton, frame: f32) -> Vec<Mat4> {
         // TODO: Is it worth precomputing this?
-        let hash_to_index: HashMap<_, _> = skeleton
-            .bones
-            .iter()
-            .enumerate()
-            .map(|(i, b)| (murmur3(b.name.as_bytes()), i))
-            .collect();
+        let hash_to_index = bone_name_hashes(skeleton);
 
         // Keep track of which bones have animations applied.
         let mut animated_transforms = vec![None; skeleton.bones.len()];
 
         for track in &self.tracks {
-            if let Some(bone_index) = match &track.bone_index {
-                BoneIndex::Index(i) => Some(*i),
-                BoneIndex::Hash(hash) => hash_to_index.get(hash).copied(),
-                BoneIndex::Name(name) => skeleton.bones.iter().position(|b| &b.name == name),
-            } {
+            if let Some(bone_index) =
+                resolve_bone_index(&track.bone_index, skeleton, &hash_to_index)
+            {
                 if let Some(transform) = track.sample_transform(frame) {
                     if bone_index < skeleton.bones.len() {
                         animated_transforms[bone_index] = Some(apply_transform(
@@ -209,6 +203,151 @@ impl Animation {
             })
             .collect()
     }
+
+    /// The index in [bones](../skeleton/struct.Skeleton.html#structfield.bones) for each of the [tracks](#structfield.tracks)
+    /// or `None` if the track does not match a bone in `skeleton`.
+    ///
+    /// Most animations only store bone indices or name hashes instead of names,
+    /// so tracks must be resolved against a [Skeleton] to determine the animated bones.
+    pub fn track_bone_indices(&self, skeleton: &Skeleton) -> Vec<Option<usize>> {
+        let hash_to_index = bone_name_hashes(skeleton);
+        self.tracks
+            .iter()
+            .map(|t| {
+                resolve_bone_index(&t.bone_index, skeleton, &hash_to_index)
+                    .filter(|i| *i < skeleton.bones.len())
+            })
+            .collect()
+    }
+
+    /// The names of the bones in `skeleton` animated by at least one of the [tracks](#structfield.tracks).
+    ///
+    /// Tracks that do not match a bone in `skeleton` are skipped.
+    /// Use [Animation::track_bone_indices] to detect tracks that do not match the skeleton.
+    pub fn animated_bone_names<'a>(&self, skeleton: &'a Skeleton) -> Vec<&'a str> {
+        self.track_bone_indices(skeleton)
+            .into_iter()
+            .flatten()
+            .map(|i| skeleton.bones[i].name.as_str())
+            .collect()
+    }
+
+    /// Create a copy of this animation for `target` by matching bones from `source` by name.
+    ///
+    /// Tracks for bones not present in `target` are removed,
+    /// and bones in `target` without a track remain in their rest pose.
+    /// Rotations are rebased through the rest transforms of each skeleton
+    /// to account for differences in the bind pose.
+    /// Translation offsets from the rest pose are scaled by the ratio of the rest translation lengths.
+    ///
+    /// Additive animations are only remapped to the new bones
+    /// since their tracks are already relative to the rest pose.
+    pub fn retarget(&self, source: &Skeleton, target: &Skeleton) -> Animation {
+        let (source_rest, target_rest): (Vec<_>, Vec<_>) = match self.space_mode {
+            SpaceMode::Local => (
+                source.bones.iter().map(|b| b.transform).collect(),
+                target.bones.iter().map(|b| b.transform).collect(),
+            ),
+            SpaceMode::Model => (
+                source.model_space_transforms(),
+                target.model_space_transforms(),
+            ),
+        };
+
+        let tracks = self
+            .tracks
+            .iter()
+            .zip(self.track_bone_indices(source))
+            .filter_map(|(track, source_index)| {
+                let source_index = source_index?;
+                let name = &source.bones[source_index].name;
+                let target_index = target.bones.iter().position(|b| &b.name == name)?;
+
+                let mut new_track = track.clone();
+                new_track.bone_index = BoneIndex::Index(target_index);
+                if self.blend_mode == BlendMode::Blend {
+                    rebase_track(
+                        &mut new_track,
+                        source_rest[source_index],
+                        target_rest[target_index],
+                    );
+                }
+                Some(new_track)
+            })
+            .collect();
+
+        Animation {
+            name: self.name.clone(),
+            space_mode: self.space_mode,
+            play_mode: self.play_mode,
+            blend_mode: self.blend_mode,
+            frames_per_second: self.frames_per_second,
+            frame_count: self.frame_count,
+            tracks,
+            morph_tracks: self.morph_tracks.clone(),
+        }
+    }
+}
+
+fn rebase_track(track: &mut Track, source_rest: Mat4, target_rest: Mat4) {
+    let (_, source_rotation, source_translation) = source_rest.to_scale_rotation_translation();
+    let (_, target_rotation, target_translation) = target_rest.to_scale_rotation_translation();
+
+    // Quaternion multiplication is linear, so the cubic coefficients can be rotated directly.
+    let rotation = target_rotation * source_rotation.inverse();
+    for keyframe in track.rotation_keyframes.values_mut() {
+        for i in 0..4 {
+            let coeffs = rotation
+                * Quat::from_xyzw(
+                    keyframe.x_coeffs[i],
+                    keyframe.y_coeffs[i],
+                    keyframe.z_coeffs[i],
+                    keyframe.w_coeffs[i],
+                );
+            keyframe.x_coeffs[i] = coeffs.x;
+            keyframe.y_coeffs[i] = coeffs.y;
+            keyframe.z_coeffs[i] = coeffs.z;
+            keyframe.w_coeffs[i] = coeffs.w;
+        }
+    }
+
+    // Apply target + (translation - source) * scale to each cubic.
+    let source_length = source_translation.length();
+    let scale = if source_length > 0.0 {
+        target_translation.length() / source_length
+    } else {
+        1.0
+    };
+    let offset = target_translation - source_translation * scale;
+    for keyframe in track.translation_keyframes.values_mut() {
+        keyframe.x_coeffs *= scale;
+        keyframe.y_coeffs *= scale;
+        keyframe.z_coeffs *= scale;
+        keyframe.x_coeffs.w += offset.x;
+        keyframe.y_coeffs.w += offset.y;
+        keyframe.z_coeffs.w += offset.z;
+    }
+}
+
+fn bone_name_hashes(skeleton: &Skeleton) -> HashMap<u32, usize> {
+    skeleton
+        .bones
+        .iter()
+        .enumerate()
+        .map(|(i, b)| (murmur3(b.name.as_bytes()), i))
+        .collect()
+}
+
+fn resolve_bone_index(
+    bone_index: &BoneIndex,
+    skeleton: &Skeleton,
+    hash_to_index: &HashMap<u32, usize>,
+) -> Option<usize> {
+    match bone_index {
+        BoneIndex::Index(i) => Some(*i),
+        BoneIndex::Hash(hash) => hash_to_index.get(hash).copied(),
+        BoneIndex::Name(name) => skeleton.bones.iter().position(|b| &b.name == name),
+    }
 }
 
 fn anim_tracks(anim: &xc3_lib::bc::anim::Anim) -> Vec<Track> {
@@ -592,6 +731,117 @@ fn apply_transform(target: Mat4, source: Mat4, blend_mode: BlendMode) -> Mat4 {
     }
 }
 
+/// Write the hierarchy of `skeleton` and the sampled frames of `animation` to `writer` in the BVH format.
+///
+/// Joint offsets use the rest pose translation of each bone in `skeleton`.
+/// Root bones have translation and rotation channels,
+/// and all other bones only have rotation channels.
+/// The motion data samples the local transforms of `animation` once per frame.
+///
+/// # Examples
+/// ``` rust no_run
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// use xc3_model::animation::export_bvh;
+///
+/// // Mio military uniform
+/// let root = xc3_model::load_model("xeno3/chr/ch/ch01027000.wimdo", None)?;
+/// let animations = xc3_model::load_animations("xeno3/chr/ch/ch01027000_event.mot")?;
+///
+/// let mut writer = std::io::BufWriter::new(std::fs::File::create("anim.bvh")?);
+/// export_bvh(root.skeleton.as_ref().unwrap(), &animations[0], &mut writer)?;
+/// # Ok(())
+/// # }
+/// ```
+pub fn export_bvh(
+    skeleton: &Skeleton,
+    animation: &Animation,
+    writer: &mut impl Write,
+) -> std::io::Result<()> {
+    // Channels in the motion section use the depth first order of the hierarchy.
+    let mut joints = Vec::new();
+
+    writeln!(writer, "HIERARCHY")?;
+    for (i, bone) in skeleton.bones.iter().enumerate() {
+        if bone.parent_index.is_none() {
+            write_bvh_joint(writer, skeleton, i, 0, &mut joints)?;
+        }
+    }
+
+    writeln!(writer, "MOTION")?;
+    writeln!(writer, "Frames: {}", animation.frame_count)?;
+    writeln!(writer, "Frame Time: {}", 1.0 / animation.frames_per_second)?;
+
+    for frame in 0..animation.frame_count {
+        let transforms = animation.local_space_transforms(skeleton, frame as f32);
+
+        let mut values = Vec::new();
+        for i in &joints {
+            let (_, rotation, translation) = transforms[*i].to_scale_rotation_translation();
+            if skeleton.bones[*i].parent_index.is_none() {
+                values.extend(translation.to_array());
+            }
+            // Match the channel order of Zrotation Xrotation Yrotation.
+            let (z, x, y) = rotation.to_euler(EulerRot::ZXY);
+            values.extend([z.to_degrees(), x.to_degrees(), y.to_degrees()]);
+        }
+
+        let line: Vec<_> = values.iter().map(|v| v.to_string()).collect();
+        writeln!(writer, "{}", line.join(" "))?;
+    }
+
+    Ok(())
+}
+
+fn write_bvh_joint(
+    writer: &mut impl Write,
+    skeleton: &Skeleton,
+    index: usize,
+    depth: usize,
+    joints: &mut Vec<usize>,
+) -> std::io::Result<()> {
+    joints.push(index);
+
+    let bone = &skeleton.bones[index];
+    let indent = "\t".repeat(depth);
+    let offset = bone.transform.col(3);
+
+    if bone.parent_index.is_none() {
+        writeln!(writer, "{indent}ROOT {}", bone.name)?;
+    } else {
+        writeln!(writer, "{indent}JOINT {}", bone.name)?;
+    }
+    writeln!(writer, "{indent}{{")?;
+    writeln!(
+        writer,
+        "{indent}\tOFFSET {} {} {}",
+        offset.x, offset.y, offset.z
+    )?;
+    if bone.parent_index.is_none() {
+        writeln!(
+            writer,
+            "{indent}\tCHANNELS 6 Xposition Yposition Zposition Zrotation Xrotation Yrotation"
+        )?;
+    } else {
+        writeln!(writer, "{indent}\tCHANNELS 3 Zrotation Xrotation Yrotation")?;
+    }
+
+    let children = skeleton.children(index);
+    if children.is_empty() {
+        // Leaf joints require an end site.
+        writeln!(writer, "{indent}\tEnd Site")?;
+        writeln!(writer, "{indent}\t{{")?;
+        writeln!(writer, "{indent}\t\tOFFSET 0 0 0")?;
+        writeln!(writer, "{indent}\t}}")?;
+    } else {
+        for child in children {
+            write_bvh_joint(writer, skeleton, child, depth + 1, joints)?;
+        }
+    }
+
+    writeln!(writer, "{indent}}}")?;
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -708,7 +958,13 @@ mod tests {
         };
 
         assert!(animation
-            .model_space_transforms(&Skeleton { bones: Vec::new() }, 0.0)
+            .model_space_transforms(
+                &Skeleton {
+                    bones: Vec::new(),
+                    procedural_bones: Vec::new(),
+                },
+                0.0
+            )
             .is_empty());
     }
 
@@ -765,6 +1021,7 @@ mod tests {
                     parent_index: Some(0),
                 },
             ],
+            procedural_bones: Vec::new(),
         };
 
         let transforms = animation.model_space_transforms(&skeleton, 0.0);
@@ -842,6 +1099,7 @@ mod tests {
                     parent_index: Some(0),
                 },
             ],
+            procedural_bones: Vec::new(),
         };
 
         let transforms = animation.model_space_transforms(&skeleton, 0.0);
@@ -919,6 +1177,7 @@ mod tests {
                     parent_index: Some(0),
                 },
             ],
+            procedural_bones: Vec::new(),
         };
 
         let transforms = animation.local_space_transforms(&skeleton, 0.0);
@@ -942,4 +1201,283 @@ mod tests {
             transforms[1]
         );
     }
+
+    #[test]
+    fn posed_skeleton_local_blend() {
+        // Crate a keyframe with a constant value.
+        let keyframe = |x, y, z, w| {
+            (
+                0.0.into(),
+                Keyframe {
+                    x_coeffs: vec4(0.0, 0.0, 0.0, x),
+                    y_coeffs: vec4(0.0, 0.0, 0.0, y),
+                    z_coeffs: vec4(0.0, 0.0, 0.0, z),
+                    w_coeffs: vec4(0.0, 0.0, 0.0, w),
+                },
+            )
+        };
+
+        let animation = Animation {
+            name: String::new(),
+            space_mode: SpaceMode::Local,
+            play_mode: PlayMode::Single,
+            blend_mode: BlendMode::Blend,
+            frames_per_second: 30.0,
+            frame_count: 1,
+            tracks: vec![Track {
+                translation_keyframes: [keyframe(1.0, 2.0, 3.0, 0.0)].into(),
+                rotation_keyframes: [keyframe(0.0, 0.0, 0.0, 1.0)].into(),
+                scale_keyframes: [keyframe(2.0, 2.0, 2.0, 0.0)].into(),
+                bone_index: BoneIndex::Name("b".to_string()),
+            }],
+            morph_tracks: None,
+        };
+
+        let skeleton = Skeleton {
+            bones: vec![
+                Bone {
+                    name: "a".to_string(),
+                    transform: Mat4::from_translation(Vec3::new(0.0, 1.0, 0.0)),
+                    parent_index: None,
+                },
+                Bone {
+                    name: "b".to_string(),
+                    transform: Mat4::IDENTITY,
+                    parent_index: Some(0),
+                },
+                Bone {
+                    name: "c".to_string(),
+                    transform: Mat4::from_translation(Vec3::new(0.0, 0.0, 4.0)),
+                    parent_index: Some(1),
+                },
+            ],
+            procedural_bones: Vec::new(),
+        };
+
+        let posed = skeleton.posed(&animation, 0.0);
+        assert_eq!(3, posed.bones.len());
+
+        // Only the animated bone should change.
+        assert_matrix_relative_eq!(skeleton.bones[0].transform, posed.bones[0].transform);
+        assert_matrix_relative_eq!(
+            animation.tracks[0].sample_transform(0.0).unwrap(),
+            posed.bones[1].transform
+        );
+        assert_matrix_relative_eq!(skeleton.bones[2].transform, posed.bones[2].transform);
+
+        let parents: Vec<_> = posed.bones.iter().map(|b| b.parent_index).collect();
+        assert_eq!(vec![None, Some(0), Some(1)], parents);
+    }
+
+    #[test]
+    fn export_bvh_joint_count() {
+        let animation = Animation {
+            name: String::new(),
+            space_mode: SpaceMode::Local,
+            play_mode: PlayMode::Single,
+            blend_mode: BlendMode::Blend,
+            frames_per_second: 30.0,
+            frame_count: 2,
+            tracks: Vec::new(),
+            morph_tracks: None,
+        };
+
+        let skeleton = Skeleton {
+            bones: vec![
+                Bone {
+                    name: "a".to_string(),
+                    transform: Mat4::from_translation(Vec3::new(0.0, 1.0, 0.0)),
+                    parent_index: None,
+                },
+                Bone {
+                    name: "b".to_string(),
+                    transform: Mat4::from_translation(Vec3::new(0.0, 2.0, 0.0)),
+                    parent_index: Some(0),
+                },
+                Bone {
+                    name: "c".to_string(),
+                    transform: Mat4::from_translation(Vec3::new(0.0, 0.0, 4.0)),
+                    parent_index: Some(0),
+                },
+            ],
+            procedural_bones: Vec::new(),
+        };
+
+        let mut bytes = Vec::new();
+        export_bvh(&skeleton, &animation, &mut bytes).unwrap();
+        let bvh = String::from_utf8(bytes).unwrap();
+
+        // Parse the joints and channels from the hierarchy.
+        let (hierarchy, motion) = bvh.split_once("MOTION\n").unwrap();
+        let joints: Vec<_> = hierarchy
+            .lines()
+            .filter_map(|l| {
+                let l = l.trim();
+                l.strip_prefix("ROOT ").or_else(|| l.strip_prefix("JOINT "))
+            })
+            .collect();
+        assert_eq!(vec!["a", "b", "c"], joints);
+
+        let channel_count: usize = hierarchy
+            .lines()
+            .filter_map(|l| l.trim().strip_prefix("CHANNELS "))
+            .map(|l| {
+                l.split_whitespace()
+                    .next()
+                    .unwrap()
+                    .parse::<usize>()
+                    .unwrap()
+            })
+            .sum();
+        assert_eq!(6 + 3 + 3, channel_count);
+        assert_eq!(
+            hierarchy.matches('{').count(),
+            hierarchy.matches('}').count()
+        );
+
+        // Each frame should have a value for every channel.
+        let mut lines = motion.lines();
+        assert_eq!(Some("Frames: 2"), lines.next());
+        assert!(lines.next().unwrap().starts_with("Frame Time: "));
+        let frames: Vec<Vec<f32>> = lines
+            .map(|l| l.split_whitespace().map(|v| v.parse().unwrap()).collect())
+            .collect();
+        assert_eq!(2, frames.len());
+        for frame in frames {
+            assert_eq!(channel_count, frame.len());
+            // The root uses the rest pose without any animation tracks.
+            assert_eq!(&[0.0, 1.0, 0.0], &frame[..3]);
+        }
+    }
+
+    #[test]
+    fn animated_bone_names_indices_hashes_names() {
+        let track = |bone_index| Track {
+            translation_keyframes: BTreeMap::new(),
+            rotation_keyframes: BTreeMap::new(),
+            scale_keyframes: BTreeMap::new(),
+            bone_index,
+        };
+
+        let animation = Animation {
+            name: String::new(),
+            space_mode: SpaceMode::Local,
+            play_mode: PlayMode::Single,
+            blend_mode: BlendMode::Blend,
+            frames_per_second: 30.0,
+            frame_count: 1,
+            tracks: vec![
+                track(BoneIndex::Index(1)),
+                track(BoneIndex::Hash(murmur3("c".as_bytes()))),
+                track(BoneIndex::Name("d".to_string())),
+                track(BoneIndex::Index(5)),
+            ],
+            morph_tracks: None,
+        };
+
+        let skeleton = Skeleton {
+            bones: ["a", "b", "c"]
+                .into_iter()
+                .map(|name| Bone {
+                    name: name.to_string(),
+                    transform: Mat4::IDENTITY,
+                    parent_index: None,
+                })
+                .collect(),
+            procedural_bones: Vec::new(),
+        };
+
+        assert_eq!(
+            vec![Some(1), Some(2), None, None],
+            animation.track_bone_indices(&skeleton)
+        );
+        assert_eq!(vec!["b", "c"], animation.animated_bone_names(&skeleton));
+    }
+
+    #[test]
+    fn retarget_renamed_bone_order() {
+        let constant = |v: Vec4| {
+            [(
+                0.0.into(),
+                Keyframe {
+                    x_coeffs: vec4(0.0, 0.0, 0.0, v.x),
+                    y_coeffs: vec4(0.0, 0.0, 0.0, v.y),
+                    z_coeffs: vec4(0.0, 0.0, 0.0, v.z),
+                    w_coeffs: vec4(0.0, 0.0, 0.0, v.w),
+                },
+            )]
+            .into()
+        };
+        let track = |bone_index, translation: Vec3, rotation: Quat| Track {
+            translation_keyframes: constant(translation.extend(0.0)),
+            rotation_keyframes: constant(Vec4::from_array(rotation.to_array())),
+            scale_keyframes: constant(Vec4::ONE),
+            bone_index,
+        };
+
+        let animation = Animation {
+            name: String::new(),
+            space_mode: SpaceMode::Local,
+            play_mode: PlayMode::Single,
+            blend_mode: BlendMode::Blend,
+            frames_per_second: 30.0,
+            frame_count: 1,
+            tracks: vec![
+                track(BoneIndex::Index(0), Vec3::ZERO, Quat::IDENTITY),
+                track(
+                    BoneIndex::Index(1),
+                    Vec3::new(0.0, 1.5, 0.0),
+                    Quat::from_rotation_x(90.0f32.to_radians()),
+                ),
+                track(BoneIndex::Index(2), Vec3::ZERO, Quat::IDENTITY),
+            ],
+            morph_tracks: None,
+        };
+
+        let bone = |name: &str, transform, parent_index| Bone {
+            name: name.to_string(),
+            transform,
+            parent_index,
+        };
+
+        let source = Skeleton {
+            bones: vec![
+                bone("root", Mat4::IDENTITY, None),
+                bone("arm", Mat4::from_translation(Vec3::Y), Some(0)),
+                bone("tail", Mat4::IDENTITY, Some(0)),
+            ],
+            procedural_bones: Vec::new(),
+        };
+
+        // The target has different bone order, bind pose, and bone lengths.
+        let target_arm_rotation = Quat::from_rotation_z(90.0f32.to_radians());
+        let target = Skeleton {
+            bones: vec![
+                bone("extra", Mat4::IDENTITY, None),
+                bone("root", Mat4::IDENTITY, None),
+                bone(
+                    "arm",
+                    Mat4::from_rotation_translation(target_arm_rotation, Vec3::new(0.0, 2.0, 0.0)),
+                    Some(1),
+                ),
+            ],
+            procedural_bones: Vec::new(),
+        };
+
+        let retargeted = animation.retarget(&source, &target);
+        assert_eq!(2, retargeted.tracks.len());
+        assert_eq!(BoneIndex::Index(1), retargeted.tracks[0].bone_index);
+        assert_eq!(BoneIndex::Index(2), retargeted.tracks[1].bone_index);
+        assert_eq!(vec!["root", "arm"], retargeted.animated_bone_names(&target));
+
+        let arm = &retargeted.tracks[1];
+        assert!(arm.sample_rotation(0.0).unwrap().abs_diff_eq(
+            target_arm_rotation * Quat::from_rotation_x(90.0f32.to_radians()),
+            0.0001
+        ));
+        assert!(arm
+            .sample_translation(0.0)
+            .unwrap()
+            .abs_diff_eq(Vec3::new(0.0, 3.0, 0.0), 0.0001));
+    }
 }
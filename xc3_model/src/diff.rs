@@ -0,0 +1,240 @@
+//! Structural comparisons between [ModelRoot] for testing conversions.
+use crate::ModelRoot;
+
+/// A single difference between two [ModelRoot] found by [ModelRoot::diff].
+///
+/// Counts and values from the first root are listed before the second root.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum Difference {
+    /// The number of models in [Models](crate::Models) differs.
+    ModelCount(usize, usize),
+    /// The number of meshes in a [Model](crate::Model) differs.
+    MeshCount {
+        model_index: usize,
+        counts: (usize, usize),
+    },
+    /// The buffer, material, LOD, or flag values for a [Mesh](crate::Mesh) differ.
+    Mesh {
+        model_index: usize,
+        mesh_index: usize,
+    },
+    VertexBufferCount(usize, usize),
+    /// The number or type of attributes for a [VertexBuffer](crate::vertex::VertexBuffer) differ.
+    VertexAttributes {
+        buffer_index: usize,
+    },
+    /// The first vertex with different values for an [AttributeData](crate::vertex::AttributeData).
+    VertexAttribute {
+        buffer_index: usize,
+        attribute_index: usize,
+        vertex_index: usize,
+    },
+    IndexBufferCount(usize, usize),
+    /// The indices for an [IndexBuffer](crate::vertex::IndexBuffer) differ.
+    IndexBuffer {
+        buffer_index: usize,
+    },
+    MaterialCount(usize, usize),
+    /// The values for a [Material](crate::Material) differ.
+    Material {
+        material_index: usize,
+    },
+    TextureCount(usize, usize),
+    /// The dimensions, format, usage, or image data for an [ImageTexture](crate::ImageTexture) differ.
+    Texture {
+        texture_index: usize,
+    },
+}
+
+impl ModelRoot {
+    /// Find the differences in meshes, vertex and index buffers, materials, and textures with `other`.
+    /// Float vertex attributes are considered equal if their difference is at most `epsilon`.
+    ///
+    /// Items are compared by index, so only the common items are compared if counts differ.
+    /// This is intended for testing that converting a model preserves its data.
+    ///
+    /// # Examples
+    /// ``` rust no_run
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let root = xc3_model::load_model("ch01011013.wimdo", None)?;
+    /// let new_root = xc3_model::load_model("ch01011013_edited.wimdo", None)?;
+    ///
+    /// for difference in root.diff(&new_root, 0.0001) {
+    ///     println!("{difference:?}");
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn diff(&self, other: &ModelRoot, epsilon: f32) -> Vec<Difference> {
+        let mut differences = Vec::new();
+
+        let models = &self.models.models;
+        let other_models = &other.models.models;
+        if models.len() != other_models.len() {
+            differences.push(Difference::ModelCount(models.len(), other_models.len()));
+        }
+        for (model_index, (model, other_model)) in models.iter().zip(other_models).enumerate() {
+            if model.meshes.len() != other_model.meshes.len() {
+                differences.push(Difference::MeshCount {
+                    model_index,
+                    counts: (model.meshes.len(), other_model.meshes.len()),
+                });
+            }
+            for (mesh_index, (mesh, other_mesh)) in
+                model.meshes.iter().zip(&other_model.meshes).enumerate()
+            {
+                if mesh != other_mesh {
+                    differences.push(Difference::Mesh {
+                        model_index,
+                        mesh_index,
+                    });
+                }
+            }
+        }
+
+        let vertex_buffers = &self.buffers.vertex_buffers;
+        let other_vertex_buffers = &other.buffers.vertex_buffers;
+        if vertex_buffers.len() != other_vertex_buffers.len() {
+            differences.push(Difference::VertexBufferCount(
+                vertex_buffers.len(),
+                other_vertex_buffers.len(),
+            ));
+        }
+        for (buffer_index, (buffer, other_buffer)) in
+            vertex_buffers.iter().zip(other_vertex_buffers).enumerate()
+        {
+            let same_attributes = buffer.attributes.len() == other_buffer.attributes.len()
+                && buffer
+                    .attributes
+                    .iter()
+                    .zip(&other_buffer.attributes)
+                    .all(|(a, b)| std::mem::discriminant(a) == std::mem::discriminant(b));
+            if !same_attributes {
+                differences.push(Difference::VertexAttributes { buffer_index });
+                continue;
+            }
+
+            for (attribute_index, (a, b)) in buffer
+                .attributes
+                .iter()
+                .zip(&other_buffer.attributes)
+                .enumerate()
+            {
+                if let Some(vertex_index) = a.first_difference(b, epsilon) {
+                    differences.push(Difference::VertexAttribute {
+                        buffer_index,
+                        attribute_index,
+                        vertex_index,
+                    });
+                }
+            }
+        }
+
+        let index_buffers = &self.buffers.index_buffers;
+        let other_index_buffers = &other.buffers.index_buffers;
+        if index_buffers.len() != other_index_buffers.len() {
+            differences.push(Difference::IndexBufferCount(
+                index_buffers.len(),
+                other_index_buffers.len(),
+            ));
+        }
+        for (buffer_index, (buffer, other_buffer)) in
+            index_buffers.iter().zip(other_index_buffers).enumerate()
+        {
+            if buffer != other_buffer {
+                differences.push(Difference::IndexBuffer { buffer_index });
+            }
+        }
+
+        let materials = &self.models.materials;
+        let other_materials = &other.models.materials;
+        if materials.len() != other_materials.len() {
+            differences.push(Difference::MaterialCount(
+                materials.len(),
+                other_materials.len(),
+            ));
+        }
+        for (material_index, (material, other_material)) in
+            materials.iter().zip(other_materials).enumerate()
+        {
+            if material != other_material {
+                differences.push(Difference::Material { material_index });
+            }
+        }
+
+        let textures = &self.image_textures;
+        let other_textures = &other.image_textures;
+        if textures.len() != other_textures.len() {
+            differences.push(Difference::TextureCount(
+                textures.len(),
+                other_textures.len(),
+            ));
+        }
+        for (texture_index, (texture, other_texture)) in
+            textures.iter().zip(other_textures).enumerate()
+        {
+            if texture != other_texture {
+                differences.push(Difference::Texture { texture_index });
+            }
+        }
+
+        differences
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use glam::{vec3, Vec3};
+
+    use crate::{
+        test_support::{self, mesh, model, model_buffers, model_root, models},
+        vertex::IndexBuffer,
+    };
+
+    fn root(positions: Vec<Vec3>) -> ModelRoot {
+        model_root(
+            models(vec![model(vec![mesh(0, 0, 1)])]),
+            model_buffers(
+                vec![test_support::positions(positions)],
+                vec![IndexBuffer {
+                    indices: vec![0, 1, 2],
+                }],
+            ),
+        )
+    }
+
+    #[test]
+    fn diff_changed_vertex_position() {
+        let a = root(vec![
+            vec3(0.0, 0.0, 0.0),
+            vec3(1.0, 0.0, 0.0),
+            vec3(0.0, 1.0, 0.0),
+        ]);
+        let b = root(vec![
+            vec3(0.0, 0.0, 0.0),
+            vec3(1.0, 0.00001, 0.0),
+            vec3(0.0, 1.0, 0.5),
+        ]);
+
+        assert!(a.diff(&a, 0.0).is_empty());
+        assert_eq!(
+            vec![Difference::VertexAttribute {
+                buffer_index: 0,
+                attribute_index: 0,
+                vertex_index: 2
+            }],
+            a.diff(&b, 0.0001)
+        );
+        assert_eq!(
+            vec![Difference::VertexAttribute {
+                buffer_index: 0,
+                attribute_index: 0,
+                vertex_index: 1
+            }],
+            a.diff(&b, 0.0)
+        );
+    }
+}
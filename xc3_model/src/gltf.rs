@@ -10,12 +10,12 @@
 //!
 //! // Models have only one root.
 //! let root = xc3_model::load_model("xeno3/chr/ch/ch01027000.wimdo", Some(&database))?;
-//! let gltf = GltfFile::new("mio_military", &[root])?;
+//! let gltf = GltfFile::from_model("mio_military", &[root])?;
 //! gltf.save("mio_military.gltf")?;
 //!
 //! // Maps have multiple roots.
 //! let roots = xc3_model::load_map("xeno3/map/ma59a.wismhd", Some(&database))?;
-//! let gltf = GltfFile::new("map", &roots)?;
+//! let gltf = GltfFile::from_map("map", &roots)?;
 //! gltf.save("map.gltf")?;
 //! # Ok(())
 //! # }
@@ -25,18 +25,19 @@ use std::{collections::BTreeMap, path::Path};
 use crate::{should_render_lod, MapRoot, ModelRoot};
 use glam::Mat4;
 use gltf::json::validation::Checked::Valid;
+use log::error;
 use rayon::prelude::*;
 use thiserror::Error;
 
 use self::{
     buffer::{BufferKey, Buffers, WeightGroupKey},
-    material::{create_map_materials, create_materials, MaterialKey},
-    texture::{image_name, TextureCache},
+    material::{create_map_materials, create_materials, extensions_used, MaterialKey},
+    texture::TextureCache,
 };
 
 mod buffer;
 mod material;
-mod texture;
+pub(crate) mod texture;
 
 // TODO: Add more error variants.
 #[derive(Debug, Error)]
@@ -52,6 +53,51 @@ pub enum SaveGltfError {
 
     #[error("error serializing JSON file")]
     Json(#[from] serde_json::Error),
+
+    #[error("error writing GLB file")]
+    Glb(#[from] gltf::Error),
+}
+
+/// Settings for converting models and maps to glTF.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct GltfOptions {
+    /// Apply the [texture_matrix](crate::Material::texture_matrix) of each material
+    /// to its textures using the `KHR_texture_transform` extension.
+    /// Only the scale and offset are applied.
+    pub texture_matrices: bool,
+    /// Skip triangles with repeated indices that have zero area.
+    /// See [IndexBuffer::triangles](crate::vertex::IndexBuffer::triangles).
+    pub remove_degenerate_triangles: bool,
+    /// Flip triangles that face away from their vertex normals.
+    /// See [IndexBuffer::fix_winding](crate::vertex::IndexBuffer::fix_winding).
+    pub fix_winding: bool,
+    /// The up axis for the exported scene.
+    /// Defaults to [UpAxis::Y].
+    pub up_axis: UpAxis,
+}
+
+/// The up axis convention for the exported glTF scene.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum UpAxis {
+    /// Y-up as required by the glTF specification.
+    /// Xenoblade models and maps also use Y-up, so the scene is not transformed.
+    /// Most applications like Blender already convert from Y-up when importing glTF files.
+    #[default]
+    Y,
+    /// Z-up by adding a single root node that rotates the scene 90 degrees around the X axis.
+    /// Skinned meshes are also rotated since the skeleton root joints are children of this node.
+    /// Only use this for applications that do not convert glTF to Z-up on import.
+    Z,
+}
+
+impl UpAxis {
+    /// The transform applied to the root node of the exported scene.
+    pub fn transform(&self) -> Mat4 {
+        match self {
+            UpAxis::Y => Mat4::IDENTITY,
+            UpAxis::Z => Mat4::from_rotation_x(std::f32::consts::FRAC_PI_2),
+        }
+    }
 }
 
 /// glTF JSON, binary, and image data for a model or map.
@@ -73,13 +119,27 @@ impl GltfFile {
     /// Convert the Xenoblade model `roots` to glTF data.
     /// See [load_model](crate::load_model) or [load_map](crate::load_map) for loading files.
     ///
+    /// All roots are added to a single scene with separate top level nodes for each root.
+    /// Each root has its own meshes, materials, and skin,
+    /// but all vertex data is stored in the same buffer.
+    ///
     /// The `model_name` is used to create resource file names and should
     /// usually match the file name for [save](GltfFile::save) without the `.gltf` extension.
     pub fn from_model(model_name: &str, roots: &[ModelRoot]) -> Result<Self, CreateGltfError> {
+        Self::from_model_with_options(model_name, roots, &GltfOptions::default())
+    }
+
+    /// Convert the Xenoblade model `roots` to glTF data like [from_model](GltfFile::from_model)
+    /// using the settings in `options`.
+    pub fn from_model_with_options(
+        model_name: &str,
+        roots: &[ModelRoot],
+        options: &GltfOptions,
+    ) -> Result<Self, CreateGltfError> {
         let mut texture_cache = TextureCache::new(roots.iter().map(|r| &r.image_textures));
 
         let (materials, material_indices, textures, samplers) =
-            create_materials(roots, &mut texture_cache);
+            create_materials(roots, &mut texture_cache, options);
 
         let mut buffers = Buffers::default();
 
@@ -114,18 +174,21 @@ impl GltfFile {
                 0,
                 skin_index,
                 root.skeleton.as_ref(),
+                options,
             )?;
         }
 
+        let scene_nodes = add_up_axis_node(&mut nodes, scene_nodes, options.up_axis);
+
         // The textures assume the images are in ascending order by index.
         // The texture cache already preserves insertion order.
         let mut images = Vec::new();
-        for key in texture_cache.generated_texture_indices.keys() {
+        for name in texture_cache.image_names(model_name) {
             images.push(gltf::json::Image {
                 buffer_view: None,
                 mime_type: None,
                 name: None,
-                uri: Some(image_name(key, model_name)),
+                uri: Some(name),
                 extensions: None,
                 extras: Default::default(),
             });
@@ -153,6 +216,7 @@ impl GltfFile {
                 name: None,
                 nodes: scene_nodes,
             }],
+            extensions_used: extensions_used(&materials),
             materials,
             textures,
             images,
@@ -174,13 +238,26 @@ impl GltfFile {
     /// Convert the Xenoblade map `roots` to glTF data.
     /// See [load_map](crate::load_map) for loading files.
     ///
+    /// All roots are added to a single scene like [from_model](GltfFile::from_model)
+    /// with a top level node for each [ModelGroup](crate::ModelGroup) [Models](crate::Models).
+    ///
     /// The `model_name` is used to create resource file names and should
     /// usually match the file name for [save](GltfFile::save) without the `.gltf` extension.
     pub fn from_map(model_name: &str, roots: &[MapRoot]) -> Result<Self, CreateGltfError> {
+        Self::from_map_with_options(model_name, roots, &GltfOptions::default())
+    }
+
+    /// Convert the Xenoblade map `roots` to glTF data like [from_map](GltfFile::from_map)
+    /// using the settings in `options`.
+    pub fn from_map_with_options(
+        model_name: &str,
+        roots: &[MapRoot],
+        options: &GltfOptions,
+    ) -> Result<Self, CreateGltfError> {
         let mut texture_cache = TextureCache::new(roots.iter().map(|r| &r.image_textures));
 
         let (materials, material_indices, textures, samplers) =
-            create_map_materials(roots, &mut texture_cache);
+            create_map_materials(roots, &mut texture_cache, options);
 
         let mut buffers = Buffers::default();
 
@@ -204,20 +281,23 @@ impl GltfFile {
                         models_index,
                         None,
                         None,
+                        options,
                     )?;
                 }
             }
         }
 
+        let scene_nodes = add_up_axis_node(&mut nodes, scene_nodes, options.up_axis);
+
         // The textures assume the images are in ascending order by index.
         // The texture cache already preserves insertion order.
         let mut images = Vec::new();
-        for key in texture_cache.generated_texture_indices.keys() {
+        for name in texture_cache.image_names(model_name) {
             images.push(gltf::json::Image {
                 buffer_view: None,
                 mime_type: None,
                 name: None,
-                uri: Some(image_name(key, model_name)),
+                uri: Some(name),
                 extensions: None,
                 extras: Default::default(),
             });
@@ -245,6 +325,7 @@ impl GltfFile {
                 name: None,
                 nodes: scene_nodes,
             }],
+            extensions_used: extensions_used(&materials),
             materials,
             textures,
             images,
@@ -270,7 +351,7 @@ impl GltfFile {
     /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
     /// # use xc3_model::gltf::GltfFile;
     /// # let roots = Vec::new();
-    /// let gltf_file = GltfFile::new("model", &roots)?;
+    /// let gltf_file = GltfFile::from_model("model", &roots)?;
     /// gltf_file.save("model.gltf")?;
     /// # Ok(())
     /// # }
@@ -290,6 +371,79 @@ impl GltfFile {
         })?;
         Ok(())
     }
+
+    /// Save the glTF data to the specified `path` as a single binary `.glb` file.
+    /// See [GltfFile::to_glb] for details.
+    ///
+    /// # Examples
+    ///
+    /// ```rust no_run
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// # use xc3_model::gltf::GltfFile;
+    /// # let roots = Vec::new();
+    /// let gltf_file = GltfFile::from_model("model", &roots)?;
+    /// gltf_file.save_glb("model.glb")?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn save_glb<P: AsRef<Path>>(&self, path: P) -> Result<(), SaveGltfError> {
+        let bytes = self.to_glb()?;
+        std::fs::write(path, bytes)?;
+        Ok(())
+    }
+
+    /// Create the data for a binary `.glb` file.
+    ///
+    /// The vertex data and PNG images are embedded in the binary chunk
+    /// with a buffer view for each image instead of using separate files.
+    pub fn to_glb(&self) -> Result<Vec<u8>, SaveGltfError> {
+        let mut root = self.root.clone();
+
+        let mut bin = self.buffer.clone();
+
+        // The images are in the same order as the image JSON objects.
+        for (image, (_, png)) in root.images.iter_mut().zip(&self.png_images) {
+            bin.resize(bin.len().next_multiple_of(4), 0u8);
+
+            let view = gltf::json::buffer::View {
+                buffer: gltf::json::Index::new(0),
+                byte_length: png.len() as u32,
+                byte_offset: Some(bin.len() as u32),
+                byte_stride: None,
+                extensions: Default::default(),
+                extras: Default::default(),
+                name: None,
+                target: None,
+            };
+            bin.extend_from_slice(png);
+
+            image.buffer_view = Some(gltf::json::Index::new(root.buffer_views.len() as u32));
+            image.mime_type = Some(gltf::json::image::MimeType("image/png".to_string()));
+            image.uri = None;
+            root.buffer_views.push(view);
+        }
+        bin.resize(bin.len().next_multiple_of(4), 0u8);
+
+        if let Some(buffer) = root.buffers.first_mut() {
+            buffer.byte_length = bin.len() as u32;
+            buffer.uri = None;
+        }
+
+        let mut json = gltf::json::serialize::to_vec(&root)?;
+        // Chunks must be aligned to 4 bytes with JSON padded using spaces.
+        json.resize(json.len().next_multiple_of(4), b' ');
+
+        let glb = gltf::binary::Glb {
+            header: gltf::binary::Header {
+                magic: *b"glTF",
+                version: 2,
+                length: (12 + 8 + json.len() + 8 + bin.len()) as u32,
+            },
+            json: json.into(),
+            bin: Some(bin.into()),
+        };
+        Ok(glb.to_vec()?)
+    }
 }
 
 fn add_models(
@@ -305,14 +459,24 @@ fn add_models(
     models_index: usize,
     skin_index: Option<usize>,
     skeleton: Option<&crate::skeleton::Skeleton>,
+    options: &GltfOptions,
 ) -> Result<(), CreateGltfError> {
     let mut group_children = Vec::new();
-    for model in &models.models {
+    for (model_index, model) in models.models.iter().enumerate() {
         let mut children = Vec::new();
 
         let model_buffers = &group_buffers[model.model_buffers_index];
 
-        for mesh in &model.meshes {
+        for (mesh_index, mesh) in model.meshes.iter().enumerate() {
+            // Skip invalid meshes instead of panicking.
+            let errors = mesh.index_errors(model_index, mesh_index, models, model_buffers);
+            if !errors.is_empty() {
+                for e in errors {
+                    error!("Skipping mesh for root {root_index}: {e}");
+                }
+                continue;
+            }
+
             // TODO: Make LOD selection configurable?
             // TODO: Add an option to export all material passes?
             let material = &models.materials[mesh.material_index];
@@ -367,10 +531,15 @@ fn add_models(
                 // Lazy load index buffers since not all are unused.
                 let index_accessor = buffers.insert_index_buffer(
                     &model_buffers.index_buffers[mesh.index_buffer_index],
-                    root_index,
-                    group_index,
-                    model.model_buffers_index,
-                    mesh.index_buffer_index,
+                    BufferKey {
+                        root_index,
+                        group_index,
+                        buffers_index: model.model_buffers_index,
+                        buffer_index: mesh.index_buffer_index,
+                    },
+                    &model_buffers.vertex_buffers[mesh.vertex_buffer_index],
+                    mesh.vertex_buffer_index,
+                    options,
                 )? as u32;
 
                 let material_index = material_indices
@@ -477,6 +646,36 @@ fn add_models(
     Ok(())
 }
 
+fn add_up_axis_node(
+    nodes: &mut Vec<gltf::json::Node>,
+    scene_nodes: Vec<gltf::json::Index<gltf::json::Node>>,
+    up_axis: UpAxis,
+) -> Vec<gltf::json::Index<gltf::json::Node>> {
+    match up_axis {
+        UpAxis::Y => scene_nodes,
+        UpAxis::Z => {
+            // Parent everything to a single node to transform the entire scene.
+            let root_node = gltf::json::Node {
+                camera: None,
+                children: Some(scene_nodes),
+                extensions: Default::default(),
+                extras: Default::default(),
+                matrix: Some(up_axis.transform().to_cols_array()),
+                mesh: None,
+                name: None,
+                rotation: None,
+                scale: None,
+                translation: None,
+                skin: None,
+                weights: None,
+            };
+            let root_node_index = nodes.len() as u32;
+            nodes.push(root_node);
+            vec![gltf::json::Index::new(root_node_index)]
+        }
+    }
+}
+
 fn morph_targets(
     vertex_buffer: &buffer::VertexBuffer,
 ) -> Option<Vec<gltf::json::mesh::MorphTarget>> {
@@ -507,7 +706,12 @@ fn create_skin(
     skeleton.as_ref().map(|skeleton| {
         let bone_start_index = nodes.len() as u32;
         for (i, bone) in skeleton.bones.iter().enumerate() {
-            let children = find_children(skeleton, i);
+            // Offset the indices since previous roots may have already added nodes.
+            let children: Vec<_> = skeleton
+                .children(i)
+                .into_iter()
+                .map(|c| gltf::json::Index::new(bone_start_index + c as u32))
+                .collect();
 
             let joint_node = gltf::json::Node {
                 camera: None,
@@ -540,12 +744,7 @@ fn create_skin(
             }
         }
 
-        // TODO: Add this to skeleton.rs?
-        let inverse_bind_matrices: Vec<_> = skeleton
-            .model_space_transforms()
-            .iter()
-            .map(|t| t.inverse())
-            .collect();
+        let inverse_bind_matrices = skeleton.inverse_bind_matrices();
 
         let accessor_index = buffers
             .add_values(
@@ -575,21 +774,224 @@ fn create_skin(
     })
 }
 
-fn find_children(
-    skeleton: &crate::skeleton::Skeleton,
-    bone_index: usize,
-) -> Vec<gltf::json::Index<gltf::json::Node>> {
-    // TODO: is is worth optimizing this lookup?
-    skeleton
-        .bones
-        .iter()
-        .enumerate()
-        .filter_map(|(child_index, b)| {
-            if b.parent_index == Some(bone_index) {
-                Some(gltf::json::Index::new(child_index as u32))
-            } else {
-                None
-            }
-        })
-        .collect()
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use glam::{vec3, Vec3};
+
+    use crate::{
+        test_support::{material, mesh, model, model_buffers, model_root, models, positions},
+        vertex::IndexBuffer,
+        BlendMode, Bone, CullMode, MeshIndexError, Models, Skeleton,
+    };
+
+    fn root(material_name: &str, skeleton: Option<Skeleton>) -> ModelRoot {
+        ModelRoot {
+            skeleton,
+            ..model_root(
+                Models {
+                    materials: vec![material(material_name)],
+                    ..models(vec![model(vec![mesh(0, 0, 0)])])
+                },
+                model_buffers(
+                    vec![positions(vec![
+                        vec3(0.0, 0.0, 0.0),
+                        vec3(1.0, 0.0, 0.0),
+                        vec3(0.0, 1.0, 0.0),
+                    ])],
+                    vec![IndexBuffer {
+                        indices: vec![0, 1, 2],
+                    }],
+                ),
+            )
+        }
+    }
+
+    #[test]
+    fn from_model_two_roots() {
+        let file = GltfFile::from_model("model", &[root("a", None), root("b", None)]).unwrap();
+
+        assert_eq!(1, file.root.scenes.len());
+        assert_eq!(2, file.root.scenes[0].nodes.len());
+        assert_eq!(2, file.root.meshes.len());
+        assert_eq!(
+            vec![Some("a".to_string()), Some("b".to_string())],
+            file.root
+                .materials
+                .iter()
+                .map(|m| m.name.clone())
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(1, file.root.buffers.len());
+        assert_eq!(file.buffer.len() as u32, file.root.buffers[0].byte_length);
+
+        // Each root has its own position and index accessors.
+        let accessors: Vec<_> = file
+            .root
+            .meshes
+            .iter()
+            .map(|m| {
+                let p = &m.primitives[0];
+                (
+                    p.attributes[&Valid(gltf::Semantic::Positions)].value(),
+                    p.indices.unwrap().value(),
+                )
+            })
+            .collect();
+        assert_ne!(accessors[0], accessors[1]);
+    }
+
+    #[test]
+    fn from_model_additive_double_sided_json() {
+        let mut root = root("a", None);
+        root.models.materials[0].flags.blend_mode = BlendMode::Additive;
+        root.models.materials[0].flags.cull_mode = CullMode::Disabled;
+
+        let file = GltfFile::from_model("model", &[root]).unwrap();
+
+        let json = serde_json::to_value(&file.root).unwrap();
+        assert_eq!("BLEND", json["materials"][0]["alphaMode"]);
+        assert_eq!(Some(true), json["materials"][0]["doubleSided"].as_bool());
+    }
+
+    #[test]
+    fn to_glb_embedded_images() {
+        let mut file = GltfFile::from_model("model", &[root("a", None)]).unwrap();
+
+        // Add images directly to avoid needing texture assignments.
+        for name in ["a.png", "b.png"] {
+            file.root.images.push(gltf::json::Image {
+                buffer_view: None,
+                mime_type: None,
+                name: None,
+                uri: Some(name.to_string()),
+                extensions: None,
+                extras: Default::default(),
+            });
+        }
+        file.png_images = vec![
+            ("a.png".to_string(), vec![1, 2, 3]),
+            ("b.png".to_string(), vec![4, 5, 6, 7, 8]),
+        ];
+
+        let bytes = file.to_glb().unwrap();
+        let glb = gltf::Gltf::from_slice(&bytes).unwrap();
+        let blob = glb.blob.as_ref().unwrap();
+
+        let buffer = glb.buffers().next().unwrap();
+        assert!(matches!(buffer.source(), gltf::buffer::Source::Bin));
+        assert_eq!(blob.len(), buffer.length());
+
+        let images: Vec<_> = glb
+            .images()
+            .map(|image| match image.source() {
+                gltf::image::Source::View { view, mime_type } => {
+                    assert_eq!("image/png", mime_type);
+                    blob[view.offset()..view.offset() + view.length()].to_vec()
+                }
+                gltf::image::Source::Uri { .. } => panic!("expected embedded image"),
+            })
+            .collect();
+        assert_eq!(vec![vec![1, 2, 3], vec![4, 5, 6, 7, 8]], images);
+    }
+
+    #[test]
+    fn from_model_invalid_mesh_indices() {
+        let mut invalid = root("a", None);
+        invalid.models.models[0].meshes[0].material_index = 1;
+        invalid.models.models[0].meshes[0].index_buffer_index = 2;
+
+        assert_eq!(
+            vec![
+                MeshIndexError::Material {
+                    model_index: 0,
+                    mesh_index: 0,
+                    index: 1
+                },
+                MeshIndexError::IndexBuffer {
+                    model_index: 0,
+                    mesh_index: 0,
+                    index: 2
+                }
+            ],
+            invalid.models.validate_indices(&invalid.buffers)
+        );
+
+        let file = GltfFile::from_model("model", &[invalid, root("b", None)]).unwrap();
+        assert_eq!(1, file.root.meshes.len());
+    }
+
+    #[test]
+    fn from_model_two_roots_skeleton_children() {
+        let skeleton = || Skeleton {
+            bones: vec![
+                Bone {
+                    name: "root".to_string(),
+                    transform: Mat4::IDENTITY,
+                    parent_index: None,
+                },
+                Bone {
+                    name: "child".to_string(),
+                    transform: Mat4::IDENTITY,
+                    parent_index: Some(0),
+                },
+            ],
+            procedural_bones: Vec::new(),
+        };
+        let file = GltfFile::from_model(
+            "model",
+            &[root("a", Some(skeleton())), root("b", Some(skeleton()))],
+        )
+        .unwrap();
+
+        assert_eq!(2, file.root.skins.len());
+        for skin in &file.root.skins {
+            let root_node = &file.root.nodes[skin.joints[0].value()];
+            let children = root_node.children.as_ref().unwrap();
+            assert_eq!(vec![skin.joints[1]], *children);
+            assert_eq!(
+                Some("child".to_string()),
+                file.root.nodes[children[0].value()].name
+            );
+        }
+    }
+
+    #[test]
+    fn from_model_up_axis_z() {
+        let options = GltfOptions {
+            up_axis: UpAxis::Z,
+            ..Default::default()
+        };
+        let file = GltfFile::from_model_with_options(
+            "model",
+            &[root("a", None), root("b", None)],
+            &options,
+        )
+        .unwrap();
+
+        // A single root node should contain the nodes for both roots.
+        assert_eq!(1, file.root.scenes[0].nodes.len());
+        let node = &file.root.nodes[file.root.scenes[0].nodes[0].value()];
+        assert_eq!(2, node.children.as_ref().unwrap().len());
+
+        // Y-up should be converted to Z-up.
+        let transform = Mat4::from_cols_array(&node.matrix.unwrap());
+        assert!(transform
+            .transform_vector3(Vec3::Y)
+            .abs_diff_eq(Vec3::Z, 0.0001));
+        assert!(transform
+            .transform_vector3(Vec3::Z)
+            .abs_diff_eq(-Vec3::Y, 0.0001));
+    }
+
+    #[test]
+    fn from_model_up_axis_y() {
+        let file = GltfFile::from_model("model", &[root("a", None)]).unwrap();
+
+        // The default should not add any additional nodes.
+        assert_eq!(1, file.root.scenes[0].nodes.len());
+        let node = &file.root.nodes[file.root.scenes[0].nodes[0].value()];
+        assert_eq!(None, node.matrix);
+    }
 }
@@ -1,4 +1,5 @@
 use std::{
+    borrow::Cow,
     collections::BTreeMap,
     io::{Cursor, Seek, Write},
 };
@@ -11,6 +12,8 @@ use gltf::{
     json::validation::Checked::{self, Valid},
 };
 
+use super::GltfOptions;
+
 type GltfAttributes = BTreeMap<
     gltf::json::validation::Checked<gltf::Semantic>,
     gltf::json::Index<gltf::json::Accessor>,
@@ -39,6 +42,14 @@ pub struct WeightGroupKey {
     pub buffer: BufferKey,
 }
 
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct IndexBufferKey {
+    pub buffer: BufferKey,
+    /// The vertex buffer used to fix the winding order if enabled.
+    /// Fixed index buffers can't be shared between different vertex buffers.
+    pub vertex_buffer_index: Option<usize>,
+}
+
 // Combined vertex data for a gltf buffer.
 #[derive(Default)]
 pub struct Buffers {
@@ -47,7 +58,7 @@ pub struct Buffers {
     pub accessors: Vec<gltf::json::Accessor>,
 
     pub vertex_buffers: BTreeMap<BufferKey, VertexBuffer>,
-    pub index_buffer_accessors: BTreeMap<BufferKey, usize>,
+    pub index_buffer_accessors: BTreeMap<IndexBufferKey, usize>,
     pub weight_groups: BTreeMap<WeightGroupKey, WeightGroup>,
 }
 
@@ -267,8 +278,13 @@ impl Buffers {
                 }
                 // Skin weights are handled separately.
                 AttributeData::WeightIndex(_) => (),
+                AttributeData::WeightIndex2(_) => (),
                 AttributeData::SkinWeights(_) => (),
                 AttributeData::BoneIndices(_) => (),
+                // Outline buffers aren't exported.
+                AttributeData::OutlineUnk(_) => (),
+                // The format of unknown attributes isn't known.
+                AttributeData::Unknown { .. } => (),
             }
         }
         Ok(attributes)
@@ -277,19 +293,31 @@ impl Buffers {
     pub fn insert_index_buffer(
         &mut self,
         index_buffer: &crate::vertex::IndexBuffer,
-        root_index: usize,
-        group_index: usize,
-        buffers_index: usize,
-        buffer_index: usize,
+        buffer: BufferKey,
+        vertex_buffer: &crate::vertex::VertexBuffer,
+        vertex_buffer_index: usize,
+        options: &GltfOptions,
     ) -> BinResult<usize> {
-        let key = BufferKey {
-            root_index,
-            group_index,
-            buffers_index,
-            buffer_index,
+        let key = IndexBufferKey {
+            buffer,
+            vertex_buffer_index: options.fix_winding.then_some(vertex_buffer_index),
         };
         if !self.index_buffer_accessors.contains_key(&key) {
-            let index_bytes = write_bytes(&index_buffer.indices)?;
+            let mut index_buffer = Cow::Borrowed(index_buffer);
+            if options.fix_winding {
+                if let (Some(positions), Some(normals)) =
+                    (vertex_buffer.positions(), vertex_buffer.normals())
+                {
+                    index_buffer.to_mut().fix_winding(positions, normals);
+                }
+            }
+
+            let indices: Cow<[u16]> = if options.remove_degenerate_triangles {
+                Cow::Owned(index_buffer.triangles().flatten().collect())
+            } else {
+                Cow::Borrowed(&index_buffer.indices)
+            };
+            let index_bytes = write_bytes(&indices)?;
 
             // The offset must be a multiple of the component data type.
             let aligned = self
@@ -313,7 +341,7 @@ impl Buffers {
             let indices = gltf::json::Accessor {
                 buffer_view: Some(gltf::json::Index::new(self.buffer_views.len() as u32)),
                 byte_offset: Some(0),
-                count: index_buffer.indices.len() as u32,
+                count: indices.len() as u32,
                 component_type: Valid(gltf::json::accessor::GenericComponentType(
                     gltf::json::accessor::ComponentType::U16,
                 )),
@@ -326,15 +354,8 @@ impl Buffers {
                 normalized: false,
                 sparse: None,
             };
-            self.index_buffer_accessors.insert(
-                BufferKey {
-                    root_index,
-                    group_index,
-                    buffers_index,
-                    buffer_index,
-                },
-                self.accessors.len(),
-            );
+            self.index_buffer_accessors
+                .insert(key, self.accessors.len());
 
             self.accessors.push(indices);
             self.buffer_views.push(view);
@@ -564,3 +585,111 @@ fn write_bytes<T: WriteBytes>(values: &[T]) -> BinResult<Vec<u8>> {
     }
     Ok(writer.into_inner())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use glam::vec4;
+
+    #[test]
+    fn write_attributes_blend() {
+        let mut buffers = Buffers::default();
+        let attributes = buffers
+            .write_attributes(&[AttributeData::Blend(vec![vec4(0.25, 0.5, 0.75, 1.0)])])
+            .unwrap();
+
+        let accessor_index = attributes[&Valid(gltf::Semantic::Extras("Blend".to_string()))];
+        let accessor = &buffers.accessors[accessor_index.value()];
+        assert_eq!(1, accessor.count);
+
+        let view = &buffers.buffer_views[accessor.buffer_view.unwrap().value()];
+        let start = view.byte_offset.unwrap() as usize;
+        let end = start + view.byte_length as usize;
+        let expected: Vec<_> = [0.25f32, 0.5, 0.75, 1.0]
+            .iter()
+            .flat_map(|f| f.to_le_bytes())
+            .collect();
+        assert_eq!(expected, buffers.buffer_bytes[start..end]);
+    }
+
+    fn index_bytes(buffers: &Buffers, accessor_index: usize) -> &[u8] {
+        let accessor = &buffers.accessors[accessor_index];
+        let view = &buffers.buffer_views[accessor.buffer_view.unwrap().value()];
+        let start = view.byte_offset.unwrap() as usize;
+        &buffers.buffer_bytes[start..start + view.byte_length as usize]
+    }
+
+    const KEY: BufferKey = BufferKey {
+        root_index: 0,
+        group_index: 0,
+        buffers_index: 0,
+        buffer_index: 0,
+    };
+
+    #[test]
+    fn insert_index_buffer_fix_winding_per_vertex_buffer() {
+        let vertex_buffer = |normal_z: f32| crate::vertex::VertexBuffer {
+            attributes: vec![
+                AttributeData::Position(vec![
+                    Vec3::new(0.0, 0.0, 0.0),
+                    Vec3::new(1.0, 0.0, 0.0),
+                    Vec3::new(0.0, 1.0, 0.0),
+                ]),
+                AttributeData::Normal(vec![Vec4::new(0.0, 0.0, normal_z, 0.0); 3]),
+            ],
+            morph_targets: Vec::new(),
+            morph_default_target: None,
+            outline_buffer_index: None,
+        };
+        let index_buffer = crate::vertex::IndexBuffer {
+            indices: vec![0, 1, 2],
+        };
+        let options = GltfOptions {
+            fix_winding: true,
+            ..Default::default()
+        };
+
+        // The same index buffer needs different winding for each vertex buffer.
+        let mut buffers = Buffers::default();
+        let front = buffers
+            .insert_index_buffer(&index_buffer, KEY, &vertex_buffer(1.0), 0, &options)
+            .unwrap();
+        let back = buffers
+            .insert_index_buffer(&index_buffer, KEY, &vertex_buffer(-1.0), 1, &options)
+            .unwrap();
+        assert_ne!(front, back);
+        assert_eq!(
+            write_bytes(&[0u16, 1, 2]).unwrap(),
+            index_bytes(&buffers, front)
+        );
+        assert_eq!(
+            write_bytes(&[0u16, 2, 1]).unwrap(),
+            index_bytes(&buffers, back)
+        );
+    }
+
+    #[test]
+    fn insert_index_buffer_shared_without_fix_winding() {
+        let vertex_buffer = crate::vertex::VertexBuffer {
+            attributes: Vec::new(),
+            morph_targets: Vec::new(),
+            morph_default_target: None,
+            outline_buffer_index: None,
+        };
+        let index_buffer = crate::vertex::IndexBuffer {
+            indices: vec![0, 1, 2],
+        };
+        let options = GltfOptions::default();
+
+        let mut buffers = Buffers::default();
+        let first = buffers
+            .insert_index_buffer(&index_buffer, KEY, &vertex_buffer, 0, &options)
+            .unwrap();
+        let second = buffers
+            .insert_index_buffer(&index_buffer, KEY, &vertex_buffer, 1, &options)
+            .unwrap();
+        assert_eq!(first, second);
+        assert_eq!(1, buffers.accessors.len());
+    }
+}
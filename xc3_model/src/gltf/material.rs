@@ -4,9 +4,14 @@ use crate::gltf::texture::{
     albedo_generated_key, metallic_roughness_generated_key, normal_generated_key, TextureCache,
 };
 use crate::{AddressMode, ImageTexture, MapRoot, ModelRoot, Sampler};
+use glam::Mat4;
 use gltf::json::validation::Checked::Valid;
+use xc3_lib::mxmd::{BlendMode, CullMode};
 
-use super::texture::{GeneratedImageKey, ImageIndex};
+use super::{
+    texture::{GeneratedImageKey, ImageIndex},
+    GltfOptions,
+};
 
 #[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 pub struct MaterialKey {
@@ -19,6 +24,7 @@ pub struct MaterialKey {
 pub fn create_materials(
     roots: &[ModelRoot],
     texture_cache: &mut TextureCache,
+    options: &GltfOptions,
 ) -> (
     Vec<gltf::json::Material>,
     BTreeMap<MaterialKey, usize>,
@@ -42,6 +48,7 @@ pub fn create_materials(
             root_index,
             0,
             0,
+            options,
         );
     }
 
@@ -56,6 +63,7 @@ pub fn create_materials(
 pub fn create_map_materials(
     roots: &[MapRoot],
     texture_cache: &mut TextureCache,
+    options: &GltfOptions,
 ) -> (
     Vec<gltf::json::Material>,
     BTreeMap<MaterialKey, usize>,
@@ -81,6 +89,7 @@ pub fn create_map_materials(
                     root_index,
                     group_index,
                     models_index,
+                    options,
                 );
             }
         }
@@ -105,6 +114,7 @@ fn add_models(
     root_index: usize,
     group_index: usize,
     models_index: usize,
+    options: &GltfOptions,
 ) {
     // Each Models has its own separately indexed samplers.
     let sampler_base_index = samplers.len();
@@ -118,6 +128,7 @@ fn add_models(
             root_index,
             sampler_base_index,
             image_textures,
+            options,
         );
         let material_flattened_index = materials.len();
         materials.push(material);
@@ -165,9 +176,15 @@ fn create_material(
     root_index: usize,
     sampler_base_index: usize,
     image_textures: &[ImageTexture],
+    options: &GltfOptions,
 ) -> gltf::json::Material {
     let assignments = material.output_assignments(image_textures);
 
+    // The texture matrix applies to all textures, so it replaces any per texture scale.
+    let texture_matrix = material
+        .texture_matrix()
+        .filter(|_| options.texture_matrices);
+
     let albedo_key = albedo_generated_key(material, &assignments, root_index);
     let albedo_index = texture_cache.insert(albedo_key);
 
@@ -191,7 +208,7 @@ fn create_material(
                 gltf::json::texture::Info {
                     index: gltf::json::Index::new(texture_index),
                     tex_coord: 0,
-                    extensions: texture_transform_ext(scale),
+                    extensions: texture_transform_ext(texture_matrix, scale),
                     extras: Default::default(),
                 }
             }),
@@ -207,7 +224,7 @@ fn create_material(
                 gltf::json::texture::Info {
                     index: gltf::json::Index::new(texture_index),
                     tex_coord: 0,
-                    extensions: texture_transform_ext(scale),
+                    extensions: texture_transform_ext(texture_matrix, scale),
                     extras: Default::default(),
                 }
             }),
@@ -241,28 +258,69 @@ fn create_material(
             }
         }),
         emissive_texture: None, // TODO: emission?
-        alpha_mode: if material.alpha_test.is_some() {
-            Valid(gltf::json::material::AlphaMode::Mask)
-        } else {
-            Valid(gltf::json::material::AlphaMode::Opaque)
-        },
+        alpha_mode: Valid(alpha_mode(material)),
         alpha_cutoff: material
             .alpha_test
             .as_ref()
             .map(|a| gltf::json::material::AlphaCutoff(a.ref_value)),
+        double_sided: material.flags.cull_mode == CullMode::Disabled,
         ..Default::default()
     }
 }
 
+fn alpha_mode(material: &crate::Material) -> gltf::json::material::AlphaMode {
+    // Alpha testing takes priority since glTF only supports a single mode.
+    if material.alpha_test.is_some() {
+        gltf::json::material::AlphaMode::Mask
+    } else {
+        match material.flags.blend_mode {
+            BlendMode::AlphaBlend | BlendMode::Additive => gltf::json::material::AlphaMode::Blend,
+            // TODO: Multiplicative blending isn't supported by glTF.
+            BlendMode::Disabled | BlendMode::Multiplicative | BlendMode::Unk6 => {
+                gltf::json::material::AlphaMode::Opaque
+            }
+        }
+    }
+}
+
+pub fn extensions_used(materials: &[gltf::json::Material]) -> Vec<String> {
+    let texture_transform = materials.iter().any(|m| {
+        [
+            &m.pbr_metallic_roughness.base_color_texture,
+            &m.pbr_metallic_roughness.metallic_roughness_texture,
+        ]
+        .iter()
+        .any(|t| {
+            t.as_ref()
+                .and_then(|t| t.extensions.as_ref())
+                .is_some_and(|e| e.texture_transform.is_some())
+        })
+    });
+
+    if texture_transform {
+        vec!["KHR_texture_transform".to_string()]
+    } else {
+        Vec::new()
+    }
+}
+
 fn texture_transform_ext(
+    texture_matrix: Option<Mat4>,
     scale: Option<[ordered_float::OrderedFloat<f32>; 2]>,
 ) -> Option<gltf_json::extensions::texture::Info> {
+    // TODO: Support rotation and shearing from the texture matrix?
+    // The UV offset is in the last column since UVs are transformed as vec4(u, v, 0.0, 1.0).
+    let (offset, scale) = match texture_matrix {
+        Some(m) => ([m.w_axis.x, m.w_axis.y], [m.x_axis.x, m.y_axis.y]),
+        None => scale.map(|[u, v]| ([0.0; 2], [u.0, v.0]))?,
+    };
+
     // TODO: Don't assume the first UV map?
-    scale.map(|[u, v]| gltf::json::extensions::texture::Info {
+    Some(gltf::json::extensions::texture::Info {
         texture_transform: Some(gltf::json::extensions::texture::TextureTransform {
-            offset: gltf::json::extensions::texture::TextureTransformOffset([0.0; 2]),
+            offset: gltf::json::extensions::texture::TextureTransformOffset(offset),
             rotation: gltf::json::extensions::texture::TextureTransformRotation(0.0),
-            scale: gltf::json::extensions::texture::TextureTransformScale([u.0, v.0]),
+            scale: gltf::json::extensions::texture::TextureTransformScale(scale),
             tex_coord: Some(0),
             extras: None,
         }),
@@ -293,3 +351,128 @@ fn add_texture(
     });
     texture_index
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{
+        test_support::{self, state_flags},
+        StateFlags, TextureAlphaTest,
+    };
+
+    fn material(
+        blend_mode: BlendMode,
+        cull_mode: CullMode,
+        alpha_test: Option<TextureAlphaTest>,
+    ) -> crate::Material {
+        crate::Material {
+            flags: StateFlags {
+                blend_mode,
+                cull_mode,
+                ..state_flags()
+            },
+            alpha_test,
+            ..test_support::material("material")
+        }
+    }
+
+    fn gltf_material(material: &crate::Material) -> gltf::json::Material {
+        gltf_material_with_options(material, &GltfOptions::default())
+    }
+
+    fn gltf_material_with_options(
+        material: &crate::Material,
+        options: &GltfOptions,
+    ) -> gltf::json::Material {
+        let mut texture_cache = TextureCache::new(std::iter::empty());
+        create_material(
+            material,
+            &mut texture_cache,
+            &mut Vec::new(),
+            0,
+            0,
+            &[],
+            options,
+        )
+    }
+
+    #[test]
+    fn material_opaque_single_sided() {
+        let material = gltf_material(&material(BlendMode::Disabled, CullMode::Back, None));
+        assert_eq!(
+            Valid(gltf::json::material::AlphaMode::Opaque),
+            material.alpha_mode
+        );
+        assert_eq!(None, material.alpha_cutoff.map(|c| c.0));
+        assert!(!material.double_sided);
+    }
+
+    #[test]
+    fn material_additive_double_sided() {
+        let material = gltf_material(&material(BlendMode::Additive, CullMode::Disabled, None));
+        assert_eq!(
+            Valid(gltf::json::material::AlphaMode::Blend),
+            material.alpha_mode
+        );
+        assert_eq!(None, material.alpha_cutoff.map(|c| c.0));
+        assert!(material.double_sided);
+    }
+
+    #[test]
+    fn material_alpha_test_mask() {
+        let material = gltf_material(&material(
+            BlendMode::AlphaBlend,
+            CullMode::Back,
+            Some(TextureAlphaTest {
+                texture_index: 0,
+                channel_index: 3,
+                ref_value: 0.5,
+            }),
+        ));
+        assert_eq!(
+            Valid(gltf::json::material::AlphaMode::Mask),
+            material.alpha_mode
+        );
+        assert_eq!(Some(0.5), material.alpha_cutoff.map(|c| c.0));
+    }
+
+    #[test]
+    fn material_texture_matrix_scale() {
+        let mut material = material(BlendMode::Disabled, CullMode::Back, None);
+        material.textures = vec![crate::Texture {
+            image_texture_index: 0,
+            sampler_index: 0,
+        }];
+        material.parameters.tex_matrix = Some(vec![[2.0, 0.0, 0.0, 0.0, 0.0, 2.0, 0.0, 0.0]]);
+
+        let transform = |m: &gltf::json::Material| {
+            m.pbr_metallic_roughness
+                .base_color_texture
+                .as_ref()
+                .unwrap()
+                .extensions
+                .as_ref()
+                .and_then(|e| e.texture_transform.as_ref())
+                .map(|t| (t.offset.0, t.scale.0))
+        };
+
+        // The matrix should only be applied if enabled.
+        let default = gltf_material(&material);
+        assert_eq!(None, transform(&default));
+        assert!(extensions_used(&[default]).is_empty());
+
+        let transformed = gltf_material_with_options(
+            &material,
+            &GltfOptions {
+                texture_matrices: true,
+                ..Default::default()
+            },
+        );
+        assert_eq!(Some(([0.0, 0.0], [2.0, 2.0])), transform(&transformed));
+        assert_eq!(
+            vec!["KHR_texture_transform".to_string()],
+            extensions_used(&[transformed])
+        );
+    }
+}
@@ -1,3 +1,5 @@
+use std::collections::BTreeSet;
+
 use crate::{ChannelAssignment, ImageTexture, OutputAssignments};
 use image_dds::image::{codecs::png::PngEncoder, RgbaImage};
 use indexmap::IndexMap;
@@ -71,11 +73,33 @@ impl TextureCache {
         }
     }
 
+    /// The unique file name for each generated image in ascending order by image index.
+    ///
+    /// Characters other than ASCII letters, digits, `_`, `-`, and `.` are replaced with `_`
+    /// to produce valid file names and URIs on all platforms.
+    /// Duplicate names have a numeric suffix like `_1` added in order,
+    /// so names are stable when exporting the same data again.
+    pub fn image_names(&self, model_name: &str) -> Vec<String> {
+        let mut used_names = BTreeSet::new();
+        self.generated_texture_indices
+            .keys()
+            .map(|key| {
+                let name = sanitize_file_name(&image_name(key, model_name));
+                unique_file_name(name, &mut used_names)
+            })
+            .collect()
+    }
+
     // TODO: Avoid unwrap?
     pub fn generate_png_images(&self, model_name: &str) -> Vec<(String, Vec<u8>)> {
+        let names = self.image_names(model_name);
+
         self.generated_texture_indices
-            .par_iter()
-            .map(|(key, _)| {
+            .keys()
+            .zip(names)
+            .collect::<Vec<_>>()
+            .into_par_iter()
+            .map(|(key, name)| {
                 // TODO: Why does this panic?
                 let image = generate_image(*key, &self.original_images).unwrap();
 
@@ -85,13 +109,41 @@ impl TextureCache {
                 let encoder = PngEncoder::new(&mut png_bytes);
                 image.write_with_encoder(encoder).unwrap();
 
-                let name = image_name(key, model_name);
                 (name, png_bytes)
             })
             .collect()
     }
 }
 
+fn sanitize_file_name(name: &str) -> String {
+    name.chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.') {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+fn unique_file_name(name: String, used_names: &mut BTreeSet<String>) -> String {
+    let (stem, extension) = match name.rsplit_once('.') {
+        Some((stem, extension)) => (stem.to_string(), format!(".{extension}")),
+        None => (name.clone(), String::new()),
+    };
+
+    let mut unique_name = name;
+    let mut suffix = 1;
+    while used_names.contains(&unique_name) {
+        unique_name = format!("{stem}_{suffix}{extension}");
+        suffix += 1;
+    }
+
+    used_names.insert(unique_name.clone());
+    unique_name
+}
+
 // TODO: Create consts for the gbuffer texture indices?
 pub fn albedo_generated_key(
     material: &crate::Material,
@@ -388,3 +440,42 @@ pub fn create_images<'a>(
     }
     png_images
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(sampler: usize) -> GeneratedImageKey {
+        GeneratedImageKey {
+            root_index: 0,
+            red_index: Some(ImageIndex {
+                image_texture: 1,
+                sampler,
+                channel: 0,
+                texcoord_scale: None,
+            }),
+            green_index: None,
+            blue_index: None,
+            alpha_index: None,
+            recalculate_normal_z: false,
+            invert_green: false,
+        }
+    }
+
+    #[test]
+    fn image_names_duplicate_names() {
+        let mut cache = TextureCache::default();
+        cache.insert(key(0));
+        cache.insert(key(1));
+        cache.insert(key(2));
+
+        assert_eq!(
+            vec![
+                "a_b_root0_r1_0_.png",
+                "a_b_root0_r1_0__1.png",
+                "a_b_root0_r1_0__2.png"
+            ],
+            cache.image_names("a:b")
+        );
+    }
+}
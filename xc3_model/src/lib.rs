@@ -29,6 +29,7 @@
 
 use std::{
     borrow::Cow,
+    collections::{BTreeMap, BTreeSet},
     io::Cursor,
     path::{Path, PathBuf},
 };
@@ -36,12 +37,13 @@ use std::{
 use animation::Animation;
 use binrw::{BinRead, BinReaderExt};
 use glam::{Mat4, Vec3};
-use log::error;
-use material::create_materials;
+use log::warn;
+use material::{apply_materials, apply_shader_vars, create_materials, material_report};
 use shader_database::ShaderDatabase;
+use skeleton::apply_skeleton;
 use texture::load_textures;
 use thiserror::Error;
-use vertex::ModelBuffers;
+use vertex::{ModelBuffers, VertexDataLegacyError};
 use xc3_lib::{
     apmd::Apmd,
     bc::Bc,
@@ -54,40 +56,56 @@ use xc3_lib::{
     mtxt::Mtxt,
     mxmd::{legacy::MxmdLegacy, Materials, Mxmd},
     sar1::Sar1,
-    xbc1::MaybeXbc1,
+    xbc1::{CompressionLevel, MaybeXbc1},
     ReadFileError,
 };
 
-pub use map::{load_map, LoadMapError};
+pub use diff::Difference;
+#[cfg(feature = "mmap")]
+pub use map::load_map_mmap;
+pub use map::{load_map, load_map_with_progress, LoadMapError, MapLoadPhase, MapLoadProgress};
 pub use material::{
-    ChannelAssignment, Material, MaterialParameters, OutputAssignment, OutputAssignments, Texture,
-    TextureAlphaTest,
+    ChannelAssignment, Material, MaterialParameters, MaterialReport, OutputAssignment,
+    OutputAssignments, Texture, TextureAlphaTest, TextureReport,
 };
 pub use sampler::{AddressMode, FilterMode, Sampler};
-pub use skeleton::{Bone, Skeleton};
-pub use texture::{ExtractedTextures, ImageFormat, ImageTexture, ViewDimension};
+pub use shader_info::{ProgramInfo, ShaderInfo};
+pub use skeleton::{Bone, ProceduralBone, Skeleton};
+pub use texture::{
+    ColorSpace, CreateKtx2Error, ExtractedTextures, ImageFormat, ImageTexture, ReplaceTextureError,
+    ViewDimension,
+};
 pub use xc3_lib::mxmd::{
     BlendMode, CullMode, DepthFunc, MeshRenderFlags2, MeshRenderPass, RenderPassType, StateFlags,
     StencilMode, StencilValue, TextureUsage,
 };
 
 pub mod animation;
+mod diff;
 
 #[cfg(feature = "gltf")]
 pub mod gltf;
 
 mod map;
 mod material;
+#[cfg(feature = "rerun")]
+mod rerun;
 mod sampler;
 pub mod shader_database;
+mod shader_info;
 mod skeleton;
 pub mod skinning;
+#[cfg(test)]
+mod test_support;
 mod texture;
+#[cfg(feature = "usd")]
+pub mod usd;
 pub mod vertex;
 
 // TODO: Document why these are different.
 // TODO: Come up with a better name
 #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, PartialEq, Clone)]
 pub struct ModelRoot {
     pub models: Models,
@@ -101,9 +119,35 @@ pub struct ModelRoot {
 
     // TODO: Do we even need to store the skinning if the weights already have the skinning bone name list?
     pub skeleton: Option<Skeleton>,
+
+    /// Metadata for the shaders embedded in the `.wimdo` file if present.
+    /// This does not require a [ShaderDatabase].
+    /// Only loaded if [LoadOptions::embedded_shaders] is enabled.
+    pub embedded_shaders: Option<ShaderInfo>,
+
+    /// The game the model was loaded from.
+    /// See [detect_game_version] for details.
+    /// Models loaded with [load_model_legacy] always use [GameVersion::Xcx].
+    pub game_version: GameVersion,
 }
 
+/// The game that a model file is from.
 #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum GameVersion {
+    /// Xenoblade Chronicles 1 Definitive Edition
+    Xc1,
+    /// Xenoblade Chronicles 2 and Torna
+    Xc2,
+    /// Xenoblade Chronicles 3 and Future Redeemed
+    Xc3,
+    /// Xenoblade Chronicles X
+    Xcx,
+}
+
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, PartialEq, Clone)]
 pub struct MapRoot {
     pub groups: Vec<ModelGroup>,
@@ -115,6 +159,7 @@ pub struct MapRoot {
 }
 
 #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, PartialEq, Clone)]
 pub struct ModelGroup {
     pub models: Vec<Models>,
@@ -126,6 +171,7 @@ pub struct ModelGroup {
 // TODO: Come up with a better name?
 /// See [Models](xc3_lib::mxmd::Models).
 #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, PartialEq, Clone)]
 pub struct Models {
     pub models: Vec<Model>,
@@ -140,6 +186,9 @@ pub struct Models {
     // TODO: Create a type for this constructed from Models?
     pub base_lod_indices: Option<Vec<u16>>,
 
+    /// The LOD distances for each group or [None] if the model has no LOD data.
+    pub lod_info: Option<LodInfo>,
+
     // TODO: Use none instead of empty?
     /// The name of the controller for each morph target like "mouth_shout".
     pub morph_controller_names: Vec<String>,
@@ -147,6 +196,20 @@ pub struct Models {
     /// The the morph controller names used for animations.
     pub animation_morph_names: Vec<String>,
 
+    /// The index into [animation_morph_names](#structfield.animation_morph_names)
+    /// for each controller in [morph_controller_names](#structfield.morph_controller_names).
+    ///
+    /// This uses the unknown [MorphController](xc3_lib::mxmd::MorphController) `unk2` field,
+    /// which is assumed but not confirmed to index the animation morphs.
+    pub morph_controller_animation_indices: Vec<usize>,
+
+    /// The name of each extra mesh like "body" or "hair".
+    /// These names are typically used for grouping meshes into parts.
+    pub ext_mesh_names: Vec<String>,
+    /// Whether each extra mesh in [ext_mesh_names](#structfield.ext_mesh_names)
+    /// should initially not be rendered.
+    pub ext_mesh_start_hidden: Vec<bool>,
+
     // TODO: make this a function instead to avoid dependencies?
     /// The minimum XYZ coordinates of the bounding volume.
     #[cfg_attr(feature = "arbitrary", arbitrary(with = arbitrary_vec3))]
@@ -157,12 +220,62 @@ pub struct Models {
     pub min_xyz: Vec3,
 }
 
+/// See [LodData](xc3_lib::mxmd::LodData).
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, PartialEq, Clone)]
+pub struct LodInfo {
+    pub groups: Vec<LodGroup>,
+}
+
+/// See [LodGroup](xc3_lib::mxmd::LodGroup).
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, PartialEq, Clone)]
+pub struct LodGroup {
+    /// One minus the [lod](struct.Mesh.html#structfield.lod) for [Mesh] with the highest level of detail.
+    pub base_lod_index: usize,
+    /// The maximum camera distance for each LOD level starting from the highest level of detail.
+    pub distances: Vec<f32>,
+}
+
+impl LodInfo {
+    pub fn from_lod_data(data: &xc3_lib::mxmd::LodData) -> Self {
+        // TODO: Is each group a contiguous range of items?
+        Self {
+            groups: data
+                .groups
+                .iter()
+                .map(|group| {
+                    let start = group.base_lod_index as usize;
+                    let end = start + group.lod_count as usize;
+                    LodGroup {
+                        base_lod_index: start,
+                        distances: data
+                            .items1
+                            .get(start..end)
+                            .unwrap_or_default()
+                            .iter()
+                            .map(|i| i.unk2)
+                            .collect(),
+                    }
+                })
+                .collect(),
+        }
+    }
+}
+
 /// See [Model](xc3_lib::mxmd::Model).
 #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, PartialEq, Clone)]
 pub struct Model {
     pub meshes: Vec<Mesh>,
     /// Each mesh has an instance for every transform in [instances](#structfield.instances).
+    ///
+    /// The transforms are already in world space.
+    /// A [ModelGroup] does not have any additional transforms,
+    /// so map props can be placed using these transforms directly.
     #[cfg_attr(feature = "arbitrary", arbitrary(with = arbitrary_mat4s))]
     pub instances: Vec<Mat4>,
     /// The index of the [ModelBuffers] in [buffers](struct.ModelGroup.html#structfield.buffers).
@@ -178,17 +291,54 @@ pub struct Model {
 
 /// See [Mesh](xc3_lib::mxmd::Mesh).
 #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, PartialEq, Clone)]
 pub struct Mesh {
     pub vertex_buffer_index: usize,
     pub index_buffer_index: usize,
     pub material_index: usize,
+    /// Index into [ext_mesh_names](struct.Models.html#structfield.ext_mesh_names).
+    pub ext_mesh_index: Option<usize>,
     pub lod: u16,
     pub flags1: u32,
     pub flags2: MeshRenderFlags2,
 }
 
+/// An out of range index for a [Mesh] found by [Models::validate_indices].
+#[derive(Debug, PartialEq, Eq, Clone, Error)]
+pub enum MeshIndexError {
+    #[error("material index {index} for mesh {mesh_index} in model {model_index} is out of range")]
+    Material {
+        model_index: usize,
+        mesh_index: usize,
+        index: usize,
+    },
+
+    #[error(
+        "vertex buffer index {index} for mesh {mesh_index} in model {model_index} is out of range"
+    )]
+    VertexBuffer {
+        model_index: usize,
+        mesh_index: usize,
+        index: usize,
+    },
+
+    #[error(
+        "index buffer index {index} for mesh {mesh_index} in model {model_index} is out of range"
+    )]
+    IndexBuffer {
+        model_index: usize,
+        mesh_index: usize,
+        index: usize,
+    },
+}
+
 impl Models {
+    /// Create models from `models` and `materials` for Xenoblade 1 DE, Xenoblade 2, or Xenoblade 3.
+    ///
+    /// Xenoblade 2 models with version `10111` do not have
+    /// [models_flags](xc3_lib::mxmd::Models::models_flags) or [extra](xc3_lib::mxmd::Models::extra) data.
+    /// Models without [lod_data](xc3_lib::mxmd::Models::lod_data) have no LOD info and render all meshes.
     pub fn from_models(
         models: &xc3_lib::mxmd::Models,
         materials: &xc3_lib::mxmd::Materials,
@@ -206,6 +356,7 @@ impl Models {
                 .lod_data
                 .as_ref()
                 .map(|data| data.groups.iter().map(|i| i.base_lod_index).collect()),
+            lod_info: models.lod_data.as_ref().map(LodInfo::from_lod_data),
             morph_controller_names: models
                 .morph_controllers
                 .as_ref()
@@ -216,6 +367,17 @@ impl Models {
                 .as_ref()
                 .map(|u| u.items1.iter().map(|i| i.name.clone()).collect())
                 .unwrap_or_default(),
+            morph_controller_animation_indices: models
+                .morph_controllers
+                .as_ref()
+                .map(morph_controller_animation_indices)
+                .unwrap_or_default(),
+            ext_mesh_names: models.ext_meshes.iter().map(|e| e.name1.clone()).collect(),
+            ext_mesh_start_hidden: models
+                .ext_meshes
+                .iter()
+                .map(|e| e.flags.start_hidden())
+                .collect(),
             min_xyz: models.min_xyz.into(),
             max_xyz: models.max_xyz.into(),
         }
@@ -259,17 +421,317 @@ impl Models {
                         tex_matrix: None,
                         work_float4: None,
                         work_color: None,
+                        work_values: Vec::new(),
+                        work_value_params: Vec::new(),
+                        shader_vars: Vec::new(),
                     },
                 })
                 .collect(),
             samplers: Vec::new(),
             base_lod_indices: None,
+            lod_info: None,
             morph_controller_names: Vec::new(),
             animation_morph_names: Vec::new(),
+            morph_controller_animation_indices: Vec::new(),
+            ext_mesh_names: Vec::new(),
+            ext_mesh_start_hidden: Vec::new(),
             max_xyz: models.max_xyz.into(),
             min_xyz: models.min_xyz.into(),
         }
     }
+
+    /// The LOD level for `distance` in the [LodGroup] at index `group`
+    /// from [lod_info](#structfield.lod_info).
+    /// The level is `0` for the highest level of detail
+    /// and the last level for distances past all thresholds.
+    ///
+    /// Meshes with this level have a [lod](struct.Mesh.html#structfield.lod)
+    /// of [base_lod_index](struct.LodGroup.html#structfield.base_lod_index) plus the level plus one.
+    /// Returns `0` if the group is not present.
+    pub fn lod_for_distance(&self, group: usize, distance: f32) -> u8 {
+        self.lod_info
+            .as_ref()
+            .and_then(|info| info.groups.get(group))
+            .map(|group| {
+                let level = group
+                    .distances
+                    .iter()
+                    .position(|d| distance <= *d)
+                    .unwrap_or(group.distances.len().saturating_sub(1));
+                level as u8
+            })
+            .unwrap_or_default()
+    }
+
+    /// The index into [morph_controller_names](#structfield.morph_controller_names)
+    /// for the controller driven by the animation morph at `anim_morph_index`
+    /// in [animation_morph_names](#structfield.animation_morph_names)
+    /// or [None] if no controller uses this animation morph.
+    ///
+    /// See [morph_controller_animation_indices](#structfield.morph_controller_animation_indices)
+    /// for limitations.
+    pub fn morph_controller_for_animation(&self, anim_morph_index: usize) -> Option<usize> {
+        self.morph_controller_animation_indices
+            .iter()
+            .position(|i| *i == anim_morph_index)
+    }
+
+    /// Remove all meshes that are not part of the base level of detail (LOD).
+    /// See [should_render_lod] for how meshes are selected.
+    ///
+    /// This does not modify any buffers.
+    /// Use [ModelRoot::keep_highest_lod] to also remove unused buffers.
+    pub fn keep_highest_lod(&mut self) {
+        for model in &mut self.models {
+            model
+                .meshes
+                .retain(|m| should_render_lod(m.lod, &self.base_lod_indices));
+        }
+    }
+
+    /// Remove all meshes that should initially not be rendered.
+    /// See [Mesh::is_initially_hidden] for how meshes are selected.
+    ///
+    /// This does not modify any buffers.
+    pub fn remove_hidden_meshes(&mut self) {
+        for model in &mut self.models {
+            model
+                .meshes
+                .retain(|m| !is_initially_hidden(m, &self.ext_mesh_start_hidden));
+        }
+    }
+
+    /// Remove any [samplers](#structfield.samplers) not used by the [materials](#structfield.materials).
+    /// Sampler indices for the remaining textures are updated to match.
+    pub fn remove_unused_samplers(&mut self) {
+        let used_samplers: BTreeSet<_> = self
+            .materials
+            .iter()
+            .flat_map(|m| m.sampler_indices())
+            .collect();
+
+        let sampler_indices = retain_used(&mut self.samplers, &used_samplers);
+
+        for material in &mut self.materials {
+            for texture in &mut material.textures {
+                if let Some(index) = sampler_indices.get(texture.sampler_index) {
+                    texture.sampler_index = *index;
+                }
+            }
+        }
+    }
+
+    /// The meshes for all [models](#structfield.models) and the index of their [Model]
+    /// sorted in ascending draw order by [Mesh::sort_key].
+    ///
+    /// Meshes with the same key retain their original order.
+    /// Meshes with an out of range material index are sorted as opaque.
+    pub fn meshes_in_draw_order(&self) -> impl Iterator<Item = (usize, &Mesh)> {
+        let mut meshes: Vec<_> = self
+            .models
+            .iter()
+            .enumerate()
+            .flat_map(|(i, model)| model.meshes.iter().map(move |mesh| (i, mesh)))
+            .collect();
+
+        meshes.sort_by_key(|(_, mesh)| match self.materials.get(mesh.material_index) {
+            Some(material) => mesh.sort_key(material),
+            None => (mesh_render_pass_order(mesh.flags2.render_pass()), 0),
+        });
+
+        meshes.into_iter()
+    }
+
+    /// Summarize the state and texture bindings for each of the [materials](#structfield.materials).
+    /// This is useful for checking shader database assignments.
+    ///
+    /// Textures are named "s0", "s1", etc based on their index in the material
+    /// to match the sampler names in [Shader](shader_database::Shader).
+    pub fn material_report(&self) -> Vec<MaterialReport> {
+        self.materials
+            .iter()
+            .map(|m| material_report(m, &self.samplers))
+            .collect()
+    }
+
+    /// Find all meshes with material or buffer indices that are out of range
+    /// for [materials](#structfield.materials) or `buffers`.
+    /// Meshes with errors should not be rendered or exported.
+    pub fn validate_indices(&self, buffers: &ModelBuffers) -> Vec<MeshIndexError> {
+        self.models
+            .iter()
+            .enumerate()
+            .flat_map(|(model_index, model)| {
+                model
+                    .meshes
+                    .iter()
+                    .enumerate()
+                    .flat_map(move |(mesh_index, mesh)| {
+                        mesh.index_errors(model_index, mesh_index, self, buffers)
+                    })
+            })
+            .collect()
+    }
+
+    /// Combine the meshes in each [Model] with the same material, LOD, flags, and extra mesh
+    /// into a single mesh using new vertex and index buffers added to `buffers`.
+    /// This reduces the number of draw calls and exported objects.
+    ///
+    /// Meshes are only combined if their vertex buffers have the same attributes
+    /// and no morph targets or outline buffers.
+    /// Meshes that can't be combined are left unchanged.
+    /// The bounds for each [Model] are recalculated from the vertex positions.
+    ///
+    /// This does not remove the original buffers since they may be used by other meshes.
+    pub fn merge_meshes_by_material(&mut self, buffers: &mut ModelBuffers) {
+        for model in &mut self.models {
+            merge_meshes_by_material(model, buffers);
+        }
+    }
+}
+
+impl Mesh {
+    /// A key for sorting meshes in ascending draw order with the assigned `material`.
+    ///
+    /// Meshes are ordered first by the [MeshRenderPass] from [flags2](#structfield.flags2)
+    /// and then by [Material::render_pass_order].
+    /// This matches the order of the passes used for rendering in xc3_wgpu.
+    ///
+    /// | Render Pass | Order |
+    /// | --- | --- |
+    /// | [MeshRenderPass::Unk1] | 0 |
+    /// | [MeshRenderPass::Unk0] | 1 |
+    /// | [MeshRenderPass::Unk4] | 2 |
+    /// | [MeshRenderPass::Unk8] | 3 |
+    /// | [MeshRenderPass::Unk2] | 4 |
+    pub fn sort_key(&self, material: &Material) -> (u8, u8) {
+        (
+            mesh_render_pass_order(self.flags2.render_pass()),
+            material.render_pass_order(),
+        )
+    }
+
+    /// The name of the assigned extra mesh in `models` or [None] if not present.
+    pub fn ext_mesh_name<'a>(&self, models: &'a Models) -> Option<&'a str> {
+        self.ext_mesh_index
+            .and_then(|i| models.ext_mesh_names.get(i))
+            .map(|n| n.as_str())
+    }
+
+    /// Returns `true` if the assigned extra mesh in `models` should initially not be rendered.
+    /// This is often used for alternate parts like facial expressions or outfit pieces.
+    pub fn is_initially_hidden(&self, models: &Models) -> bool {
+        is_initially_hidden(self, &models.ext_mesh_start_hidden)
+    }
+
+    /// The indices of the bones with non zero weights for the vertices used by this mesh.
+    ///
+    /// The indices are into [bone_names](skinning::SkinWeights::bone_names)
+    /// for the buffer from [Weights::weight_buffer](skinning::Weights::weight_buffer).
+    /// The weight group is selected using the [RenderPassType] of the material in `models`
+    /// at [material_index](#structfield.material_index).
+    /// Only [AttributeData::WeightIndex](vertex::AttributeData::WeightIndex) values are used for skinning.
+    ///
+    /// This is useful for splitting or validating meshes for skinning palettes with a limited number of bones.
+    pub fn used_bones(
+        &self,
+        models: &Models,
+        buffers: &ModelBuffers,
+        weights: &skinning::Weights,
+    ) -> BTreeSet<usize> {
+        let mut bones = BTreeSet::new();
+
+        let Some(skin_weights) = weights.weight_buffer(self.flags2.into()) else {
+            return bones;
+        };
+        let Some(pass_type) = models
+            .materials
+            .get(self.material_index)
+            .map(|m| m.pass_type)
+        else {
+            return bones;
+        };
+        let start_index =
+            weights
+                .weight_groups
+                .weights_start_index(self.flags2.into(), self.lod, pass_type);
+
+        let weight_indices = buffers
+            .vertex_buffers
+            .get(self.vertex_buffer_index)
+            .and_then(|b| {
+                b.attributes.iter().find_map(|a| match a {
+                    vertex::AttributeData::WeightIndex(values) => Some(values),
+                    _ => None,
+                })
+            });
+        let vertex_indices = buffers
+            .index_buffers
+            .get(self.index_buffer_index)
+            .map(|b| &b.indices);
+
+        if let (Some(weight_indices), Some(vertex_indices)) = (weight_indices, vertex_indices) {
+            // Vertex buffers may be shared, so only check vertices used by this mesh.
+            let vertex_indices: BTreeSet<_> = vertex_indices.iter().collect();
+            for vertex_index in vertex_indices {
+                let Some(weight_index) = weight_indices.get(*vertex_index as usize) else {
+                    continue;
+                };
+                let index = weight_index[0] as usize + start_index;
+                if let (Some(bone_indices), Some(bone_weights)) = (
+                    skin_weights.bone_indices.get(index),
+                    skin_weights.weights.get(index),
+                ) {
+                    for (bone_index, weight) in bone_indices.iter().zip(bone_weights.to_array()) {
+                        if weight > 0.0 {
+                            bones.insert(*bone_index as usize);
+                        }
+                    }
+                }
+            }
+        }
+
+        bones
+    }
+
+    pub(crate) fn index_errors(
+        &self,
+        model_index: usize,
+        mesh_index: usize,
+        models: &Models,
+        buffers: &ModelBuffers,
+    ) -> Vec<MeshIndexError> {
+        let mut errors = Vec::new();
+        if self.material_index >= models.materials.len() {
+            errors.push(MeshIndexError::Material {
+                model_index,
+                mesh_index,
+                index: self.material_index,
+            });
+        }
+        if self.vertex_buffer_index >= buffers.vertex_buffers.len() {
+            errors.push(MeshIndexError::VertexBuffer {
+                model_index,
+                mesh_index,
+                index: self.vertex_buffer_index,
+            });
+        }
+        if self.index_buffer_index >= buffers.index_buffers.len() {
+            errors.push(MeshIndexError::IndexBuffer {
+                model_index,
+                mesh_index,
+                index: self.index_buffer_index,
+            });
+        }
+        errors
+    }
+}
+
+fn is_initially_hidden(mesh: &Mesh, ext_mesh_start_hidden: &[bool]) -> bool {
+    mesh.ext_mesh_index
+        .and_then(|i| ext_mesh_start_hidden.get(i))
+        .copied()
+        .unwrap_or_default()
 }
 
 impl Model {
@@ -285,6 +747,7 @@ impl Model {
                 vertex_buffer_index: mesh.vertex_buffer_index as usize,
                 index_buffer_index: mesh.index_buffer_index as usize,
                 material_index: mesh.material_index as usize,
+                ext_mesh_index: Some(mesh.ext_mesh_index as usize),
                 lod: mesh.lod,
                 flags1: mesh.flags1,
                 flags2: mesh.flags2,
@@ -309,6 +772,7 @@ impl Model {
                 vertex_buffer_index: mesh.vertex_buffer_index as usize,
                 index_buffer_index: mesh.index_buffer_index as usize,
                 material_index: mesh.material_index as usize,
+                ext_mesh_index: None,
                 lod: 0,
                 flags1: mesh.flags1,
                 flags2: mesh.flags2.try_into().unwrap(),
@@ -363,6 +827,12 @@ pub enum LoadModelError {
     #[error("expected packed wimdo vertex data but found none")]
     MissingMxmdVertexData,
 
+    #[error("expected wismt vertex data but found legacy streaming data")]
+    MissingMsrdVertexData,
+
+    #[error("expected wismt textures but found legacy streaming data")]
+    MissingMsrdTextures,
+
     #[error("error loading image texture")]
     Image(#[from] texture::CreateImageTextureError),
 
@@ -379,6 +849,34 @@ pub enum LoadModelError {
     Wismt(#[source] ReadFileError),
 }
 
+#[derive(Debug, Error)]
+pub enum CreateMxmdModelError {
+    #[error("error extracting stream data")]
+    ExtractFiles(#[from] xc3_lib::msrd::streaming::ExtractFilesError),
+
+    #[error("error writing vertex data")]
+    VertexData(#[source] binrw::Error),
+
+    #[error("error creating Mibl for image texture {index}")]
+    Texture {
+        index: usize,
+        #[source]
+        source: xc3_lib::mibl::CreateMiblError,
+    },
+
+    #[error("error compressing streams")]
+    Stream(#[from] xc3_lib::xbc1::CreateXbc1Error),
+}
+
+#[derive(Debug, Error)]
+pub enum WriteWimdoWismtError {
+    #[error("error creating model files")]
+    Create(#[from] CreateMxmdModelError),
+
+    #[error("error writing model files")]
+    Io(#[from] std::io::Error),
+}
+
 // TODO: Take an iterator for wimdo paths and merge to support xc1?
 /// Load a model from a `.wimdo` or `.pcmdo` file.
 /// The corresponding `.wismt` or `.pcsmt` and `.chr` or `.arc` should be in the same directory.
@@ -432,6 +930,42 @@ pub enum LoadModelError {
 pub fn load_model<P: AsRef<Path>>(
     wimdo_path: P,
     shader_database: Option<&ShaderDatabase>,
+) -> Result<ModelRoot, LoadModelError> {
+    load_model_with_options(wimdo_path, shader_database, &LoadOptions::default())
+}
+
+/// Settings for loading models with [load_model_with_options].
+#[derive(Debug, Clone, PartialEq)]
+pub struct LoadOptions {
+    /// Log a warning if a model with vertex skinning has no `.chr` or `.arc` skeleton.
+    /// Disable this for intentionally static loads where a missing skeleton is expected.
+    /// Defaults to `true`.
+    pub warn_missing_skeleton: bool,
+    /// Also add bones from shared skeletons like `ch01012000.chr` for `ch01012013.wimdo` if present.
+    /// This reads additional `.chr` files and may add bones not in the model's own skeleton.
+    /// Defaults to `false`.
+    pub base_skeletons: bool,
+    /// Read the program metadata for shaders embedded in the `.wimdo` file
+    /// into [embedded_shaders](struct.ModelRoot.html#structfield.embedded_shaders).
+    /// Defaults to `false`.
+    pub embedded_shaders: bool,
+}
+
+impl Default for LoadOptions {
+    fn default() -> Self {
+        Self {
+            warn_missing_skeleton: true,
+            base_skeletons: false,
+            embedded_shaders: false,
+        }
+    }
+}
+
+/// Load a model like [load_model] using the settings in `options`.
+pub fn load_model_with_options<P: AsRef<Path>>(
+    wimdo_path: P,
+    shader_database: Option<&ShaderDatabase>,
+    options: &LoadOptions,
 ) -> Result<ModelRoot, LoadModelError> {
     let wimdo_path = wimdo_path.as_ref();
 
@@ -448,89 +982,382 @@ pub fn load_model<P: AsRef<Path>>(
     let streaming_data = StreamingData::new(&mxmd, &wismt_path, is_pc, chr_tex_folder.as_deref())?;
 
     let model_name = model_name(wimdo_path);
-    let spch = shader_database.and_then(|database| database.files.get(&model_name));
+    let spch = shader_database.and_then(|database| database.get_with_fallback(&model_name));
 
-    let chr = load_chr(wimdo_path, model_name);
+    // Only search for a skeleton if the model can use it.
+    let chr = if mxmd.has_skinning() {
+        load_chr(wimdo_path, model_name.clone())
+    } else {
+        None
+    };
+    if options.warn_missing_skeleton {
+        warn_missing_skeleton(&mxmd, chr.as_ref());
+    }
 
-    ModelRoot::from_mxmd_model(&mxmd, chr, &streaming_data, spch)
+    let mut root = ModelRoot::from_mxmd_model(&mxmd, chr, &streaming_data, spch)?;
+    if let Some(skeleton) = root.skeleton.as_mut().filter(|_| options.base_skeletons) {
+        merge_base_skeletons(
+            skeleton,
+            wimdo_path,
+            model_name,
+            mxmd.models.skinning.as_ref(),
+        );
+    }
+    if options.embedded_shaders {
+        root.embedded_shaders = mxmd.spch.as_ref().and_then(embedded_shader_info);
+    }
+    Ok(root)
 }
 
-fn load_chr(wimdo_path: &Path, model_name: String) -> Option<Sar1> {
-    // TODO: Does every wimdo have a chr file?
-    // TODO: Does something control the chr name used?
-    // TODO: This won't load the base skeleton chr for xc3.
-    Sar1::from_file(wimdo_path.with_extension("chr"))
-        .ok()
-        .or_else(|| Sar1::from_file(wimdo_path.with_extension("arc")).ok())
-        .or_else(|| {
-            // Keep trying with more 0's at the end to match in game naming conventions.
-            // XC1: pc010101.wimdo -> pc010000.chr.
-            // XC3: ch01012013.wimdo -> ch01012010.chr.
-            (0..model_name.len()).find_map(|i| {
-                let mut chr_name = model_name.clone();
-                chr_name.replace_range(chr_name.len() - i.., &"0".repeat(i));
-                let chr_path = wimdo_path.with_file_name(chr_name).with_extension("chr");
-                Sar1::from_file(chr_path).ok()
-            })
-        })
+fn embedded_shader_info(spch: &xc3_lib::spch::Spch) -> Option<ShaderInfo> {
+    // Invalid shader metadata shouldn't prevent loading the model.
+    match ShaderInfo::from_spch(spch) {
+        Ok(info) => Some(info),
+        Err(e) => {
+            warn!("Failed to read embedded shader info: {e}");
+            None
+        }
+    }
 }
 
-// TODO: separate legacy module with its own error type?
-/// Load a model from a `.camdo` file.
-/// The corresponding `.casmt`should be in the same directory.
+/// Find the `.chr` or `.arc` skeleton file used by [load_model] for `wimdo_path`
+/// or [None] if no file exists.
 ///
-/// # Examples
+/// The skeleton may use a different name than the model like
+/// `pc010000.chr` for `pc010101.wimdo` or `ch01012010.chr` for `ch01012013.wimdo`.
+pub fn find_chr_path<P: AsRef<Path>>(wimdo_path: P) -> Option<PathBuf> {
+    let wimdo_path = wimdo_path.as_ref();
+    chr_paths(wimdo_path, model_name(wimdo_path))
+        .into_iter()
+        .find(|p| p.exists())
+}
+
+/// Load the skeleton for `wimdo_path` using the file from [find_chr_path].
+/// The `skinning` should be from the model's [Mxmd].
+///
+/// If `base_skeletons` is `true`, bones from shared skeletons like
+/// `ch01012000.chr` for `ch01012013.wimdo` are also added if present.
+/// This matches the behavior of [load_model_with_options] with [LoadOptions::base_skeletons].
+pub fn load_skeleton<P: AsRef<Path>>(
+    wimdo_path: P,
+    skinning: Option<&xc3_lib::mxmd::Skinning>,
+    base_skeletons: bool,
+) -> Option<Skeleton> {
+    // Skeletons require skinning, so avoid searching for files for static models.
+    let skinning = skinning?;
+
+    let wimdo_path = wimdo_path.as_ref();
+    let model_name = model_name(wimdo_path);
+    let chr = load_chr(wimdo_path, model_name.clone());
+    let mut skeleton = create_skeleton(chr.as_ref(), Some(skinning))?;
+    if base_skeletons {
+        merge_base_skeletons(&mut skeleton, wimdo_path, model_name, Some(skinning));
+    }
+    Some(skeleton)
+}
+
+/// Summary information for a model without any vertex data or textures.
+/// See [load_model_info].
+#[derive(Debug, PartialEq, Clone)]
+pub struct ModelInfo {
+    /// The name of each [Material].
+    pub material_names: Vec<String>,
+    /// The number of [Model] in the [Models].
+    pub model_count: usize,
+    /// The total number of [Mesh] for all models.
+    pub mesh_count: usize,
+    /// The bone names from the [Skeleton] or the model's skinning if no skeleton is found.
+    pub bone_names: Vec<String>,
+    pub max_xyz: Vec3,
+    pub min_xyz: Vec3,
+}
+
+/// Load summary information for the model in `wimdo_path`
+/// without decoding any vertex data or textures.
 ///
+/// This only reads the `.wimdo` and skeleton files and does not read the `.wismt`,
+/// making it much faster than [load_model] for indexing large numbers of models.
+///
+/// # Examples
 /// ``` rust no_run
 /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
-/// use xc3_model::load_model_legacy;
-///
-/// // Tatsu
-/// let root = load_model_legacy("xenox/chr_np/np009001.camdo");
+/// // Shulk
+/// let info = xc3_model::load_model_info("xeno1/chr/pc/pc010101.wimdo")?;
+/// println!("{} meshes", info.mesh_count);
 /// # Ok(())
 /// # }
 /// ```
-pub fn load_model_legacy<P: AsRef<Path>>(camdo_path: P) -> ModelRoot {
-    // TODO: avoid unwrap.
-    let camdo_path = camdo_path.as_ref();
-    let mxmd: MxmdLegacy = MxmdLegacy::from_file(camdo_path).unwrap();
-    let casmt = mxmd
-        .streaming
-        .as_ref()
-        .map(|_| std::fs::read(camdo_path.with_extension("casmt")).unwrap());
-    ModelRoot::from_mxmd_model_legacy(&mxmd, casmt).unwrap()
+pub fn load_model_info<P: AsRef<Path>>(wimdo_path: P) -> Result<ModelInfo, LoadModelError> {
+    let wimdo_path = wimdo_path.as_ref();
+    let mxmd = load_wimdo(wimdo_path)?;
+
+    let skinning = mxmd.models.skinning.as_ref();
+    let bone_names = match load_skeleton(wimdo_path, skinning, true) {
+        Some(skeleton) => skeleton.bones.into_iter().map(|b| b.name).collect(),
+        None => skinning
+            .map(|s| s.bones.iter().map(|b| b.name.clone()).collect())
+            .unwrap_or_default(),
+    };
+
+    Ok(ModelInfo {
+        material_names: mxmd
+            .materials
+            .materials
+            .iter()
+            .map(|m| m.name.clone())
+            .collect(),
+        model_count: mxmd.models.models.len(),
+        mesh_count: mxmd.models.models.iter().map(|m| m.meshes.len()).sum(),
+        bone_names,
+        max_xyz: mxmd.models.max_xyz.into(),
+        min_xyz: mxmd.models.min_xyz.into(),
+    })
 }
 
-impl ModelRoot {
-    // TODO: fuzz test this?
-    /// Load models from parsed file data for Xenoblade 1 DE, Xenoblade 2, or Xenoblade 3.
-    pub fn from_mxmd_model(
-        mxmd: &Mxmd,
-        chr: Option<Sar1>,
-        streaming_data: &StreamingData<'_>,
-        spch: Option<&shader_database::Spch>,
-    ) -> Result<Self, LoadModelError> {
-        if mxmd.models.skinning.is_some() && chr.is_none() {
-            error!("Failed to load .arc or .chr skeleton for model with vertex skinning.");
+/// Extract only the vertex data from the `.wismt` or `.pcsmt` streaming data in `msrd`.
+/// See [ModelBuffers::from_vertex_data] for converting the vertex data.
+///
+/// No textures are decompressed, which is faster when only the geometry is needed.
+/// Legacy streaming data only stores textures,
+/// so the vertex data should be read from the [Mxmd] instead.
+///
+/// # Examples
+/// ``` rust no_run
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// use xc3_lib::msrd::Msrd;
+/// use xc3_model::vertex::ModelBuffers;
+///
+/// // Mio military
+/// let msrd = Msrd::from_file("xeno3/chr/ch/ch01027000.wismt")?;
+/// let vertex_data = xc3_model::vertex_data_from_msrd(&msrd)?;
+/// let buffers = ModelBuffers::from_vertex_data(&vertex_data, None)?;
+/// # Ok(())
+/// # }
+/// ```
+pub fn vertex_data_from_msrd(msrd: &Msrd) -> Result<xc3_lib::vertex::VertexData, LoadModelError> {
+    match &msrd.streaming.inner {
+        xc3_lib::msrd::StreamingInner::Streaming(_) => {
+            msrd.extract_vertex_data().map_err(Into::into)
         }
+        xc3_lib::msrd::StreamingInner::StreamingLegacy(_) => {
+            Err(LoadModelError::MissingMsrdVertexData)
+        }
+    }
+}
 
-        // TODO: Store the skeleton with the root since this is the only place we actually make one?
-        // TODO: Some sort of error if maps have any skinning set?
-        let skeleton = create_skeleton(chr.as_ref(), mxmd.models.skinning.as_ref());
+/// Detect the game version for `mxmd` from the file version and game specific data.
+///
+/// Xenoblade 2 uses version `10111`.
+/// Xenoblade 1 DE and Xenoblade 3 both use version `10112`.
+/// Xenoblade 3 models are identified by their `chr/tex/nx` texture information
+/// or by [ModelsExtraData](xc3_lib::mxmd::ModelsExtraData) sizes not used by other games.
+/// Xenoblade 3 models without a `.wismt` file may not have any of this data
+/// and will be detected as [GameVersion::Xc1].
+///
+/// The [ModelsExtraData](xc3_lib::mxmd::ModelsExtraData) check is a heuristic.
+/// It has not been verified against the headers of every Xenoblade 1 DE and Xenoblade 3 model,
+/// so some models may be detected as the wrong game.
+///
+/// # Examples
+/// ``` rust no_run
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// use xc3_lib::mxmd::Mxmd;
+/// use xc3_model::{detect_game_version, GameVersion};
+///
+/// let mxmd = Mxmd::from_file("xeno3/chr/ch/ch01027000.wimdo")?;
+/// let database = match detect_game_version(&mxmd) {
+///     GameVersion::Xc1 => "xc1.json",
+///     GameVersion::Xc2 => "xc2.json",
+///     GameVersion::Xc3 => "xc3.json",
+///     GameVersion::Xcx => "xcx.json",
+/// };
+/// # Ok(())
+/// # }
+/// ```
+pub fn detect_game_version(mxmd: &Mxmd) -> GameVersion {
+    if mxmd.version == 10111 {
+        return GameVersion::Xc2;
+    }
 
-        let buffers =
-            ModelBuffers::from_vertex_data(&streaming_data.vertex, mxmd.models.skinning.as_ref())
-                .map_err(LoadModelError::VertexData)?;
+    // Only Xenoblade 3 includes chr/tex/nx information in the streaming header.
+    let has_chr_textures =
+        mxmd.streaming
+            .as_ref()
+            .is_some_and(|streaming| match &streaming.inner {
+                xc3_lib::msrd::StreamingInner::Streaming(data) => {
+                    data.texture_resources.chr_textures.is_some()
+                }
+                xc3_lib::msrd::StreamingInner::StreamingLegacy(_) => false,
+            });
 
-        let models = Models::from_models(&mxmd.models, &mxmd.materials, spch);
+    // TODO: Check that Xenoblade 1 DE never uses the Unk4 or Unk5 sizes.
+    let has_xc3_models_data = matches!(
+        mxmd.models.extra,
+        Some(xc3_lib::mxmd::ModelsExtraData::Unk4(_) | xc3_lib::mxmd::ModelsExtraData::Unk5(_))
+    );
 
-        let image_textures = load_textures(&streaming_data.textures)?;
+    if has_chr_textures || has_xc3_models_data {
+        GameVersion::Xc3
+    } else {
+        GameVersion::Xc1
+    }
+}
 
-        Ok(Self {
-            models,
-            buffers,
+/// Extract only the textures from the `.wismt` streaming data in `msrd`.
+///
+/// High resolution textures are combined with their low resolution textures
+/// like with [load_model]. The vertex data and shaders are not parsed.
+/// Xenoblade 3 textures stored in `chr/tex/nx` are not loaded,
+/// so only the textures in the `.wismt` are returned for these models.
+///
+/// # Examples
+/// ``` rust no_run
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// use xc3_lib::msrd::Msrd;
+///
+/// // Mio military
+/// let msrd = Msrd::from_file("xeno3/chr/ch/ch01027000.wismt")?;
+/// for (name, texture) in xc3_model::extract_textures_from_msrd(&msrd)? {
+///     texture.to_image()?.save(format!("{name}.png"))?;
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub fn extract_textures_from_msrd(
+    msrd: &Msrd,
+) -> Result<Vec<(String, ImageTexture)>, LoadModelError> {
+    match &msrd.streaming.inner {
+        xc3_lib::msrd::StreamingInner::Streaming(_) => msrd
+            .extract_textures(None)?
+            .into_iter()
+            .map(|texture| {
+                ImageTexture::from_mibl(
+                    &texture.mibl_final(),
+                    Some(texture.name.clone()),
+                    Some(texture.usage),
+                )
+                .map(|image| (texture.name.clone(), image))
+                .map_err(|e| LoadModelError::Image(e.into()))
+            })
+            .collect(),
+        xc3_lib::msrd::StreamingInner::StreamingLegacy(_) => {
+            Err(LoadModelError::MissingMsrdTextures)
+        }
+    }
+}
+
+fn warn_missing_skeleton(mxmd: &Mxmd, chr: Option<&Sar1>) {
+    if mxmd.has_skinning() && chr.is_none() {
+        warn!("Failed to load .arc or .chr skeleton for model with vertex skinning.");
+    }
+}
+
+fn load_chr(wimdo_path: &Path, model_name: String) -> Option<Sar1> {
+    chr_paths(wimdo_path, model_name)
+        .into_iter()
+        .find_map(|p| Sar1::from_file(p).ok())
+}
+
+fn merge_base_skeletons(
+    skeleton: &mut Skeleton,
+    wimdo_path: &Path,
+    model_name: String,
+    skinning: Option<&xc3_lib::mxmd::Skinning>,
+) {
+    // Xenoblade 3 models can inherit bones from skeletons with more trailing 0's.
+    // The first chr is already used for the model's skeleton.
+    for chr in chr_paths(wimdo_path, model_name)
+        .into_iter()
+        .filter_map(|p| Sar1::from_file(p).ok())
+        .skip(1)
+    {
+        if let Some(base) = create_skeleton(Some(&chr), skinning) {
+            skeleton.merge(&base);
+        }
+    }
+}
+
+fn chr_paths(wimdo_path: &Path, model_name: String) -> Vec<PathBuf> {
+    // TODO: Does every wimdo have a chr file?
+    // TODO: Does something control the chr name used?
+    let mut paths: Vec<_> = [
+        wimdo_path.with_extension("chr"),
+        wimdo_path.with_extension("arc"),
+    ]
+    .into_iter()
+    .chain((0..model_name.len()).map(|i| {
+        // Keep trying with more 0's at the end to match in game naming conventions.
+        // XC1: pc010101.wimdo -> pc010000.chr.
+        // XC3: ch01012013.wimdo -> ch01012010.chr.
+        let mut chr_name = model_name.clone();
+        chr_name.replace_range(chr_name.len() - i.., &"0".repeat(i));
+        wimdo_path.with_file_name(chr_name).with_extension("chr")
+    }))
+    .collect();
+
+    // The first name with trailing 0's is usually the same as the model's chr.
+    let mut unique_paths = BTreeSet::new();
+    paths.retain(|p| unique_paths.insert(p.clone()));
+    paths
+}
+
+// TODO: separate legacy module with its own error type?
+/// Load a model from a `.camdo` file.
+/// The corresponding `.casmt`should be in the same directory.
+///
+/// # Examples
+///
+/// ``` rust no_run
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// use xc3_model::load_model_legacy;
+///
+/// // Tatsu
+/// let root = load_model_legacy("xenox/chr_np/np009001.camdo");
+/// # Ok(())
+/// # }
+/// ```
+pub fn load_model_legacy<P: AsRef<Path>>(camdo_path: P) -> ModelRoot {
+    // TODO: avoid unwrap.
+    let camdo_path = camdo_path.as_ref();
+    let mxmd: MxmdLegacy = MxmdLegacy::from_file(camdo_path).unwrap();
+    let casmt = mxmd
+        .streaming
+        .as_ref()
+        .map(|_| std::fs::read(camdo_path.with_extension("casmt")).unwrap());
+    ModelRoot::from_mxmd_model_legacy(&mxmd, casmt).unwrap()
+}
+
+impl ModelRoot {
+    // TODO: fuzz test this?
+    /// Load models from parsed file data for Xenoblade 1 DE, Xenoblade 2, or Xenoblade 3.
+    ///
+    /// Skinned models without a `chr` will not have a [Skeleton].
+    /// Unlike [load_model], this does not log a warning for a missing skeleton.
+    pub fn from_mxmd_model(
+        mxmd: &Mxmd,
+        chr: Option<Sar1>,
+        streaming_data: &StreamingData<'_>,
+        spch: Option<&shader_database::Spch>,
+    ) -> Result<Self, LoadModelError> {
+        // TODO: Store the skeleton with the root since this is the only place we actually make one?
+        // TODO: Some sort of error if maps have any skinning set?
+        let skeleton = create_skeleton(chr.as_ref(), mxmd.models.skinning.as_ref());
+
+        let buffers =
+            ModelBuffers::from_vertex_data(&streaming_data.vertex, mxmd.models.skinning.as_ref())
+                .map_err(LoadModelError::VertexData)?;
+
+        let models = Models::from_models(&mxmd.models, &mxmd.materials, spch);
+
+        let image_textures = load_textures(&streaming_data.textures)?;
+
+        Ok(Self {
+            models,
+            buffers,
             image_textures,
             skeleton,
+            embedded_shaders: None,
+            game_version: detect_game_version(mxmd),
         })
     }
 
@@ -554,42 +1381,80 @@ impl ModelRoot {
             buffers,
             image_textures,
             skeleton: Some(skeleton),
+            embedded_shaders: None,
+            // Only Xenoblade X uses the legacy format.
+            game_version: GameVersion::Xcx,
         })
     }
 
     // TODO: module for conversions?
     // TODO: Not possible to make files compatible with all game versions?
     // TODO: Will it be possible to do full imports in the future?
-    // TODO: Include chr to support skeleton edits?
     // TODO: How to properly test this?
     /// Apply the values from this model onto the original `mxmd` and `msrd`.
     ///
     /// Some of the original values will be retained due to exporting limitations.
     /// For best results, use the [Mxmd] and [Msrd] used to initialize this model.
+    /// Skeleton edits are applied separately using [ModelRoot::to_chr].
     ///
     /// If no edits were made to this model, the resulting files will attempt
     /// to recreate the originals used to initialize this model as closely as possible.
-    pub fn to_mxmd_model(&self, mxmd: &Mxmd, msrd: &Msrd) -> (Mxmd, Msrd) {
+    ///
+    /// All [image_textures](#structfield.image_textures) are swizzled and packed into the streams of the new [Msrd].
+    /// High resolution textures for Xenoblade 3 models that use the `chr/tex/nx` folder
+    /// are also stored in the [Msrd] to avoid modifying textures shared with other models.
+    pub fn to_mxmd_model(
+        &self,
+        mxmd: &Mxmd,
+        msrd: &Msrd,
+    ) -> Result<(Mxmd, Msrd), CreateMxmdModelError> {
+        self.to_mxmd_model_with_level(mxmd, msrd, CompressionLevel::Default)
+    }
+
+    /// Apply the values from this model onto the original `mxmd` and `msrd`
+    /// and compress the streams using the specified `level`.
+    /// See [ModelRoot::to_mxmd_model] and [Msrd::from_extracted_files_with_level] for details.
+    pub fn to_mxmd_model_with_level(
+        &self,
+        mxmd: &Mxmd,
+        msrd: &Msrd,
+        level: CompressionLevel,
+    ) -> Result<(Mxmd, Msrd), CreateMxmdModelError> {
         // TODO: Does this need to even extract vertex/textures?
-        let (_, spch, _) = msrd.extract_files(None).unwrap();
+        let (_, spch, _) = msrd.extract_files(None)?;
 
-        let textures: Vec<_> = self
+        let textures = self
             .image_textures
             .iter()
-            .map(ImageTexture::extracted_texture)
-            .collect();
+            .enumerate()
+            .map(|(index, image)| {
+                ImageTexture::extracted_texture(image)
+                    .map_err(|source| CreateMxmdModelError::Texture { index, source })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
 
-        let new_vertex = self.buffers.to_vertex_data().unwrap();
+        let new_vertex = self
+            .buffers
+            .to_vertex_data(false, false)
+            .map_err(CreateMxmdModelError::VertexData)?;
 
         let mut new_mxmd = mxmd.clone();
 
         // TODO: Rebuild materials.
+        apply_materials(&mut new_mxmd.materials.materials, &self.models.materials);
+        apply_shader_vars(
+            &mut new_mxmd.materials.shader_vars,
+            &new_mxmd.materials.materials,
+            &self.models.materials,
+        );
+
         // TODO: How many of these mesh fields can use a default value?
         new_mxmd.models.models = self
             .models
             .models
             .iter()
-            .map(|model| xc3_lib::mxmd::Model {
+            .enumerate()
+            .map(|(i, model)| xc3_lib::mxmd::Model {
                 meshes: model
                     .meshes
                     .iter()
@@ -602,7 +1467,7 @@ impl ModelRoot {
                         material_index: m.material_index as u16,
                         unk2: 0,
                         unk3: 0,
-                        ext_mesh_index: 0, // TODO: add field to mesh?
+                        ext_mesh_index: m.ext_mesh_index.unwrap_or_default() as u16,
                         unk4: 0,
                         unk5: 0,
                         lod: m.lod,
@@ -618,7 +1483,14 @@ impl ModelRoot {
                 min_xyz: model.min_xyz.to_array(),
                 bounding_radius: model.bounding_radius,
                 unks1: [0; 3],
-                unk2: mxmd.models.models[0].unk2,
+                // Preserve the value from the original model if present.
+                unk2: mxmd
+                    .models
+                    .models
+                    .get(i)
+                    .or(mxmd.models.models.first())
+                    .map(|m| m.unk2)
+                    .unwrap_or_default(),
                 unks: [0; 3],
             })
             .collect();
@@ -643,12 +1515,643 @@ impl ModelRoot {
             .map(|s| s.inner.has_chr_textures())
             .unwrap_or_default();
 
-        let new_msrd =
-            Msrd::from_extracted_files(&new_vertex, &spch, &textures, use_chr_textures).unwrap();
+        let new_msrd = Msrd::from_extracted_files_with_level(
+            &new_vertex,
+            &spch,
+            &textures,
+            use_chr_textures,
+            level,
+        )?;
         new_mxmd.streaming = Some(new_msrd.streaming.clone());
 
-        (new_mxmd, new_msrd)
+        Ok((new_mxmd, new_msrd))
+    }
+
+    /// Apply the values from this model onto the original Xenoblade X `mxmd`.
+    ///
+    /// This only patches the vertex and index buffers using [ModelBuffers::to_vertex_data_legacy],
+    /// so vertices and indices can be edited but not added or removed.
+    /// All other values and the `.casmt` textures are unchanged.
+    ///
+    /// [MxmdLegacy] can't be written yet, so use [ModelRoot::to_camdo_legacy] to save the result.
+    pub fn to_mxmd_model_legacy(
+        &self,
+        mxmd: &MxmdLegacy,
+    ) -> Result<MxmdLegacy, VertexDataLegacyError> {
+        let mut new_mxmd = mxmd.clone();
+        new_mxmd.vertex = self.buffers.to_vertex_data_legacy(&mxmd.vertex)?;
+        Ok(new_mxmd)
+    }
+
+    /// Apply the values from this model onto the original `.camdo` file data for `mxmd`.
+    /// See [ModelRoot::to_mxmd_model_legacy] for details.
+    ///
+    /// The new buffer data is written over the original data at the same offsets,
+    /// so all other bytes of `camdo` are unchanged.
+    /// The original `.casmt` should be used unchanged.
+    pub fn to_camdo_legacy(
+        &self,
+        mxmd: &MxmdLegacy,
+        camdo: &[u8],
+    ) -> Result<Vec<u8>, VertexDataLegacyError> {
+        // The vertex data offset follows the magic, version, models, materials, and unk1.
+        let vertex_data_offset = camdo
+            .get(20..24)
+            .map(|b| u32::from_be_bytes(b.try_into().unwrap()))
+            .ok_or(VertexDataLegacyError::DataOutOfRange {
+                offset: 20,
+                size: 4,
+                len: camdo.len(),
+            })?;
+
+        let mut new_camdo = camdo.to_vec();
+        self.buffers.write_vertex_data_legacy(
+            &mxmd.vertex,
+            vertex_data_offset as usize,
+            &mut new_camdo,
+        )?;
+        Ok(new_camdo)
+    }
+
+    /// Apply the bone transforms from [skeleton](#structfield.skeleton)
+    /// onto the original `.chr` or `.arc` file used to initialize this model.
+    ///
+    /// Only bones from the original skeleton with the same name and parent are updated.
+    /// Adding, removing, or renaming bones is not supported.
+    /// Entries other than the skeleton are retained as is.
+    /// Entries that fail to parse are also retained as is with a warning.
+    ///
+    /// # Examples
+    /// ``` rust no_run
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// use xc3_lib::sar1::Sar1;
+    ///
+    /// let chr = Sar1::from_file("ch01012010.chr")?;
+    /// let root = xc3_model::load_model("ch01012013.wimdo", None)?;
+    ///
+    /// // Apply edits to the skeleton.
+    ///
+    /// let new_chr = root.to_chr(&chr)?;
+    /// new_chr.save("ch01012010.chr")?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn to_chr(&self, chr: &Sar1) -> std::io::Result<Sar1> {
+        let mut new_chr = chr.clone();
+
+        if let Some(skeleton) = &self.skeleton {
+            for (i, entry) in new_chr.entries.iter_mut().enumerate() {
+                match entry.read_data::<xc3_lib::bc::Bc>() {
+                    Ok(mut bc) => {
+                        if let xc3_lib::bc::BcData::Skel(skel) = &mut bc.data {
+                            apply_skeleton(&mut skel.skeleton, skeleton);
+                            *entry = xc3_lib::sar1::Entry::new(entry.name.clone(), &bc)?;
+                        }
+                    }
+                    Err(e) => warn!(
+                        "Skipping skeleton edits for chr entry {i} {:?}: {e}",
+                        entry.name
+                    ),
+                }
+            }
+        }
+
+        Ok(new_chr)
     }
+
+    /// Apply the values from this model onto the original `mxmd` and `msrd`
+    /// and write the `.wimdo` and `.wismt` file data.
+    /// See [ModelRoot::to_mxmd_model] for details.
+    ///
+    /// Streams are compressed with the same settings used by [Msrd::from_extracted_files].
+    /// Use [ModelRoot::write_wimdo_wismt_with_level] to choose the compression level.
+    ///
+    /// # Examples
+    /// ``` rust no_run
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// use xc3_lib::{msrd::Msrd, mxmd::Mxmd};
+    ///
+    /// let mxmd = Mxmd::from_file("ch01011013.wimdo")?;
+    /// let msrd = Msrd::from_file("ch01011013.wismt")?;
+    /// let root = xc3_model::load_model("ch01011013.wimdo", None)?;
+    ///
+    /// // Apply edits to the model.
+    ///
+    /// let (wimdo, wismt) = root.write_wimdo_wismt(&mxmd, &msrd)?;
+    /// std::fs::write("ch01011013.wimdo", wimdo)?;
+    /// std::fs::write("ch01011013.wismt", wismt)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn write_wimdo_wismt(
+        &self,
+        mxmd: &Mxmd,
+        msrd: &Msrd,
+    ) -> Result<(Vec<u8>, Vec<u8>), WriteWimdoWismtError> {
+        self.write_wimdo_wismt_with_level(mxmd, msrd, CompressionLevel::Default)
+    }
+
+    /// Apply the values from this model onto the original `mxmd` and `msrd`
+    /// and write the `.wimdo` and `.wismt` file data using the specified compression `level`.
+    /// See [ModelRoot::write_wimdo_wismt] for details.
+    pub fn write_wimdo_wismt_with_level(
+        &self,
+        mxmd: &Mxmd,
+        msrd: &Msrd,
+        level: CompressionLevel,
+    ) -> Result<(Vec<u8>, Vec<u8>), WriteWimdoWismtError> {
+        let (new_mxmd, new_msrd) = self.to_mxmd_model_with_level(mxmd, msrd, level)?;
+
+        let mut wimdo = Cursor::new(Vec::new());
+        new_mxmd.write(&mut wimdo)?;
+
+        let mut wismt = Cursor::new(Vec::new());
+        new_msrd.write(&mut wismt)?;
+
+        Ok((wimdo.into_inner(), wismt.into_inner()))
+    }
+
+    /// Remove all meshes that are not part of the base level of detail (LOD)
+    /// and any vertex or index buffers that are no longer used.
+    /// Buffer indices for the remaining meshes are updated to match.
+    pub fn keep_highest_lod(&mut self) {
+        self.models.keep_highest_lod();
+
+        let meshes = || self.models.models.iter().flat_map(|m| m.meshes.iter());
+        let used_vertex_buffers: BTreeSet<_> = meshes().map(|m| m.vertex_buffer_index).collect();
+        let used_index_buffers: BTreeSet<_> = meshes().map(|m| m.index_buffer_index).collect();
+
+        let vertex_buffer_indices =
+            retain_used(&mut self.buffers.vertex_buffers, &used_vertex_buffers);
+        let index_buffer_indices =
+            retain_used(&mut self.buffers.index_buffers, &used_index_buffers);
+
+        for model in &mut self.models.models {
+            for mesh in &mut model.meshes {
+                mesh.vertex_buffer_index = vertex_buffer_indices[mesh.vertex_buffer_index];
+                mesh.index_buffer_index = index_buffer_indices[mesh.index_buffer_index];
+            }
+        }
+    }
+
+    /// Update material texture indices after removing the textures at the original indices in `removed`
+    /// from [image_textures](#structfield.image_textures).
+    ///
+    /// Indices for the remaining textures are shifted to match.
+    /// Indices that refer to removed or out of range textures use an [ImageTexture::fallback]
+    /// added to the end of [image_textures](#structfield.image_textures) if needed.
+    pub fn repair_texture_indices(&mut self, removed: &[usize]) {
+        let removed: BTreeSet<_> = removed.iter().copied().collect();
+        let old_count = self.image_textures.len() + removed.len();
+
+        let mut fallback_index = None;
+        for (material_index, material) in self.models.materials.iter_mut().enumerate() {
+            for texture in &mut material.textures {
+                let index = texture.image_texture_index;
+                if index < old_count && !removed.contains(&index) {
+                    texture.image_texture_index = index - removed.range(..index).count();
+                } else {
+                    warn!(
+                        "Replacing removed texture {index} for material {material_index} with a fallback texture"
+                    );
+                    texture.image_texture_index = *fallback_index.get_or_insert_with(|| {
+                        self.image_textures.push(ImageTexture::fallback());
+                        self.image_textures.len() - 1
+                    });
+                }
+            }
+        }
+    }
+
+    /// Replace the texture at `index` in [image_textures](#structfield.image_textures)
+    /// with the image file at `path` encoded to the same [ImageFormat].
+    /// See [ImageTexture::with_image_file] for supported files.
+    ///
+    /// Logs a warning if the new dimensions are not powers of two
+    /// or have a different aspect ratio than the original texture.
+    pub fn replace_texture(
+        &mut self,
+        index: usize,
+        path: &Path,
+    ) -> Result<(), ReplaceTextureError> {
+        let count = self.image_textures.len();
+        let texture = self
+            .image_textures
+            .get_mut(index)
+            .ok_or(ReplaceTextureError::IndexOutOfRange { index, count })?;
+
+        let new_texture = texture.with_image_file(path)?;
+
+        let (width, height) = (new_texture.width, new_texture.height);
+        if (width, height) != (texture.width, texture.height) {
+            if !width.is_power_of_two() || !height.is_power_of_two() {
+                warn!(
+                    "Replacement for texture {index} has non power of two dimensions {width}x{height}"
+                );
+            }
+            // Compare ratios without rounding by cross multiplying.
+            if width as u64 * texture.height as u64 != height as u64 * texture.width as u64 {
+                warn!(
+                    "Replacement for texture {index} changes the aspect ratio from {}x{} to {width}x{height}",
+                    texture.width, texture.height
+                );
+            }
+        }
+
+        *texture = new_texture;
+        Ok(())
+    }
+
+    /// Create a new root containing only the mesh at `mesh_index` for the model at `model_index`
+    /// or [None] if the mesh or its material or buffer indices are out of range.
+    ///
+    /// The new root has a single [Model] and [Mesh] with the mesh's [Material]
+    /// and only the textures and samplers used by that material.
+    /// The vertex and index buffers contain only the vertices used by the mesh.
+    /// The skin weights and [skeleton](#structfield.skeleton) are preserved,
+    /// so the extracted mesh can still be animated.
+    pub fn extract_mesh(&self, model_index: usize, mesh_index: usize) -> Option<ModelRoot> {
+        let model = self.models.models.get(model_index)?;
+        let mesh = model.meshes.get(mesh_index)?;
+        if !mesh
+            .index_errors(model_index, mesh_index, &self.models, &self.buffers)
+            .is_empty()
+        {
+            return None;
+        }
+
+        let (vertex_buffer, index_buffer) = self.buffers.vertex_buffers[mesh.vertex_buffer_index]
+            .compact(&self.buffers.index_buffers[mesh.index_buffer_index].indices);
+
+        let mut material = self.models.materials[mesh.material_index].clone();
+
+        // Add textures and samplers in the order they are first used by the material.
+        let mut image_textures = Vec::new();
+        let mut image_texture_indices = BTreeMap::new();
+        let mut samplers = Vec::new();
+        let mut sampler_indices = BTreeMap::new();
+        for texture in &mut material.textures {
+            if let Some(image_texture) = self.image_textures.get(texture.image_texture_index) {
+                texture.image_texture_index = *image_texture_indices
+                    .entry(texture.image_texture_index)
+                    .or_insert_with(|| {
+                        image_textures.push(image_texture.clone());
+                        image_textures.len() - 1
+                    });
+            }
+            if let Some(sampler) = self.models.samplers.get(texture.sampler_index) {
+                texture.sampler_index = *sampler_indices
+                    .entry(texture.sampler_index)
+                    .or_insert_with(|| {
+                        samplers.push(sampler.clone());
+                        samplers.len() - 1
+                    });
+            }
+        }
+
+        let ext_mesh_name = mesh.ext_mesh_name(&self.models);
+
+        let new_mesh = Mesh {
+            vertex_buffer_index: 0,
+            index_buffer_index: 0,
+            material_index: 0,
+            ext_mesh_index: ext_mesh_name.map(|_| 0),
+            ..mesh.clone()
+        };
+
+        Some(ModelRoot {
+            models: Models {
+                models: vec![Model {
+                    meshes: vec![new_mesh],
+                    instances: model.instances.clone(),
+                    model_buffers_index: 0,
+                    max_xyz: model.max_xyz,
+                    min_xyz: model.min_xyz,
+                    bounding_radius: model.bounding_radius,
+                }],
+                materials: vec![material],
+                samplers,
+                base_lod_indices: self.models.base_lod_indices.clone(),
+                lod_info: self.models.lod_info.clone(),
+                morph_controller_names: self.models.morph_controller_names.clone(),
+                animation_morph_names: self.models.animation_morph_names.clone(),
+                morph_controller_animation_indices: self
+                    .models
+                    .morph_controller_animation_indices
+                    .clone(),
+                ext_mesh_names: ext_mesh_name
+                    .map(|n| vec![n.to_string()])
+                    .unwrap_or_default(),
+                ext_mesh_start_hidden: ext_mesh_name
+                    .map(|_| vec![mesh.is_initially_hidden(&self.models)])
+                    .unwrap_or_default(),
+                max_xyz: model.max_xyz,
+                min_xyz: model.min_xyz,
+            },
+            buffers: ModelBuffers {
+                vertex_buffers: vec![vertex_buffer],
+                outline_buffers: Vec::new(),
+                index_buffers: vec![index_buffer],
+                unk_buffers: Vec::new(),
+                // Weight indices still refer to the original skin weights.
+                weights: self.buffers.weights.clone(),
+            },
+            image_textures,
+            skeleton: self.skeleton.clone(),
+            embedded_shaders: self.embedded_shaders.clone(),
+            game_version: self.game_version,
+        })
+    }
+
+    /// Summary counts for all meshes and textures in this model.
+    /// Vertex and triangle counts include all levels of detail (LOD).
+    /// See [ModelRoot::base_lod_statistics] for only counting the base LOD.
+    pub fn statistics(&self) -> ModelStats {
+        self.mesh_statistics(|_| true)
+    }
+
+    /// Summary counts like [ModelRoot::statistics]
+    /// but only counting meshes selected by [should_render_lod].
+    pub fn base_lod_statistics(&self) -> ModelStats {
+        self.mesh_statistics(|mesh| should_render_lod(mesh.lod, &self.models.base_lod_indices))
+    }
+
+    fn mesh_statistics(&self, include_mesh: impl Fn(&Mesh) -> bool) -> ModelStats {
+        let mut vertex_count = 0;
+        let mut triangle_count = 0;
+        for model in &self.models.models {
+            let meshes: Vec<_> = model.meshes.iter().filter(|m| include_mesh(m)).collect();
+
+            // Meshes may share vertex buffers, so only count each buffer once.
+            let vertex_buffers: BTreeSet<_> =
+                meshes.iter().map(|m| m.vertex_buffer_index).collect();
+            let vertices: usize = vertex_buffers
+                .iter()
+                .filter_map(|i| self.buffers.vertex_buffers.get(*i))
+                .map(|b| b.vertex_count())
+                .sum();
+            let triangles: usize = meshes
+                .iter()
+                .filter_map(|m| self.buffers.index_buffers.get(m.index_buffer_index))
+                .map(|b| b.indices.len() / 3)
+                .sum();
+
+            vertex_count += vertices * model.instances.len();
+            triangle_count += triangles * model.instances.len();
+        }
+
+        ModelStats {
+            vertex_count,
+            triangle_count,
+            material_count: self.models.materials.len(),
+            texture_count: self.image_textures.len(),
+            texture_size: self.image_textures.iter().map(|t| t.image_data.len()).sum(),
+            bone_count: self
+                .skeleton
+                .as_ref()
+                .map(|s| s.bones.len())
+                .unwrap_or_default(),
+        }
+    }
+}
+
+/// Summary counts for a [ModelRoot] from [ModelRoot::statistics] or [ModelRoot::base_lod_statistics].
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub struct ModelStats {
+    /// The number of vertices in the vertex buffers used by each [Model] times its number of instances.
+    pub vertex_count: usize,
+    /// The number of triangles for each [Mesh] times the number of instances for its [Model].
+    pub triangle_count: usize,
+    /// The number of [materials](struct.Models.html#structfield.materials).
+    pub material_count: usize,
+    /// The number of [image_textures](struct.ModelRoot.html#structfield.image_textures).
+    pub texture_count: usize,
+    /// The total size in bytes of the image data for all textures.
+    pub texture_size: usize,
+    /// The number of bones in the [Skeleton] or `0` if there is no skeleton.
+    pub bone_count: usize,
+}
+
+impl ModelGroup {
+    /// Create a copy with each [Model] duplicated once for each of its
+    /// [instances](struct.Model.html#structfield.instances).
+    /// Each copy has a single identity instance
+    /// and uses new vertex buffers with the instance transform already applied.
+    ///
+    /// This increases memory usage but is useful for formats or applications
+    /// that don't support instancing.
+    pub fn expand_instances(&self) -> Self {
+        let mut buffers = self.buffers.clone();
+
+        let models = self
+            .models
+            .iter()
+            .map(|models| {
+                let mut new_models = Vec::new();
+                for model in &models.models {
+                    for transform in &model.instances {
+                        let model_buffers = &mut buffers[model.model_buffers_index];
+                        new_models.push(expand_instance(model, *transform, model_buffers));
+                    }
+                }
+
+                Models {
+                    models: new_models,
+                    ..models.clone()
+                }
+            })
+            .collect();
+
+        Self { models, buffers }
+    }
+}
+
+fn expand_instance(model: &Model, transform: Mat4, buffers: &mut ModelBuffers) -> Model {
+    // Meshes may share vertex buffers, so only transform each buffer once.
+    let mut new_buffer_indices = BTreeMap::new();
+    let meshes = model
+        .meshes
+        .iter()
+        .map(|mesh| {
+            let vertex_buffer_index = *new_buffer_indices
+                .entry(mesh.vertex_buffer_index)
+                .or_insert_with(|| {
+                    let mut buffer = buffers.vertex_buffers[mesh.vertex_buffer_index].clone();
+                    buffer.transform(transform);
+                    buffers.vertex_buffers.push(buffer);
+                    buffers.vertex_buffers.len() - 1
+                });
+            Mesh {
+                vertex_buffer_index,
+                ..mesh.clone()
+            }
+        })
+        .collect();
+
+    // Find the new bounds containing the transformed corners of the original bounds.
+    let (min_xyz, max_xyz) = (0..8)
+        .map(|i| {
+            let corner = Vec3::new(
+                if i & 1 == 0 {
+                    model.min_xyz.x
+                } else {
+                    model.max_xyz.x
+                },
+                if i & 2 == 0 {
+                    model.min_xyz.y
+                } else {
+                    model.max_xyz.y
+                },
+                if i & 4 == 0 {
+                    model.min_xyz.z
+                } else {
+                    model.max_xyz.z
+                },
+            );
+            transform.transform_point3(corner)
+        })
+        .fold((Vec3::INFINITY, Vec3::NEG_INFINITY), |(min, max), p| {
+            (min.min(p), max.max(p))
+        });
+    let (scale, _, _) = transform.to_scale_rotation_translation();
+
+    Model {
+        meshes,
+        instances: vec![Mat4::IDENTITY],
+        model_buffers_index: model.model_buffers_index,
+        max_xyz,
+        min_xyz,
+        bounding_radius: model.bounding_radius * scale.abs().max_element(),
+    }
+}
+
+fn merge_meshes_by_material(model: &mut Model, buffers: &mut ModelBuffers) {
+    // Skinning depends on the flags and material, so grouped meshes use the same weights.
+    let mut groups: Vec<Vec<Mesh>> = Vec::new();
+    for mesh in std::mem::take(&mut model.meshes) {
+        match groups.iter_mut().find(|g| {
+            let m = &g[0];
+            m.material_index == mesh.material_index
+                && m.lod == mesh.lod
+                && m.flags1 == mesh.flags1
+                && m.flags2 == mesh.flags2
+                && m.ext_mesh_index == mesh.ext_mesh_index
+        }) {
+            Some(group) => group.push(mesh),
+            None => groups.push(vec![mesh]),
+        }
+    }
+
+    for group in groups {
+        if group.len() == 1 {
+            model.meshes.extend(group);
+            continue;
+        }
+
+        let mut merged: Option<(Mesh, vertex::VertexBuffer, Vec<u16>)> = None;
+        let mut merged_count = 0;
+        for mesh in group {
+            let vertex_buffer = &buffers.vertex_buffers[mesh.vertex_buffer_index];
+            if !vertex_buffer.morph_targets.is_empty()
+                || vertex_buffer.outline_buffer_index.is_some()
+            {
+                warn!(
+                    "Skipping mesh with morph targets or outlines for material {}",
+                    mesh.material_index
+                );
+                model.meshes.push(mesh);
+                continue;
+            }
+
+            let indices = &buffers.index_buffers[mesh.index_buffer_index].indices;
+            let (new_vertices, new_indices) = vertex_buffer.compact(indices);
+            match &mut merged {
+                Some((_, vertices, indices)) => {
+                    if vertices.append(indices, &new_vertices, &new_indices.indices) {
+                        merged_count += 1;
+                    } else {
+                        warn!(
+                            "Skipping mesh with incompatible attributes for material {}",
+                            mesh.material_index
+                        );
+                        model.meshes.push(mesh);
+                    }
+                }
+                None => {
+                    merged = Some((mesh, new_vertices, new_indices.indices));
+                    merged_count = 1;
+                }
+            }
+        }
+
+        if let Some((mesh, vertices, indices)) = merged {
+            if merged_count > 1 {
+                buffers.vertex_buffers.push(vertices);
+                buffers.index_buffers.push(vertex::IndexBuffer { indices });
+                model.meshes.push(Mesh {
+                    vertex_buffer_index: buffers.vertex_buffers.len() - 1,
+                    index_buffer_index: buffers.index_buffers.len() - 1,
+                    ..mesh
+                });
+            } else {
+                model.meshes.push(mesh);
+            }
+        }
+    }
+
+    let (min_xyz, max_xyz) = model
+        .meshes
+        .iter()
+        .flat_map(|m| &buffers.vertex_buffers[m.vertex_buffer_index].attributes)
+        .filter_map(|a| match a {
+            vertex::AttributeData::Position(values) => Some(values),
+            _ => None,
+        })
+        .flatten()
+        .fold((Vec3::INFINITY, Vec3::NEG_INFINITY), |(min, max), p| {
+            (min.min(*p), max.max(*p))
+        });
+    if min_xyz.cmple(max_xyz).all() {
+        model.min_xyz = min_xyz;
+        model.max_xyz = max_xyz;
+    }
+}
+
+fn mesh_render_pass_order(pass: MeshRenderPass) -> u8 {
+    match pass {
+        MeshRenderPass::Unk1 => 0,
+        MeshRenderPass::Unk0 => 1,
+        MeshRenderPass::Unk4 => 2,
+        MeshRenderPass::Unk8 => 3,
+        MeshRenderPass::Unk2 => 4,
+    }
+}
+
+/// Remove items not in `used_indices` and return the new index for each original index.
+fn retain_used<T>(items: &mut Vec<T>, used_indices: &BTreeSet<usize>) -> Vec<usize> {
+    let mut new_indices = vec![0; items.len()];
+    let mut new_index = 0;
+    let mut i = 0;
+    items.retain(|_| {
+        let keep = used_indices.contains(&i);
+        if keep {
+            new_indices[i] = new_index;
+            new_index += 1;
+        }
+        i += 1;
+        keep
+    });
+    new_indices
+}
+
+fn morph_controller_animation_indices(controllers: &xc3_lib::mxmd::MorphControllers) -> Vec<usize> {
+    // TODO: unk2 is assumed to index ModelUnk1 items but hasn't been confirmed for all models.
+    controllers
+        .controllers
+        .iter()
+        .map(|c| c.unk2 as usize)
+        .collect()
 }
 
 fn load_skeleton_legacy(mxmd: &MxmdLegacy) -> Skeleton {
@@ -663,6 +2166,7 @@ fn load_skeleton_legacy(mxmd: &MxmdLegacy) -> Skeleton {
                 parent_index: b.parent_index.try_into().ok(),
             })
             .collect(),
+        procedural_bones: Vec::new(),
     }
 }
 
@@ -730,18 +2234,63 @@ enum Wimdo {
     Apmd(Apmd),
 }
 
-fn load_wimdo(wimdo_path: &Path) -> Result<Mxmd, LoadModelError> {
+/// Load the raw entries from the [Apmd] archive in a `.wimdo` file.
+/// This includes the [Mxmd] used by [load_model] as well as any additional data.
+///
+/// Returns an empty list if the `.wimdo` contains only an [Mxmd].
+/// Use [Entry::read_data](xc3_lib::apmd::Entry::read_data) to parse the entry data.
+///
+/// # Examples
+/// ``` rust no_run
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let entries = xc3_model::load_apmd_entries("xeno3/chr/oj/oj03010100.wimdo")?;
+/// for entry in entries {
+///     println!("{:?}: {} bytes", entry.entry_type, entry.entry_data.len());
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub fn load_apmd_entries<P: AsRef<Path>>(
+    wimdo_path: P,
+) -> Result<Vec<xc3_lib::apmd::Entry>, LoadModelError> {
+    match read_wimdo(wimdo_path.as_ref())? {
+        Wimdo::Mxmd(_) => Ok(Vec::new()),
+        Wimdo::Apmd(apmd) => Ok(apmd.entries),
+    }
+}
+
+fn read_wimdo(wimdo_path: &Path) -> Result<Wimdo, LoadModelError> {
     let mut reader = Cursor::new(
         std::fs::read(wimdo_path).map_err(|e| LoadModelError::Wimdo {
             path: wimdo_path.to_owned(),
             source: e.into(),
         })?,
     );
-    let wimdo: Wimdo = reader.read_le().map_err(|e| LoadModelError::Wimdo {
+    reader.read_le().map_err(|e| LoadModelError::Wimdo {
         path: wimdo_path.to_owned(),
         source: e,
-    })?;
-    match wimdo {
+    })
+}
+
+/// Load the [Mxmd] from a `.wimdo` file used by [load_model].
+///
+/// Some `.wimdo` files for maps and objects store the [Mxmd] as an entry in an [Apmd] archive.
+/// These models typically have no `.wismt` and embed their vertex data and textures in the [Mxmd].
+///
+/// # Examples
+/// ``` rust no_run
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let mxmd = xc3_model::load_mxmd("xeno3/chr/oj/oj03010100.wimdo")?;
+/// println!("{}", mxmd.vertex_data.is_some());
+/// # Ok(())
+/// # }
+/// ```
+pub fn load_mxmd<P: AsRef<Path>>(wimdo_path: P) -> Result<Mxmd, LoadModelError> {
+    load_wimdo(wimdo_path.as_ref())
+}
+
+fn load_wimdo(wimdo_path: &Path) -> Result<Mxmd, LoadModelError> {
+    match read_wimdo(wimdo_path)? {
         Wimdo::Mxmd(mxmd) => Ok(*mxmd),
         Wimdo::Apmd(apmd) => apmd
             .entries
@@ -771,6 +2320,41 @@ pub struct StreamingData<'a> {
 }
 
 impl<'a> StreamingData<'a> {
+    /// Load only the vertex data without decompressing any textures.
+    /// The returned [textures](#structfield.textures) will always be empty.
+    pub fn vertex_only(
+        mxmd: &'a Mxmd,
+        wismt_path: &Path,
+        is_pc: bool,
+    ) -> Result<StreamingData<'a>, LoadModelError> {
+        let textures = if is_pc {
+            ExtractedTextures::Pc(Vec::new())
+        } else {
+            ExtractedTextures::Switch(Vec::new())
+        };
+
+        match mxmd.streaming.as_ref().map(|s| &s.inner) {
+            Some(xc3_lib::msrd::StreamingInner::Streaming(_)) => {
+                let msrd = Msrd::from_file(wismt_path).map_err(LoadModelError::Wismt)?;
+                let vertex = vertex_data_from_msrd(&msrd)?;
+
+                Ok(StreamingData {
+                    vertex: Cow::Owned(vertex),
+                    textures,
+                })
+            }
+            // Legacy streaming data only stores textures in the wismt.
+            Some(xc3_lib::msrd::StreamingInner::StreamingLegacy(_)) | None => Ok(StreamingData {
+                vertex: Cow::Borrowed(
+                    mxmd.vertex_data
+                        .as_ref()
+                        .ok_or(LoadModelError::MissingMxmdVertexData)?,
+                ),
+                textures,
+            }),
+        }
+    }
+
     pub fn new(
         mxmd: &'a Mxmd,
         wismt_path: &Path,
@@ -855,6 +2439,10 @@ enum AnimFile {
 
 /// Load all animations from a `.anm`, `.mot`, or `.motstm_data` file.
 ///
+/// `.mot` files are [Sar1] archives that may be compressed in [Xbc1](xc3_lib::xbc1::Xbc1).
+/// `.anm` and `.motstm_data` files store a single uncompressed [Bc]
+/// and don't require any additional decompression.
+///
 /// # Examples
 /// ``` rust no_run
 /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
@@ -875,42 +2463,138 @@ enum AnimFile {
 pub fn load_animations<P: AsRef<Path>>(
     anim_path: P,
 ) -> Result<Vec<Animation>, DecompressStreamError> {
-    let mut reader = Cursor::new(std::fs::read(anim_path)?);
-    let anim_file: AnimFile = reader.read_le()?;
+    let animations = load_named_animations(anim_path)?;
+    Ok(animations.into_iter().map(|(_, a)| a).collect())
+}
 
+/// Load all animations from a `.anm`, `.mot`, or `.motstm_data` file
+/// with the [Sar1] entry name for each animation.
+///
+/// Standalone `.anm` or `.motstm_data` files use the [name](animation::Animation::name)
+/// stored in the animation data instead.
+///
+/// # Examples
+/// ``` rust no_run
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// // Mio military uniform
+/// let animations = xc3_model::load_named_animations("xeno3/chr/ch/ch01027000_event.mot")?;
+/// for (name, _) in &animations {
+///     println!("{name}");
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub fn load_named_animations<P: AsRef<Path>>(
+    anim_path: P,
+) -> Result<Vec<(String, Animation)>, DecompressStreamError> {
     let mut animations = Vec::new();
+    for (name, bc) in load_anim_entries(anim_path)? {
+        if let Some(animation) = bc_animation(bc?) {
+            animations.push((name.unwrap_or_else(|| animation.name.clone()), animation));
+        }
+    }
+    Ok(animations)
+}
+
+/// The results of loading an animation file with [load_animations_verbose].
+#[derive(Debug)]
+pub struct AnimationsReport {
+    /// The animations with the name for each animation like [load_named_animations].
+    pub animations: Vec<(String, Animation)>,
+    /// The names of entries that were read successfully but do not contain animation data.
+    pub skipped_entries: Vec<String>,
+    /// The entries that could not be read.
+    pub entry_errors: Vec<AnimationEntryError>,
+}
+
+#[derive(Debug, Error)]
+#[error("error reading animation entry {name:?}")]
+pub struct AnimationEntryError {
+    pub name: String,
+    #[source]
+    pub source: binrw::Error,
+}
+
+/// Load all animations from a `.anm`, `.mot`, or `.motstm_data` file
+/// like [load_named_animations] while reporting entries that could not be loaded.
+///
+/// Unlike [load_named_animations], entries that fail to parse do not prevent
+/// loading the remaining animations. This can help diagnose files with no animations.
+/// Errors reading or decompressing the file itself are still returned as an error.
+///
+/// # Examples
+/// ``` rust no_run
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// // Mio military uniform
+/// let report = xc3_model::load_animations_verbose("xeno3/chr/ch/ch01027000_event.mot")?;
+/// for error in &report.entry_errors {
+///     println!("{error}: {}", error.source);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub fn load_animations_verbose<P: AsRef<Path>>(
+    anim_path: P,
+) -> Result<AnimationsReport, DecompressStreamError> {
+    let mut report = AnimationsReport {
+        animations: Vec::new(),
+        skipped_entries: Vec::new(),
+        entry_errors: Vec::new(),
+    };
+
+    for (name, bc) in load_anim_entries(anim_path)? {
+        match bc {
+            Ok(bc) => match bc_animation(bc) {
+                Some(animation) => report
+                    .animations
+                    .push((name.unwrap_or_else(|| animation.name.clone()), animation)),
+                None => report.skipped_entries.push(name.unwrap_or_default()),
+            },
+            Err(e) => report.entry_errors.push(AnimationEntryError {
+                name: name.unwrap_or_default(),
+                source: e,
+            }),
+        }
+    }
+
+    Ok(report)
+}
+
+// Standalone BC files don't have an entry name.
+type AnimEntry = (Option<String>, binrw::BinResult<Bc>);
+
+fn load_anim_entries<P: AsRef<Path>>(
+    anim_path: P,
+) -> Result<Vec<AnimEntry>, DecompressStreamError> {
+    let mut reader = Cursor::new(std::fs::read(anim_path)?);
+    let anim_file: AnimFile = reader.read_le()?;
 
     // Most animations are in sar1 archives.
     // Xenoblade 1 DE compresses the sar1 archive.
     // Some animations are in standalone BC files.
     match anim_file {
         AnimFile::Sar1(sar1) => match sar1 {
-            MaybeXbc1::Uncompressed(sar1) => {
-                for entry in &sar1.entries {
-                    let bc = entry.read_data::<xc3_lib::bc::Bc>()?;
-                    add_bc_animations(&mut animations, bc);
-                }
-            }
+            MaybeXbc1::Uncompressed(sar1) => Ok(sar1_entries(&sar1)),
             MaybeXbc1::Xbc1(xbc1) => {
                 let sar1: Sar1 = xbc1.extract()?;
-                for entry in &sar1.entries {
-                    let bc = entry.read_data::<xc3_lib::bc::Bc>()?;
-                    add_bc_animations(&mut animations, bc);
-                }
+                Ok(sar1_entries(&sar1))
             }
         },
-        AnimFile::Bc(bc) => {
-            add_bc_animations(&mut animations, bc);
-        }
+        AnimFile::Bc(bc) => Ok(vec![(None, Ok(bc))]),
     }
+}
 
-    Ok(animations)
+fn sar1_entries(sar1: &Sar1) -> Vec<AnimEntry> {
+    sar1.entries
+        .iter()
+        .map(|entry| (Some(entry.name.clone()), entry.read_data::<Bc>()))
+        .collect()
 }
 
-fn add_bc_animations(animations: &mut Vec<Animation>, bc: Bc) {
-    if let xc3_lib::bc::BcData::Anim(anim) = bc.data {
-        let animation = Animation::from_anim(&anim);
-        animations.push(animation);
+fn bc_animation(bc: Bc) -> Option<Animation> {
+    match bc.data {
+        xc3_lib::bc::BcData::Anim(anim) => Some(Animation::from_anim(&anim)),
+        _ => None,
     }
 }
 
@@ -1019,3 +2703,1328 @@ macro_rules! assert_hex_eq {
         pretty_assertions::assert_str_eq!(hex::encode($a), hex::encode($b))
     };
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use glam::{vec3, Vec4};
+
+    use crate::{
+        test_support::{
+            index_buffer, log_messages, material, mesh, model, model_buffers, model_root, models,
+            mxmd, mxmd_material, positions, sampler, state_flags, vertex_buffer, TempDir,
+        },
+        vertex::{IndexBuffer, VertexBuffer},
+    };
+
+    #[test]
+    fn keep_highest_lod_two_lods() {
+        let mut root = model_root(
+            Models {
+                base_lod_indices: Some(vec![0]),
+                ..models(vec![model(vec![
+                    mesh(0, 0, 1),
+                    mesh(1, 1, 2),
+                    mesh(2, 2, 1),
+                    mesh(1, 3, 2),
+                ])])
+            },
+            model_buffers(
+                vec![vertex_buffer(1), vertex_buffer(2), vertex_buffer(3)],
+                vec![
+                    index_buffer(1),
+                    index_buffer(2),
+                    index_buffer(3),
+                    index_buffer(4),
+                ],
+            ),
+        );
+
+        root.keep_highest_lod();
+
+        assert_eq!(
+            vec![mesh(0, 0, 1), mesh(1, 1, 1)],
+            root.models.models[0].meshes
+        );
+        assert_eq!(
+            vec![vertex_buffer(1), vertex_buffer(3)],
+            root.buffers.vertex_buffers
+        );
+        assert_eq!(
+            vec![index_buffer(1), index_buffer(3)],
+            root.buffers.index_buffers
+        );
+    }
+
+    #[test]
+    fn keep_highest_lod_two_models_without_lod_data() {
+        let mut root = model_root(
+            models(vec![
+                model(vec![mesh(0, 1, 0), mesh(1, 1, 0)]),
+                model(vec![mesh(1, 0, 0)]),
+            ]),
+            model_buffers(
+                vec![vertex_buffer(1), vertex_buffer(2), vertex_buffer(3)],
+                vec![index_buffer(1), index_buffer(2)],
+            ),
+        );
+
+        root.keep_highest_lod();
+
+        // Meshes without LOD data are always kept.
+        // Buffers not used by any model are still removed.
+        assert_eq!(
+            vec![mesh(0, 1, 0), mesh(1, 1, 0)],
+            root.models.models[0].meshes
+        );
+        assert_eq!(vec![mesh(1, 0, 0)], root.models.models[1].meshes);
+        assert_eq!(
+            vec![vertex_buffer(1), vertex_buffer(2)],
+            root.buffers.vertex_buffers
+        );
+        assert_eq!(
+            vec![index_buffer(1), index_buffer(2)],
+            root.buffers.index_buffers
+        );
+    }
+
+    #[test]
+    fn statistics_two_lods_two_instances() {
+        let root = ModelRoot {
+            image_textures: vec![ImageTexture::fallback()],
+            ..model_root(
+                Models {
+                    base_lod_indices: Some(vec![0]),
+                    ..models(vec![Model {
+                        instances: vec![Mat4::IDENTITY, Mat4::from_translation(Vec3::X)],
+                        ..model(vec![mesh(0, 0, 1), mesh(0, 1, 1), mesh(1, 2, 2)])
+                    }])
+                },
+                model_buffers(
+                    vec![vertex_buffer(4), vertex_buffer(5)],
+                    vec![index_buffer(6), index_buffer(3), index_buffer(9)],
+                ),
+            )
+        };
+
+        let texture_size = ImageTexture::fallback().image_data.len();
+        assert_eq!(
+            ModelStats {
+                vertex_count: 18,
+                triangle_count: 12,
+                material_count: 0,
+                texture_count: 1,
+                texture_size,
+                bone_count: 0,
+            },
+            root.statistics()
+        );
+        assert_eq!(
+            ModelStats {
+                vertex_count: 8,
+                triangle_count: 6,
+                material_count: 0,
+                texture_count: 1,
+                texture_size,
+                bone_count: 0,
+            },
+            root.base_lod_statistics()
+        );
+    }
+
+    #[test]
+    fn replace_texture_png() {
+        let dir = TempDir::new("replace_texture_png");
+        let folder = dir.path();
+
+        let path = folder.join("texture.png");
+        image_dds::image::RgbaImage::from_raw(4, 4, [1, 2, 3, 4].repeat(16))
+            .unwrap()
+            .save(&path)
+            .unwrap();
+
+        let mut root = ModelRoot {
+            image_textures: vec![ImageTexture::fallback()],
+            ..model_root(models(Vec::new()), model_buffers(Vec::new(), Vec::new()))
+        };
+
+        let result = root.replace_texture(0, &path);
+        let out_of_range = root.replace_texture(1, &path);
+        result.unwrap();
+
+        assert!(matches!(
+            out_of_range,
+            Err(ReplaceTextureError::IndexOutOfRange { index: 1, count: 1 })
+        ));
+
+        let texture = &root.image_textures[0];
+        assert_eq!(Some("fallback"), texture.name.as_deref());
+        assert_eq!(ImageFormat::R8G8B8A8Unorm, texture.image_format);
+        assert_eq!((4, 4), (texture.width, texture.height));
+        // 4x4, 2x2, and 1x1 mipmaps.
+        assert_eq!(3, texture.mipmap_count);
+        assert_eq!((16 + 4 + 1) * 4, texture.image_data.len());
+        assert_eq!(&[1, 2, 3, 4], &texture.image_data[..4]);
+    }
+
+    #[test]
+    fn extract_mesh_used_textures() {
+        let texture = |name: &str| ImageTexture {
+            name: Some(name.to_string()),
+            ..ImageTexture::fallback()
+        };
+        let material = |name: &str, textures: Vec<Texture>| Material {
+            textures,
+            ..material(name)
+        };
+
+        let mut legs = mesh(1, 1, 0);
+        legs.material_index = 1;
+
+        let root = ModelRoot {
+            image_textures: vec![texture("a"), texture("b"), texture("c")],
+            ..model_root(
+                Models {
+                    materials: vec![
+                        material(
+                            "body",
+                            vec![Texture {
+                                image_texture_index: 0,
+                                sampler_index: 0,
+                            }],
+                        ),
+                        material(
+                            "legs",
+                            vec![
+                                Texture {
+                                    image_texture_index: 2,
+                                    sampler_index: 1,
+                                },
+                                Texture {
+                                    image_texture_index: 1,
+                                    sampler_index: 1,
+                                },
+                                Texture {
+                                    image_texture_index: 2,
+                                    sampler_index: 1,
+                                },
+                            ],
+                        ),
+                    ],
+                    samplers: vec![
+                        sampler(AddressMode::Repeat),
+                        sampler(AddressMode::ClampToEdge),
+                    ],
+                    ..models(vec![model(vec![mesh(0, 0, 0), legs])])
+                },
+                model_buffers(
+                    vec![
+                        vertex_buffer(3),
+                        positions(vec![
+                            vec3(0.0, 0.0, 0.0),
+                            vec3(1.0, 0.0, 0.0),
+                            vec3(2.0, 0.0, 0.0),
+                            vec3(3.0, 0.0, 0.0),
+                        ]),
+                    ],
+                    vec![
+                        index_buffer(3),
+                        IndexBuffer {
+                            indices: vec![3, 1, 3],
+                        },
+                    ],
+                ),
+            )
+        };
+
+        let new_root = root.extract_mesh(0, 1).unwrap();
+
+        assert_eq!(1, new_root.models.models.len());
+        assert_eq!(vec![mesh(0, 0, 0)], new_root.models.models[0].meshes);
+        assert_eq!(1, new_root.models.materials.len());
+        assert_eq!("legs", new_root.models.materials[0].name);
+        assert_eq!(
+            vec![
+                Texture {
+                    image_texture_index: 0,
+                    sampler_index: 0,
+                },
+                Texture {
+                    image_texture_index: 1,
+                    sampler_index: 0,
+                },
+                Texture {
+                    image_texture_index: 0,
+                    sampler_index: 0,
+                },
+            ],
+            new_root.models.materials[0].textures
+        );
+        assert_eq!(
+            vec![Some("c".to_string()), Some("b".to_string())],
+            new_root
+                .image_textures
+                .iter()
+                .map(|t| t.name.clone())
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(
+            vec![sampler(AddressMode::ClampToEdge)],
+            new_root.models.samplers
+        );
+        assert_eq!(
+            vec![vertex::AttributeData::Position(vec![
+                vec3(3.0, 0.0, 0.0),
+                vec3(1.0, 0.0, 0.0),
+            ])],
+            new_root.buffers.vertex_buffers[0].attributes
+        );
+        assert_eq!(vec![0, 1, 0], new_root.buffers.index_buffers[0].indices);
+
+        assert_eq!(None, root.extract_mesh(0, 2));
+        assert_eq!(None, root.extract_mesh(1, 0));
+    }
+
+    #[test]
+    fn repair_texture_indices_removed_texture() {
+        let texture = |name: &str| ImageTexture {
+            name: Some(name.to_string()),
+            ..ImageTexture::fallback()
+        };
+        let material_texture = |image_texture_index| Texture {
+            image_texture_index,
+            sampler_index: 0,
+        };
+
+        let mut root = ModelRoot {
+            image_textures: vec![texture("a"), texture("b"), texture("c")],
+            ..model_root(
+                Models {
+                    materials: vec![Material {
+                        textures: vec![
+                            material_texture(0),
+                            material_texture(1),
+                            material_texture(2),
+                            material_texture(1),
+                        ],
+                        ..material("body")
+                    }],
+                    ..models(Vec::new())
+                },
+                model_buffers(Vec::new(), Vec::new()),
+            )
+        };
+
+        root.image_textures.remove(1);
+        root.repair_texture_indices(&[1]);
+
+        assert_eq!(
+            vec![texture("a"), texture("c"), ImageTexture::fallback()],
+            root.image_textures
+        );
+        assert_eq!(
+            vec![
+                material_texture(0),
+                material_texture(2),
+                material_texture(1),
+                material_texture(2)
+            ],
+            root.models.materials[0].textures
+        );
+        assert_eq!(
+            (1, 1),
+            root.image_textures[2].to_image().unwrap().dimensions()
+        );
+    }
+
+    #[test]
+    fn remove_unused_samplers_remap() {
+        let mut models = Models {
+            materials: vec![Material {
+                textures: vec![
+                    Texture {
+                        image_texture_index: 0,
+                        sampler_index: 2,
+                    },
+                    Texture {
+                        image_texture_index: 1,
+                        sampler_index: 0,
+                    },
+                    Texture {
+                        image_texture_index: 2,
+                        sampler_index: 2,
+                    },
+                ],
+                ..material("body")
+            }],
+            samplers: vec![
+                sampler(AddressMode::Repeat),
+                sampler(AddressMode::ClampToEdge),
+                sampler(AddressMode::MirrorRepeat),
+            ],
+            ..models(Vec::new())
+        };
+
+        assert_eq!(
+            BTreeSet::from([0, 2]),
+            models.materials[0].sampler_indices()
+        );
+
+        models.remove_unused_samplers();
+        assert_eq!(
+            vec![
+                sampler(AddressMode::Repeat),
+                sampler(AddressMode::MirrorRepeat)
+            ],
+            models.samplers
+        );
+        assert_eq!(
+            vec![1, 0, 1],
+            models.materials[0]
+                .textures
+                .iter()
+                .map(|t| t.sampler_index)
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn meshes_in_draw_order_opaque_transparent() {
+        let material = |blend_mode| Material {
+            flags: StateFlags {
+                blend_mode,
+                ..state_flags()
+            },
+            ..material("")
+        };
+
+        let mesh = |material_index, render_pass| Mesh {
+            material_index,
+            flags2: MeshRenderFlags2::try_from(render_pass as u32).unwrap(),
+            ..mesh(0, 0, 1)
+        };
+
+        let models = Models {
+            materials: vec![
+                material(BlendMode::Disabled),
+                material(BlendMode::AlphaBlend),
+                material(BlendMode::Additive),
+            ],
+            ..models(vec![
+                model(vec![
+                    mesh(2, MeshRenderPass::Unk8),
+                    mesh(0, MeshRenderPass::Unk0),
+                    mesh(1, MeshRenderPass::Unk8),
+                ]),
+                model(vec![
+                    mesh(0, MeshRenderPass::Unk2),
+                    mesh(0, MeshRenderPass::Unk1),
+                ]),
+            ])
+        };
+
+        assert_eq!(
+            (3, 3),
+            models.models[0].meshes[0].sort_key(&models.materials[2])
+        );
+        assert_eq!(
+            vec![
+                (1, mesh(0, MeshRenderPass::Unk1)),
+                (0, mesh(0, MeshRenderPass::Unk0)),
+                (0, mesh(1, MeshRenderPass::Unk8)),
+                (0, mesh(2, MeshRenderPass::Unk8)),
+                (1, mesh(0, MeshRenderPass::Unk2)),
+            ],
+            models
+                .meshes_in_draw_order()
+                .map(|(i, m)| (i, m.clone()))
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn lod_for_distance_groups() {
+        let item = |distance| xc3_lib::mxmd::LodItem1 {
+            unk1: [0; 4],
+            unk2: distance,
+            unk3: [0; 4],
+            unk4: [0; 2],
+        };
+        let group = |base_lod_index, lod_count| xc3_lib::mxmd::LodGroup {
+            base_lod_index,
+            lod_count,
+            unk1: 0,
+            unk2: 0,
+        };
+        let lod_data = xc3_lib::mxmd::LodData {
+            unk1: 0,
+            items1: vec![item(10.0), item(50.0), item(100.0), item(20.0)],
+            groups: vec![group(0, 3), group(3, 1)],
+            unks: [0; 4],
+        };
+
+        let models = Models {
+            base_lod_indices: Some(vec![0, 3]),
+            lod_info: Some(LodInfo::from_lod_data(&lod_data)),
+            ..models(Vec::new())
+        };
+
+        assert_eq!(
+            Some(LodInfo {
+                groups: vec![
+                    LodGroup {
+                        base_lod_index: 0,
+                        distances: vec![10.0, 50.0, 100.0]
+                    },
+                    LodGroup {
+                        base_lod_index: 3,
+                        distances: vec![20.0]
+                    }
+                ]
+            }),
+            models.lod_info
+        );
+
+        assert_eq!(0, models.lod_for_distance(0, 5.0));
+        assert_eq!(0, models.lod_for_distance(0, 10.0));
+        assert_eq!(1, models.lod_for_distance(0, 30.0));
+        assert_eq!(2, models.lod_for_distance(0, 75.0));
+        assert_eq!(2, models.lod_for_distance(0, 500.0));
+        assert_eq!(0, models.lod_for_distance(1, 500.0));
+        assert_eq!(0, models.lod_for_distance(2, 30.0));
+    }
+
+    #[test]
+    fn morph_controller_for_animation_indices() {
+        let controller = |name: &str, unk2| xc3_lib::mxmd::MorphController {
+            name1: name.to_string(),
+            name2: None,
+            unk1: 7,
+            unk2,
+            unk3: 0,
+            unk4: 3,
+            unk: [0; 3],
+        };
+        let controllers = xc3_lib::mxmd::MorphControllers {
+            controllers: vec![
+                controller("mouth_shout", 2),
+                controller("eye_close", 0),
+                controller("mouth_smile", 1),
+            ],
+            unk1: 0,
+            unk: [0; 3],
+        };
+
+        let models = Models {
+            morph_controller_names: vec![
+                "mouth_shout".to_string(),
+                "eye_close".to_string(),
+                "mouth_smile".to_string(),
+            ],
+            animation_morph_names: vec![
+                "eye_close".to_string(),
+                "mouth_smile".to_string(),
+                "mouth_shout".to_string(),
+                "unused".to_string(),
+            ],
+            morph_controller_animation_indices: morph_controller_animation_indices(&controllers),
+            ..models(Vec::new())
+        };
+
+        assert_eq!(Some(1), models.morph_controller_for_animation(0));
+        assert_eq!(Some(2), models.morph_controller_for_animation(1));
+        assert_eq!(Some(0), models.morph_controller_for_animation(2));
+        assert_eq!(None, models.morph_controller_for_animation(3));
+    }
+
+    #[test]
+    fn merge_meshes_by_material_two_meshes() {
+        let mut models = models(vec![Model {
+            max_xyz: Vec3::splat(10.0),
+            min_xyz: Vec3::splat(-10.0),
+            ..model(vec![mesh(0, 0, 1), mesh(1, 1, 1)])
+        }]);
+
+        let mut buffers = model_buffers(
+            vec![
+                positions(vec![
+                    vec3(0.0, 0.0, 0.0),
+                    vec3(1.0, 0.0, 0.0),
+                    vec3(0.0, 1.0, 0.0),
+                ]),
+                positions(vec![
+                    vec3(0.0, 0.0, 1.0),
+                    vec3(2.0, 0.0, 1.0),
+                    vec3(0.0, 2.0, 1.0),
+                ]),
+            ],
+            vec![
+                IndexBuffer {
+                    indices: vec![0, 1, 2],
+                },
+                IndexBuffer {
+                    indices: vec![2, 1, 0],
+                },
+            ],
+        );
+
+        models.merge_meshes_by_material(&mut buffers);
+
+        assert_eq!(vec![mesh(2, 2, 1)], models.models[0].meshes);
+        assert_eq!(
+            positions(vec![
+                vec3(0.0, 0.0, 0.0),
+                vec3(1.0, 0.0, 0.0),
+                vec3(0.0, 1.0, 0.0),
+                vec3(0.0, 2.0, 1.0),
+                vec3(2.0, 0.0, 1.0),
+                vec3(0.0, 0.0, 1.0),
+            ]),
+            buffers.vertex_buffers[2]
+        );
+        assert_eq!(vec![0, 1, 2, 3, 4, 5], buffers.index_buffers[2].indices);
+        assert_eq!(Vec3::ZERO, models.models[0].min_xyz);
+        assert_eq!(vec3(2.0, 2.0, 1.0), models.models[0].max_xyz);
+    }
+
+    #[test]
+    fn expand_instances_three_instances() {
+        let group = ModelGroup {
+            models: vec![models(vec![Model {
+                instances: vec![
+                    Mat4::IDENTITY,
+                    Mat4::from_translation(vec3(1.0, 0.0, 0.0)),
+                    Mat4::from_translation(vec3(0.0, 2.0, 0.0)),
+                ],
+                ..model(vec![mesh(0, 0, 1), mesh(0, 1, 1)])
+            }])],
+            buffers: vec![model_buffers(
+                vec![vertex_buffer(1)],
+                vec![index_buffer(3), index_buffer(3)],
+            )],
+        };
+
+        let expanded = group.expand_instances();
+
+        let models = &expanded.models[0].models;
+        assert_eq!(3, models.len());
+        assert!(models.iter().all(|m| m.instances == vec![Mat4::IDENTITY]));
+
+        // Meshes sharing a buffer should still share the transformed buffer.
+        assert_eq!(vec![mesh(1, 0, 1), mesh(1, 1, 1)], models[0].meshes);
+        assert_eq!(vec![mesh(2, 0, 1), mesh(2, 1, 1)], models[1].meshes);
+        assert_eq!(vec![mesh(3, 0, 1), mesh(3, 1, 1)], models[2].meshes);
+        assert_eq!(vec3(1.0, 0.0, 0.0), models[1].min_xyz);
+        assert_eq!(vec3(1.0, 3.0, 1.0), models[2].max_xyz);
+
+        let vertex_buffers = &expanded.buffers[0].vertex_buffers;
+        assert_eq!(4, vertex_buffers.len());
+        assert_eq!(
+            vertex::AttributeData::Position(vec![vec3(0.0, 2.0, 0.0)]),
+            vertex_buffers[3].attributes[0]
+        );
+    }
+
+    #[test]
+    fn chr_paths_unique() {
+        // The model's chr and repeated trailing 0 names should only be checked once.
+        assert_eq!(
+            [
+                "chr/ch01012013.chr",
+                "chr/ch01012013.arc",
+                "chr/ch01012010.chr",
+                "chr/ch01012000.chr",
+                "chr/ch01010000.chr",
+                "chr/ch01000000.chr",
+                "chr/ch00000000.chr",
+                "chr/c000000000.chr",
+            ]
+            .map(PathBuf::from)
+            .to_vec(),
+            chr_paths(Path::new("chr/ch01012013.wimdo"), "ch01012013".to_string())
+        );
+    }
+
+    #[test]
+    fn find_chr_path_trailing_zeros() {
+        let dir = TempDir::new("find_chr_path_trailing_zeros");
+        let folder = dir.path();
+        std::fs::write(folder.join("pc010000.chr"), []).unwrap();
+
+        assert_eq!(
+            Some(folder.join("pc010000.chr")),
+            find_chr_path(folder.join("pc010101.wimdo"))
+        );
+        assert_eq!(None, find_chr_path(folder.join("pc020101.wimdo")));
+
+        // Files with the same name as the model take priority.
+        std::fs::write(folder.join("pc010101.arc"), []).unwrap();
+        assert_eq!(
+            Some(folder.join("pc010101.arc")),
+            find_chr_path(folder.join("pc010101.wimdo"))
+        );
+    }
+
+    #[test]
+    fn mesh_ext_mesh_name() {
+        let mut models = Models {
+            ext_mesh_names: vec!["body".to_string(), "hair".to_string()],
+            ext_mesh_start_hidden: vec![false, true],
+            ..models(Vec::new())
+        };
+
+        let mut mesh = mesh(0, 0, 1);
+        assert_eq!(None, mesh.ext_mesh_name(&models));
+        assert!(!mesh.is_initially_hidden(&models));
+
+        mesh.ext_mesh_index = Some(0);
+        assert_eq!(Some("body"), mesh.ext_mesh_name(&models));
+        assert!(!mesh.is_initially_hidden(&models));
+
+        mesh.ext_mesh_index = Some(1);
+        assert_eq!(Some("hair"), mesh.ext_mesh_name(&models));
+        assert!(mesh.is_initially_hidden(&models));
+
+        mesh.ext_mesh_index = Some(2);
+        assert_eq!(None, mesh.ext_mesh_name(&models));
+        assert!(!mesh.is_initially_hidden(&models));
+
+        let mut hidden_mesh = mesh.clone();
+        hidden_mesh.ext_mesh_index = Some(1);
+        models.models.push(model(vec![mesh.clone(), hidden_mesh]));
+        models.remove_hidden_meshes();
+        assert_eq!(vec![mesh], models.models[0].meshes);
+    }
+
+    fn mxmd_skinning() -> xc3_lib::mxmd::Skinning {
+        xc3_lib::mxmd::Skinning {
+            count1: 0,
+            count2: 0,
+            bones: Vec::new(),
+            inverse_bind_transforms: Vec::new(),
+            transforms2: None,
+            transforms3: None,
+            bone_indices: Vec::new(),
+            unk_offset4: None,
+            unk_offset5: None,
+            as_bone_data: None,
+            unk: None,
+        }
+    }
+
+    #[test]
+    fn mxmd_has_skinning() {
+        let mut mxmd = mxmd(10112);
+        assert!(!mxmd.has_skinning());
+
+        mxmd.models.skinning = Some(mxmd_skinning());
+        assert!(mxmd.has_skinning());
+    }
+
+    #[test]
+    fn load_model_missing_skeleton_warning() {
+        let dir = TempDir::new("load_model_missing_skeleton_warning");
+        let folder = dir.path();
+
+        let mut mxmd = mxmd(10112);
+        mxmd.models.skinning = Some(mxmd_skinning());
+        let buffers = model_buffers(vec![vertex_buffer(3)], vec![index_buffer(3)]);
+        mxmd.vertex_data = Some(buffers.to_vertex_data(false, false).unwrap());
+
+        let path = folder.join("ch01011013.wimdo");
+        mxmd.save(&path).unwrap();
+
+        let warnings = log_messages(|| {
+            load_model(&path, None).unwrap();
+        });
+        let suppressed_warnings = log_messages(|| {
+            let options = LoadOptions {
+                warn_missing_skeleton: false,
+                ..Default::default()
+            };
+            load_model_with_options(&path, None, &options).unwrap();
+        });
+
+        let message = "Failed to load .arc or .chr skeleton for model with vertex skinning.";
+        assert!(warnings.iter().any(|m| m == message));
+        assert!(!suppressed_warnings.iter().any(|m| m == message));
+    }
+
+    #[test]
+    fn load_model_embedded_shaders_option() {
+        let dir = TempDir::new("load_model_embedded_shaders_option");
+        let folder = dir.path();
+
+        let mut mxmd = mxmd(10112);
+        mxmd.spch = Some(xc3_lib::spch::Spch {
+            version: 0,
+            slct_offsets: Vec::new(),
+            unk4s: Vec::new(),
+            slct_section: Vec::new(),
+            xv4_section: Vec::new(),
+            unk_section: Vec::new(),
+            string_section: None,
+            unk7: 0,
+            padding: [0; 4],
+        });
+        let buffers = model_buffers(Vec::new(), Vec::new());
+        mxmd.vertex_data = Some(buffers.to_vertex_data(false, false).unwrap());
+
+        let path = folder.join("ch01011013.wimdo");
+        mxmd.save(&path).unwrap();
+
+        // Shaders are only read when requested.
+        let root = load_model(&path, None).unwrap();
+        assert_eq!(None, root.embedded_shaders);
+
+        let options = LoadOptions {
+            embedded_shaders: true,
+            ..Default::default()
+        };
+        let root = load_model_with_options(&path, None, &options).unwrap();
+        assert_eq!(
+            Some(ShaderInfo {
+                programs: Vec::new()
+            }),
+            root.embedded_shaders
+        );
+    }
+
+    #[test]
+    fn load_model_info_without_wismt() {
+        let dir = TempDir::new("load_model_info_without_wismt");
+        let folder = dir.path();
+
+        let mxmd = mxmd(10111);
+        let path = folder.join("pc010101.wimdo");
+        mxmd.save(&path).unwrap();
+
+        // The full model requires vertex data from the missing wismt.
+        assert!(!path.with_extension("wismt").exists());
+        assert!(load_model(&path, None).is_err());
+
+        let info = load_model_info(&path).unwrap();
+
+        assert_eq!(
+            ModelInfo {
+                material_names: Vec::new(),
+                model_count: 0,
+                mesh_count: 0,
+                bone_names: Vec::new(),
+                max_xyz: Vec3::ONE,
+                min_xyz: -Vec3::ONE,
+            },
+            info
+        );
+    }
+
+    #[test]
+    fn mesh_used_bones_weight_indices() {
+        let buffers = model_buffers(
+            vec![VertexBuffer {
+                attributes: vec![vertex::AttributeData::WeightIndex(vec![
+                    [0, 0],
+                    [1, 0],
+                    [2, 0],
+                    [3, 0],
+                ])],
+                morph_targets: Vec::new(),
+                morph_default_target: None,
+                outline_buffer_index: None,
+            }],
+            // The last vertex is only used by another mesh.
+            vec![IndexBuffer {
+                indices: vec![0, 1, 2, 2, 1, 0],
+            }],
+        );
+
+        let weights = skinning::Weights {
+            weight_buffers: vec![skinning::SkinWeights {
+                bone_indices: vec![[0, 1, 0, 0], [2, 0, 0, 0], [4, 3, 0, 0], [5, 0, 0, 0]],
+                weights: vec![
+                    Vec4::new(0.5, 0.5, 0.0, 0.0),
+                    Vec4::new(1.0, 0.0, 0.0, 0.0),
+                    Vec4::new(1.0, 0.0, 0.0, 0.0),
+                    Vec4::new(1.0, 0.0, 0.0, 0.0),
+                ],
+                bone_names: (0..6).map(|i| i.to_string()).collect(),
+            }],
+            weight_groups: skinning::WeightGroups::Groups {
+                weight_groups: Vec::new(),
+                weight_lods: Vec::new(),
+            },
+        };
+
+        let models = Models {
+            materials: vec![
+                material("a"),
+                Material {
+                    pass_type: RenderPassType::Unk1,
+                    ..material("b")
+                },
+            ],
+            ..models(Vec::new())
+        };
+
+        // Bone 3 has zero weight and bone 5 is only used by unused vertices.
+        assert_eq!(
+            BTreeSet::from([0, 1, 2, 4]),
+            mesh(0, 0, 0).used_bones(&models, &buffers, &weights)
+        );
+
+        // The material's pass type selects a weight group starting at 1.
+        let weights = skinning::Weights {
+            weight_groups: skinning::WeightGroups::Groups {
+                weight_groups: vec![weight_group(0), weight_group(1)],
+                weight_lods: vec![xc3_lib::vertex::WeightLod {
+                    group_indices_plus_one: [1, 2, 0, 0, 0, 0, 0, 0, 0],
+                }],
+            },
+            ..weights
+        };
+        let mesh = Mesh {
+            material_index: 1,
+            ..mesh(0, 0, 0)
+        };
+        assert_eq!(
+            BTreeSet::from([2, 4, 5]),
+            mesh.used_bones(&models, &buffers, &weights)
+        );
+
+        // Meshes without a material have no weight group.
+        let mesh = Mesh {
+            material_index: 2,
+            ..mesh
+        };
+        assert!(mesh.used_bones(&models, &buffers, &weights).is_empty());
+    }
+
+    fn weight_group(input_start_index: u32) -> xc3_lib::vertex::WeightGroup {
+        xc3_lib::vertex::WeightGroup {
+            output_start_index: 0,
+            input_start_index,
+            count: 4,
+            unks: [0; 4],
+            lod_group_index: 0,
+            lod_index: 0,
+            max_influences: 2,
+            unk4: 0,
+            unks2: [0; 2],
+        }
+    }
+
+    #[test]
+    fn detect_game_version_mxmd_versions() {
+        assert_eq!(GameVersion::Xc2, detect_game_version(&mxmd(10111)));
+        assert_eq!(GameVersion::Xc1, detect_game_version(&mxmd(10112)));
+
+        let mut xc3 = mxmd(10112);
+        xc3.models.extra = Some(xc3_lib::mxmd::ModelsExtraData::Unk4(
+            xc3_lib::mxmd::ModelsExtraDataUnk4 {
+                model_unk10: None,
+                model_unk5: None,
+                model_unk6: None,
+                unk: None,
+            },
+        ));
+        assert_eq!(GameVersion::Xc3, detect_game_version(&xc3));
+    }
+
+    #[test]
+    fn load_apmd_entries_two_entries() {
+        let dir = TempDir::new("load_apmd_entries_two_entries");
+        let folder = dir.path();
+
+        let entries = vec![
+            xc3_lib::apmd::Entry {
+                entry_type: xc3_lib::apmd::EntryType::Mxmd,
+                entry_data: vec![1; 16],
+            },
+            xc3_lib::apmd::Entry {
+                entry_type: xc3_lib::apmd::EntryType::Dmis,
+                entry_data: vec![2; 8],
+            },
+        ];
+        let apmd = Apmd {
+            version: 10003,
+            entries: entries.clone(),
+            unk2: 0,
+            unk3: 0,
+            unk: [0; 8],
+        };
+        let path = folder.join("oj03010100.wimdo");
+        apmd.save(&path).unwrap();
+
+        let new_entries = load_apmd_entries(&path).unwrap();
+
+        assert_eq!(entries, new_entries);
+    }
+
+    #[test]
+    fn load_model_apmd_embedded_vertex_data() {
+        let dir = TempDir::new("load_model_apmd_embedded_vertex_data");
+        let folder = dir.path();
+
+        let buffers = model_buffers(
+            vec![positions(vec![
+                vec3(0.0, 0.0, 0.0),
+                vec3(1.0, 0.0, 0.0),
+                vec3(0.0, 1.0, 0.0),
+            ])],
+            vec![IndexBuffer {
+                indices: vec![0, 1, 2],
+            }],
+        );
+
+        let mut mxmd = mxmd(10112);
+        mxmd.vertex_data = Some(buffers.to_vertex_data(false, false).unwrap());
+
+        let mut mxmd_data = Cursor::new(Vec::new());
+        mxmd.write(&mut mxmd_data).unwrap();
+
+        let apmd = Apmd {
+            version: 10003,
+            entries: vec![xc3_lib::apmd::Entry {
+                entry_type: xc3_lib::apmd::EntryType::Mxmd,
+                entry_data: mxmd_data.into_inner(),
+            }],
+            unk2: 0,
+            unk3: 0,
+            unk: [0; 8],
+        };
+        let path = folder.join("oj03010100.wimdo");
+        apmd.save(&path).unwrap();
+
+        let new_mxmd = load_mxmd(&path);
+        let root = load_model(&path, None);
+
+        assert_eq!(mxmd.vertex_data, new_mxmd.unwrap().vertex_data);
+        assert!(!path.with_extension("wismt").exists());
+        assert_eq!(buffers, root.unwrap().buffers);
+    }
+
+    fn mxmd_model(buffer_index: u16, lod: u16, max_xyz: [f32; 3]) -> xc3_lib::mxmd::Model {
+        xc3_lib::mxmd::Model {
+            meshes: vec![xc3_lib::mxmd::Mesh {
+                flags1: 0,
+                flags2: 0u32.try_into().unwrap(),
+                vertex_buffer_index: buffer_index,
+                index_buffer_index: buffer_index,
+                unk_index: 0,
+                material_index: 0,
+                unk2: 0,
+                unk3: 0,
+                ext_mesh_index: 0,
+                unk4: 0,
+                unk5: 0,
+                lod,
+                alpha_table_index: 0,
+                unk6: 0,
+                unk7: 0,
+                unk8: 0,
+                unk9: 0,
+            }],
+            unk1: 0,
+            max_xyz,
+            min_xyz: [0.0; 3],
+            bounding_radius: 1.0,
+            unks1: [0; 3],
+            unk2: (0, 0),
+            unks: [0; 3],
+        }
+    }
+
+    #[test]
+    fn load_model_multiple_models() {
+        let dir = TempDir::new("load_model_multiple_models");
+        let folder = dir.path();
+
+        let buffers = model_buffers(
+            vec![vertex_buffer(3), vertex_buffer(3)],
+            vec![index_buffer(3), index_buffer(3)],
+        );
+
+        let mut mxmd = mxmd(10112);
+        mxmd.models.models = vec![mxmd_model(0, 0, [1.0; 3]), mxmd_model(1, 0, [2.0; 3])];
+        mxmd.vertex_data = Some(buffers.to_vertex_data(false, false).unwrap());
+
+        let path = folder.join("ch01011013.wimdo");
+        mxmd.save(&path).unwrap();
+
+        let root = load_model(&path, None);
+
+        // Every model should be loaded and not just the first.
+        let models = root.unwrap().models.models;
+        assert_eq!(2, models.len());
+        assert_eq!(0, models[0].meshes[0].vertex_buffer_index);
+        assert_eq!(Vec3::ONE, models[0].max_xyz);
+        assert_eq!(1, models[1].meshes[0].vertex_buffer_index);
+        assert_eq!(Vec3::splat(2.0), models[1].max_xyz);
+    }
+
+    #[test]
+    fn load_model_xc2() {
+        let dir = TempDir::new("load_model_xc2");
+        let folder = dir.path();
+
+        let buffers = model_buffers(vec![vertex_buffer(3)], vec![index_buffer(3)]);
+
+        // Xenoblade 2 models don't have the optional data used by Xenoblade 3.
+        let mut mxmd = mxmd(10111);
+        mxmd.models.models = vec![mxmd_model(0, 1, [1.0; 3])];
+        mxmd.materials.materials = vec![xc3_lib::mxmd::Material {
+            name: "xc2_material".to_string(),
+            ..mxmd_material()
+        }];
+        mxmd.vertex_data = Some(buffers.to_vertex_data(false, false).unwrap());
+
+        let path = folder.join("pc010101.wimdo");
+        mxmd.save(&path).unwrap();
+
+        let root = load_model(&path, None);
+
+        let root = root.unwrap();
+        assert_eq!(GameVersion::Xc2, root.game_version);
+
+        let mut models = root.models;
+        assert_eq!(None, models.base_lod_indices);
+        assert_eq!(None, models.lod_info);
+        assert!(models.ext_mesh_names.is_empty());
+
+        assert_eq!(1, models.materials.len());
+        assert_eq!("xc2_material", models.materials[0].name);
+
+        let mesh = &models.models[0].meshes[0];
+        assert_eq!(0, mesh.vertex_buffer_index);
+        assert_eq!(0, mesh.index_buffer_index);
+        assert_eq!(0, mesh.material_index);
+        assert_eq!(None, mesh.ext_mesh_name(&models));
+        assert!(!mesh.is_initially_hidden(&models));
+
+        // Meshes without LOD data should not be removed.
+        models.keep_highest_lod();
+        models.remove_hidden_meshes();
+        assert_eq!(1, models.models[0].meshes.len());
+    }
+
+    // Create the original files and a model with an edited texture.
+    fn edited_texture_model(
+        use_chr_textures: bool,
+        buffers: ModelBuffers,
+    ) -> (Mxmd, Msrd, ModelRoot) {
+        let texture = |value: u8| ImageTexture {
+            name: Some("0123abcd".to_string()),
+            usage: Some(TextureUsage::Col),
+            width: 256,
+            height: 256,
+            depth: 1,
+            view_dimension: ViewDimension::D2,
+            image_format: ImageFormat::BC7Unorm,
+            mipmap_count: 2,
+            image_data: (0..256 * 256 + 128 * 128)
+                .map(|i| (i as u8).wrapping_add(value))
+                .collect(),
+        };
+
+        let spch = xc3_lib::spch::Spch {
+            version: 0,
+            slct_offsets: Vec::new(),
+            unk4s: Vec::new(),
+            slct_section: Vec::new(),
+            xv4_section: Vec::new(),
+            unk_section: Vec::new(),
+            string_section: None,
+            unk7: 0,
+            padding: [0; 4],
+        };
+        let msrd = Msrd::from_extracted_files(
+            &buffers.to_vertex_data(false, false).unwrap(),
+            &spch,
+            &[ImageTexture::extracted_texture(&texture(0)).unwrap()],
+            use_chr_textures,
+        )
+        .unwrap();
+        let mut mxmd = mxmd(10112);
+        mxmd.streaming = Some(msrd.streaming.clone());
+
+        let root = ModelRoot {
+            image_textures: vec![texture(1)],
+            ..model_root(models(Vec::new()), buffers)
+        };
+
+        (mxmd, msrd, root)
+    }
+
+    fn to_mxmd_model_edited_texture(use_chr_textures: bool, level: CompressionLevel) {
+        let buffers = model_buffers(Vec::new(), Vec::new());
+        let (mxmd, msrd, root) = edited_texture_model(use_chr_textures, buffers);
+        let edited = &root.image_textures[0];
+
+        let (new_mxmd, new_msrd) = root.to_mxmd_model_with_level(&mxmd, &msrd, level).unwrap();
+        assert_eq!(
+            use_chr_textures,
+            new_mxmd
+                .streaming
+                .as_ref()
+                .is_some_and(|s| s.inner.has_chr_textures())
+        );
+
+        let textures = new_msrd.extract_textures(None).unwrap();
+        assert_eq!(1, textures.len());
+        assert!(textures[0].high.is_some());
+
+        let new_texture = ImageTexture::from_mibl(
+            &textures[0].mibl_final(),
+            Some(textures[0].name.clone()),
+            Some(textures[0].usage),
+        )
+        .unwrap();
+        assert_eq!(edited, &new_texture);
+    }
+
+    #[test]
+    fn to_mxmd_model_edited_texture_wismt() {
+        to_mxmd_model_edited_texture(false, CompressionLevel::Default);
+    }
+
+    #[test]
+    fn to_mxmd_model_edited_texture_chr_tex_nx() {
+        to_mxmd_model_edited_texture(true, CompressionLevel::Default);
+    }
+
+    #[test]
+    fn to_mxmd_model_edited_texture_fast_compression() {
+        to_mxmd_model_edited_texture(false, CompressionLevel::Fast);
+    }
+
+    #[test]
+    fn write_wimdo_wismt_reload() {
+        let dir = TempDir::new("write_wimdo_wismt_reload");
+        let path = dir.path().join("ch01011013.wimdo");
+
+        let buffers = model_buffers(vec![vertex_buffer(3)], vec![index_buffer(3)]);
+        let (mxmd, msrd, root) = edited_texture_model(false, buffers);
+
+        let (wimdo, wismt) = root.write_wimdo_wismt(&mxmd, &msrd).unwrap();
+        std::fs::write(&path, wimdo).unwrap();
+        std::fs::write(path.with_extension("wismt"), wismt).unwrap();
+
+        let new_root = load_model(&path, None).unwrap();
+        assert_eq!(root.buffers, new_root.buffers);
+        assert_eq!(root.image_textures, new_root.image_textures);
+    }
+
+    #[test]
+    fn load_animations_verbose_malformed_entry() {
+        let dir = TempDir::new("load_animations_verbose_malformed_entry");
+        let folder = dir.path();
+
+        let sar1 = Sar1 {
+            file_size: 0,
+            version: 256,
+            entries: vec![xc3_lib::sar1::Entry::from_entry_data(
+                "malformed.anm".to_string(),
+                vec![1; 16],
+            )],
+            data_offset: 0,
+            unk4: 0,
+            unk5: 0,
+            name: String::new(),
+        };
+        let path = folder.join("malformed.mot");
+        sar1.save(&path).unwrap();
+
+        let report = load_animations_verbose(&path).unwrap();
+        // Invalid entries are still an error without the report.
+        let result = load_animations(&path);
+
+        assert!(report.animations.is_empty());
+        assert!(report.skipped_entries.is_empty());
+        assert_eq!(1, report.entry_errors.len());
+        assert_eq!("malformed.anm", report.entry_errors[0].name);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn to_chr_malformed_entry_warning() {
+        let root = ModelRoot {
+            skeleton: Some(Skeleton {
+                bones: Vec::new(),
+                procedural_bones: Vec::new(),
+            }),
+            ..model_root(models(Vec::new()), model_buffers(Vec::new(), Vec::new()))
+        };
+        let chr = Sar1 {
+            file_size: 0,
+            version: 256,
+            entries: vec![xc3_lib::sar1::Entry::from_entry_data(
+                "malformed.skl".to_string(),
+                vec![1; 16],
+            )],
+            data_offset: 0,
+            unk4: 0,
+            unk5: 0,
+            name: String::new(),
+        };
+
+        let mut new_chr = None;
+        let warnings = log_messages(|| {
+            new_chr = Some(root.to_chr(&chr).unwrap());
+        });
+
+        // The entry can't be edited but should not be silently removed or changed.
+        assert_eq!(Some(chr), new_chr);
+        assert!(warnings
+            .iter()
+            .any(|m| m.starts_with("Skipping skeleton edits for chr entry 0 \"malformed.skl\"")));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn model_root_serde_round_trip() {
+        let root = ModelRoot {
+            skeleton: Some(Skeleton {
+                bones: vec![Bone {
+                    name: "root".to_string(),
+                    transform: Mat4::from_translation(vec3(1.0, 2.0, 3.0)),
+                    parent_index: None,
+                }],
+                procedural_bones: Vec::new(),
+            }),
+            ..model_root(
+                Models {
+                    base_lod_indices: Some(vec![0]),
+                    morph_controller_names: vec!["mouth".to_string()],
+                    ext_mesh_names: vec!["body".to_string()],
+                    ext_mesh_start_hidden: vec![false],
+                    ..models(vec![model(vec![mesh(0, 0, 1)])])
+                },
+                model_buffers(
+                    vec![VertexBuffer {
+                        attributes: vec![
+                            vertex::AttributeData::Position(vec![vec3(0.0, 1.0, 2.0)]),
+                            vertex::AttributeData::Normal(vec![Vec4::new(0.0, 0.0, 1.0, 0.0)]),
+                        ],
+                        morph_targets: vec![vertex::MorphTarget {
+                            morph_controller_index: 0,
+                            position_deltas: vec![Vec3::ONE],
+                            normal_deltas: vec![Vec4::ZERO],
+                            tangent_deltas: vec![Vec4::ZERO],
+                            vertex_indices: vec![0],
+                        }],
+                        morph_default_target: None,
+                        outline_buffer_index: None,
+                    }],
+                    vec![IndexBuffer {
+                        indices: vec![0, 0, 0],
+                    }],
+                ),
+            )
+        };
+
+        let json = serde_json::to_string(&root).unwrap();
+        let new_root: ModelRoot = serde_json::from_str(&json).unwrap();
+        assert_eq!(root, new_root);
+    }
+}
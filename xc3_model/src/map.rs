@@ -1,8 +1,8 @@
-use std::{io::Cursor, path::Path};
+use std::{io::Cursor, path::Path, sync::mpsc::Sender};
 
 use glam::{Mat4, Vec3};
 use indexmap::IndexMap;
-use log::error;
+use log::warn;
 use rayon::prelude::*;
 use thiserror::Error;
 use xc3_lib::{
@@ -18,7 +18,8 @@ use crate::{
     create_materials, create_samplers, model_name,
     shader_database::ShaderDatabase,
     texture::{self, CreateImageTextureError, ImageTexture},
-    MapRoot, Material, Model, ModelBuffers, ModelGroup, Models, Texture,
+    AddressMode, FilterMode, LodInfo, MapRoot, Material, Model, ModelBuffers, ModelGroup, Models,
+    Sampler, Texture,
 };
 
 #[derive(Debug, Error)]
@@ -61,68 +62,245 @@ pub enum LoadMapError {
 pub fn load_map<P: AsRef<Path>>(
     wismhd_path: P,
     shader_database: Option<&ShaderDatabase>,
+) -> Result<Vec<MapRoot>, LoadMapError> {
+    load_map_with_progress(wismhd_path, shader_database, |_| ())
+}
+
+/// The current stage of [load_map_with_progress].
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum MapLoadPhase {
+    /// Env and foliage models with their embedded textures.
+    Env,
+    /// Map models using the shared map textures.
+    Map,
+    /// Prop models using the shared map textures.
+    Props,
+    /// Decoding the shared map textures.
+    Textures,
+}
+
+/// Progress information passed to the callback in [load_map_with_progress].
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct MapLoadProgress {
+    pub phase: MapLoadPhase,
+    /// The number of completed items for this phase.
+    pub completed: usize,
+    /// The total number of items for this phase.
+    pub total: usize,
+}
+
+/// Load a map from a `.wismhd` file like [load_map]
+/// while reporting progress for each [MapLoadPhase].
+///
+/// The callback is called once when a phase starts with `completed` set to 0
+/// and again each time an item finishes loading until `completed` equals `total`.
+/// Phases are reported in the order
+/// [MapLoadPhase::Env], [MapLoadPhase::Map], [MapLoadPhase::Props], and [MapLoadPhase::Textures].
+/// The callback is only called from the calling thread and never from inside parallel sections.
+///
+/// # Examples
+/// ``` rust no_run
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// use xc3_model::{load_map_with_progress, shader_database::ShaderDatabase};
+///
+/// let database = ShaderDatabase::from_file("xc3.json")?;
+/// let roots = load_map_with_progress("xeno3/map/ma01a.wismhd", Some(&database), |p| {
+///     println!("{:?}: {}/{}", p.phase, p.completed, p.total)
+/// })?;
+/// # Ok(())
+/// # }
+/// ```
+pub fn load_map_with_progress<P: AsRef<Path>>(
+    wismhd_path: P,
+    shader_database: Option<&ShaderDatabase>,
+    progress: impl FnMut(MapLoadProgress),
 ) -> Result<Vec<MapRoot>, LoadMapError> {
     let msmd = Msmd::from_file(wismhd_path.as_ref()).map_err(LoadMapError::Wismhd)?;
     let wismda = std::fs::read(wismhd_path.as_ref().with_extension("wismda"))?;
 
-    // Loading is CPU intensive due to decompression and decoding.
-    // The .wismda is loaded into memory as &[u8].
-    // Extracting can be parallelized without locks by creating multiple readers.
     let model_folder = model_name(wismhd_path.as_ref());
+    load_map_from_wismda(&msmd, &wismda, model_folder, shader_database, progress)
+}
+
+/// Load a map from a `.wismhd` file like [load_map]
+/// but memory map the `.wismda` instead of reading the entire file into memory.
+/// This reduces peak memory usage for large maps.
+///
+/// The `.wismda` file should not be modified while loading.
+///
+/// # Examples
+/// ``` rust no_run
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// use xc3_model::{load_map_mmap, shader_database::ShaderDatabase};
+///
+/// let database = ShaderDatabase::from_file("xc3.json")?;
+/// let roots = load_map_mmap("xeno3/map/ma01a.wismhd", Some(&database))?;
+/// # Ok(())
+/// # }
+/// ```
+#[cfg(feature = "mmap")]
+pub fn load_map_mmap<P: AsRef<Path>>(
+    wismhd_path: P,
+    shader_database: Option<&ShaderDatabase>,
+) -> Result<Vec<MapRoot>, LoadMapError> {
+    let msmd = Msmd::from_file(wismhd_path.as_ref()).map_err(LoadMapError::Wismhd)?;
+    let file = std::fs::File::open(wismhd_path.as_ref().with_extension("wismda"))?;
+    // SAFETY: The file is only read and is assumed to not be modified while mapped.
+    let wismda = unsafe { memmap2::Mmap::map(&file)? };
+
+    let model_folder = model_name(wismhd_path.as_ref());
+    load_map_from_wismda(&msmd, &wismda, model_folder, shader_database, |_| ())
+}
 
+fn load_map_from_wismda(
+    msmd: &Msmd,
+    wismda: &[u8],
+    model_folder: String,
+    shader_database: Option<&ShaderDatabase>,
+    mut progress: impl FnMut(MapLoadProgress),
+) -> Result<Vec<MapRoot>, LoadMapError> {
+    // Loading is CPU intensive due to decompression and decoding.
+    // The .wismda is accessed as &[u8] from memory or a memory mapped file.
+    // Extracting can be parallelized without locks by creating multiple readers.
     // Some maps don't use XBC1 compressed archives in the .wismda file.
     let compressed = msmd.wismda_info.compressed_length != msmd.wismda_info.decompressed_length;
 
-    // TODO: Better way to combine models?
-    let mut roots = Vec::new();
-
-    for (i, model) in msmd.env_models.iter().enumerate() {
-        let root = load_env_model(
-            &wismda,
-            compressed,
-            model,
-            i,
-            &model_folder,
-            shader_database,
-        )?;
-        roots.push(root);
-    }
+    // Env and foliage models embed their own textures.
+    // These can be loaded independently from the map textures and models.
+    let env_total = msmd.env_models.len() + msmd.foliage_models.len();
+    let ((env_roots, foliage_roots), texture_cache) =
+        with_progress(MapLoadPhase::Env, env_total, &mut progress, |sender| {
+            rayon::join(
+                || {
+                    rayon::join(
+                        || {
+                            msmd.env_models
+                                .par_iter()
+                                .enumerate()
+                                .map(|(i, model)| {
+                                    let root = load_env_model(
+                                        wismda,
+                                        compressed,
+                                        model,
+                                        i,
+                                        &model_folder,
+                                        shader_database,
+                                    );
+                                    let _ = sender.send(());
+                                    root
+                                })
+                                .collect::<Result<Vec<_>, _>>()
+                        },
+                        || {
+                            msmd.foliage_models
+                                .par_iter()
+                                .map(|model| {
+                                    let root = load_foliage_model(wismda, compressed, model);
+                                    let _ = sender.send(());
+                                    root
+                                })
+                                .collect::<Result<Vec<_>, _>>()
+                        },
+                    )
+                },
+                || TextureCache::new(msmd, wismda, compressed),
+            )
+        });
 
-    for foliage_model in &msmd.foliage_models {
-        let root = load_foliage_model(&wismda, compressed, foliage_model)?;
-        roots.push(root);
-    }
+    // Preserve the ordering of env models, foliage models, and then map models.
+    let mut roots = env_roots?;
+    roots.extend(foliage_roots?);
 
-    // TODO: How much does a mutable cache negatively impact parallelization?
     // TODO: Is there enough reuse for it to be worth caching these?
-    let mut texture_cache = TextureCache::new(&msmd, &wismda, compressed)?;
-
-    let map_model_group = map_models_group(
-        &msmd,
-        &wismda,
-        compressed,
-        &model_folder,
-        &mut texture_cache,
-        shader_database,
+    // Texture indices are assigned serially to keep the ordering deterministic.
+    let mut texture_cache = texture_cache?;
+
+    let map_model_group = with_progress(
+        MapLoadPhase::Map,
+        msmd.map_models.len(),
+        &mut progress,
+        |sender| {
+            map_models_group(
+                msmd,
+                wismda,
+                compressed,
+                &model_folder,
+                &mut texture_cache,
+                shader_database,
+                sender,
+            )
+        },
+    )?;
+
+    let prop_model_group = with_progress(
+        MapLoadPhase::Props,
+        msmd.prop_models.len(),
+        &mut progress,
+        |sender| {
+            props_group(
+                msmd,
+                wismda,
+                compressed,
+                model_folder,
+                &mut texture_cache,
+                shader_database,
+                sender,
+            )
+        },
     )?;
 
-    let prop_model_group = props_group(
-        &msmd,
-        &wismda,
-        compressed,
-        model_folder,
-        &mut texture_cache,
-        shader_database,
+    let image_textures = with_progress(
+        MapLoadPhase::Textures,
+        texture_cache.texture_to_image_texture_index.len(),
+        &mut progress,
+        |sender| texture_cache.image_textures(sender),
     )?;
 
     roots.push(MapRoot {
         groups: vec![map_model_group, prop_model_group],
-        image_textures: texture_cache.image_textures()?,
+        image_textures,
     });
 
     Ok(roots)
 }
 
+/// Run `f` on a separate thread and report progress for each item sent by `f`.
+/// This keeps the callback on the calling thread without requiring Send + Sync.
+fn with_progress<T, F>(
+    phase: MapLoadPhase,
+    total: usize,
+    progress: &mut impl FnMut(MapLoadProgress),
+    f: F,
+) -> T
+where
+    T: Send,
+    F: FnOnce(&Sender<()>) -> T + Send,
+{
+    progress(MapLoadProgress {
+        phase,
+        completed: 0,
+        total,
+    });
+
+    let (sender, receiver) = std::sync::mpsc::channel();
+    std::thread::scope(|s| {
+        let handle = s.spawn(move || f(&sender));
+
+        // The loop ends once f returns and drops the sender.
+        for (i, _) in receiver.iter().enumerate() {
+            progress(MapLoadProgress {
+                phase,
+                completed: i + 1,
+                total,
+            });
+        }
+
+        handle
+            .join()
+            .unwrap_or_else(|e| std::panic::resume_unwind(e))
+    })
+}
+
 // TODO: Is there a better way of doing this?
 // Lazy loading for the image textures.
 struct TextureCache {
@@ -195,24 +373,28 @@ impl TextureCache {
         self.high_textures.get(index)
     }
 
-    fn image_textures(&self) -> Result<Vec<ImageTexture>, CreateImageTextureError> {
+    fn image_textures(
+        &self,
+        sender: &Sender<()>,
+    ) -> Result<Vec<ImageTexture>, CreateImageTextureError> {
         self.texture_to_image_texture_index
             .par_iter()
             .map(
                 |((low_texture_index, low_textures_entry_index, texture_index), _)| {
                     let low = self.get_low_texture(*low_textures_entry_index, *low_texture_index);
 
-                    if let Some(mibl) = self
+                    let texture = if let Some(mibl) = self
                         .get_high_texture(*texture_index)
                         .or(low.map(|low| &low.1))
                     {
                         ImageTexture::from_mibl(mibl, None, low.map(|l| l.0)).map_err(Into::into)
                     } else {
                         // TODO: What do do if both indices are negative?
-                        error!("No mibl for low: {low_texture_index}, low entry: {low_textures_entry_index}, high: {texture_index}");
-                        let (usage, mibl) = self.get_low_texture(0, 0).unwrap();
-                        ImageTexture::from_mibl(mibl, None, Some(*usage)).map_err(Into::into)
-                    }
+                        warn!("No mibl for low: {low_texture_index}, low entry: {low_textures_entry_index}, high: {texture_index}");
+                        Ok(ImageTexture::fallback())
+                    };
+                    let _ = sender.send(());
+                    texture
                 },
             )
             .collect()
@@ -221,11 +403,12 @@ impl TextureCache {
 
 fn map_models_group(
     msmd: &Msmd,
-    wismda: &Vec<u8>,
+    wismda: &[u8],
     compressed: bool,
     model_folder: &str,
     texture_cache: &mut TextureCache,
     shader_database: Option<&ShaderDatabase>,
+    sender: &Sender<()>,
 ) -> Result<ModelGroup, LoadMapError> {
     let buffers = create_buffers(&msmd.map_vertex_data, wismda, compressed)?;
 
@@ -236,34 +419,46 @@ fn map_models_group(
         .map(|m| m.entry.extract(&mut Cursor::new(wismda), compressed))
         .collect::<Result<Vec<_>, _>>()?;
 
-    let mut models = Vec::new();
-    models.extend(map_model_data.iter().enumerate().map(|(i, model_data)| {
-        // Remove one layer of indirection from texture lookups.
-        let material_root_texture_indices: Vec<_> = model_data
-            .textures
-            .iter()
-            .map(|t| texture_cache.insert(t))
-            .collect();
-
-        load_map_model_group(
-            model_data,
-            i,
-            model_folder,
-            &material_root_texture_indices,
-            shader_database,
-        )
-    }));
+    // Remove one layer of indirection from texture lookups.
+    let material_root_texture_indices: Vec<Vec<_>> = map_model_data
+        .iter()
+        .map(|model_data| {
+            model_data
+                .textures
+                .iter()
+                .map(|t| texture_cache.insert(t))
+                .collect()
+        })
+        .collect();
+
+    let models = map_model_data
+        .par_iter()
+        .zip(material_root_texture_indices.par_iter())
+        .enumerate()
+        .map(|(i, (model_data, texture_indices))| {
+            let models = load_map_model_group(
+                model_data,
+                i,
+                model_folder,
+                texture_indices,
+                shader_database,
+            );
+            let _ = sender.send(());
+            models
+        })
+        .collect();
 
     Ok(ModelGroup { models, buffers })
 }
 
 fn props_group(
     msmd: &Msmd,
-    wismda: &Vec<u8>,
+    wismda: &[u8],
     compressed: bool,
     model_folder: String,
     texture_cache: &mut TextureCache,
     shader_database: Option<&ShaderDatabase>,
+    sender: &Sender<()>,
 ) -> Result<ModelGroup, LoadMapError> {
     let buffers = create_buffers(&msmd.prop_vertex_data, wismda, compressed)?;
 
@@ -280,26 +475,34 @@ fn props_group(
         .map(|m| m.entry.extract(&mut Cursor::new(wismda), compressed))
         .collect::<Result<Vec<_>, _>>()?;
 
-    let models = prop_model_data
+    // Remove one layer of indirection from texture lookups.
+    let material_root_texture_indices: Vec<Vec<_>> = prop_model_data
         .iter()
-        .enumerate()
-        .map(|(i, model_data)| {
-            // Remove one layer of indirection from texture lookups.
-            let material_root_texture_indices: Vec<_> = model_data
+        .map(|model_data| {
+            model_data
                 .textures
                 .iter()
                 .map(|t| texture_cache.insert(t))
-                .collect();
+                .collect()
+        })
+        .collect();
 
-            load_prop_model_group(
+    let models = prop_model_data
+        .par_iter()
+        .zip(material_root_texture_indices.par_iter())
+        .enumerate()
+        .map(|(i, (model_data, texture_indices))| {
+            let models = load_prop_model_group(
                 model_data,
                 i,
                 msmd.parts.as_ref(),
                 &prop_positions,
                 &model_folder,
-                &material_root_texture_indices,
+                texture_indices,
                 shader_database,
-            )
+            );
+            let _ = sender.send(());
+            models
         })
         .collect();
 
@@ -308,7 +511,7 @@ fn props_group(
 
 fn create_buffers(
     vertex_data: &[StreamEntry<xc3_lib::vertex::VertexData>],
-    wismda: &Vec<u8>,
+    wismda: &[u8],
     compressed: bool,
 ) -> Result<Vec<ModelBuffers>, DecompressStreamError> {
     // Process vertex data ahead of time in parallel.
@@ -390,8 +593,16 @@ fn load_prop_model_group(
             .lod_data
             .as_ref()
             .map(|data| data.groups.iter().map(|i| i.base_lod_index).collect()),
+        lod_info: model_data
+            .models
+            .lod_data
+            .as_ref()
+            .map(LodInfo::from_lod_data),
         morph_controller_names: Vec::new(),
         animation_morph_names: Vec::new(),
+        morph_controller_animation_indices: Vec::new(),
+        ext_mesh_names: Vec::new(),
+        ext_mesh_start_hidden: Vec::new(),
         min_xyz: model_data.models.min_xyz.into(),
         max_xyz: model_data.models.max_xyz.into(),
     };
@@ -564,8 +775,16 @@ fn load_map_model_group(
             .lod_data
             .as_ref()
             .map(|data| data.groups.iter().map(|i| i.base_lod_index).collect()),
+        lod_info: model_data
+            .models
+            .lod_data
+            .as_ref()
+            .map(LodInfo::from_lod_data),
         morph_controller_names: Vec::new(),
         animation_morph_names: Vec::new(),
+        morph_controller_animation_indices: Vec::new(),
+        ext_mesh_names: Vec::new(),
+        ext_mesh_start_hidden: Vec::new(),
         min_xyz: model_data.models.min_xyz.into(),
         max_xyz: model_data.models.max_xyz.into(),
     }
@@ -627,7 +846,8 @@ fn load_foliage_model(
         .map(ImageTexture::from_packed_texture)
         .collect::<Result<Vec<_>, _>>()?;
 
-    let materials = foliage_materials(&model_data.materials);
+    let materials = foliage_materials(&model_data.materials, image_textures.len());
+    let samplers = foliage_samplers(image_textures.len());
 
     // TODO: foliage models are instanced somehow for grass clumps?
     let models = model_data
@@ -639,20 +859,28 @@ fn load_foliage_model(
 
     let buffers = ModelBuffers::from_vertex_data(&model_data.vertex_data, None)?;
 
-    // TODO: foliage samplers?
     // TODO: is it worth making a skeleton here?
     Ok(MapRoot {
         groups: vec![ModelGroup {
             models: vec![Models {
                 models,
                 materials,
-                samplers: Vec::new(),
+                samplers,
                 base_lod_indices: model_data
                     .models
                     .lod_data
+                    .as_ref()
                     .map(|data| data.groups.iter().map(|i| i.base_lod_index).collect()),
+                lod_info: model_data
+                    .models
+                    .lod_data
+                    .as_ref()
+                    .map(LodInfo::from_lod_data),
                 morph_controller_names: Vec::new(),
                 animation_morph_names: Vec::new(),
+                morph_controller_animation_indices: Vec::new(),
+                ext_mesh_names: Vec::new(),
+                ext_mesh_start_hidden: Vec::new(),
                 min_xyz: model_data.models.min_xyz.into(),
                 max_xyz: model_data.models.max_xyz.into(),
             }],
@@ -662,16 +890,22 @@ fn load_foliage_model(
     })
 }
 
-fn foliage_materials(materials: &FoliageMaterials) -> Vec<Material> {
+fn foliage_materials(materials: &FoliageMaterials, texture_count: usize) -> Vec<Material> {
     let materials = materials
         .materials
         .iter()
         .map(|material| {
             // TODO: Where are the textures?
-            let textures = vec![Texture {
-                image_texture_index: 0,
-                sampler_index: 0,
-            }];
+            // None of the known FoliageMaterial fields have been identified as texture indices.
+            // Use the first embedded texture if present to avoid an out of range index.
+            let textures = if texture_count > 0 {
+                vec![Texture {
+                    image_texture_index: 0,
+                    sampler_index: 0,
+                }]
+            } else {
+                Vec::new()
+            };
 
             // TODO: Foliage shaders?
             let shader = None;
@@ -703,6 +937,24 @@ fn foliage_materials(materials: &FoliageMaterials) -> Vec<Material> {
     materials
 }
 
+fn foliage_samplers(texture_count: usize) -> Vec<Sampler> {
+    // TODO: Foliage sampler flags?
+    // Add a single repeating sampler for the texture assigned in foliage_materials.
+    if texture_count > 0 {
+        vec![Sampler {
+            address_mode_u: AddressMode::Repeat,
+            address_mode_v: AddressMode::Repeat,
+            address_mode_w: AddressMode::Repeat,
+            min_filter: FilterMode::Linear,
+            mag_filter: FilterMode::Linear,
+            mip_filter: FilterMode::Linear,
+            mipmaps: true,
+        }]
+    } else {
+        Vec::new()
+    }
+}
+
 fn apply_material_texture_indices(
     materials: &mut Vec<Material>,
     material_root_texture_indices: &[usize],
@@ -718,3 +970,251 @@ fn apply_material_texture_indices(
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn load_map_with_progress_missing_file() {
+        // Errors should occur before reporting any progress.
+        let mut count = 0;
+        let result = load_map_with_progress("missing.wismhd", None, |_| count += 1);
+        assert!(matches!(result, Err(LoadMapError::Wismhd(_))));
+        assert_eq!(0, count);
+    }
+
+    #[cfg(feature = "mmap")]
+    #[test]
+    fn load_map_mmap_missing_file() {
+        let result = load_map_mmap("missing.wismhd", None);
+        assert!(matches!(result, Err(LoadMapError::Wismhd(_))));
+    }
+
+    #[cfg(feature = "mmap")]
+    #[test]
+    fn load_map_mmap_small_map() {
+        use binrw::BinReaderExt;
+        use xc3_lib::msmd::{Dlgt, EnvironmentData, Ibl, Nerd, WismdaInfo};
+
+        let dir = crate::test_support::TempDir::new("load_map_mmap_small_map");
+        let wismhd_path = dir.path().join("ma00a.wismhd");
+
+        let buffers = crate::test_support::model_buffers(
+            vec![crate::test_support::positions(vec![
+                Vec3::ZERO,
+                Vec3::X,
+                Vec3::Y,
+            ])],
+            vec![crate::vertex::IndexBuffer {
+                indices: vec![0, 1, 2],
+            }],
+        );
+        let mut vertex_data = Cursor::new(Vec::new());
+        buffers
+            .to_vertex_data(false, false)
+            .unwrap()
+            .write(&mut vertex_data)
+            .unwrap();
+        let vertex_data = vertex_data.into_inner();
+
+        // Store the vertex data uncompressed at a non zero offset.
+        let mut wismda = vec![0u8; 16];
+        wismda.extend_from_slice(&vertex_data);
+        std::fs::write(wismhd_path.with_extension("wismda"), &wismda).unwrap();
+
+        let entry: StreamEntry<xc3_lib::vertex::VertexData> = Cursor::new(
+            [
+                16u32.to_le_bytes(),
+                (vertex_data.len() as u32).to_le_bytes(),
+            ]
+            .concat(),
+        )
+        .read_le()
+        .unwrap();
+
+        let msmd = Msmd {
+            version: 10112,
+            unk1: [0; 4],
+            map_models: Vec::new(),
+            prop_models: Vec::new(),
+            unk1_1: [0; 2],
+            env_models: Vec::new(),
+            wismda_info: WismdaInfo {
+                compressed_length: wismda.len() as u32,
+                unk1: 0,
+                decompressed_length: wismda.len() as u32,
+                streaming_buffer_length: 0,
+                unks: [0; 15],
+            },
+            unk2_1: 0,
+            effects: None,
+            unk2: [0; 3],
+            prop_vertex_data: Vec::new(),
+            textures: Vec::new(),
+            strings_offset: 0,
+            foliage_models: Vec::new(),
+            prop_positions: Vec::new(),
+            foliage_data: Vec::new(),
+            unk3_1: 0,
+            unk3_2: 0,
+            dlgt: Dlgt {
+                version: 0,
+                unk1: 0,
+                unk2: 0,
+            },
+            unk_lights: Vec::new(),
+            low_textures: Vec::new(),
+            unk4: [0; 6],
+            parts: None,
+            unk4_2: 0,
+            low_models: Vec::new(),
+            env_flags: 2,
+            unk_foliage_data: Vec::new(),
+            map_vertex_data: vec![entry],
+            nerd: EnvironmentData::Nerd(Nerd {
+                version: 0,
+                unk1: 0,
+                unk2: 0,
+                unk3: 0,
+                unk4: 0,
+                unk5: 0,
+                unk6: [0; 6],
+            }),
+            unk6: [0; 3],
+            ibl: Ibl {
+                unk1: Vec::new(),
+                unk3: 0,
+                unk4: 0,
+                unk5: 0,
+                unk6: 0,
+            },
+            cmld: None,
+            unk5_2: 0,
+            unk5_3: 0,
+            unk5_4: None,
+            unk5_5: 0,
+            unk5_6: 0,
+            unk7: [0; 8],
+        };
+        msmd.save(&wismhd_path).unwrap();
+
+        let roots = load_map(&wismhd_path, None).unwrap();
+        let mmap_roots = load_map_mmap(&wismhd_path, None).unwrap();
+        assert_eq!(roots, mmap_roots);
+
+        let map_buffers = &mmap_roots[0].groups[0].buffers[0];
+        assert_eq!(
+            Some(&[Vec3::ZERO, Vec3::X, Vec3::Y][..]),
+            map_buffers.vertex_buffers[0].positions()
+        );
+        assert_eq!(buffers.index_buffers, map_buffers.index_buffers);
+    }
+
+    #[test]
+    fn prop_instances_world_translation() {
+        let props = [PropLod {
+            base_lod_index: 1,
+            lod_count: 1,
+        }];
+        let transform = Mat4::from_translation(Vec3::new(1.0, 2.0, 3.0));
+        let instance = PropInstance {
+            transform: transform.to_cols_array_2d(),
+            position: [1.0, 2.0, 3.0],
+            radius: 1.0,
+            center: [1.0, 2.0, 3.0],
+            prop_index: 0,
+            unk1: 0,
+            part_id: 0,
+            unk3: 0,
+            unk4: 0,
+            unks: [0; 2],
+        };
+
+        let mut model_instances = vec![Vec::new(); 2];
+        add_prop_instances(&mut model_instances, &props, &[instance]);
+
+        // Instances are already in world space without any group transforms.
+        assert!(model_instances[0].is_empty());
+        assert_eq!(
+            vec![Vec3::new(1.0, 2.0, 3.0)],
+            model_instances[1]
+                .iter()
+                .map(|t| t.transform_point3(Vec3::ZERO))
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn with_progress_parallel_items() {
+        let mut reported = Vec::new();
+        let sum: usize = with_progress(
+            MapLoadPhase::Props,
+            4,
+            &mut |p| reported.push(p),
+            |sender| {
+                (0..4usize)
+                    .into_par_iter()
+                    .map(|i| {
+                        sender.send(()).unwrap();
+                        i
+                    })
+                    .sum()
+            },
+        );
+        assert_eq!(6, sum);
+
+        // Each item should be reported once after the start of the phase.
+        assert_eq!(
+            (0..=4)
+                .map(|completed| MapLoadProgress {
+                    phase: MapLoadPhase::Props,
+                    completed,
+                    total: 4
+                })
+                .collect::<Vec<_>>(),
+            reported
+        );
+    }
+
+    #[test]
+    fn foliage_materials_texture_count() {
+        let materials = FoliageMaterials {
+            materials: vec![xc3_lib::map::FoliageMaterial {
+                name: "grass".to_string(),
+                unk1: 0,
+                unk2: 0,
+                unk3: 0,
+                unk4: 0,
+                unk5: 0,
+                unk6: 0,
+                unk7: 0,
+                unk8: 0,
+                unk9: 0,
+                unk10: 0,
+                unk11: 0,
+                unk12: 0,
+                unk13: 0,
+                unk14: 0,
+            }],
+            unk1: 0,
+            unk2: 0,
+            unk3: 0,
+            unk4: 0,
+            unk5: 0,
+        };
+
+        assert!(foliage_materials(&materials, 0)[0].textures.is_empty());
+        assert_eq!(
+            vec![Texture {
+                image_texture_index: 0,
+                sampler_index: 0,
+            }],
+            foliage_materials(&materials, 2)[0].textures
+        );
+
+        // Texture sampler indices should always be in range.
+        assert!(foliage_samplers(0).is_empty());
+        assert_eq!(1, foliage_samplers(2).len());
+    }
+}
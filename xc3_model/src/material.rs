@@ -1,13 +1,20 @@
+use std::collections::BTreeSet;
+
+use glam::Mat4;
 use log::warn;
-use xc3_lib::mxmd::{Materials, RenderPassType, StateFlags, Technique, TextureUsage};
+use xc3_lib::mxmd::{
+    BlendMode, CullMode, DepthFunc, MaterialParameter, Materials, ParamType, RenderPassType,
+    StateFlags, Technique, TextureUsage,
+};
 
 use crate::{
-    shader_database::{BufferDependency, Shader, Spch, TextureDependency},
-    ImageTexture,
+    shader_database::{BufferDependency, Dependency, Shader, Spch, TextureDependency},
+    ImageTexture, Sampler,
 };
 
 /// See [Material](xc3_lib::mxmd::Material) and [FoliageMaterial](xc3_lib::map::FoliageMaterial).
 #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, PartialEq, Clone)]
 pub struct Material {
     pub name: String,
@@ -27,6 +34,7 @@ pub struct Material {
 
 /// Information for alpha testing based on sampled texture values.
 #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, PartialEq, Clone)]
 pub struct TextureAlphaTest {
     /// The texture in [textures](struct.Material.html#structfield.textures) used for alpha testing.
@@ -39,6 +47,7 @@ pub struct TextureAlphaTest {
 
 /// Values assigned to known shader uniforms or `None` if not present.
 #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, PartialEq, Clone)]
 pub struct MaterialParameters {
     pub mat_color: [f32; 4],
@@ -47,6 +56,19 @@ pub struct MaterialParameters {
     pub tex_matrix: Option<Vec<[f32; 8]>>, // TODO: mat2x4?
     pub work_float4: Option<Vec<[f32; 4]>>,
     pub work_color: Option<Vec<[f32; 4]>>,
+    /// The material's values from [work_values](xc3_lib::mxmd::Materials::work_values)
+    /// starting at [work_value_start_index](xc3_lib::mxmd::Material::work_value_start_index).
+    /// See [Material::parameter] for accessing the values for each parameter.
+    pub work_values: Vec<f32>,
+    /// The parameter table for the material's [Technique] describing the layout of `work_values`.
+    pub work_value_params: Vec<MaterialParameter>,
+    /// The material's values from [shader_vars](xc3_lib::mxmd::Materials::shader_vars)
+    /// starting at [shader_var_start_index](xc3_lib::mxmd::Material::shader_var_start_index)
+    /// with [shader_var_count](xc3_lib::mxmd::Material::shader_var_count) elements.
+    /// The meaning of these values is not known yet, so they are stored unmodified.
+    /// Edited values are written by [ModelRoot::to_mxmd_model](crate::ModelRoot::to_mxmd_model)
+    /// if the number of values does not change.
+    pub shader_vars: Vec<(u16, u16)>,
 }
 
 impl Default for MaterialParameters {
@@ -57,12 +79,16 @@ impl Default for MaterialParameters {
             tex_matrix: None,
             work_float4: None,
             work_color: None,
+            work_values: Vec::new(),
+            work_value_params: Vec::new(),
+            shader_vars: Vec::new(),
         }
     }
 }
 
 /// Selects an [ImageTexture] and [Sampler](crate::Sampler).
 #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct Texture {
     /// The index of the [ImageTexture] in [image_textures](struct.ModelRoot.html#structfield.image_textures).
@@ -71,6 +97,38 @@ pub struct Texture {
     pub sampler_index: usize,
 }
 
+/// A summary of a [Material] and its texture bindings for debugging material assignments.
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, PartialEq, Clone)]
+pub struct MaterialReport {
+    pub name: String,
+    pub blend_mode: BlendMode,
+    pub cull_mode: CullMode,
+    pub depth_func: DepthFunc,
+    pub depth_write_mode: u8,
+    pub pass_type: RenderPassType,
+    /// `true` if the material has a [Shader] from the database.
+    pub has_shader: bool,
+    pub textures: Vec<TextureReport>,
+}
+
+/// The resolved bindings for a single [Texture] in a [MaterialReport].
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, PartialEq, Clone)]
+pub struct TextureReport {
+    /// The shader sampler name like "s0".
+    pub name: String,
+    pub image_texture_index: usize,
+    pub sampler_index: usize,
+    /// The sampler at [sampler_index](#structfield.sampler_index) or [None] if out of range.
+    pub sampler: Option<Sampler>,
+    /// The shader outputs like "o0.x" that sample this texture.
+    /// This is empty if the material has no [Shader] or the texture is unused.
+    pub outputs: Vec<String>,
+}
+
 pub fn create_materials(materials: &Materials, spch: Option<&Spch>) -> Vec<Material> {
     materials
         .materials
@@ -108,6 +166,39 @@ pub fn create_materials(materials: &Materials, spch: Option<&Spch>) -> Vec<Mater
         .collect()
 }
 
+/// Update the editable values in `materials` from the corresponding `new_materials`.
+pub(crate) fn apply_materials(
+    materials: &mut [xc3_lib::mxmd::Material],
+    new_materials: &[Material],
+) {
+    for (material, new_material) in materials.iter_mut().zip(new_materials) {
+        material.color = new_material.color();
+        // Preserve the original bytes for unmodified values that don't convert to a valid float.
+        if alpha_test_ref(material.alpha_test_ref) != new_material.alpha_test_ref() {
+            material.alpha_test_ref = alpha_test_ref_bytes(new_material.alpha_test_ref());
+        }
+    }
+}
+
+pub(crate) fn apply_shader_vars(
+    shader_vars: &mut [(u16, u16)],
+    materials: &[xc3_lib::mxmd::Material],
+    new_materials: &[Material],
+) {
+    for (material, new_material) in materials.iter().zip(new_materials) {
+        // Changing the count would require updating the indices for other materials.
+        let start = material.shader_var_start_index as usize;
+        let new_values = &new_material.parameters.shader_vars;
+        match shader_vars.get_mut(start..start + material.shader_var_count as usize) {
+            Some(values) if values.len() == new_values.len() => values.copy_from_slice(new_values),
+            _ => warn!(
+                "Skipping shader vars for material {:?} with a different count",
+                new_material.name
+            ),
+        }
+    }
+}
+
 fn get_shader(material: &xc3_lib::mxmd::Material, spch: Option<&Spch>) -> Option<Shader> {
     // TODO: How to choose between the two fragment shaders?
     let program_index = material.techniques.first()?.technique_index as usize;
@@ -145,13 +236,23 @@ fn find_alpha_test_texture(
         Some(TextureAlphaTest {
             texture_index,
             channel_index,
-            ref_value: 0.5,
+            ref_value: alpha_test_ref(material.alpha_test_ref),
         })
     } else {
         None
     }
 }
 
+fn material_shader_vars<'a>(
+    shader_vars: &'a [(u16, u16)],
+    material: &xc3_lib::mxmd::Material,
+) -> &'a [(u16, u16)] {
+    // Clamp the range in case the indices are out of bounds.
+    let start = (material.shader_var_start_index as usize).min(shader_vars.len());
+    let end = (start + material.shader_var_count as usize).min(shader_vars.len());
+    &shader_vars[start..end]
+}
+
 // TODO: Some elements get set by values not in the floats array?
 // TODO: How to test this?
 // TODO: This doesn't work properly for all models?
@@ -161,16 +262,29 @@ fn assign_parameters(
 ) -> MaterialParameters {
     let work_values = &materials.work_values[material.work_value_start_index as usize..];
 
-    // TODO: alpha test ref?
+    let technique = get_technique(material, &materials.techniques);
+    let work_value_params = technique.map(|t| t.parameters.clone()).unwrap_or_default();
+
+    // Only store the values used by this material's parameters.
+    let work_values_end = work_value_params
+        .iter()
+        .map(|p| p.work_value_index as usize + param_len(p))
+        .max()
+        .unwrap_or_default()
+        .min(work_values.len());
+
     let mut parameters = MaterialParameters {
         mat_color: material.color,
-        alpha_test_ref: 0.5,
+        alpha_test_ref: alpha_test_ref(material.alpha_test_ref),
         tex_matrix: None,
         work_float4: None,
         work_color: None,
+        work_values: work_values[..work_values_end].to_vec(),
+        work_value_params,
+        shader_vars: material_shader_vars(&materials.shader_vars, material).to_vec(),
     };
 
-    if let Some(info) = get_technique(material, &materials.techniques) {
+    if let Some(info) = technique {
         for param in &info.parameters {
             match param.param_type {
                 xc3_lib::mxmd::ParamType::Unk0 => (),
@@ -230,6 +344,30 @@ fn read_param<const N: usize>(
         .collect()
 }
 
+fn alpha_test_ref(bytes: [u8; 4]) -> f32 {
+    // The final byte is the exponent for common values like 0.5, so assume this is an f32.
+    // TODO: Why do some materials have invalid values?
+    let value = f32::from_le_bytes(bytes);
+    if (0.0..=1.0).contains(&value) {
+        value
+    } else {
+        0.5
+    }
+}
+
+fn alpha_test_ref_bytes(value: f32) -> [u8; 4] {
+    value.to_le_bytes()
+}
+
+fn param_len(param: &MaterialParameter) -> usize {
+    // TODO: Find the sizes for the remaining types.
+    let component_count = match param.param_type {
+        ParamType::TexMatrix => 8,
+        _ => 4,
+    };
+    param.count as usize * component_count
+}
+
 // TODO: create get methods for naming the outputs?
 /// Assignment information for the channels of each output.
 /// This includes channels from textures, material parameters, or shader constants.
@@ -263,6 +401,140 @@ pub enum ChannelAssignment {
 
 // TODO: Test cases for this?
 impl Material {
+    /// The unique indices into [samplers](struct.Models.html#structfield.samplers)
+    /// for the [textures](#structfield.textures).
+    pub fn sampler_indices(&self) -> BTreeSet<usize> {
+        self.textures.iter().map(|t| t.sampler_index).collect()
+    }
+
+    /// Returns `true` if the material should be rendered after opaque materials
+    /// based on the [BlendMode] and [RenderPassType].
+    ///
+    /// Materials with any pass type other than [RenderPassType::Unk0]
+    /// do not write to all G-Buffer outputs and are also considered transparent.
+    pub fn is_transparent(&self) -> bool {
+        self.pass_type != RenderPassType::Unk0
+            || matches!(
+                self.flags.blend_mode,
+                BlendMode::AlphaBlend | BlendMode::Additive | BlendMode::Multiplicative
+            )
+    }
+
+    /// A value for sorting materials in ascending draw order.
+    /// Materials that are not [transparent](#method.is_transparent) always return `0`.
+    /// Opaque materials in later passes like [RenderPassType::Unk1]
+    /// with [BlendMode::Disabled] still sort after the first pass.
+    ///
+    /// Materials are ordered first by [RenderPassType] and then by [BlendMode]
+    /// using the pass order times `4` plus the blend order.
+    ///
+    /// | Pass Type | Order |
+    /// | --- | --- |
+    /// | [RenderPassType::Unk0] | 0 |
+    /// | [RenderPassType::Unk1], [RenderPassType::Unk6], [RenderPassType::Unk9] | 1 |
+    /// | [RenderPassType::Unk7] | 2 |
+    ///
+    /// | Blend Mode | Order |
+    /// | --- | --- |
+    /// | [BlendMode::Disabled], [BlendMode::Unk6] | 0 |
+    /// | [BlendMode::AlphaBlend] | 1 |
+    /// | [BlendMode::Multiplicative] | 2 |
+    /// | [BlendMode::Additive] | 3 |
+    pub fn render_pass_order(&self) -> u8 {
+        let pass_order = match self.pass_type {
+            RenderPassType::Unk0 => 0,
+            RenderPassType::Unk1 => 1,
+            RenderPassType::Unk6 => 1,
+            RenderPassType::Unk7 => 2, // TODO: eye effects after other transparent meshes?
+            RenderPassType::Unk9 => 1,
+        };
+        let blend_order = match self.flags.blend_mode {
+            BlendMode::Disabled => 0,
+            BlendMode::AlphaBlend => 1,
+            BlendMode::Multiplicative => 2,
+            BlendMode::Additive => 3,
+            BlendMode::Unk6 => 0,
+        };
+        pass_order * 4 + blend_order
+    }
+
+    /// The UV transform from the first [tex_matrix](struct.MaterialParameters.html#structfield.tex_matrix) value
+    /// or [None] if the material has no `gTexMat` parameter.
+    ///
+    /// The two rows of `gTexMat` transform UV coordinates as `dot(row, vec4(u, v, 0.0, 1.0))`.
+    /// The remaining rows are filled in from the identity matrix.
+    pub fn texture_matrix(&self) -> Option<Mat4> {
+        let m = self.parameters.tex_matrix.as_ref()?.first()?;
+        Some(
+            Mat4::from_cols_array_2d(&[
+                [m[0], m[1], m[2], m[3]],
+                [m[4], m[5], m[6], m[7]],
+                [0.0, 0.0, 1.0, 0.0],
+                [0.0, 0.0, 0.0, 1.0],
+            ])
+            .transpose(),
+        )
+    }
+
+    /// The color multiplier assigned to the `gMatCol` shader uniform.
+    pub fn color(&self) -> [f32; 4] {
+        self.parameters.mat_color
+    }
+
+    /// Set the color multiplier assigned to the `gMatCol` shader uniform.
+    /// This value is written to [color](xc3_lib::mxmd::Material::color)
+    /// by [to_mxmd_model](crate::ModelRoot::to_mxmd_model).
+    pub fn set_color(&mut self, color: [f32; 4]) {
+        self.parameters.mat_color = color;
+    }
+
+    /// The reference value for alpha testing in the range `0.0` to `1.0`.
+    pub fn alpha_test_ref(&self) -> f32 {
+        self.parameters.alpha_test_ref
+    }
+
+    /// Set the reference value for alpha testing clamped to the range `0.0` to `1.0`.
+    /// This value is written to [alpha_test_ref](xc3_lib::mxmd::Material::alpha_test_ref)
+    /// by [to_mxmd_model](crate::ModelRoot::to_mxmd_model).
+    pub fn set_alpha_test_ref(&mut self, value: f32) {
+        let value = value.clamp(0.0, 1.0);
+        self.parameters.alpha_test_ref = value;
+        if let Some(alpha_test) = &mut self.alpha_test {
+            alpha_test.ref_value = value;
+        }
+    }
+
+    /// The values for the first parameter with type `param_type`
+    /// or [None] if the material does not use this parameter.
+    ///
+    /// Each [MaterialParameter] in
+    /// [work_value_params](struct.MaterialParameters.html#structfield.work_value_params)
+    /// starts at `work_value_index` in the material's
+    /// [work_values](struct.MaterialParameters.html#structfield.work_values)
+    /// and contains `count` elements.
+    /// Elements for [ParamType::TexMatrix] are 8 floats for the first two rows of the matrix.
+    /// Elements for all other types are assumed to be 4 floats.
+    pub fn parameter(&self, param_type: ParamType) -> Option<&[f32]> {
+        let param = self
+            .parameters
+            .work_value_params
+            .iter()
+            .find(|p| p.param_type == param_type)?;
+        let start = param.work_value_index as usize;
+        self.parameters
+            .work_values
+            .get(start..start + param_len(param))
+    }
+
+    /// The raw values from
+    /// [shader_vars](struct.MaterialParameters.html#structfield.shader_vars)
+    /// for this material.
+    ///
+    /// The meaning of each value is not known yet, so all values are returned unmodified.
+    pub fn shader_vars(&self) -> &[(u16, u16)] {
+        &self.parameters.shader_vars
+    }
+
     // TODO: Store these values instead of making them a method?
     /// Get the texture or value assigned to each shader output texture and channel.
     /// Most model shaders write to the G-Buffer textures.
@@ -339,6 +611,52 @@ impl Material {
     }
 }
 
+pub(crate) fn material_report(material: &Material, samplers: &[Sampler]) -> MaterialReport {
+    MaterialReport {
+        name: material.name.clone(),
+        blend_mode: material.flags.blend_mode,
+        cull_mode: material.flags.cull_mode,
+        depth_func: material.flags.depth_func,
+        depth_write_mode: material.flags.depth_write_mode,
+        pass_type: material.pass_type,
+        has_shader: material.shader.is_some(),
+        textures: material
+            .textures
+            .iter()
+            .enumerate()
+            .map(|(i, texture)| {
+                let name = format!("s{i}");
+                let outputs = material
+                    .shader
+                    .as_ref()
+                    .map(|s| texture_outputs(s, &name))
+                    .unwrap_or_default();
+
+                TextureReport {
+                    name,
+                    image_texture_index: texture.image_texture_index,
+                    sampler_index: texture.sampler_index,
+                    sampler: samplers.get(texture.sampler_index).cloned(),
+                    outputs,
+                }
+            })
+            .collect(),
+    }
+}
+
+fn texture_outputs(shader: &Shader, name: &str) -> Vec<String> {
+    shader
+        .output_dependencies
+        .iter()
+        .filter(|(_, dependencies)| {
+            dependencies
+                .iter()
+                .any(|d| matches!(d, Dependency::Texture(t) if t.name == name))
+        })
+        .map(|(output, _)| output.clone())
+        .collect()
+}
+
 fn output_assignments(shader: &Shader, parameters: &MaterialParameters) -> OutputAssignments {
     OutputAssignments {
         assignments: [0, 1, 2, 3, 4, 5].map(|i| output_assignment(shader, parameters, i)),
@@ -429,3 +747,271 @@ fn extract_parameter(p: &BufferDependency, parameters: &MaterialParameters) -> O
         _ => None,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{
+        test_support::{self, mxmd_material, state_flags},
+        AddressMode,
+    };
+
+    fn material(blend_mode: BlendMode, pass_type: RenderPassType) -> Material {
+        Material {
+            flags: StateFlags {
+                blend_mode,
+                ..state_flags()
+            },
+            pass_type,
+            ..test_support::material("material")
+        }
+    }
+
+    #[test]
+    fn apply_materials_color() {
+        let mut new_material = material(BlendMode::Disabled, RenderPassType::Unk0);
+        new_material.set_color([0.5, 0.25, 1.0, 1.0]);
+        assert_eq!([0.5, 0.25, 1.0, 1.0], new_material.color());
+
+        let mut materials = vec![xc3_lib::mxmd::Material {
+            state_flags: new_material.flags,
+            ..mxmd_material()
+        }];
+        apply_materials(&mut materials, &[new_material]);
+        assert_eq!([0.5, 0.25, 1.0, 1.0], materials[0].color);
+    }
+
+    #[test]
+    fn apply_materials_alpha_test_ref() {
+        let mut new_material = material(BlendMode::Disabled, RenderPassType::Unk0);
+        new_material.set_alpha_test_ref(0.3);
+        assert_eq!(0.3, new_material.alpha_test_ref());
+
+        let mut materials = vec![xc3_lib::mxmd::Material {
+            state_flags: new_material.flags,
+            ..mxmd_material()
+        }];
+        apply_materials(&mut materials, &[new_material]);
+        assert_eq!([0x9a, 0x99, 0x99, 0x3e], materials[0].alpha_test_ref);
+        assert_eq!(0.3, alpha_test_ref(materials[0].alpha_test_ref));
+    }
+
+    #[test]
+    fn apply_shader_vars_same_count() {
+        let mut new_material = material(BlendMode::Disabled, RenderPassType::Unk0);
+        new_material.parameters.shader_vars = vec![(8, 9), (10, 11)];
+
+        let materials = vec![xc3_lib::mxmd::Material {
+            shader_var_start_index: 1,
+            shader_var_count: 2,
+            ..mxmd_material()
+        }];
+        let mut shader_vars = vec![(0, 1), (2, 3), (4, 5), (6, 7)];
+        apply_shader_vars(&mut shader_vars, &materials, &[new_material.clone()]);
+        assert_eq!(vec![(0, 1), (8, 9), (10, 11), (6, 7)], shader_vars);
+
+        // The count can't change without affecting other materials.
+        new_material.parameters.shader_vars.push((12, 13));
+        apply_shader_vars(&mut shader_vars, &materials, &[new_material]);
+        assert_eq!(vec![(0, 1), (8, 9), (10, 11), (6, 7)], shader_vars);
+    }
+
+    #[test]
+    fn parameter_tex_matrix() {
+        let mut material = material(BlendMode::Disabled, RenderPassType::Unk0);
+        material.parameters.work_values = vec![
+            1.0, 2.0, 3.0, 4.0, // gWrkFl4[0]
+            5.0, 0.0, 0.0, 0.0, // gTexMat[0] row 0
+            0.0, 6.0, 0.0, 0.0, // gTexMat[0] row 1
+            7.0, 0.0, 0.0, 0.0, // gTexMat[1] row 0
+            0.0, 8.0, 0.0, 0.0, // gTexMat[1] row 1
+        ];
+        material.parameters.work_value_params = vec![
+            MaterialParameter {
+                param_type: ParamType::WorkFloat4,
+                work_value_index: 0,
+                unk: 0,
+                count: 1,
+            },
+            MaterialParameter {
+                param_type: ParamType::TexMatrix,
+                work_value_index: 4,
+                unk: 0,
+                count: 2,
+            },
+        ];
+
+        assert_eq!(
+            Some(&[1.0, 2.0, 3.0, 4.0][..]),
+            material.parameter(ParamType::WorkFloat4)
+        );
+        assert_eq!(
+            Some(
+                &[5.0, 0.0, 0.0, 0.0, 0.0, 6.0, 0.0, 0.0, 7.0, 0.0, 0.0, 0.0, 0.0, 8.0, 0.0, 0.0][..]
+            ),
+            material.parameter(ParamType::TexMatrix)
+        );
+        assert_eq!(None, material.parameter(ParamType::WorkColor));
+    }
+
+    #[test]
+    fn material_shader_vars_range() {
+        let material = xc3_lib::mxmd::Material {
+            shader_var_start_index: 1,
+            shader_var_count: 2,
+            ..mxmd_material()
+        };
+        let shader_vars = [(0, 1), (2, 3), (4, 5), (6, 7)];
+        assert_eq!(
+            &[(2, 3), (4, 5)],
+            material_shader_vars(&shader_vars, &material)
+        );
+        assert_eq!(
+            &[(2, 3)],
+            material_shader_vars(&shader_vars[..2], &material)
+        );
+        assert!(material_shader_vars(&[], &material).is_empty());
+    }
+
+    #[test]
+    fn create_materials_shader_vars() {
+        let materials = Materials {
+            materials: vec![
+                xc3_lib::mxmd::Material {
+                    shader_var_start_index: 0,
+                    shader_var_count: 1,
+                    ..mxmd_material()
+                },
+                xc3_lib::mxmd::Material {
+                    shader_var_start_index: 1,
+                    shader_var_count: 2,
+                    ..mxmd_material()
+                },
+            ],
+            shader_vars: vec![(0, 1), (2, 3), (4, 5), (6, 7)],
+            ..test_support::mxmd(10112).materials
+        };
+        let materials = create_materials(&materials, None);
+        assert_eq!(&[(0, 1)], materials[0].shader_vars());
+        assert_eq!(&[(2, 3), (4, 5)], materials[1].shader_vars());
+    }
+
+    #[test]
+    fn texture_matrix_scale_offset() {
+        let mut material = material(BlendMode::Disabled, RenderPassType::Unk0);
+        assert_eq!(None, material.texture_matrix());
+
+        material.parameters.tex_matrix = Some(vec![[2.0, 0.0, 0.0, 0.5, 0.0, 3.0, 0.0, 0.25]]);
+        let matrix = material.texture_matrix().unwrap();
+        assert_eq!(
+            glam::vec4(2.5, 3.25, 0.0, 1.0),
+            matrix * glam::vec4(1.0, 1.0, 0.0, 1.0)
+        );
+    }
+
+    #[test]
+    fn alpha_test_ref_values() {
+        assert_eq!(0.5, alpha_test_ref(0.5f32.to_le_bytes()));
+        assert_eq!(0.3, alpha_test_ref([0x9a, 0x99, 0x99, 0x3e]));
+        assert_eq!(0.0, alpha_test_ref([0; 4]));
+        assert_eq!(0.5, alpha_test_ref(f32::NAN.to_le_bytes()));
+        assert_eq!(0.5, alpha_test_ref(2.0f32.to_le_bytes()));
+    }
+
+    #[test]
+    fn render_pass_opaque() {
+        let opaque = material(BlendMode::Disabled, RenderPassType::Unk0);
+        assert!(!opaque.is_transparent());
+        assert_eq!(0, opaque.render_pass_order());
+    }
+
+    #[test]
+    fn render_pass_alpha_blend() {
+        let alpha = material(BlendMode::AlphaBlend, RenderPassType::Unk0);
+        assert!(alpha.is_transparent());
+        assert_eq!(1, alpha.render_pass_order());
+
+        let alpha_unk1 = material(BlendMode::AlphaBlend, RenderPassType::Unk1);
+        assert!(alpha_unk1.is_transparent());
+        assert_eq!(5, alpha_unk1.render_pass_order());
+    }
+
+    #[test]
+    fn render_pass_additive() {
+        let additive = material(BlendMode::Additive, RenderPassType::Unk0);
+        assert!(additive.is_transparent());
+        assert_eq!(3, additive.render_pass_order());
+
+        let additive_unk7 = material(BlendMode::Additive, RenderPassType::Unk7);
+        assert!(additive_unk7.is_transparent());
+        assert_eq!(11, additive_unk7.render_pass_order());
+    }
+
+    #[test]
+    fn material_report_texture_bindings() {
+        let texture = |name: &str, channels: &str| {
+            Dependency::Texture(TextureDependency {
+                name: name.to_string(),
+                channels: channels.to_string(),
+                texcoord: None,
+            })
+        };
+
+        let material = Material {
+            flags: StateFlags {
+                blend_mode: BlendMode::AlphaBlend,
+                cull_mode: CullMode::Disabled,
+                ..state_flags()
+            },
+            textures: vec![
+                Texture {
+                    image_texture_index: 2,
+                    sampler_index: 0,
+                },
+                Texture {
+                    image_texture_index: 5,
+                    sampler_index: 1,
+                },
+                Texture {
+                    image_texture_index: 1,
+                    sampler_index: 3,
+                },
+            ],
+            shader: Some(Shader {
+                output_dependencies: [
+                    ("o0.x".to_string(), vec![texture("s0", "x")]),
+                    ("o2.x".to_string(), vec![texture("s1", "xy")]),
+                    ("o2.y".to_string(), vec![texture("s1", "xy")]),
+                ]
+                .into(),
+            }),
+            ..test_support::material("body")
+        };
+        let samplers = vec![
+            Sampler {
+                address_mode_w: AddressMode::ClampToEdge,
+                ..test_support::sampler(AddressMode::Repeat)
+            };
+            2
+        ];
+
+        let report = material_report(&material, &samplers);
+        assert_eq!("body", report.name);
+        assert_eq!(BlendMode::AlphaBlend, report.blend_mode);
+        assert_eq!(CullMode::Disabled, report.cull_mode);
+        assert!(report.has_shader);
+
+        let textures = &report.textures;
+        assert_eq!(3, textures.len());
+        assert_eq!(
+            vec!["s0", "s1", "s2"],
+            textures.iter().map(|t| t.name.as_str()).collect::<Vec<_>>()
+        );
+        assert_eq!(5, textures[1].image_texture_index);
+        assert_eq!(vec!["o2.x", "o2.y"], textures[1].outputs);
+        assert!(textures[1].sampler.is_some());
+        assert!(textures[2].sampler.is_none());
+        assert!(textures[2].outputs.is_empty());
+    }
+}
@@ -0,0 +1,68 @@
+//! Logging of models and skeletons to the [Rerun](https://www.rerun.io/) viewer for debugging.
+use glam::Vec3;
+use rerun::{LineStrips3D, Points3D, RecordingStream, RecordingStreamResult};
+
+use crate::ModelRoot;
+
+impl ModelRoot {
+    /// Log the vertex positions of each mesh as points and the skeleton as line strips to `stream`.
+    ///
+    /// Points are logged to entities like "model/0/mesh/1" with all instance transforms applied.
+    /// Bones are logged to the "skeleton" entity as lines from each parent to its child in the rest pose.
+    ///
+    /// # Examples
+    /// ``` rust no_run
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let root = xc3_model::load_model("xeno3/chr/ch/ch01027000.wimdo", None)?;
+    ///
+    /// let stream = rerun::RecordingStreamBuilder::new("xc3_model").spawn()?;
+    /// root.log_to_rerun(&stream)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn log_to_rerun(&self, stream: &RecordingStream) -> RecordingStreamResult<()> {
+        for (model_index, model) in self.models.models.iter().enumerate() {
+            for (mesh_index, mesh) in model.meshes.iter().enumerate() {
+                let positions = self
+                    .buffers
+                    .vertex_buffers
+                    .get(mesh.vertex_buffer_index)
+                    .and_then(|b| b.positions());
+
+                if let Some(positions) = positions {
+                    let points = model.instances.iter().flat_map(|transform| {
+                        positions
+                            .iter()
+                            .map(move |p| transform.transform_point3(*p).to_array())
+                    });
+
+                    stream.log(
+                        format!("model/{model_index}/mesh/{mesh_index}"),
+                        &Points3D::new(points),
+                    )?;
+                }
+            }
+        }
+
+        if let Some(skeleton) = &self.skeleton {
+            let transforms = skeleton.model_space_transforms();
+
+            let strips: Vec<_> = skeleton
+                .bones
+                .iter()
+                .zip(transforms.iter())
+                .filter_map(|(bone, transform)| {
+                    let parent = transforms.get(bone.parent_index?)?;
+                    Some(vec![
+                        parent.transform_point3(Vec3::ZERO).to_array(),
+                        transform.transform_point3(Vec3::ZERO).to_array(),
+                    ])
+                })
+                .collect();
+
+            stream.log("skeleton", &LineStrips3D::new(strips))?;
+        }
+
+        Ok(())
+    }
+}
@@ -1,5 +1,6 @@
 /// See [SamplerFlags](xc3_lib::mxmd::SamplerFlags).
 #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, PartialEq, Clone, Eq)]
 pub struct Sampler {
     /// Addressing for the U or S texture coordinate.
@@ -17,6 +18,7 @@ pub struct Sampler {
 
 /// Texel mixing mode when sampling between texels.
 #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
 pub enum FilterMode {
     Nearest,
@@ -25,6 +27,7 @@ pub enum FilterMode {
 
 /// How edges should be handled in texture addressing.
 #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
 pub enum AddressMode {
     ClampToEdge,
@@ -33,6 +36,16 @@ pub enum AddressMode {
 }
 
 impl Sampler {
+    /// Addressing for the U or S texture coordinate.
+    pub fn address_mode_u(&self) -> AddressMode {
+        self.address_mode_u
+    }
+
+    /// Addressing for the V or T texture coordinate.
+    pub fn address_mode_v(&self) -> AddressMode {
+        self.address_mode_v
+    }
+
     /// The highest rendered texture mipmap LOD.
     pub fn lod_max_clamp(&self) -> f32 {
         // Values taken from tests using Ryujinx with Vulkan.
@@ -55,13 +68,16 @@ impl Sampler {
 
 impl From<xc3_lib::mxmd::SamplerFlags> for Sampler {
     fn from(flags: xc3_lib::mxmd::SamplerFlags) -> Self {
+        // Force clamp ignores the values of the previous wrap and filter flags.
+        let force_clamp = flags.force_clamp();
+        let nearest = flags.nearest() && !force_clamp;
         Self {
-            address_mode_u: address_mode(flags.repeat_u(), flags.mirror_u()),
-            address_mode_v: address_mode(flags.repeat_v(), flags.mirror_v()),
+            address_mode_u: address_mode(flags.repeat_u(), flags.mirror_u(), force_clamp),
+            address_mode_v: address_mode(flags.repeat_v(), flags.mirror_v(), force_clamp),
             address_mode_w: AddressMode::ClampToEdge,
-            mag_filter: filter_mode(flags.nearest()),
-            min_filter: filter_mode(flags.nearest()),
-            mip_filter: filter_mode(flags.nearest()),
+            mag_filter: filter_mode(nearest),
+            min_filter: filter_mode(nearest),
+            mip_filter: filter_mode(nearest),
             mipmaps: !flags.disable_mipmap_filter(),
         }
     }
@@ -75,8 +91,11 @@ fn filter_mode(nearest: bool) -> FilterMode {
     }
 }
 
-fn address_mode(repeat: bool, mirror: bool) -> AddressMode {
-    if mirror {
+fn address_mode(repeat: bool, mirror: bool, force_clamp: bool) -> AddressMode {
+    // Force clamp takes precedence over mirror, which takes precedence over repeat.
+    if force_clamp {
+        AddressMode::ClampToEdge
+    } else if mirror {
         AddressMode::MirrorRepeat
     } else if repeat {
         AddressMode::Repeat
@@ -187,4 +206,47 @@ mod tests {
             Sampler::from(SamplerFlags::from(0b_01010000))
         );
     }
+
+    #[test]
+    fn descriptor_0x3f() {
+        assert_eq!(
+            Sampler {
+                address_mode_u: AddressMode::ClampToEdge,
+                address_mode_v: AddressMode::ClampToEdge,
+                address_mode_w: AddressMode::ClampToEdge,
+                mag_filter: FilterMode::Linear,
+                min_filter: FilterMode::Linear,
+                mip_filter: FilterMode::Linear,
+                mipmaps: true,
+            },
+            Sampler::from(SamplerFlags::from(0b_00111111))
+        );
+    }
+
+    #[test]
+    fn address_mode_flag_combinations() {
+        // (force_clamp, mirror, repeat) for each coordinate.
+        let expected = |force_clamp, mirror, repeat| match (force_clamp, mirror, repeat) {
+            (true, _, _) => AddressMode::ClampToEdge,
+            (false, true, _) => AddressMode::MirrorRepeat,
+            (false, false, true) => AddressMode::Repeat,
+            (false, false, false) => AddressMode::ClampToEdge,
+        };
+
+        // Check all combinations of repeat_u, repeat_v, mirror_u, mirror_v, nearest, force_clamp.
+        for bits in 0u32..64 {
+            let flag = |i: u32| bits & (1 << i) != 0;
+            let sampler = Sampler::from(SamplerFlags::from(bits));
+            assert_eq!(
+                expected(flag(5), flag(2), flag(0)),
+                sampler.address_mode_u(),
+                "{bits:#b}"
+            );
+            assert_eq!(
+                expected(flag(5), flag(3), flag(1)),
+                sampler.address_mode_v(),
+                "{bits:#b}"
+            );
+        }
+    }
 }
@@ -70,6 +70,23 @@ impl ShaderDatabase {
         std::fs::write(path, json)?;
         Ok(())
     }
+
+    /// Find the shaders for the `.wimdo` file name `model_name` without the extension.
+    ///
+    /// If there is no exact match, characters at the end of the name are replaced with `0`
+    /// until a match is found using the same convention as `.chr` skeleton files.
+    /// For example, `"ch01012013"` tries `"ch01012010"`, `"ch01012000"`, and so on.
+    pub fn get_with_fallback(&self, model_name: &str) -> Option<&Spch> {
+        self.files.get(model_name).or_else(|| {
+            (1..model_name.len())
+                .filter(|i| model_name.is_char_boundary(model_name.len() - i))
+                .find_map(|i| {
+                    let mut name = model_name.to_string();
+                    name.replace_range(name.len() - i.., &"0".repeat(i));
+                    self.files.get(&name)
+                })
+        })
+    }
 }
 
 /// Shaders for the different map model types.
@@ -107,6 +124,7 @@ pub struct ShaderProgram {
 /// Node based editors like Blender's shader editor should use these values
 /// to determine how to construct node groups.
 #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, PartialEq, Clone)]
 pub struct Shader {
     /// A list of input dependencies like "s0.xyz" assigned to each output like "out_attr0.x".
@@ -416,6 +434,34 @@ fn spch_from_indexed(spch: SpchIndexed, dependencies: &[Dependency]) -> Spch {
 mod tests {
     use super::*;
 
+    fn spch(program_count: usize) -> Spch {
+        Spch {
+            programs: vec![
+                ShaderProgram {
+                    shaders: Vec::new()
+                };
+                program_count
+            ],
+        }
+    }
+
+    #[test]
+    fn get_with_fallback_exact_and_zero_padded() {
+        let database = ShaderDatabase {
+            files: [
+                ("ch01012010".to_string(), spch(1)),
+                ("ch01012013".to_string(), spch(2)),
+            ]
+            .into(),
+            map_files: IndexMap::new(),
+        };
+
+        assert_eq!(Some(&spch(2)), database.get_with_fallback("ch01012013"));
+        assert_eq!(Some(&spch(1)), database.get_with_fallback("ch01012012"));
+        assert_eq!(Some(&spch(1)), database.get_with_fallback("ch01012019"));
+        assert_eq!(None, database.get_with_fallback("ch02012012"));
+    }
+
     #[test]
     fn material_channel_assignment_empty() {
         let shader = Shader {
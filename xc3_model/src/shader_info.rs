@@ -0,0 +1,76 @@
+use binrw::BinResult;
+use xc3_lib::spch::Spch;
+
+/// Metadata for the shader programs in an embedded [Spch].
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, PartialEq, Clone)]
+pub struct ShaderInfo {
+    pub programs: Vec<ProgramInfo>,
+}
+
+/// The names of the resources used by the shaders for a single [Nvsd](xc3_lib::spch::Nvsd).
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, PartialEq, Clone)]
+pub struct ProgramInfo {
+    /// The index into [slct_offsets](xc3_lib::spch::Spch::slct_offsets).
+    pub slct_index: usize,
+    /// The index into [programs](xc3_lib::spch::Slct::programs) for the [Slct](xc3_lib::spch::Slct).
+    pub nvsd_index: usize,
+    /// The name from the [string_section](xc3_lib::spch::Spch::string_section).
+    /// Not all programs have an associated name.
+    pub name: Option<String>,
+    pub uniform_buffers: Vec<String>,
+    pub storage_buffers: Vec<String>,
+    pub uniforms: Vec<String>,
+    pub samplers: Vec<String>,
+    pub attributes: Vec<String>,
+}
+
+impl ShaderInfo {
+    /// Read the metadata for all programs in `spch` without decompressing or decompiling any shaders.
+    pub fn from_spch(spch: &Spch) -> BinResult<Self> {
+        let mut programs = Vec::new();
+
+        for (slct_index, slct_offset) in spch.slct_offsets.iter().enumerate() {
+            let slct = slct_offset.read_slct(&spch.slct_section)?;
+
+            let name = spch
+                .string_section
+                .as_ref()
+                .and_then(|s| s.program_names.get(slct_index))
+                .map(|n| n.name.clone());
+
+            for (nvsd_index, program) in slct.programs.iter().enumerate() {
+                let nvsd = program.read_nvsd()?;
+
+                let buffer_names = |buffers: Option<Vec<xc3_lib::spch::UniformBuffer>>| {
+                    buffers
+                        .unwrap_or_default()
+                        .into_iter()
+                        .map(|b| b.name)
+                        .collect()
+                };
+
+                programs.push(ProgramInfo {
+                    slct_index,
+                    nvsd_index,
+                    name: name.clone(),
+                    uniform_buffers: buffer_names(nvsd.uniform_buffers),
+                    storage_buffers: buffer_names(nvsd.storage_buffers),
+                    uniforms: nvsd.uniforms.into_iter().map(|u| u.name).collect(),
+                    samplers: nvsd
+                        .samplers
+                        .unwrap_or_default()
+                        .into_iter()
+                        .map(|s| s.name)
+                        .collect(),
+                    attributes: nvsd.attributes.into_iter().map(|a| a.name).collect(),
+                });
+            }
+        }
+
+        Ok(Self { programs })
+    }
+}
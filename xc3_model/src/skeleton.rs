@@ -1,20 +1,28 @@
+use std::collections::BTreeMap;
+
 use glam::{vec3, Mat4, Quat};
 use log::warn;
 
+use crate::animation::Animation;
+
 #[cfg(feature = "arbitrary")]
-use crate::arbitrary_mat4;
+use crate::{arbitrary_mat4, arbitrary_mat4s};
 
 /// See [Skeleton](xc3_lib::bc::skel::Skeleton) and [Skinning](xc3_lib::mxmd::Skinning).
 // TODO: Assume bones appear after their parents?
 #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, PartialEq, Clone)]
 pub struct Skeleton {
     /// The hierarchy of bones in the skeleton.
     pub bones: Vec<Bone>,
+    /// Additional data for procedurally animated bones like hair or cloth.
+    pub procedural_bones: Vec<ProceduralBone>,
 }
 
 /// A single node in the skeleton heirarchy.
 #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, PartialEq, Clone)]
 pub struct Bone {
     /// The name used by some animations to identify this bone.
@@ -27,6 +35,22 @@ pub struct Bone {
     pub parent_index: Option<usize>,
 }
 
+/// A bone animated at runtime like hair or cloth physics bones.
+/// See [AsBone](xc3_lib::mxmd::AsBone).
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, PartialEq, Clone)]
+pub struct ProceduralBone {
+    /// The name of the procedurally animated [Bone].
+    pub name: String,
+    /// The name of the parent [Bone] for the procedural animation.
+    pub parent_name: String,
+    // TODO: What do these transforms represent?
+    /// The transforms from [AsBoneData](xc3_lib::mxmd::AsBoneData) for this bone.
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = arbitrary_mat4s))]
+    pub transforms: Vec<Mat4>,
+}
+
 impl Skeleton {
     // TODO: Test this?
     pub fn from_skel(
@@ -69,11 +93,11 @@ impl Skeleton {
 
         // Add parenting and transform information for additional bones.
         // TODO: Does the mxmd have parenting information for all bones?
-        if let Some(as_bone_data) = skinning
+        let as_bone_data = skinning
             .as_bone_data
             .as_ref()
-            .and_then(|d| d.as_bone_data.as_ref())
-        {
+            .and_then(|d| d.as_bone_data.as_ref());
+        if let Some(as_bone_data) = as_bone_data {
             for as_bone in &as_bone_data.bones {
                 update_bone(
                     &mut bones,
@@ -83,6 +107,9 @@ impl Skeleton {
                 );
             }
         }
+        let procedural_bones = as_bone_data
+            .map(|data| procedural_bones(data, skinning))
+            .unwrap_or_default();
 
         if let Some(unk4) = skinning
             .unk_offset4
@@ -108,14 +135,17 @@ impl Skeleton {
             }
         }
 
-        Self { bones }
+        Self {
+            bones,
+            procedural_bones,
+        }
     }
 
     /// The global transform for each bone in model space
     /// by recursively applying the parent transform.
     ///
     /// This is also known as the bone's "rest pose" or "bind pose".
-    /// For inverse bind matrices, simply invert the model space transforms.
+    /// See [inverse_bind_matrices](Skeleton::inverse_bind_matrices) for skinning.
     pub fn model_space_transforms(&self) -> Vec<Mat4> {
         let mut final_transforms: Vec<_> = self.bones.iter().map(|b| b.transform).collect();
 
@@ -128,6 +158,174 @@ impl Skeleton {
 
         final_transforms
     }
+
+    /// The inverse of the [model_space_transforms](Skeleton::model_space_transforms) for each bone.
+    ///
+    /// These are the inverse bind matrices used for skinning and match the
+    /// [inverse_bind_transforms](xc3_lib::mxmd::Skinning::inverse_bind_transforms)
+    /// stored in the model for bones with the same name within floating point tolerance.
+    pub fn inverse_bind_matrices(&self) -> Vec<Mat4> {
+        self.model_space_transforms()
+            .iter()
+            .map(|t| t.inverse())
+            .collect()
+    }
+
+    /// Set the local transform of the bone at `index` so that its model space transform is `transform`.
+    /// This is the inverse of [model_space_transforms](Skeleton::model_space_transforms) for a single bone.
+    ///
+    /// The local transforms of child bones are unchanged,
+    /// so children will move with the bone.
+    pub fn set_model_space_transform(&mut self, index: usize, transform: Mat4) {
+        let parent_transform = self.bones[index]
+            .parent_index
+            .map(|p| self.model_space_transforms()[p])
+            .unwrap_or(Mat4::IDENTITY);
+        self.bones[index].transform = parent_transform.inverse() * transform;
+    }
+
+    /// Create a skeleton with the names and parents from `bones`
+    /// and local transforms calculated from the model space `transforms` for each bone.
+    /// This is the inverse of [model_space_transforms](Skeleton::model_space_transforms).
+    pub fn from_model_space_transforms(bones: &[Bone], transforms: &[Mat4]) -> Self {
+        Self {
+            bones: bones
+                .iter()
+                .zip(transforms)
+                .map(|(bone, transform)| Bone {
+                    name: bone.name.clone(),
+                    transform: bone
+                        .parent_index
+                        .map(|p| transforms[p].inverse() * *transform)
+                        .unwrap_or(*transform),
+                    parent_index: bone.parent_index,
+                })
+                .collect(),
+            procedural_bones: Vec::new(),
+        }
+    }
+
+    /// The indices of the bones with the bone at `index` as their parent in ascending order.
+    pub fn children(&self, index: usize) -> Vec<usize> {
+        self.bones
+            .iter()
+            .enumerate()
+            .filter(|(_, b)| b.parent_index == Some(index))
+            .map(|(i, _)| i)
+            .collect()
+    }
+
+    /// The model space distance from each bone to its first child.
+    /// See [model_space_transforms](Skeleton::model_space_transforms).
+    ///
+    /// Bones without children use the length of their parent
+    /// or `1.0` if the bone also has no parent.
+    /// This is useful for drawing bones as tapered shapes.
+    pub fn bone_lengths(&self) -> Vec<f32> {
+        let transforms = self.model_space_transforms();
+        let position = |i: usize| transforms[i].col(3).truncate();
+
+        let lengths: Vec<_> = (0..self.bones.len())
+            .map(|i| {
+                self.children(i)
+                    .first()
+                    .map(|c| position(i).distance(position(*c)))
+            })
+            .collect();
+
+        // A parent always has at least one child, so its length is already known.
+        lengths
+            .iter()
+            .zip(&self.bones)
+            .map(|(length, bone)| {
+                length
+                    .or_else(|| bone.parent_index.and_then(|p| lengths[p]))
+                    .unwrap_or(1.0)
+            })
+            .collect()
+    }
+
+    /// Add any bones from `base` not already present in this skeleton.
+    ///
+    /// Bones are matched by name, so new bones can be parented to existing bones.
+    /// New bones are added after their parents even if `base` lists them out of order.
+    /// Bones whose parent can't be found are added without a parent with a warning.
+    /// Existing bones are not modified.
+    pub fn merge(&mut self, base: &Skeleton) {
+        let mut pending: Vec<_> = base.bones.iter().collect();
+        loop {
+            let pending_count = pending.len();
+            pending.retain(|bone| {
+                if self.bones.iter().any(|b| b.name == bone.name) {
+                    return false;
+                }
+
+                let parent_index = match bone.parent_index {
+                    Some(p) => {
+                        let parent_name = base.bones.get(p).map(|b| &b.name);
+                        match self.bones.iter().position(|b| Some(&b.name) == parent_name) {
+                            Some(index) => Some(index),
+                            // Try again once the parent has been added.
+                            None => return true,
+                        }
+                    }
+                    None => None,
+                };
+
+                self.bones.push(Bone {
+                    name: bone.name.clone(),
+                    transform: bone.transform,
+                    parent_index,
+                });
+                false
+            });
+
+            if pending.len() == pending_count {
+                break;
+            }
+        }
+
+        for bone in pending {
+            if self.bones.iter().any(|b| b.name == bone.name) {
+                continue;
+            }
+            warn!(
+                "Adding bone {:?} without a parent since its parent could not be found",
+                bone.name
+            );
+            self.bones.push(Bone {
+                name: bone.name.clone(),
+                transform: bone.transform,
+                parent_index: None,
+            });
+        }
+
+        for bone in &base.procedural_bones {
+            if !self.procedural_bones.iter().any(|b| b.name == bone.name) {
+                self.procedural_bones.push(bone.clone());
+            }
+        }
+    }
+
+    /// Create a new skeleton with the bone transforms for `animation` at `frame`.
+    ///
+    /// The bone hierarchy is unchanged, and bones without animation tracks keep their rest pose transform.
+    /// See [Animation::local_space_transforms] for the transform values.
+    pub fn posed(&self, animation: &Animation, frame: f32) -> Self {
+        let transforms = animation.local_space_transforms(self, frame);
+        Self {
+            bones: self
+                .bones
+                .iter()
+                .zip(transforms)
+                .map(|(bone, transform)| Bone {
+                    transform,
+                    ..bone.clone()
+                })
+                .collect(),
+            procedural_bones: self.procedural_bones.clone(),
+        }
+    }
 }
 
 fn update_bone(
@@ -151,6 +349,88 @@ fn update_bone(
     }
 }
 
+fn procedural_bones(
+    as_bone_data: &xc3_lib::mxmd::AsBoneData,
+    skinning: &xc3_lib::mxmd::Skinning,
+) -> Vec<ProceduralBone> {
+    // Each bone has 3 transforms.
+    as_bone_data
+        .bones
+        .iter()
+        .zip(as_bone_data.unk2.chunks_exact(3))
+        .filter_map(|(as_bone, transforms)| {
+            Some(ProceduralBone {
+                name: skinning
+                    .bones
+                    .get(as_bone.bone_index as usize)?
+                    .name
+                    .clone(),
+                parent_name: skinning
+                    .bones
+                    .get(as_bone.parent_index as usize)?
+                    .name
+                    .clone(),
+                transforms: transforms.iter().map(Mat4::from_cols_array_2d).collect(),
+            })
+        })
+        .collect()
+}
+
+/// Update the bone transforms in `skel` from the bones in `skeleton` with the same name.
+///
+/// Bones are matched by name since `skeleton` may be reordered
+/// or contain additional bones merged from other skeletons.
+/// Bones with a different parent than in `skel` are skipped with a warning.
+/// Bones not present in `skel` are ignored, so adding, removing, or renaming bones is not supported.
+pub(crate) fn apply_skeleton(skel: &mut xc3_lib::bc::skel::Skeleton, skeleton: &Skeleton) {
+    let bone_indices: BTreeMap<_, _> = skeleton
+        .bones
+        .iter()
+        .enumerate()
+        .map(|(i, b)| (b.name.as_str(), i))
+        .collect();
+
+    let names = &skel.names.elements;
+    for (i, transform) in skel.transforms.iter_mut().enumerate() {
+        let Some(name) = names.get(i).map(|n| n.name.as_str()) else {
+            continue;
+        };
+        let Some(bone) = bone_indices.get(name).map(|i| &skeleton.bones[*i]) else {
+            continue;
+        };
+
+        let skel_parent = skel
+            .parent_indices
+            .elements
+            .get(i)
+            .and_then(|p| usize::try_from(*p).ok())
+            .and_then(|p| names.get(p))
+            .map(|n| n.name.as_str());
+        let parent = bone
+            .parent_index
+            .and_then(|p| skeleton.bones.get(p))
+            .map(|b| b.name.as_str());
+        if skel_parent != parent {
+            warn!(
+                "Skipping bone {name:?} with parent {parent:?} that does not match skel parent {skel_parent:?}"
+            );
+            continue;
+        }
+
+        *transform = bc_transform(bone.transform, transform);
+    }
+}
+
+fn bc_transform(transform: Mat4, original: &xc3_lib::bc::Transform) -> xc3_lib::bc::Transform {
+    // Preserve the unused fourth component from the original values.
+    let (s, r, t) = transform.to_scale_rotation_translation();
+    xc3_lib::bc::Transform {
+        translation: [t.x, t.y, t.z, original.translation[3]],
+        rotation_quaternion: r.to_array(),
+        scale: [s.x, s.y, s.z, original.scale[3]],
+    }
+}
+
 // TODO: Test the order of transforms.
 fn bone_transform(b: &xc3_lib::bc::Transform) -> Mat4 {
     Mat4::from_translation(vec3(b.translation[0], b.translation[1], b.translation[2]))
@@ -160,7 +440,433 @@ fn bone_transform(b: &xc3_lib::bc::Transform) -> Mat4 {
 
 #[cfg(test)]
 mod tests {
+    use super::*;
+
     // TODO: Test global/world transforms and inverse bind transforms
     #[test]
     fn test() {}
+
+    fn mxmd_bone(name: &str) -> xc3_lib::mxmd::Bone {
+        xc3_lib::mxmd::Bone {
+            name: name.to_string(),
+            unk1: 0.0,
+            unk_type: (0, 0),
+            unk_index: 0,
+            unk: [0; 2],
+        }
+    }
+
+    fn bc_skeleton(transforms: Vec<xc3_lib::bc::Transform>) -> xc3_lib::bc::Bc {
+        xc3_lib::bc::Bc {
+            unk1: 0,
+            data_size: 0,
+            address_count: 0,
+            data: xc3_lib::bc::BcData::Skel(xc3_lib::bc::skel::Skel {
+                skeleton: xc3_lib::bc::skel::Skeleton {
+                    unk1: xc3_lib::bc::BcList {
+                        elements: Vec::new(),
+                        unk1: -1,
+                    },
+                    unk2: 0,
+                    root_bone_name: "root".to_string(),
+                    parent_indices: xc3_lib::bc::BcList {
+                        elements: vec![-1, 0],
+                        unk1: -1,
+                    },
+                    names: xc3_lib::bc::BcList {
+                        elements: ["root", "spine"]
+                            .into_iter()
+                            .map(|name| xc3_lib::bc::skel::BoneName {
+                                name: name.to_string(),
+                                unk: [0; 2],
+                            })
+                            .collect(),
+                        unk1: -1,
+                    },
+                    transforms,
+                    unk3: -1,
+                    extra_track_slots: Vec::new(),
+                    unk4: -1,
+                    mt_indices: Vec::new(),
+                    unk5: -1,
+                    mt_names: Vec::new(),
+                    unk6: -1,
+                    mt_transforms: Vec::new(),
+                    unk7: -1,
+                    labels: xc3_lib::bc::BcList {
+                        elements: Vec::new(),
+                        unk1: -1,
+                    },
+                    extra: xc3_lib::bc::skel::SkeletonExtra::Unk0,
+                },
+            }),
+            addresses: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn apply_skeleton_edited_transform() {
+        let identity = xc3_lib::bc::Transform {
+            translation: [0.0, 0.0, 0.0, 1.0],
+            rotation_quaternion: [0.0, 0.0, 0.0, 1.0],
+            scale: [1.0, 1.0, 1.0, 1.0],
+        };
+        let mut bc = bc_skeleton(vec![identity.clone(); 2]);
+
+        let skeleton = Skeleton {
+            bones: vec![
+                Bone {
+                    name: "root".to_string(),
+                    transform: Mat4::IDENTITY,
+                    parent_index: None,
+                },
+                Bone {
+                    name: "spine".to_string(),
+                    transform: Mat4::from_translation(vec3(1.0, 2.0, 3.0))
+                        * Mat4::from_scale(vec3(2.0, 2.0, 2.0)),
+                    parent_index: Some(0),
+                },
+            ],
+            procedural_bones: Vec::new(),
+        };
+
+        if let xc3_lib::bc::BcData::Skel(skel) = &mut bc.data {
+            apply_skeleton(&mut skel.skeleton, &skeleton);
+        }
+
+        // Check the values after writing and reading the skel bytes.
+        let entry = xc3_lib::sar1::Entry::new("skeleton.skl".to_string(), &bc).unwrap();
+        let new_bc: xc3_lib::bc::Bc = entry.read_data().unwrap();
+        match new_bc.data {
+            xc3_lib::bc::BcData::Skel(skel) => {
+                assert_eq!(identity, skel.skeleton.transforms[0]);
+                assert_eq!(
+                    xc3_lib::bc::Transform {
+                        translation: [1.0, 2.0, 3.0, 1.0],
+                        rotation_quaternion: [0.0, 0.0, 0.0, 1.0],
+                        scale: [2.0, 2.0, 2.0, 1.0],
+                    },
+                    skel.skeleton.transforms[1]
+                );
+            }
+            _ => panic!("expected skel data"),
+        }
+    }
+
+    #[test]
+    fn apply_skeleton_match_names() {
+        let identity = xc3_lib::bc::Transform {
+            translation: [0.0, 0.0, 0.0, 1.0],
+            rotation_quaternion: [0.0, 0.0, 0.0, 1.0],
+            scale: [1.0, 1.0, 1.0, 1.0],
+        };
+        let mut bc = bc_skeleton(vec![identity.clone(); 2]);
+
+        // Reordered bones with a merged base skeleton bone not in the skel.
+        let skeleton = Skeleton {
+            bones: vec![
+                Bone {
+                    name: "base".to_string(),
+                    transform: Mat4::from_translation(vec3(4.0, 5.0, 6.0)),
+                    parent_index: None,
+                },
+                Bone {
+                    name: "spine".to_string(),
+                    transform: Mat4::from_translation(vec3(1.0, 2.0, 3.0)),
+                    parent_index: Some(2),
+                },
+                Bone {
+                    name: "root".to_string(),
+                    transform: Mat4::from_translation(vec3(0.0, 1.0, 0.0)),
+                    parent_index: Some(0),
+                },
+            ],
+            procedural_bones: Vec::new(),
+        };
+
+        if let xc3_lib::bc::BcData::Skel(skel) = &mut bc.data {
+            apply_skeleton(&mut skel.skeleton, &skeleton);
+
+            // The root has a different parent, so it should not be changed.
+            assert_eq!(identity, skel.skeleton.transforms[0]);
+            assert_eq!(
+                xc3_lib::bc::Transform {
+                    translation: [1.0, 2.0, 3.0, 1.0],
+                    rotation_quaternion: [0.0, 0.0, 0.0, 1.0],
+                    scale: [1.0, 1.0, 1.0, 1.0],
+                },
+                skel.skeleton.transforms[1]
+            );
+            assert_eq!(
+                vec!["root", "spine"],
+                skel.skeleton
+                    .names
+                    .elements
+                    .iter()
+                    .map(|n| n.name.as_str())
+                    .collect::<Vec<_>>()
+            );
+        }
+    }
+
+    #[test]
+    fn bone_lengths_chain() {
+        let skeleton = Skeleton {
+            bones: vec![
+                Bone {
+                    name: "root".to_string(),
+                    transform: Mat4::IDENTITY,
+                    parent_index: None,
+                },
+                Bone {
+                    name: "spine".to_string(),
+                    transform: Mat4::from_translation(vec3(0.0, 2.0, 0.0)),
+                    parent_index: Some(0),
+                },
+                Bone {
+                    name: "head".to_string(),
+                    transform: Mat4::from_translation(vec3(0.0, 3.0, 0.0)),
+                    parent_index: Some(1),
+                },
+            ],
+            procedural_bones: Vec::new(),
+        };
+
+        assert_eq!(vec![1], skeleton.children(0));
+        assert_eq!(vec![2], skeleton.children(1));
+        assert!(skeleton.children(2).is_empty());
+        assert_eq!(vec![2.0, 3.0, 3.0], skeleton.bone_lengths());
+    }
+
+    #[test]
+    fn inverse_bind_matrices_chain() {
+        let skeleton = Skeleton {
+            bones: vec![
+                Bone {
+                    name: "root".to_string(),
+                    transform: Mat4::from_translation(vec3(1.0, 0.0, 0.0)),
+                    parent_index: None,
+                },
+                Bone {
+                    name: "spine".to_string(),
+                    transform: Mat4::from_translation(vec3(0.0, 2.0, 0.0)),
+                    parent_index: Some(0),
+                },
+            ],
+            procedural_bones: Vec::new(),
+        };
+
+        assert_eq!(
+            vec![
+                Mat4::from_translation(vec3(-1.0, 0.0, 0.0)),
+                Mat4::from_translation(vec3(-1.0, -2.0, 0.0)),
+            ],
+            skeleton.inverse_bind_matrices()
+        );
+    }
+
+    #[test]
+    fn set_model_space_transform_child() {
+        let mut skeleton = Skeleton {
+            bones: vec![
+                Bone {
+                    name: "root".to_string(),
+                    transform: Mat4::from_translation(vec3(1.0, 0.0, 0.0)),
+                    parent_index: None,
+                },
+                Bone {
+                    name: "child".to_string(),
+                    transform: Mat4::IDENTITY,
+                    parent_index: Some(0),
+                },
+            ],
+            procedural_bones: Vec::new(),
+        };
+
+        skeleton.set_model_space_transform(1, Mat4::from_translation(vec3(1.0, 2.0, 3.0)));
+        assert_eq!(
+            Mat4::from_translation(vec3(0.0, 2.0, 3.0)),
+            skeleton.bones[1].transform
+        );
+
+        let transforms = skeleton.model_space_transforms();
+        assert_eq!(Mat4::from_translation(vec3(1.0, 2.0, 3.0)), transforms[1]);
+        assert_eq!(
+            skeleton.bones,
+            Skeleton::from_model_space_transforms(&skeleton.bones, &transforms).bones
+        );
+    }
+
+    #[test]
+    fn merge_base_skeleton_by_name() {
+        let mut skeleton = Skeleton {
+            bones: vec![
+                Bone {
+                    name: "root".to_string(),
+                    transform: Mat4::IDENTITY,
+                    parent_index: None,
+                },
+                Bone {
+                    name: "hair".to_string(),
+                    transform: Mat4::from_translation(vec3(0.0, 3.0, 0.0)),
+                    parent_index: Some(0),
+                },
+            ],
+            procedural_bones: Vec::new(),
+        };
+
+        let base = Skeleton {
+            bones: vec![
+                Bone {
+                    name: "root".to_string(),
+                    transform: Mat4::from_translation(vec3(1.0, 0.0, 0.0)),
+                    parent_index: None,
+                },
+                Bone {
+                    name: "spine".to_string(),
+                    transform: Mat4::from_translation(vec3(0.0, 1.0, 0.0)),
+                    parent_index: Some(0),
+                },
+                Bone {
+                    name: "head".to_string(),
+                    transform: Mat4::from_translation(vec3(0.0, 2.0, 0.0)),
+                    parent_index: Some(1),
+                },
+            ],
+            procedural_bones: Vec::new(),
+        };
+
+        skeleton.merge(&base);
+
+        assert_eq!(
+            Skeleton {
+                bones: vec![
+                    Bone {
+                        name: "root".to_string(),
+                        transform: Mat4::IDENTITY,
+                        parent_index: None,
+                    },
+                    Bone {
+                        name: "hair".to_string(),
+                        transform: Mat4::from_translation(vec3(0.0, 3.0, 0.0)),
+                        parent_index: Some(0),
+                    },
+                    Bone {
+                        name: "spine".to_string(),
+                        transform: Mat4::from_translation(vec3(0.0, 1.0, 0.0)),
+                        parent_index: Some(0),
+                    },
+                    Bone {
+                        name: "head".to_string(),
+                        transform: Mat4::from_translation(vec3(0.0, 2.0, 0.0)),
+                        parent_index: Some(2),
+                    },
+                ],
+                procedural_bones: Vec::new(),
+            },
+            skeleton
+        );
+    }
+
+    #[test]
+    fn merge_base_skeleton_parents_out_of_order() {
+        let bone = |name: &str, parent_index| Bone {
+            name: name.to_string(),
+            transform: Mat4::IDENTITY,
+            parent_index,
+        };
+        let mut skeleton = Skeleton {
+            bones: vec![bone("root", None)],
+            procedural_bones: Vec::new(),
+        };
+
+        // The children are listed before their parents.
+        let base = Skeleton {
+            bones: vec![
+                bone("root", None),
+                bone("head", Some(3)),
+                bone("missing_parent", Some(10)),
+                bone("spine", Some(4)),
+                bone("hip", Some(0)),
+            ],
+            procedural_bones: Vec::new(),
+        };
+
+        let warnings = crate::test_support::log_messages(|| skeleton.merge(&base));
+
+        assert_eq!(
+            vec![
+                bone("root", None),
+                bone("hip", Some(0)),
+                bone("spine", Some(1)),
+                bone("head", Some(2)),
+                bone("missing_parent", None),
+            ],
+            skeleton.bones
+        );
+        assert_eq!(
+            vec![
+                "Adding bone \"missing_parent\" without a parent since its parent could not be found"
+                    .to_string()
+            ],
+            warnings
+        );
+    }
+
+    #[test]
+    fn procedural_bones_parent_names() {
+        let as_bone_data = xc3_lib::mxmd::AsBoneData {
+            bones: vec![
+                xc3_lib::mxmd::AsBone {
+                    bone_index: 2,
+                    parent_index: 1,
+                    unk: [0; 19],
+                },
+                xc3_lib::mxmd::AsBone {
+                    bone_index: 3,
+                    parent_index: 2,
+                    unk: [0; 19],
+                },
+            ],
+            unk1: Vec::new(),
+            unk2: vec![Mat4::IDENTITY.to_cols_array_2d(); 6],
+            unk3: 0,
+            unk: [0; 2],
+        };
+
+        let skinning = xc3_lib::mxmd::Skinning {
+            count1: 4,
+            count2: 4,
+            bones: vec![
+                mxmd_bone("root"),
+                mxmd_bone("head"),
+                mxmd_bone("AS_hair1"),
+                mxmd_bone("AS_hair2"),
+            ],
+            inverse_bind_transforms: vec![Mat4::IDENTITY.to_cols_array_2d(); 4],
+            transforms2: None,
+            transforms3: None,
+            bone_indices: vec![2, 3],
+            unk_offset4: None,
+            unk_offset5: None,
+            as_bone_data: None,
+            unk: None,
+        };
+
+        assert_eq!(
+            vec![
+                ProceduralBone {
+                    name: "AS_hair1".to_string(),
+                    parent_name: "head".to_string(),
+                    transforms: vec![Mat4::IDENTITY; 3]
+                },
+                ProceduralBone {
+                    name: "AS_hair2".to_string(),
+                    parent_name: "AS_hair1".to_string(),
+                    transforms: vec![Mat4::IDENTITY; 3]
+                }
+            ],
+            procedural_bones(&as_bone_data, &skinning)
+        );
+    }
 }
@@ -9,6 +9,7 @@ use crate::arbitrary_vec4s;
 // TODO: come up with a better name?
 /// See [Weights](xc3_lib::vertex::Weights).
 #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, PartialEq, Clone)]
 pub struct Weights {
     /// Attributes for buffers containing skin weights.
@@ -22,6 +23,7 @@ pub struct Weights {
 }
 
 #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, PartialEq, Clone)]
 pub enum WeightGroups {
     Legacy {
@@ -61,6 +63,16 @@ impl Weights {
         }
     }
 
+    /// The normalized non zero bone weights for each entry in the weight buffer at `buffer_index`.
+    /// See [SkinWeights::vertex_bone_names] for details.
+    ///
+    /// For legacy models that combine multiple buffers, use [Self::weight_buffer] instead.
+    pub fn vertex_bone_names(&self, buffer_index: usize) -> Option<Vec<Vec<(String, f32)>>> {
+        self.weight_buffers
+            .get(buffer_index)
+            .map(|b| b.vertex_bone_names())
+    }
+
     fn concatenate_buffers(
         &self,
         weight_buffer_indices: [usize; 6],
@@ -172,6 +184,7 @@ pub struct VertexWeight {
 
 #[derive(Debug, PartialEq, Clone)]
 #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct SkinWeights {
     pub bone_indices: Vec<[u8; 4]>,
     #[cfg_attr(feature = "arbitrary", arbitrary(with = arbitrary_vec4s))]
@@ -272,6 +285,41 @@ impl SkinWeights {
         influences
     }
 
+    /// The bone names and weights for each element in [weights](#structfield.weights).
+    ///
+    /// Zero weights are removed, and the remaining weights are normalized to sum to 1.0.
+    /// Use [Self::reindex] first to get the values for each vertex.
+    pub fn vertex_bone_names(&self) -> Vec<Vec<(String, f32)>> {
+        self.bone_indices
+            .iter()
+            .zip(&self.weights)
+            .map(|(indices, weights)| {
+                let mut influences: Vec<_> = indices
+                    .iter()
+                    .zip(weights.to_array())
+                    .filter(|(_, weight)| *weight > 0.0)
+                    .filter_map(|(i, weight)| match self.bone_names.get(*i as usize) {
+                        Some(name) => Some((name.clone(), weight)),
+                        None => {
+                            error!(
+                                "Bone index {i} out of range for {} bones",
+                                self.bone_names.len()
+                            );
+                            None
+                        }
+                    })
+                    .collect();
+
+                let sum: f32 = influences.iter().map(|(_, w)| w).sum();
+                for (_, weight) in &mut influences {
+                    *weight /= sum;
+                }
+
+                influences
+            })
+            .collect()
+    }
+
     // TODO: Remove the names parameter and add a modify names method?
     /// Convert the per-bone `influences` to per-vertex indices and weights.
     /// The `bone_names` provide the mapping from bone names to bone indices.
@@ -495,6 +543,34 @@ mod tests {
         );
     }
 
+    #[test]
+    fn vertex_bone_names_normalized() {
+        let weights = Weights {
+            weight_buffers: vec![SkinWeights {
+                bone_indices: vec![[0, 1, 0, 0], [2, 1, 0, 0], [0, 0, 0, 0]],
+                weights: vec![
+                    vec4(0.5, 0.5, 0.0, 0.0),
+                    vec4(0.25, 0.25, 0.0, 0.0),
+                    Vec4::ZERO,
+                ],
+                bone_names: vec!["a".to_string(), "b".to_string(), "c".to_string()],
+            }],
+            weight_groups: WeightGroups::Legacy {
+                weight_buffer_indices: [0; 6],
+            },
+        };
+
+        assert_eq!(
+            Some(vec![
+                vec![("a".to_string(), 0.5), ("b".to_string(), 0.5)],
+                vec![("c".to_string(), 0.5), ("b".to_string(), 0.5)],
+                Vec::new()
+            ]),
+            weights.vertex_bone_names(0)
+        );
+        assert_eq!(None, weights.vertex_bone_names(1));
+    }
+
     #[test]
     fn weight_group_index_pc082402_fiora() {
         // xeno1/chr/pc/pc082402.wimdo
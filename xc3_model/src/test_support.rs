@@ -0,0 +1,274 @@
+//! Minimal values shared by the unit tests.
+//!
+//! Tests should start from these values and only set the fields they check
+//! using struct update syntax like `Models { base_lod_indices, ..models(Vec::new()) }`.
+use std::path::{Path, PathBuf};
+
+use glam::{Mat4, Vec3};
+use xc3_lib::mxmd::{Materials, Mxmd};
+
+use crate::{
+    vertex::{AttributeData, IndexBuffer, ModelBuffers, VertexBuffer},
+    AddressMode, BlendMode, CullMode, DepthFunc, FilterMode, GameVersion, Material,
+    MaterialParameters, Mesh, Model, ModelRoot, Models, RenderPassType, Sampler, StateFlags,
+    StencilMode, StencilValue,
+};
+
+/// A unique folder in the system temp directory that is removed when dropped.
+pub(crate) struct TempDir(PathBuf);
+
+impl TempDir {
+    pub(crate) fn new(name: &str) -> Self {
+        // Make the name unique to avoid collisions with concurrent test runs.
+        static COUNT: std::sync::atomic::AtomicUsize = std::sync::atomic::AtomicUsize::new(0);
+        let path = std::env::temp_dir().join(format!(
+            "xc3_model_{name}_{}_{}",
+            std::process::id(),
+            COUNT.fetch_add(1, std::sync::atomic::Ordering::Relaxed)
+        ));
+        std::fs::create_dir_all(&path).unwrap();
+        Self(path)
+    }
+
+    pub(crate) fn path(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+thread_local! {
+    static LOG_MESSAGES: std::cell::RefCell<Vec<String>> =
+        const { std::cell::RefCell::new(Vec::new()) };
+}
+
+// Tests run on separate threads, so each test only sees its own messages.
+struct CapturingLogger;
+
+impl log::Log for CapturingLogger {
+    fn enabled(&self, _: &log::Metadata) -> bool {
+        true
+    }
+
+    fn log(&self, record: &log::Record) {
+        LOG_MESSAGES.with(|m| m.borrow_mut().push(record.args().to_string()));
+    }
+
+    fn flush(&self) {}
+}
+
+static LOGGER: CapturingLogger = CapturingLogger;
+
+pub(crate) fn log_messages(f: impl FnOnce()) -> Vec<String> {
+    // The logger can only be set once, so ignore errors from other tests.
+    let _ = log::set_logger(&LOGGER);
+    log::set_max_level(log::LevelFilter::Trace);
+
+    LOG_MESSAGES.with(|m| m.borrow_mut().clear());
+    f();
+    LOG_MESSAGES.with(|m| m.take())
+}
+
+pub(crate) fn state_flags() -> StateFlags {
+    StateFlags {
+        depth_write_mode: 0,
+        blend_mode: BlendMode::Disabled,
+        cull_mode: CullMode::Back,
+        unk4: 0,
+        stencil_value: StencilValue::Unk0,
+        stencil_mode: StencilMode::Unk0,
+        depth_func: DepthFunc::LessEqual,
+        color_write_mode: 0,
+    }
+}
+
+pub(crate) fn material(name: &str) -> Material {
+    Material {
+        name: name.to_string(),
+        flags: state_flags(),
+        textures: Vec::new(),
+        alpha_test: None,
+        shader: None,
+        pass_type: RenderPassType::Unk0,
+        parameters: MaterialParameters::default(),
+    }
+}
+
+pub(crate) fn sampler(address_mode: AddressMode) -> Sampler {
+    Sampler {
+        address_mode_u: address_mode,
+        address_mode_v: address_mode,
+        address_mode_w: address_mode,
+        min_filter: FilterMode::Linear,
+        mag_filter: FilterMode::Linear,
+        mip_filter: FilterMode::Linear,
+        mipmaps: true,
+    }
+}
+
+pub(crate) fn mesh(vertex_buffer_index: usize, index_buffer_index: usize, lod: u16) -> Mesh {
+    Mesh {
+        vertex_buffer_index,
+        index_buffer_index,
+        material_index: 0,
+        ext_mesh_index: None,
+        lod,
+        flags1: 0,
+        flags2: 0u32.try_into().unwrap(),
+    }
+}
+
+pub(crate) fn model(meshes: Vec<Mesh>) -> Model {
+    Model {
+        meshes,
+        instances: vec![Mat4::IDENTITY],
+        model_buffers_index: 0,
+        max_xyz: Vec3::ONE,
+        min_xyz: Vec3::ZERO,
+        bounding_radius: 1.0,
+    }
+}
+
+pub(crate) fn models(models: Vec<Model>) -> Models {
+    Models {
+        models,
+        materials: Vec::new(),
+        samplers: Vec::new(),
+        base_lod_indices: None,
+        lod_info: None,
+        morph_controller_names: Vec::new(),
+        animation_morph_names: Vec::new(),
+        morph_controller_animation_indices: Vec::new(),
+        ext_mesh_names: Vec::new(),
+        ext_mesh_start_hidden: Vec::new(),
+        max_xyz: Vec3::ONE,
+        min_xyz: Vec3::ZERO,
+    }
+}
+
+pub(crate) fn positions(positions: Vec<Vec3>) -> VertexBuffer {
+    VertexBuffer {
+        attributes: vec![AttributeData::Position(positions)],
+        morph_targets: Vec::new(),
+        morph_default_target: None,
+        outline_buffer_index: None,
+    }
+}
+
+pub(crate) fn vertex_buffer(vertex_count: usize) -> VertexBuffer {
+    positions(vec![Vec3::ZERO; vertex_count])
+}
+
+pub(crate) fn index_buffer(index_count: usize) -> IndexBuffer {
+    IndexBuffer {
+        indices: vec![0; index_count],
+    }
+}
+
+pub(crate) fn model_buffers(
+    vertex_buffers: Vec<VertexBuffer>,
+    index_buffers: Vec<IndexBuffer>,
+) -> ModelBuffers {
+    ModelBuffers {
+        vertex_buffers,
+        outline_buffers: Vec::new(),
+        index_buffers,
+        unk_buffers: Vec::new(),
+        weights: None,
+    }
+}
+
+pub(crate) fn model_root(models: Models, buffers: ModelBuffers) -> ModelRoot {
+    ModelRoot {
+        models,
+        buffers,
+        image_textures: Vec::new(),
+        skeleton: None,
+        embedded_shaders: None,
+        game_version: GameVersion::Xc3,
+    }
+}
+
+pub(crate) fn mxmd_material() -> xc3_lib::mxmd::Material {
+    xc3_lib::mxmd::Material {
+        name: "material".to_string(),
+        flags: 0u32.into(),
+        render_flags: 0,
+        color: [1.0; 4],
+        alpha_test_ref: [0; 4],
+        textures: Vec::new(),
+        state_flags: state_flags(),
+        m_unks1_1: 0,
+        m_unks1_2: 0,
+        m_unks1_3: 0,
+        m_unks1_4: 0,
+        work_value_start_index: 0,
+        shader_var_start_index: 0,
+        shader_var_count: 0,
+        techniques: Vec::new(),
+        unk5: 0,
+        callback_start_index: 0,
+        callback_count: 0,
+        m_unks2: [0; 3],
+        alpha_test_texture_index: 0,
+        m_unks3: [0; 8],
+    }
+}
+
+pub(crate) fn mxmd(version: u32) -> Mxmd {
+    Mxmd {
+        version,
+        models: xc3_lib::mxmd::Models {
+            models_flags: None,
+            max_xyz: [1.0; 3],
+            min_xyz: [-1.0; 3],
+            models: Vec::new(),
+            unk2: 0,
+            skinning: None,
+            model_unk11: None,
+            unks3_1: [0; 13],
+            ext_meshes: Vec::new(),
+            unks3_2: [0; 2],
+            model_unk8: None,
+            unk3_3: 0,
+            model_unk7: None,
+            morph_controllers: None,
+            model_unk1: None,
+            model_unk3: None,
+            lod_data: None,
+            alpha_table: None,
+            unk_field2: 0,
+            model_unk9: [0; 2],
+            extra: None,
+        },
+        materials: Materials {
+            materials: Vec::new(),
+            unk1: 0,
+            unk2: 0,
+            work_values: Vec::new(),
+            shader_vars: Vec::new(),
+            callbacks: None,
+            unk4: 0,
+            techniques: Vec::new(),
+            unks1: [0; 2],
+            alpha_test_textures: Vec::new(),
+            unks3: [0; 3],
+            material_unk2: None,
+            material_unk3: None,
+            unks3_1: [0; 2],
+            samplers: None,
+            unks4: [0; 3],
+        },
+        unk1: None,
+        vertex_data: None,
+        spch: None,
+        packed_textures: None,
+        unk5: 0,
+        streaming: None,
+        unk: [0; 9],
+    }
+}
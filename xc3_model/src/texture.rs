@@ -1,16 +1,25 @@
-use image_dds::{ddsfile::Dds, error::CreateImageError, CreateDdsError, Surface};
-use log::error;
+use std::path::{Path, PathBuf};
+
+use image_dds::{
+    ddsfile::Dds, error::CreateImageError, CreateDdsError, Mipmaps, Quality, Surface, SurfaceRgba8,
+};
+use log::{error, warn};
 use thiserror::Error;
 use xc3_lib::{
+    dds::DdsExt,
     mibl::{CreateMiblError, Mibl, SwizzleError},
     msrd::streaming::{ExtractedTexture, HighTexture},
     mtxt::Mtxt,
     mxmd::PackedTexture,
+    xbc1::Xbc1,
 };
 
+pub use ktx2::CreateKtx2Error;
 pub use xc3_lib::mibl::{ImageFormat, ViewDimension};
 pub use xc3_lib::mxmd::TextureUsage;
 
+mod ktx2;
+
 #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[derive(Debug)]
 pub enum ExtractedTextures {
@@ -37,10 +46,44 @@ pub enum CreateImageTextureError {
 
     #[error("error converting Mibl texture")]
     Mibl(#[from] xc3_lib::mibl::CreateMiblError),
+
+    #[error("error reading data")]
+    Io(#[from] std::io::Error),
+
+    #[error("expected base mip level with {expected} bytes but found {actual} bytes")]
+    BaseMipSize { expected: usize, actual: usize },
+}
+
+#[derive(Debug, Error)]
+pub enum ReplaceTextureError {
+    #[error("texture index {index} is out of range for {count} textures")]
+    IndexOutOfRange { index: usize, count: usize },
+
+    #[error("error reading DDS file")]
+    Dds(#[from] image_dds::ddsfile::Error),
+
+    #[error("error reading image file")]
+    Image(#[from] image_dds::image::ImageError),
+
+    #[error("error encoding image surface")]
+    Surface(#[from] image_dds::error::SurfaceError),
+
+    #[error("error creating texture")]
+    Texture(#[from] CreateImageTextureError),
+}
+
+/// The color space of the RGB channels for texture data.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum ColorSpace {
+    /// Gamma encoded color data like albedo textures.
+    Srgb,
+    /// Non color data like normal maps or masks.
+    Linear,
 }
 
 /// A non swizzled version of an [Mibl] texture.
 #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, PartialEq, Clone)]
 pub struct ImageTexture {
     /// An optional name assigned to some textures.
@@ -111,6 +154,71 @@ impl ImageTexture {
         })
     }
 
+    /// The RGBA color for [ImageTexture::fallback].
+    pub const MAGENTA: [u8; 4] = [255, 0, 255, 255];
+
+    /// A 1x1 [ImageFormat::R8G8B8A8Unorm] texture with the color `rgba`.
+    pub fn solid_color(rgba: [u8; 4]) -> Self {
+        Self {
+            name: None,
+            usage: None,
+            width: 1,
+            height: 1,
+            depth: 1,
+            view_dimension: ViewDimension::D2,
+            image_format: ImageFormat::R8G8B8A8Unorm,
+            mipmap_count: 1,
+            image_data: rgba.to_vec(),
+        }
+    }
+
+    /// A 1x1 [ImageTexture::MAGENTA] texture for replacing missing or removed textures.
+    pub fn fallback() -> Self {
+        Self {
+            name: Some("fallback".to_string()),
+            ..Self::solid_color(Self::MAGENTA)
+        }
+    }
+
+    /// Load all the `.wismt`, `.witex`, and `.witx` textures in `folder` and its subfolders.
+    /// Textures are returned with their file name without the extension and sorted by path.
+    ///
+    /// Files compressed in an [Xbc1] archive are decompressed before reading the [Mibl].
+    /// For Xenoblade 3 `chr/tex/nx` folders, the base mip levels in `h` are
+    /// combined with the corresponding textures in `m`.
+    /// Files that fail to load are skipped with a warning.
+    ///
+    /// # Examples
+    /// ``` rust no_run
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// use xc3_model::ImageTexture;
+    ///
+    /// let textures = ImageTexture::from_tex_folder("xeno3/chr/tex/nx".as_ref())?;
+    /// for (name, texture) in textures {
+    ///     texture.to_image()?.save(format!("{name}.png"))?;
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn from_tex_folder(
+        folder: &Path,
+    ) -> Result<Vec<(String, ImageTexture)>, CreateImageTextureError> {
+        let mut paths = Vec::new();
+        tex_folder_paths(folder, &mut paths)?;
+        paths.sort();
+
+        Ok(paths
+            .iter()
+            .filter_map(|p| match read_tex_folder_texture(p) {
+                Ok(texture) => Some(texture),
+                Err(e) => {
+                    warn!("Skipping texture {p:?}: {e}");
+                    None
+                }
+            })
+            .collect())
+    }
+
     pub(crate) fn from_packed_texture(
         texture: &PackedTexture,
     ) -> Result<Self, CreateImageTextureError> {
@@ -118,6 +226,103 @@ impl ImageTexture {
         Self::from_mibl(&mibl, Some(texture.name.clone()), Some(texture.usage)).map_err(Into::into)
     }
 
+    /// Add the non swizzled `base_mip` with the existing mipmaps.
+    /// The base mip should have twice the current width and height
+    /// and contain the base level for each array layer.
+    ///
+    /// This is the equivalent of [Mibl::with_base_mip] for deswizzled data.
+    pub fn with_base_mip(&self, base_mip: &[u8]) -> Result<Self, CreateImageTextureError> {
+        let mut texture = Self {
+            name: self.name.clone(),
+            usage: self.usage,
+            width: self.width * 2,
+            height: self.height * 2,
+            depth: self.depth,
+            view_dimension: self.view_dimension,
+            image_format: self.image_format,
+            mipmap_count: self.mipmap_count.max(1) + 1,
+            image_data: Vec::new(),
+        };
+
+        let base_mip_size = texture.mip_size(0);
+        let expected = base_mip_size * self.layers() as usize;
+        if base_mip.len() != expected {
+            return Err(CreateImageTextureError::BaseMipSize {
+                expected,
+                actual: base_mip.len(),
+            });
+        }
+
+        // Data is ordered by layer and then mipmap.
+        let layer_size: usize = (0..self.mipmap_count.max(1))
+            .map(|m| self.mip_size(m))
+            .sum();
+        for (base, layer) in base_mip
+            .chunks_exact(base_mip_size)
+            .zip(self.image_data.chunks(layer_size))
+        {
+            texture.image_data.extend_from_slice(base);
+            texture.image_data.extend_from_slice(layer);
+        }
+
+        Ok(texture)
+    }
+
+    /// Load the `.dds` or other supported image file like `.png` from `path`
+    /// and encode it to this texture's [image_format](#structfield.image_format).
+    ///
+    /// The width and height are taken from the new image and mipmaps are regenerated.
+    /// The [name](#structfield.name) and [usage](#structfield.usage) are preserved.
+    pub fn with_image_file(&self, path: &Path) -> Result<Self, ReplaceTextureError> {
+        let is_dds = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .is_some_and(|e| e.eq_ignore_ascii_case("dds"));
+
+        let rgba = if is_dds {
+            let dds = Dds::from_file(path)?;
+            Surface::from_dds(&dds)?.decode_rgba8()?
+        } else {
+            let image = image_dds::image::open(path)?.to_rgba8();
+            SurfaceRgba8 {
+                width: image.width(),
+                height: image.height(),
+                depth: 1,
+                layers: 1,
+                mipmaps: 1,
+                data: image.into_raw(),
+            }
+        };
+
+        let surface = rgba.encode(
+            self.image_format.into(),
+            Quality::Normal,
+            Mipmaps::GeneratedAutomatic,
+        )?;
+        Self::from_surface(surface, self.name.clone(), self.usage).map_err(Into::into)
+    }
+
+    /// The color space for the texture data based on the [usage](#structfield.usage).
+    /// Textures without a usage or with unrecognized usages default to [ColorSpace::Srgb].
+    ///
+    /// The image format will still use unorm instead of srgb,
+    /// so the data should not be gamma corrected when decoding.
+    pub fn color_space(&self) -> ColorSpace {
+        match self.usage {
+            Some(
+                TextureUsage::Nrm
+                | TextureUsage::Nrm2
+                | TextureUsage::Temp
+                | TextureUsage::Temp2
+                | TextureUsage::Alp
+                | TextureUsage::Alp2
+                | TextureUsage::Alp3
+                | TextureUsage::Alp4,
+            ) => ColorSpace::Linear,
+            _ => ColorSpace::Srgb,
+        }
+    }
+
     /// Decode the first mip level for all depth slices and array layers to RGBA8.
     pub fn to_image(&self) -> Result<image_dds::image::RgbaImage, CreateImageError> {
         // Only decode the mip we actually use to improve performance.
@@ -126,6 +331,57 @@ impl ImageTexture {
             .to_image(0)
     }
 
+    /// Decode a single array layer and mip level for all depth slices to RGBA8.
+    /// Cube map faces are ordered as `+X, -X, +Y, -Y, +Z, -Z`.
+    pub fn to_layer_image(
+        &self,
+        layer: u32,
+        mipmap: u32,
+    ) -> Result<image_dds::image::RgbaImage, CreateImageError> {
+        self.to_surface()
+            .decode_layers_mipmaps_rgba8(layer..layer + 1, mipmap..mipmap + 1)?
+            .to_image(0)
+    }
+
+    /// Decode a preview of the first array layer that fits within `max_dim` pixels
+    /// while preserving the aspect ratio.
+    ///
+    /// Only the smallest mip level with a width or height of at least `max_dim` is decoded,
+    /// so large textures don't need to decode their full resolution base level.
+    pub fn thumbnail(&self, max_dim: u32) -> Result<image_dds::image::RgbaImage, CreateImageError> {
+        let max_dim = max_dim.max(1);
+        let image = self.to_layer_image(0, self.thumbnail_mipmap(max_dim))?;
+
+        let (width, height) = image.dimensions();
+        if width <= max_dim && height <= max_dim {
+            return Ok(image);
+        }
+
+        let (new_width, new_height) = if width >= height {
+            (
+                max_dim,
+                (height as u64 * max_dim as u64 / width as u64).max(1) as u32,
+            )
+        } else {
+            (
+                (width as u64 * max_dim as u64 / height as u64).max(1) as u32,
+                max_dim,
+            )
+        };
+        // The thumbnail filter averages each block of source pixels.
+        Ok(image_dds::image::imageops::thumbnail(
+            &image, new_width, new_height,
+        ))
+    }
+
+    /// The smallest mip level with a width or height of at least `max_dim`.
+    fn thumbnail_mipmap(&self, max_dim: u32) -> u32 {
+        (0..self.mipmap_count.max(1))
+            .rev()
+            .find(|m| (self.width >> m).max(self.height >> m) >= max_dim)
+            .unwrap_or(0)
+    }
+
     /// Return the number of array layers in this surface.
     pub fn layers(&self) -> u32 {
         if self.view_dimension == ViewDimension::Cube {
@@ -135,6 +391,35 @@ impl ImageTexture {
         }
     }
 
+    /// The image data for a single array layer and mip level
+    /// or [None] if the layer or mip level is out of range.
+    pub fn layer_mip_data(&self, layer: u32, mipmap: u32) -> Option<&[u8]> {
+        if layer >= self.layers() || mipmap >= self.mipmap_count.max(1) {
+            return None;
+        }
+
+        // Data is ordered by layer and then mipmap.
+        let layer_size: usize = (0..self.mipmap_count.max(1))
+            .map(|m| self.mip_size(m))
+            .sum();
+        let mip_offset: usize = (0..mipmap).map(|m| self.mip_size(m)).sum();
+
+        let start = layer as usize * layer_size + mip_offset;
+        self.image_data.get(start..start + self.mip_size(mipmap))
+    }
+
+    /// The size in bytes of a single array layer for the given mip level.
+    fn mip_size(&self, mipmap: u32) -> usize {
+        let (block_width, block_height) = block_dimensions(self.image_format);
+        let width = (self.width >> mipmap).max(1);
+        let height = (self.height >> mipmap).max(1);
+        let depth = (self.depth >> mipmap).max(1);
+        width.div_ceil(block_width) as usize
+            * height.div_ceil(block_height) as usize
+            * depth as usize
+            * self.image_format.bytes_per_pixel()
+    }
+
     /// Create a view of all image data in this texture
     /// to use with encode or decode operations.
     pub fn to_surface(&self) -> image_dds::Surface<&[u8]> {
@@ -190,13 +475,15 @@ impl ImageTexture {
         Mibl::from_surface(self.to_surface())
     }
 
-    pub(crate) fn extracted_texture(image: &ImageTexture) -> ExtractedTexture<Mibl> {
+    pub(crate) fn extracted_texture(
+        image: &ImageTexture,
+    ) -> Result<ExtractedTexture<Mibl>, CreateMiblError> {
         // Low textures typically use a smaller 4x4 version of the texture.
         // Resizing and decoding and encoding the full texture is expensive.
         // The low texture is only visible briefly before data is streamed in.
         // We can cheat and just use the first GOB (512 bytes) of compressed image data.
         let low = xc3_lib::mibl::Mibl {
-            image_data: image.image_data[..512].to_vec(),
+            image_data: image.image_data[..image.image_data.len().min(512)].to_vec(),
             footer: xc3_lib::mibl::MiblFooter {
                 image_size: 4096,
                 unk: 0x1000,
@@ -210,16 +497,47 @@ impl ImageTexture {
             },
         };
 
-        let (mid, base_mip) = image.to_mibl().unwrap().split_base_mip();
-        ExtractedTexture {
-            name: image.name.clone().unwrap(),
-            usage: image.usage.unwrap(),
+        let (mid, base_mip) = image.to_mibl()?.split_base_mip();
+
+        let name = image.name.clone().unwrap_or_else(|| {
+            warn!("Missing name for image texture. Using an empty name.");
+            String::new()
+        });
+        // TODO: Is there a better default for textures without usage hints?
+        let usage = image.usage.unwrap_or_else(|| {
+            warn!(
+                "Missing usage for image texture {name:?}. Using {:?}.",
+                TextureUsage::Col
+            );
+            TextureUsage::Col
+        });
+
+        Ok(ExtractedTexture {
+            name,
+            usage,
             low,
             high: Some(HighTexture {
                 mid,
                 base_mip: Some(base_mip),
             }),
-        }
+        })
+    }
+}
+
+fn block_dimensions(format: ImageFormat) -> (u32, u32) {
+    match format {
+        ImageFormat::R8Unorm
+        | ImageFormat::R8G8B8A8Unorm
+        | ImageFormat::R16G16B16A16Float
+        | ImageFormat::R4G4B4A4Unorm
+        | ImageFormat::B8G8R8A8Unorm => (1, 1),
+        ImageFormat::BC1Unorm
+        | ImageFormat::BC2Unorm
+        | ImageFormat::BC3Unorm
+        | ImageFormat::BC4Unorm
+        | ImageFormat::BC5Unorm
+        | ImageFormat::BC7Unorm
+        | ImageFormat::BC6UFloat => (4, 4),
     }
 }
 
@@ -255,6 +573,10 @@ fn mtxt_usage(usage: xc3_lib::mxmd::legacy::TextureUsage) -> Option<TextureUsage
     }
 }
 
+/// Convert the extracted textures to [ImageTexture].
+///
+/// Switch textures are deswizzled from the Tegra X1 block linear layout.
+/// PC textures already use a standard row-major layout and are not deswizzled.
 pub fn load_textures(
     textures: &ExtractedTextures,
 ) -> Result<Vec<ImageTexture>, CreateImageTextureError> {
@@ -284,6 +606,62 @@ pub fn load_textures(
     }
 }
 
+fn tex_folder_paths(folder: &Path, paths: &mut Vec<PathBuf>) -> std::io::Result<()> {
+    for entry in std::fs::read_dir(folder)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            // Base mip levels are only loaded as part of the texture in "m".
+            if path.file_name().is_some_and(|n| n != "h") {
+                tex_folder_paths(&path, paths)?;
+            }
+        } else if path
+            .extension()
+            .is_some_and(|e| e == "wismt" || e == "witex" || e == "witx")
+        {
+            paths.push(path);
+        }
+    }
+    Ok(())
+}
+
+fn read_tex_folder_texture(path: &Path) -> Result<(String, ImageTexture), CreateImageTextureError> {
+    let name = path
+        .file_stem()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .to_string();
+
+    let mut mibl = Mibl::from_bytes(read_maybe_xbc1(path)?)?;
+
+    // The high resolution base mip levels don't have a Mibl footer.
+    if let Some(h_path) = base_mip_path(path) {
+        let base_mip = Xbc1::from_file(h_path)?.decompress()?;
+        mibl = mibl.with_base_mip(&base_mip);
+    }
+
+    let texture = ImageTexture::from_mibl(&mibl, Some(name.clone()), None)?;
+    Ok((name, texture))
+}
+
+fn base_mip_path(path: &Path) -> Option<PathBuf> {
+    let parent = path.parent()?;
+    if parent.file_name()? == "m" {
+        let h_path = parent.parent()?.join("h").join(path.file_name()?);
+        h_path.exists().then_some(h_path)
+    } else {
+        None
+    }
+}
+
+fn read_maybe_xbc1(path: &Path) -> Result<Vec<u8>, CreateImageTextureError> {
+    let bytes = std::fs::read(path)?;
+    if bytes.starts_with(b"xbc1") {
+        Xbc1::from_bytes(&bytes)?.decompress().map_err(Into::into)
+    } else {
+        Ok(bytes)
+    }
+}
+
 #[cfg(feature = "arbitrary")]
 fn arbitrary_dds_textures(
     _u: &mut arbitrary::Unstructured,
@@ -291,3 +669,291 @@ fn arbitrary_dds_textures(
     // TODO: Generate random DDS files?
     Ok(Vec::new())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cube_texture() -> ImageTexture {
+        // 2x2 RGBA faces with 2 mipmaps filled with the face index.
+        let image_data = (0..6u8)
+            .flat_map(|face| {
+                let base = [face; 2 * 2 * 4];
+                let mip1 = [face + 10; 4];
+                base.into_iter().chain(mip1)
+            })
+            .collect();
+
+        ImageTexture {
+            name: None,
+            usage: None,
+            width: 2,
+            height: 2,
+            depth: 1,
+            view_dimension: ViewDimension::Cube,
+            image_format: ImageFormat::R8G8B8A8Unorm,
+            mipmap_count: 2,
+            image_data,
+        }
+    }
+
+    fn rgba_texture(image_data: Vec<u8>) -> ImageTexture {
+        ImageTexture {
+            name: None,
+            usage: None,
+            width: 4,
+            height: 4,
+            depth: 1,
+            view_dimension: ViewDimension::D2,
+            image_format: ImageFormat::R8G8B8A8Unorm,
+            mipmap_count: 1,
+            image_data,
+        }
+    }
+
+    #[test]
+    fn extracted_texture_missing_name_usage() {
+        let texture = rgba_texture(vec![0u8; 4 * 4 * 4]);
+
+        let messages = crate::test_support::log_messages(|| {
+            let extracted = ImageTexture::extracted_texture(&texture).unwrap();
+            assert_eq!("", extracted.name);
+            assert_eq!(TextureUsage::Col, extracted.usage);
+        });
+        assert_eq!(2, messages.len());
+    }
+
+    #[test]
+    fn extracted_texture_not_enough_data() {
+        let texture = rgba_texture(vec![0u8; 4]);
+        assert!(matches!(
+            ImageTexture::extracted_texture(&texture),
+            Err(CreateMiblError::SwizzleError(_))
+        ));
+    }
+
+    #[test]
+    fn cube_layer_mip_data() {
+        let texture = cube_texture();
+        assert_eq!(6, texture.layers());
+        assert_eq!(Some(&[0u8; 16][..]), texture.layer_mip_data(0, 0));
+        assert_eq!(Some(&[10u8; 4][..]), texture.layer_mip_data(0, 1));
+        assert_eq!(Some(&[1u8; 16][..]), texture.layer_mip_data(1, 0));
+        assert_eq!(Some(&[15u8; 4][..]), texture.layer_mip_data(5, 1));
+        assert_eq!(None, texture.layer_mip_data(6, 0));
+        assert_eq!(None, texture.layer_mip_data(0, 2));
+    }
+
+    #[test]
+    fn cube_layer_images() {
+        let texture = cube_texture();
+
+        let face0 = texture.to_layer_image(0, 0).unwrap();
+        let face1 = texture.to_layer_image(1, 0).unwrap();
+        assert_eq!((2, 2), face0.dimensions());
+        assert_eq!(&[0u8; 16][..], face0.as_raw());
+        assert_eq!(&[1u8; 16][..], face1.as_raw());
+        assert_ne!(face0, face1);
+
+        let face5_mip1 = texture.to_layer_image(5, 1).unwrap();
+        assert_eq!((1, 1), face5_mip1.dimensions());
+        assert_eq!(&[15u8; 4][..], face5_mip1.as_raw());
+    }
+
+    #[test]
+    fn solid_color_image() {
+        let texture = ImageTexture::solid_color([1, 2, 3, 4]);
+        let image = texture.to_image().unwrap();
+        assert_eq!((1, 1), image.dimensions());
+        assert_eq!(&[1, 2, 3, 4], image.as_raw().as_slice());
+
+        let fallback = ImageTexture::fallback().to_image().unwrap();
+        assert_eq!(&ImageTexture::MAGENTA, fallback.as_raw().as_slice());
+    }
+
+    #[test]
+    fn bc7_thumbnail_small_mip() {
+        let mut texture = ImageTexture {
+            name: None,
+            usage: None,
+            width: 1024,
+            height: 512,
+            depth: 1,
+            view_dimension: ViewDimension::D2,
+            image_format: ImageFormat::BC7Unorm,
+            mipmap_count: 11,
+            image_data: Vec::new(),
+        };
+        let size = (0..texture.mipmap_count).map(|m| texture.mip_size(m)).sum();
+        texture.image_data = vec![0u8; size];
+
+        // 1024 -> 512 -> 256 -> 128
+        assert_eq!(3, texture.thumbnail_mipmap(128));
+        assert_eq!(4, texture.thumbnail_mipmap(100));
+        assert_eq!(10, texture.thumbnail_mipmap(1));
+
+        let thumbnail = texture.thumbnail(128).unwrap();
+        assert_eq!((128, 64), thumbnail.dimensions());
+
+        let thumbnail = texture.thumbnail(100).unwrap();
+        assert_eq!((100, 50), thumbnail.dimensions());
+    }
+
+    #[test]
+    fn load_textures_pc_switch_same_data() {
+        let data: Vec<u8> = (0..64).collect();
+        let surface = || Surface {
+            width: 4,
+            height: 4,
+            depth: 1,
+            layers: 1,
+            mipmaps: 1,
+            image_format: image_dds::ImageFormat::Rgba8Unorm,
+            data: &data,
+        };
+        let dds = surface().to_dds().unwrap();
+        let mibl = Mibl::from_surface(surface()).unwrap();
+
+        let pc = load_textures(&ExtractedTextures::Pc(vec![ExtractedTexture {
+            name: "texture".to_string(),
+            usage: TextureUsage::Col,
+            low: dds,
+            high: None,
+        }]))
+        .unwrap();
+
+        let switch = load_textures(&ExtractedTextures::Switch(vec![ExtractedTexture {
+            name: "texture".to_string(),
+            usage: TextureUsage::Col,
+            low: mibl,
+            high: None,
+        }]))
+        .unwrap();
+
+        // PC textures should not be deswizzled.
+        assert_eq!(data, pc[0].image_data);
+        assert_eq!(pc, switch);
+    }
+
+    #[test]
+    fn cube_dds_layers() {
+        let texture = cube_texture();
+        let dds = texture.to_dds().unwrap();
+        let new_texture = ImageTexture::from_dds(&dds, None, None).unwrap();
+        assert_eq!(ViewDimension::Cube, new_texture.view_dimension);
+        assert_eq!(texture.image_data, new_texture.image_data);
+    }
+
+    #[test]
+    fn with_base_mip_2x() {
+        let texture = ImageTexture {
+            name: None,
+            usage: None,
+            width: 1,
+            height: 1,
+            depth: 1,
+            view_dimension: ViewDimension::D2,
+            image_format: ImageFormat::R8G8B8A8Unorm,
+            mipmap_count: 1,
+            image_data: vec![1, 2, 3, 4],
+        };
+
+        let new_texture = texture.with_base_mip(&[5; 16]).unwrap();
+        assert_eq!(2, new_texture.width);
+        assert_eq!(2, new_texture.height);
+        assert_eq!(2, new_texture.mipmap_count);
+        assert_eq!(Some(&[5u8; 16][..]), new_texture.layer_mip_data(0, 0));
+        assert_eq!(Some(&[1u8, 2, 3, 4][..]), new_texture.layer_mip_data(0, 1));
+
+        assert!(matches!(
+            texture.with_base_mip(&[5; 4]),
+            Err(CreateImageTextureError::BaseMipSize {
+                expected: 16,
+                actual: 4
+            })
+        ));
+    }
+
+    #[test]
+    fn cube_with_base_mip() {
+        let texture = cube_texture();
+        let base_mip: Vec<_> = (0..6u8).flat_map(|face| [face + 20; 4 * 4 * 4]).collect();
+
+        let new_texture = texture.with_base_mip(&base_mip).unwrap();
+        assert_eq!(
+            (4, 4, 3),
+            (
+                new_texture.width,
+                new_texture.height,
+                new_texture.mipmap_count
+            )
+        );
+        assert_eq!(Some(&[20u8; 64][..]), new_texture.layer_mip_data(0, 0));
+        assert_eq!(Some(&[0u8; 16][..]), new_texture.layer_mip_data(0, 1));
+        assert_eq!(Some(&[25u8; 64][..]), new_texture.layer_mip_data(5, 0));
+        assert_eq!(Some(&[15u8; 4][..]), new_texture.layer_mip_data(5, 2));
+    }
+
+    #[test]
+    fn tex_folder_single_texture() {
+        let dir = crate::test_support::TempDir::new("tex_folder_single_texture");
+        let folder = dir.path();
+
+        let data: Vec<u8> = (0..4 * 4 * 4).collect();
+        let texture = ImageTexture::from_surface(
+            Surface {
+                width: 4,
+                height: 4,
+                depth: 1,
+                layers: 1,
+                mipmaps: 1,
+                image_format: image_dds::ImageFormat::Rgba8Unorm,
+                data: &data,
+            },
+            None,
+            None,
+        )
+        .unwrap();
+
+        let mibl = texture.to_mibl().unwrap();
+        let xbc1 = Xbc1::new(
+            "0000".to_string(),
+            &mibl,
+            xc3_lib::xbc1::CompressionType::Zlib,
+        )
+        .unwrap();
+        xbc1.save(folder.join("abc.wismt")).unwrap();
+
+        // Invalid files should not prevent loading the other textures.
+        std::fs::write(folder.join("bad.wismt"), [0u8; 4]).unwrap();
+
+        let textures = ImageTexture::from_tex_folder(folder).unwrap();
+
+        assert_eq!(1, textures.len());
+        let (name, new_texture) = &textures[0];
+        assert_eq!("abc", name);
+        assert_eq!(Some("abc".to_string()), new_texture.name);
+        assert_eq!(
+            (4, 4, 1),
+            (
+                new_texture.width,
+                new_texture.height,
+                new_texture.mipmap_count
+            )
+        );
+        assert_eq!(ImageFormat::R8G8B8A8Unorm, new_texture.image_format);
+        assert_eq!(data, new_texture.image_data);
+    }
+
+    #[test]
+    fn color_space_from_usage() {
+        let mut texture = cube_texture();
+        assert_eq!(ColorSpace::Srgb, texture.color_space());
+
+        texture.usage = Some(TextureUsage::Col);
+        assert_eq!(ColorSpace::Srgb, texture.color_space());
+
+        texture.usage = Some(TextureUsage::Nrm);
+        assert_eq!(ColorSpace::Linear, texture.color_space());
+    }
+}
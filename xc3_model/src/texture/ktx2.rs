@@ -0,0 +1,509 @@
+//! Conversions from [ImageTexture] to the [KTX2](https://registry.khronos.org/KTX/specs/2.0/ktxspec.v2.html) container format.
+use std::io::{Cursor, Write};
+
+use binrw::BinWrite;
+use thiserror::Error;
+
+use super::{block_dimensions, ColorSpace, ImageFormat, ImageTexture, ViewDimension};
+
+const HEADER_SIZE: u32 = 80;
+const LEVEL_INDEX_SIZE: u32 = 24;
+
+// Values from the Khronos Data Format Specification.
+const KHR_DF_MODEL_RGBSDA: u8 = 1;
+const KHR_DF_MODEL_BC1A: u8 = 128;
+const KHR_DF_MODEL_BC2: u8 = 129;
+const KHR_DF_MODEL_BC3: u8 = 130;
+const KHR_DF_MODEL_BC4: u8 = 131;
+const KHR_DF_MODEL_BC5: u8 = 132;
+const KHR_DF_MODEL_BC6H: u8 = 133;
+const KHR_DF_MODEL_BC7: u8 = 134;
+
+const KHR_DF_PRIMARIES_BT709: u8 = 1;
+const KHR_DF_TRANSFER_LINEAR: u8 = 1;
+const KHR_DF_TRANSFER_SRGB: u8 = 2;
+
+const KHR_DF_CHANNEL_RED: u8 = 0;
+const KHR_DF_CHANNEL_GREEN: u8 = 1;
+const KHR_DF_CHANNEL_BLUE: u8 = 2;
+const KHR_DF_CHANNEL_ALPHA: u8 = 15;
+const KHR_DF_CHANNEL_BC1A_ALPHAPRESENT: u8 = 1;
+
+const KHR_DF_SAMPLE_DATATYPE_LINEAR: u8 = 0x10;
+const KHR_DF_SAMPLE_DATATYPE_SIGNED: u8 = 0x40;
+const KHR_DF_SAMPLE_DATATYPE_FLOAT: u8 = 0x80;
+
+#[derive(Debug, Error)]
+pub enum CreateKtx2Error {
+    #[error("error writing data")]
+    Binrw(#[from] binrw::Error),
+
+    #[error("error writing data")]
+    Io(#[from] std::io::Error),
+
+    #[error("expected at least {expected} bytes of image data but found {actual}")]
+    NotEnoughData { expected: usize, actual: usize },
+}
+
+#[derive(BinWrite)]
+#[bw(magic = b"\xABKTX 20\xBB\r\n\x1A\n")]
+struct Header {
+    vk_format: u32,
+    type_size: u32,
+    pixel_width: u32,
+    pixel_height: u32,
+    pixel_depth: u32,
+    layer_count: u32,
+    face_count: u32,
+    level_count: u32,
+    supercompression_scheme: u32,
+    dfd_byte_offset: u32,
+    dfd_byte_length: u32,
+    kvd_byte_offset: u32,
+    kvd_byte_length: u32,
+    sgd_byte_offset: u64,
+    sgd_byte_length: u64,
+}
+
+#[derive(BinWrite)]
+struct LevelIndex {
+    byte_offset: u64,
+    byte_length: u64,
+    uncompressed_byte_length: u64,
+}
+
+/// The basic data format descriptor block.
+#[derive(BinWrite)]
+struct DataFormatDescriptor {
+    total_size: u32,
+    vendor_id_descriptor_type: u32,
+    version_number: u16,
+    descriptor_block_size: u16,
+    color_model: u8,
+    color_primaries: u8,
+    transfer_function: u8,
+    flags: u8,
+    texel_block_dimension: [u8; 4],
+    bytes_plane: [u8; 8],
+    samples: Vec<Sample>,
+}
+
+#[derive(BinWrite)]
+struct Sample {
+    bit_offset: u16,
+    bit_length: u8,
+    channel_type: u8,
+    sample_position: [u8; 4],
+    sample_lower: u32,
+    sample_upper: u32,
+}
+
+impl Sample {
+    fn new(channel_type: u8, bit_offset: u16, bit_count: u8, lower: u32, upper: u32) -> Self {
+        Self {
+            bit_offset,
+            bit_length: bit_count - 1,
+            channel_type,
+            sample_position: [0; 4],
+            sample_lower: lower,
+            sample_upper: upper,
+        }
+    }
+}
+
+impl ImageTexture {
+    /// Create a KTX2 file with the image data for all layers and mipmaps.
+    ///
+    /// Block compressed data is copied as is without decoding or encoding.
+    /// Textures with a [ColorSpace::Srgb] [color_space](ImageTexture::color_space) use sRGB formats when possible.
+    ///
+    /// The KTX2 data is not referenced by glTF exports
+    /// since `KHR_texture_basisu` requires ETC1S or UASTC data instead of BCn.
+    pub fn to_ktx2(&self) -> Result<Vec<u8>, CreateKtx2Error> {
+        let srgb = self.color_space() == ColorSpace::Srgb && has_srgb_format(self.image_format);
+        let bytes_per_block = self.image_format.bytes_per_pixel();
+
+        // Images are never arrays, so only cube maps have more than one face.
+        let faces = self.layers() as usize;
+        let levels = self.mipmap_count.max(1);
+
+        // The image data is ordered by layer and then mipmap.
+        let level_sizes: Vec<_> = (0..levels).map(|level| self.mip_size(level)).collect();
+        let layer_size: usize = level_sizes.iter().sum();
+
+        let expected = layer_size * faces;
+        if self.image_data.len() < expected {
+            return Err(CreateKtx2Error::NotEnoughData {
+                expected,
+                actual: self.image_data.len(),
+            });
+        }
+
+        let dfd = data_format_descriptor(self.image_format, srgb);
+        let dfd_byte_offset = HEADER_SIZE + LEVEL_INDEX_SIZE * levels;
+        let dfd_byte_length = dfd.total_size;
+
+        // Mipmaps are stored from smallest to largest with faces for each level.
+        let alignment = lcm(bytes_per_block, 4);
+        let mut level_indices: Vec<_> = (0..levels as usize)
+            .map(|_| LevelIndex {
+                byte_offset: 0,
+                byte_length: 0,
+                uncompressed_byte_length: 0,
+            })
+            .collect();
+        let mut level_data = Vec::new();
+        let data_start = (dfd_byte_offset + dfd_byte_length) as usize;
+        for level in (0..levels as usize).rev() {
+            let padding = (data_start + level_data.len()).next_multiple_of(alignment)
+                - (data_start + level_data.len());
+            level_data.resize(level_data.len() + padding, 0u8);
+
+            let byte_offset = data_start + level_data.len();
+            let level_offset: usize = level_sizes[..level].iter().sum();
+            for face in 0..faces {
+                let start = face * layer_size + level_offset;
+                level_data.extend_from_slice(&self.image_data[start..start + level_sizes[level]]);
+            }
+
+            let byte_length = (level_sizes[level] * faces) as u64;
+            level_indices[level] = LevelIndex {
+                byte_offset: byte_offset as u64,
+                byte_length,
+                uncompressed_byte_length: byte_length,
+            };
+        }
+
+        let header = Header {
+            vk_format: vk_format(self.image_format, srgb),
+            type_size: type_size(self.image_format),
+            pixel_width: self.width,
+            pixel_height: self.height,
+            pixel_depth: if self.view_dimension == ViewDimension::D3 {
+                self.depth
+            } else {
+                0
+            },
+            layer_count: 0,
+            face_count: faces as u32,
+            level_count: levels,
+            supercompression_scheme: 0,
+            dfd_byte_offset,
+            dfd_byte_length,
+            kvd_byte_offset: 0,
+            kvd_byte_length: 0,
+            sgd_byte_offset: 0,
+            sgd_byte_length: 0,
+        };
+
+        let mut writer = Cursor::new(Vec::new());
+        header.write_le(&mut writer)?;
+        level_indices.write_le(&mut writer)?;
+        dfd.write_le(&mut writer)?;
+        writer.write_all(&level_data)?;
+
+        Ok(writer.into_inner())
+    }
+}
+
+fn has_srgb_format(format: ImageFormat) -> bool {
+    matches!(
+        format,
+        ImageFormat::R8Unorm
+            | ImageFormat::R8G8B8A8Unorm
+            | ImageFormat::BC1Unorm
+            | ImageFormat::BC2Unorm
+            | ImageFormat::BC3Unorm
+            | ImageFormat::BC7Unorm
+            | ImageFormat::B8G8R8A8Unorm
+    )
+}
+
+fn vk_format(format: ImageFormat, srgb: bool) -> u32 {
+    // VkFormat values from the Vulkan specification.
+    match (format, srgb) {
+        (ImageFormat::R8Unorm, false) => 9,
+        (ImageFormat::R8Unorm, true) => 15,
+        (ImageFormat::R8G8B8A8Unorm, false) => 37,
+        (ImageFormat::R8G8B8A8Unorm, true) => 43,
+        (ImageFormat::R16G16B16A16Float, _) => 97,
+        // VK_FORMAT_A4R4G4B4_UNORM_PACK16
+        (ImageFormat::R4G4B4A4Unorm, _) => 1000340000,
+        (ImageFormat::BC1Unorm, false) => 133,
+        (ImageFormat::BC1Unorm, true) => 134,
+        (ImageFormat::BC2Unorm, false) => 135,
+        (ImageFormat::BC2Unorm, true) => 136,
+        (ImageFormat::BC3Unorm, false) => 137,
+        (ImageFormat::BC3Unorm, true) => 138,
+        (ImageFormat::BC4Unorm, _) => 139,
+        (ImageFormat::BC5Unorm, _) => 141,
+        (ImageFormat::BC6UFloat, _) => 143,
+        (ImageFormat::BC7Unorm, false) => 145,
+        (ImageFormat::BC7Unorm, true) => 146,
+        (ImageFormat::B8G8R8A8Unorm, false) => 44,
+        (ImageFormat::B8G8R8A8Unorm, true) => 50,
+    }
+}
+
+fn type_size(format: ImageFormat) -> u32 {
+    match format {
+        ImageFormat::R16G16B16A16Float | ImageFormat::R4G4B4A4Unorm => 2,
+        _ => 1,
+    }
+}
+
+fn data_format_descriptor(format: ImageFormat, srgb: bool) -> DataFormatDescriptor {
+    let (color_model, samples) = color_model_samples(format, srgb);
+    let (block_width, block_height) = block_dimensions(format);
+
+    let descriptor_block_size = 24 + 16 * samples.len() as u16;
+    DataFormatDescriptor {
+        total_size: 4 + descriptor_block_size as u32,
+        vendor_id_descriptor_type: 0,
+        version_number: 2,
+        descriptor_block_size,
+        color_model,
+        color_primaries: KHR_DF_PRIMARIES_BT709,
+        transfer_function: if srgb {
+            KHR_DF_TRANSFER_SRGB
+        } else {
+            KHR_DF_TRANSFER_LINEAR
+        },
+        flags: 0,
+        texel_block_dimension: [block_width as u8 - 1, block_height as u8 - 1, 0, 0],
+        bytes_plane: [format.bytes_per_pixel() as u8, 0, 0, 0, 0, 0, 0, 0],
+        samples,
+    }
+}
+
+fn color_model_samples(format: ImageFormat, srgb: bool) -> (u8, Vec<Sample>) {
+    // The alpha channel is always linear even for sRGB formats.
+    let alpha = if srgb {
+        KHR_DF_CHANNEL_ALPHA | KHR_DF_SAMPLE_DATATYPE_LINEAR
+    } else {
+        KHR_DF_CHANNEL_ALPHA
+    };
+
+    let half_float = KHR_DF_SAMPLE_DATATYPE_FLOAT | KHR_DF_SAMPLE_DATATYPE_SIGNED;
+    let one = 1.0f32.to_bits();
+    let negative_one = (-1.0f32).to_bits();
+
+    match format {
+        ImageFormat::R8Unorm => (
+            KHR_DF_MODEL_RGBSDA,
+            vec![Sample::new(KHR_DF_CHANNEL_RED, 0, 8, 0, 255)],
+        ),
+        ImageFormat::R8G8B8A8Unorm => (
+            KHR_DF_MODEL_RGBSDA,
+            vec![
+                Sample::new(KHR_DF_CHANNEL_RED, 0, 8, 0, 255),
+                Sample::new(KHR_DF_CHANNEL_GREEN, 8, 8, 0, 255),
+                Sample::new(KHR_DF_CHANNEL_BLUE, 16, 8, 0, 255),
+                Sample::new(alpha, 24, 8, 0, 255),
+            ],
+        ),
+        ImageFormat::B8G8R8A8Unorm => (
+            KHR_DF_MODEL_RGBSDA,
+            vec![
+                Sample::new(KHR_DF_CHANNEL_BLUE, 0, 8, 0, 255),
+                Sample::new(KHR_DF_CHANNEL_GREEN, 8, 8, 0, 255),
+                Sample::new(KHR_DF_CHANNEL_RED, 16, 8, 0, 255),
+                Sample::new(alpha, 24, 8, 0, 255),
+            ],
+        ),
+        ImageFormat::R16G16B16A16Float => (
+            KHR_DF_MODEL_RGBSDA,
+            vec![
+                Sample::new(KHR_DF_CHANNEL_RED | half_float, 0, 16, negative_one, one),
+                Sample::new(KHR_DF_CHANNEL_GREEN | half_float, 16, 16, negative_one, one),
+                Sample::new(KHR_DF_CHANNEL_BLUE | half_float, 32, 16, negative_one, one),
+                Sample::new(KHR_DF_CHANNEL_ALPHA | half_float, 48, 16, negative_one, one),
+            ],
+        ),
+        // Use the same channel order as image_dds.
+        ImageFormat::R4G4B4A4Unorm => (
+            KHR_DF_MODEL_RGBSDA,
+            vec![
+                Sample::new(KHR_DF_CHANNEL_BLUE, 0, 4, 0, 15),
+                Sample::new(KHR_DF_CHANNEL_GREEN, 4, 4, 0, 15),
+                Sample::new(KHR_DF_CHANNEL_RED, 8, 4, 0, 15),
+                Sample::new(KHR_DF_CHANNEL_ALPHA, 12, 4, 0, 15),
+            ],
+        ),
+        ImageFormat::BC1Unorm => (
+            KHR_DF_MODEL_BC1A,
+            vec![Sample::new(
+                KHR_DF_CHANNEL_BC1A_ALPHAPRESENT,
+                0,
+                64,
+                0,
+                u32::MAX,
+            )],
+        ),
+        ImageFormat::BC2Unorm => (
+            KHR_DF_MODEL_BC2,
+            vec![
+                Sample::new(alpha, 0, 64, 0, u32::MAX),
+                Sample::new(KHR_DF_CHANNEL_RED, 64, 64, 0, u32::MAX),
+            ],
+        ),
+        ImageFormat::BC3Unorm => (
+            KHR_DF_MODEL_BC3,
+            vec![
+                Sample::new(alpha, 0, 64, 0, u32::MAX),
+                Sample::new(KHR_DF_CHANNEL_RED, 64, 64, 0, u32::MAX),
+            ],
+        ),
+        ImageFormat::BC4Unorm => (
+            KHR_DF_MODEL_BC4,
+            vec![Sample::new(KHR_DF_CHANNEL_RED, 0, 64, 0, u32::MAX)],
+        ),
+        ImageFormat::BC5Unorm => (
+            KHR_DF_MODEL_BC5,
+            vec![
+                Sample::new(KHR_DF_CHANNEL_RED, 0, 64, 0, u32::MAX),
+                Sample::new(KHR_DF_CHANNEL_GREEN, 64, 64, 0, u32::MAX),
+            ],
+        ),
+        ImageFormat::BC6UFloat => (
+            KHR_DF_MODEL_BC6H,
+            vec![Sample::new(
+                KHR_DF_CHANNEL_RED | KHR_DF_SAMPLE_DATATYPE_FLOAT,
+                0,
+                128,
+                0,
+                one,
+            )],
+        ),
+        ImageFormat::BC7Unorm => (
+            KHR_DF_MODEL_BC7,
+            vec![Sample::new(KHR_DF_CHANNEL_RED, 0, 128, 0, u32::MAX)],
+        ),
+    }
+}
+
+fn lcm(a: usize, b: usize) -> usize {
+    a / gcd(a, b) * b
+}
+
+fn gcd(a: usize, b: usize) -> usize {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::TextureUsage;
+
+    fn read_u32(bytes: &[u8], offset: usize) -> u32 {
+        u32::from_le_bytes(bytes[offset..offset + 4].try_into().unwrap())
+    }
+
+    fn read_u64(bytes: &[u8], offset: usize) -> u64 {
+        u64::from_le_bytes(bytes[offset..offset + 8].try_into().unwrap())
+    }
+
+    #[test]
+    fn bc7_srgb_header() {
+        // 8x8 with 3 mipmaps requires 4 + 1 + 1 blocks.
+        let image = ImageTexture {
+            name: None,
+            usage: Some(TextureUsage::Col),
+            width: 8,
+            height: 8,
+            depth: 1,
+            view_dimension: ViewDimension::D2,
+            image_format: ImageFormat::BC7Unorm,
+            mipmap_count: 3,
+            image_data: (0..6 * 16).map(|i| i as u8).collect(),
+        };
+        let bytes = image.to_ktx2().unwrap();
+
+        assert_eq!(b"\xABKTX 20\xBB\r\n\x1A\n", &bytes[..12]);
+        // vkFormat is VK_FORMAT_BC7_SRGB_BLOCK.
+        assert_eq!(146, read_u32(&bytes, 12));
+        assert_eq!(1, read_u32(&bytes, 16));
+        assert_eq!(8, read_u32(&bytes, 20));
+        assert_eq!(8, read_u32(&bytes, 24));
+        assert_eq!(0, read_u32(&bytes, 28));
+        assert_eq!(0, read_u32(&bytes, 32));
+        assert_eq!(1, read_u32(&bytes, 36));
+        assert_eq!(3, read_u32(&bytes, 40));
+        assert_eq!(0, read_u32(&bytes, 44));
+
+        // The data format descriptor follows the level index.
+        let dfd_offset = read_u32(&bytes, 48) as usize;
+        assert_eq!(80 + 3 * 24, dfd_offset);
+        assert_eq!(44, read_u32(&bytes, 52));
+        assert_eq!(44, read_u32(&bytes, dfd_offset));
+        assert_eq!(KHR_DF_MODEL_BC7, bytes[dfd_offset + 12]);
+        assert_eq!(KHR_DF_TRANSFER_SRGB, bytes[dfd_offset + 14]);
+        assert_eq!([3, 3, 0, 0], bytes[dfd_offset + 16..dfd_offset + 20]);
+        assert_eq!(16, bytes[dfd_offset + 20]);
+
+        // Levels are stored from smallest to largest and aligned to the block size.
+        assert_eq!(208, read_u64(&bytes, 80 + 2 * 24));
+        assert_eq!(16, read_u64(&bytes, 80 + 2 * 24 + 8));
+        assert_eq!(224, read_u64(&bytes, 80 + 24));
+        assert_eq!(16, read_u64(&bytes, 80 + 24 + 8));
+        assert_eq!(240, read_u64(&bytes, 80));
+        assert_eq!(64, read_u64(&bytes, 80 + 8));
+        assert_eq!(304, bytes.len());
+
+        assert_eq!(&image.image_data[..64], &bytes[240..304]);
+        assert_eq!(&image.image_data[64..80], &bytes[224..240]);
+        assert_eq!(&image.image_data[80..96], &bytes[208..224]);
+    }
+
+    #[test]
+    fn cube_face_count() {
+        let image = ImageTexture {
+            name: None,
+            usage: None,
+            width: 4,
+            height: 4,
+            depth: 1,
+            view_dimension: ViewDimension::Cube,
+            image_format: ImageFormat::BC7Unorm,
+            mipmap_count: 1,
+            image_data: (0..6 * 16).map(|i| i as u8).collect(),
+        };
+        let bytes = image.to_ktx2().unwrap();
+
+        // Cube maps have 6 faces and no array layers.
+        assert_eq!(0, read_u32(&bytes, 32));
+        assert_eq!(6, read_u32(&bytes, 36));
+        let offset = read_u64(&bytes, 80) as usize;
+        assert_eq!(96, read_u64(&bytes, 88));
+        assert_eq!(&image.image_data[..], &bytes[offset..offset + 96]);
+    }
+
+    #[test]
+    fn color_space_vk_format() {
+        let image = |usage| ImageTexture {
+            name: None,
+            usage,
+            width: 4,
+            height: 4,
+            depth: 1,
+            view_dimension: ViewDimension::D2,
+            image_format: ImageFormat::BC7Unorm,
+            mipmap_count: 1,
+            image_data: vec![0; 16],
+        };
+
+        // Formats should match the color space used for other conversions.
+        // VK_FORMAT_BC7_SRGB_BLOCK
+        assert_eq!(146, read_u32(&image(None).to_ktx2().unwrap(), 12));
+        // VK_FORMAT_BC7_UNORM_BLOCK
+        assert_eq!(
+            145,
+            read_u32(&image(Some(TextureUsage::Nrm)).to_ktx2().unwrap(), 12)
+        );
+    }
+}
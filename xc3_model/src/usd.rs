@@ -0,0 +1,826 @@
+//! Conversions from xc3_model types to Universal Scene Description (USD).
+//!
+//! The stage is written as a text `.usda` layer with generated PNG textures
+//! stored in the same directory. Materials use `UsdPreviewSurface`,
+//! and skeletons use a `UsdSkel` `Skeleton` prim.
+//! Meshes are not bound to the skeleton, so vertex skinning is not exported yet.
+//! Binary `.usdc` and `.usdz` files are not written directly,
+//! but tools like `usdcat` or `usdzip` can convert the `.usda` layer.
+//!
+//! # Getting Started
+//! ```rust no_run
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! use xc3_model::usd::UsdFile;
+//! use xc3_model::shader_database::ShaderDatabase;
+//!
+//! let database = ShaderDatabase::from_file("xc3.json")?;
+//!
+//! // Models have only one root.
+//! let root = xc3_model::load_model("xeno3/chr/ch/ch01027000.wimdo", Some(&database))?;
+//! let usd = UsdFile::from_model("mio_military", &[root]);
+//! usd.save("mio_military.usda")?;
+//!
+//! // Maps have multiple roots.
+//! let roots = xc3_model::load_map("xeno3/map/ma59a.wismhd", Some(&database))?;
+//! let usd = UsdFile::from_map("map", &roots);
+//! usd.save("map.usda")?;
+//! # Ok(())
+//! # }
+//! ```
+use std::{collections::BTreeSet, fmt::Write, path::Path};
+
+use glam::Mat4;
+use log::error;
+use rayon::prelude::*;
+use xc3_lib::mxmd::{BlendMode, CullMode};
+
+use crate::{
+    gltf::texture::{
+        albedo_generated_key, metallic_roughness_generated_key, normal_generated_key,
+        GeneratedImageKey, TextureCache,
+    },
+    should_render_lod,
+    skeleton::Skeleton,
+    vertex::{AttributeData, ModelBuffers},
+    AddressMode, ImageTexture, MapRoot, ModelRoot, Models,
+};
+
+/// USD stage text and image data for a model or map.
+#[derive(Debug)]
+pub struct UsdFile {
+    /// The text for the `.usda` root layer.
+    pub stage: String,
+    /// The file name with PNG extension and PNG file data for all generated textures.
+    pub png_images: Vec<(String, Vec<u8>)>,
+}
+
+impl UsdFile {
+    /// Convert the Xenoblade model `roots` to a USD stage.
+    /// See [load_model](crate::load_model) for loading files.
+    ///
+    /// Each root is a separate prim with its own materials and meshes.
+    /// Roots with a skeleton use a `SkelRoot` prim containing a `Skeleton`.
+    /// Meshes do not have skinning bindings to the `Skeleton`.
+    ///
+    /// The `model_name` is used to create resource file names and the default prim name.
+    pub fn from_model(model_name: &str, roots: &[ModelRoot]) -> Self {
+        let mut texture_cache = TextureCache::new(roots.iter().map(|r| &r.image_textures));
+
+        let root_materials: Vec<_> = roots
+            .iter()
+            .enumerate()
+            .map(|(root_index, root)| {
+                create_materials(
+                    &root.models,
+                    &root.image_textures,
+                    root_index,
+                    &mut texture_cache,
+                )
+            })
+            .collect();
+
+        let image_names = texture_cache.image_names(model_name);
+
+        let default_prim = identifier(model_name);
+        let mut stage = stage_header(&default_prim);
+
+        for (root_index, (root, materials)) in roots.iter().zip(&root_materials).enumerate() {
+            let root_path = format!("/{default_prim}/root{root_index}");
+            let prim_type = if root.skeleton.is_some() {
+                "SkelRoot"
+            } else {
+                "Xform"
+            };
+            writeln!(&mut stage, "    def {prim_type} \"root{root_index}\"").unwrap();
+            writeln!(&mut stage, "    {{").unwrap();
+
+            if let Some(skeleton) = &root.skeleton {
+                write_skeleton(&mut stage, skeleton, 2);
+            }
+
+            write_models(
+                &mut stage,
+                &root.models,
+                std::slice::from_ref(&root.buffers),
+                materials,
+                &image_names,
+                root_index,
+                &root_path,
+                2,
+            );
+
+            writeln!(&mut stage, "    }}").unwrap();
+        }
+        stage.push_str("}\n");
+
+        let png_images = texture_cache.generate_png_images(model_name);
+
+        Self { stage, png_images }
+    }
+
+    /// Convert the Xenoblade map `roots` to a USD stage.
+    /// See [load_map](crate::load_map) for loading files.
+    ///
+    /// All roots are added to a single stage like [from_model](UsdFile::from_model)
+    /// with a prim for each [ModelGroup](crate::ModelGroup) [Models](crate::Models).
+    pub fn from_map(model_name: &str, roots: &[MapRoot]) -> Self {
+        let mut texture_cache = TextureCache::new(roots.iter().map(|r| &r.image_textures));
+
+        let mut root_materials = Vec::new();
+        for (root_index, root) in roots.iter().enumerate() {
+            let mut group_materials = Vec::new();
+            for group in &root.groups {
+                let mut models_materials = Vec::new();
+                for models in &group.models {
+                    models_materials.push(create_materials(
+                        models,
+                        &root.image_textures,
+                        root_index,
+                        &mut texture_cache,
+                    ));
+                }
+                group_materials.push(models_materials);
+            }
+            root_materials.push(group_materials);
+        }
+
+        let image_names = texture_cache.image_names(model_name);
+
+        let default_prim = identifier(model_name);
+        let mut stage = stage_header(&default_prim);
+
+        for (root_index, (root, group_materials)) in roots.iter().zip(&root_materials).enumerate() {
+            writeln!(&mut stage, "    def Xform \"root{root_index}\"").unwrap();
+            writeln!(&mut stage, "    {{").unwrap();
+
+            for (group_index, (group, models_materials)) in
+                root.groups.iter().zip(group_materials).enumerate()
+            {
+                for (models_index, (models, materials)) in
+                    group.models.iter().zip(models_materials).enumerate()
+                {
+                    let name = format!("group{group_index}_models{models_index}");
+                    let models_path = format!("/{default_prim}/root{root_index}/{name}");
+
+                    writeln!(&mut stage, "        def Xform \"{name}\"").unwrap();
+                    writeln!(&mut stage, "        {{").unwrap();
+                    write_models(
+                        &mut stage,
+                        models,
+                        &group.buffers,
+                        materials,
+                        &image_names,
+                        root_index,
+                        &models_path,
+                        3,
+                    );
+                    writeln!(&mut stage, "        }}").unwrap();
+                }
+            }
+
+            writeln!(&mut stage, "    }}").unwrap();
+        }
+        stage.push_str("}\n");
+
+        let png_images = texture_cache.generate_png_images(model_name);
+
+        Self { stage, png_images }
+    }
+
+    /// Save the stage to the specified `path` with images stored in the same directory.
+    /// The `path` should have the `.usda` extension.
+    ///
+    /// # Examples
+    ///
+    /// ```rust no_run
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// # use xc3_model::usd::UsdFile;
+    /// # let roots = Vec::new();
+    /// let usd_file = UsdFile::from_model("model", &roots);
+    /// usd_file.save("model.usda")?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> std::io::Result<()> {
+        let path = path.as_ref();
+
+        std::fs::write(path, &self.stage)?;
+
+        // Save images in parallel since PNG encoding is CPU intensive.
+        self.png_images.par_iter().try_for_each(|(name, image)| {
+            let output = path.with_file_name(name);
+            std::fs::write(output, image)
+        })?;
+        Ok(())
+    }
+}
+
+// The material textures are resolved before writing since
+// image names are only known after all images are added to the cache.
+struct MaterialPrim {
+    name: String,
+    albedo: Option<TextureInput>,
+    normal: Option<TextureInput>,
+    metallic_roughness: Option<TextureInput>,
+    opacity_threshold: Option<f32>,
+    alpha_blend: bool,
+}
+
+struct TextureInput {
+    image_index: u32,
+    wrap_s: &'static str,
+    wrap_t: &'static str,
+}
+
+fn create_materials(
+    models: &Models,
+    image_textures: &[ImageTexture],
+    root_index: usize,
+    texture_cache: &mut TextureCache,
+) -> Vec<MaterialPrim> {
+    let mut used_names = BTreeSet::new();
+
+    models
+        .materials
+        .iter()
+        .map(|material| {
+            let assignments = material.output_assignments(image_textures);
+
+            let mut texture_input = |key: GeneratedImageKey| {
+                let image_index = texture_cache.insert(key)?;
+                // Assume all channels use the same sampler.
+                let sampler = key.red_index.and_then(|i| models.samplers.get(i.sampler));
+                Some(TextureInput {
+                    image_index,
+                    wrap_s: sampler
+                        .map(|s| wrap_mode(s.address_mode_u()))
+                        .unwrap_or("repeat"),
+                    wrap_t: sampler
+                        .map(|s| wrap_mode(s.address_mode_v()))
+                        .unwrap_or("repeat"),
+                })
+            };
+
+            MaterialPrim {
+                name: unique_identifier(identifier(&material.name), &mut used_names),
+                albedo: texture_input(albedo_generated_key(material, &assignments, root_index)),
+                normal: texture_input(normal_generated_key(material, &assignments, root_index)),
+                metallic_roughness: texture_input(metallic_roughness_generated_key(
+                    material,
+                    &assignments,
+                    root_index,
+                )),
+                opacity_threshold: material.alpha_test.as_ref().map(|a| a.ref_value),
+                alpha_blend: matches!(
+                    material.flags.blend_mode,
+                    BlendMode::AlphaBlend | BlendMode::Additive
+                ),
+            }
+        })
+        .collect()
+}
+
+fn wrap_mode(address_mode: AddressMode) -> &'static str {
+    match address_mode {
+        AddressMode::ClampToEdge => "clamp",
+        AddressMode::Repeat => "repeat",
+        AddressMode::MirrorRepeat => "mirror",
+    }
+}
+
+fn stage_header(default_prim: &str) -> String {
+    let mut stage = String::new();
+    stage.push_str("#usda 1.0\n");
+    stage.push_str("(\n");
+    writeln!(&mut stage, "    defaultPrim = \"{default_prim}\"").unwrap();
+    stage.push_str("    metersPerUnit = 1\n");
+    // Xenoblade models and maps use Y-up.
+    stage.push_str("    upAxis = \"Y\"\n");
+    stage.push_str(")\n\n");
+    writeln!(&mut stage, "def Xform \"{default_prim}\"").unwrap();
+    stage.push_str("{\n");
+    stage
+}
+
+fn write_models(
+    out: &mut String,
+    models: &Models,
+    group_buffers: &[ModelBuffers],
+    materials: &[MaterialPrim],
+    image_names: &[String],
+    root_index: usize,
+    path: &str,
+    depth: usize,
+) {
+    let indent = "    ".repeat(depth);
+
+    writeln!(out, "{indent}def Scope \"Materials\"").unwrap();
+    writeln!(out, "{indent}{{").unwrap();
+    for material in materials {
+        let material_path = format!("{path}/Materials/{}", material.name);
+        write_material(out, material, image_names, &material_path, depth + 1);
+    }
+    writeln!(out, "{indent}}}").unwrap();
+
+    for (model_index, model) in models.models.iter().enumerate() {
+        let model_buffers = &group_buffers[model.model_buffers_index];
+
+        writeln!(out, "{indent}def Xform \"model{model_index}\"").unwrap();
+        writeln!(out, "{indent}{{").unwrap();
+
+        // Instancing is applied at the model level.
+        // TODO: Use native USD instancing to avoid duplicating mesh data for maps.
+        for (instance_index, instance) in model.instances.iter().enumerate() {
+            writeln!(out, "{indent}    def Xform \"instance{instance_index}\"").unwrap();
+            writeln!(out, "{indent}    {{").unwrap();
+            if *instance != Mat4::IDENTITY {
+                writeln!(
+                    out,
+                    "{indent}        matrix4d xformOp:transform = {}",
+                    matrix(instance)
+                )
+                .unwrap();
+                writeln!(
+                    out,
+                    "{indent}        uniform token[] xformOpOrder = [\"xformOp:transform\"]"
+                )
+                .unwrap();
+            }
+
+            for (mesh_index, mesh) in model.meshes.iter().enumerate() {
+                // Skip invalid meshes instead of panicking.
+                let errors = mesh.index_errors(model_index, mesh_index, models, model_buffers);
+                if !errors.is_empty() {
+                    for e in errors {
+                        error!("Skipping mesh for root {root_index}: {e}");
+                    }
+                    continue;
+                }
+
+                // TODO: Make LOD selection configurable?
+                let material = &models.materials[mesh.material_index];
+                if should_render_lod(mesh.lod, &models.base_lod_indices)
+                    && !material.name.ends_with("_outline")
+                    && !material.name.contains("_speff_")
+                {
+                    let vertex_buffer = &model_buffers.vertex_buffers[mesh.vertex_buffer_index];
+                    let index_buffer = &model_buffers.index_buffers[mesh.index_buffer_index];
+
+                    let material_path =
+                        format!("{path}/Materials/{}", materials[mesh.material_index].name);
+
+                    write_mesh(
+                        out,
+                        &format!("mesh{mesh_index}"),
+                        &vertex_buffer.attributes,
+                        &index_buffer.indices,
+                        &material_path,
+                        material.flags.cull_mode == CullMode::Disabled,
+                        depth + 2,
+                    );
+                }
+            }
+
+            writeln!(out, "{indent}    }}").unwrap();
+        }
+
+        writeln!(out, "{indent}}}").unwrap();
+    }
+}
+
+fn write_mesh(
+    out: &mut String,
+    name: &str,
+    attributes: &[AttributeData],
+    indices: &[u16],
+    material_path: &str,
+    double_sided: bool,
+    depth: usize,
+) {
+    let indent = "    ".repeat(depth);
+
+    // TODO: Export skin weights using UsdSkelBindingAPI.
+    let Some(positions) = attributes.iter().find_map(|a| match a {
+        AttributeData::Position(values) => Some(values),
+        _ => None,
+    }) else {
+        error!("Skipping mesh {name:?} without vertex positions");
+        return;
+    };
+
+    writeln!(
+        out,
+        "{indent}def Mesh \"{name}\" (\n{indent}    prepend apiSchemas = [\"MaterialBindingAPI\"]\n{indent})"
+    )
+    .unwrap();
+    writeln!(out, "{indent}{{").unwrap();
+
+    let face_count = indices.len() / 3;
+    writeln!(
+        out,
+        "{indent}    int[] faceVertexCounts = [{}]",
+        list((0..face_count).map(|_| 3))
+    )
+    .unwrap();
+    writeln!(
+        out,
+        "{indent}    int[] faceVertexIndices = [{}]",
+        list(indices[..face_count * 3].iter())
+    )
+    .unwrap();
+    writeln!(
+        out,
+        "{indent}    point3f[] points = [{}]",
+        list(
+            positions
+                .iter()
+                .map(|p| format!("({}, {}, {})", float(p.x), float(p.y), float(p.z)))
+        )
+    )
+    .unwrap();
+
+    for attribute in attributes {
+        match attribute {
+            AttributeData::Normal(values) => {
+                writeln!(
+                    out,
+                    "{indent}    normal3f[] normals = [{}] (\n{indent}        interpolation = \"vertex\"\n{indent}    )",
+                    list(
+                        values
+                            .iter()
+                            .map(|n| format!("({}, {}, {})", float(n.x), float(n.y), float(n.z)))
+                    )
+                )
+                .unwrap();
+            }
+            AttributeData::TexCoord0(values) => {
+                // USD uses a bottom left origin for texture coordinates.
+                writeln!(
+                    out,
+                    "{indent}    texCoord2f[] primvars:st = [{}] (\n{indent}        interpolation = \"vertex\"\n{indent}    )",
+                    list(
+                        values
+                            .iter()
+                            .map(|uv| format!("({}, {})", float(uv.x), float(1.0 - uv.y)))
+                    )
+                )
+                .unwrap();
+            }
+            _ => (),
+        }
+    }
+
+    if double_sided {
+        writeln!(out, "{indent}    uniform bool doubleSided = 1").unwrap();
+    }
+    writeln!(out, "{indent}    rel material:binding = <{material_path}>").unwrap();
+    writeln!(
+        out,
+        "{indent}    uniform token subdivisionScheme = \"none\""
+    )
+    .unwrap();
+    writeln!(out, "{indent}}}").unwrap();
+}
+
+fn write_material(
+    out: &mut String,
+    material: &MaterialPrim,
+    image_names: &[String],
+    path: &str,
+    depth: usize,
+) {
+    let indent = "    ".repeat(depth);
+
+    writeln!(out, "{indent}def Material \"{}\"", material.name).unwrap();
+    writeln!(out, "{indent}{{").unwrap();
+    writeln!(
+        out,
+        "{indent}    token outputs:surface.connect = <{path}/PreviewSurface.outputs:surface>"
+    )
+    .unwrap();
+
+    writeln!(out, "{indent}    def Shader \"PreviewSurface\"").unwrap();
+    writeln!(out, "{indent}    {{").unwrap();
+    writeln!(
+        out,
+        "{indent}        uniform token info:id = \"UsdPreviewSurface\""
+    )
+    .unwrap();
+    if material.albedo.is_some() {
+        writeln!(
+            out,
+            "{indent}        color3f inputs:diffuseColor.connect = <{path}/Albedo.outputs:rgb>"
+        )
+        .unwrap();
+        // The albedo alpha contains the alpha test mask if present.
+        if material.opacity_threshold.is_some() || material.alpha_blend {
+            writeln!(
+                out,
+                "{indent}        float inputs:opacity.connect = <{path}/Albedo.outputs:a>"
+            )
+            .unwrap();
+        }
+    }
+    if let Some(threshold) = material.opacity_threshold {
+        writeln!(
+            out,
+            "{indent}        float inputs:opacityThreshold = {}",
+            float(threshold)
+        )
+        .unwrap();
+    }
+    if material.metallic_roughness.is_some() {
+        // Use the same channel packing as glTF.
+        writeln!(
+            out,
+            "{indent}        float inputs:occlusion.connect = <{path}/MetallicRoughness.outputs:r>"
+        )
+        .unwrap();
+        writeln!(
+            out,
+            "{indent}        float inputs:roughness.connect = <{path}/MetallicRoughness.outputs:g>"
+        )
+        .unwrap();
+        writeln!(
+            out,
+            "{indent}        float inputs:metallic.connect = <{path}/MetallicRoughness.outputs:b>"
+        )
+        .unwrap();
+    }
+    if material.normal.is_some() {
+        writeln!(
+            out,
+            "{indent}        normal3f inputs:normal.connect = <{path}/Normal.outputs:rgb>"
+        )
+        .unwrap();
+    }
+    writeln!(out, "{indent}        token outputs:surface").unwrap();
+    writeln!(out, "{indent}    }}").unwrap();
+
+    let has_textures = material.albedo.is_some()
+        || material.normal.is_some()
+        || material.metallic_roughness.is_some();
+    if has_textures {
+        // TODO: Don't assume the first UV map?
+        writeln!(out, "{indent}    def Shader \"TexCoord\"").unwrap();
+        writeln!(out, "{indent}    {{").unwrap();
+        writeln!(
+            out,
+            "{indent}        uniform token info:id = \"UsdPrimvarReader_float2\""
+        )
+        .unwrap();
+        writeln!(out, "{indent}        string inputs:varname = \"st\"").unwrap();
+        writeln!(out, "{indent}        float2 outputs:result").unwrap();
+        writeln!(out, "{indent}    }}").unwrap();
+    }
+
+    let textures = [
+        ("Albedo", &material.albedo, "sRGB"),
+        ("Normal", &material.normal, "raw"),
+        ("MetallicRoughness", &material.metallic_roughness, "raw"),
+    ];
+    for (name, texture, color_space) in textures {
+        if let Some(texture) = texture {
+            writeln!(out, "{indent}    def Shader \"{name}\"").unwrap();
+            writeln!(out, "{indent}    {{").unwrap();
+            writeln!(
+                out,
+                "{indent}        uniform token info:id = \"UsdUVTexture\""
+            )
+            .unwrap();
+            writeln!(
+                out,
+                "{indent}        asset inputs:file = @{}@",
+                image_names[texture.image_index as usize]
+            )
+            .unwrap();
+            writeln!(
+                out,
+                "{indent}        float2 inputs:st.connect = <{path}/TexCoord.outputs:result>"
+            )
+            .unwrap();
+            writeln!(
+                out,
+                "{indent}        token inputs:sourceColorSpace = \"{color_space}\""
+            )
+            .unwrap();
+            writeln!(
+                out,
+                "{indent}        token inputs:wrapS = \"{}\"",
+                texture.wrap_s
+            )
+            .unwrap();
+            writeln!(
+                out,
+                "{indent}        token inputs:wrapT = \"{}\"",
+                texture.wrap_t
+            )
+            .unwrap();
+            if name == "Normal" {
+                // Convert from unorm to snorm values.
+                writeln!(out, "{indent}        float4 inputs:scale = (2, 2, 2, 1)").unwrap();
+                writeln!(out, "{indent}        float4 inputs:bias = (-1, -1, -1, 0)").unwrap();
+            }
+            writeln!(out, "{indent}        float3 outputs:rgb").unwrap();
+            writeln!(out, "{indent}        float outputs:r").unwrap();
+            writeln!(out, "{indent}        float outputs:g").unwrap();
+            writeln!(out, "{indent}        float outputs:b").unwrap();
+            writeln!(out, "{indent}        float outputs:a").unwrap();
+            writeln!(out, "{indent}    }}").unwrap();
+        }
+    }
+
+    writeln!(out, "{indent}}}").unwrap();
+}
+
+fn write_skeleton(out: &mut String, skeleton: &Skeleton, depth: usize) {
+    let indent = "    ".repeat(depth);
+
+    // Joints are identified by their path in the hierarchy like "root/child".
+    let mut used_paths = BTreeSet::new();
+    let mut joints: Vec<String> = Vec::new();
+    for bone in &skeleton.bones {
+        let name = identifier(&bone.name);
+        let path = match bone.parent_index.and_then(|p| joints.get(p)) {
+            Some(parent) => format!("{parent}/{name}"),
+            None => name,
+        };
+        joints.push(unique_identifier(path, &mut used_paths));
+    }
+
+    let rest_transforms = skeleton.bones.iter().map(|b| matrix(&b.transform));
+    let bind_transforms = skeleton.model_space_transforms();
+
+    writeln!(out, "{indent}def Skeleton \"Skeleton\"").unwrap();
+    writeln!(out, "{indent}{{").unwrap();
+    writeln!(
+        out,
+        "{indent}    uniform token[] joints = [{}]",
+        list(joints.iter().map(|j| format!("\"{j}\"")))
+    )
+    .unwrap();
+    writeln!(
+        out,
+        "{indent}    uniform matrix4d[] bindTransforms = [{}]",
+        list(bind_transforms.iter().map(matrix))
+    )
+    .unwrap();
+    writeln!(
+        out,
+        "{indent}    uniform matrix4d[] restTransforms = [{}]",
+        list(rest_transforms)
+    )
+    .unwrap();
+    writeln!(out, "{indent}}}").unwrap();
+}
+
+fn list<T: ToString>(values: impl Iterator<Item = T>) -> String {
+    values.map(|v| v.to_string()).collect::<Vec<_>>().join(", ")
+}
+
+fn matrix(m: &Mat4) -> String {
+    // USD matrices use row vectors, so each row is a column of the glam matrix.
+    let [x, y, z, w] = m.to_cols_array_2d();
+    let row = |r: [f32; 4]| {
+        format!(
+            "({}, {}, {}, {})",
+            float(r[0]),
+            float(r[1]),
+            float(r[2]),
+            float(r[3])
+        )
+    };
+    format!("( {}, {}, {}, {} )", row(x), row(y), row(z), row(w))
+}
+
+fn float(f: f32) -> String {
+    if f.is_nan() {
+        "nan".to_string()
+    } else if f.is_infinite() {
+        if f > 0.0 {
+            "inf".to_string()
+        } else {
+            "-inf".to_string()
+        }
+    } else {
+        f.to_string()
+    }
+}
+
+/// Convert `name` to a valid prim name using only ASCII letters, digits, and `_`.
+fn identifier(name: &str) -> String {
+    let name: String = name
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+    if name.is_empty() || name.starts_with(|c: char| c.is_ascii_digit()) {
+        format!("_{name}")
+    } else {
+        name
+    }
+}
+
+fn unique_identifier(name: String, used_names: &mut BTreeSet<String>) -> String {
+    let mut unique_name = name.clone();
+    let mut suffix = 1;
+    while used_names.contains(&unique_name) {
+        unique_name = format!("{name}_{suffix}");
+        suffix += 1;
+    }
+
+    used_names.insert(unique_name.clone());
+    unique_name
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use glam::vec3;
+
+    use crate::{
+        test_support::{material, mesh, model, model_buffers, model_root, models, positions},
+        vertex::IndexBuffer,
+        Bone,
+    };
+
+    fn root(material_name: &str, skeleton: Option<Skeleton>) -> ModelRoot {
+        ModelRoot {
+            skeleton,
+            ..model_root(
+                Models {
+                    materials: vec![material(material_name)],
+                    ..models(vec![model(vec![mesh(0, 0, 0)])])
+                },
+                model_buffers(
+                    vec![positions(vec![
+                        vec3(0.0, 0.0, 0.0),
+                        vec3(1.0, 0.0, 0.0),
+                        vec3(0.0, 1.0, 0.0),
+                    ])],
+                    vec![IndexBuffer {
+                        indices: vec![0, 1, 2],
+                    }],
+                ),
+            )
+        }
+    }
+
+    fn prim_count(stage: &str) -> usize {
+        stage
+            .lines()
+            .filter(|l| l.trim_start().starts_with("def "))
+            .count()
+    }
+
+    #[test]
+    fn from_model_two_roots() {
+        let file = UsdFile::from_model("model", &[root("a", None), root("a.b", None)]);
+
+        // The default prim and each root's Xform, Materials, Material,
+        // PreviewSurface, model, instance, and Mesh.
+        assert_eq!(15, prim_count(&file.stage));
+        assert!(file.stage.starts_with("#usda 1.0\n"));
+        assert!(file.stage.contains("defaultPrim = \"model\""));
+        assert!(file
+            .stage
+            .contains("rel material:binding = </model/root0/Materials/a>"));
+        assert!(file
+            .stage
+            .contains("rel material:binding = </model/root1/Materials/a_b>"));
+        assert!(file.png_images.is_empty());
+    }
+
+    #[test]
+    fn from_model_skeleton() {
+        let skeleton = Skeleton {
+            bones: vec![
+                Bone {
+                    name: "root".to_string(),
+                    transform: Mat4::IDENTITY,
+                    parent_index: None,
+                },
+                Bone {
+                    name: "spine 1".to_string(),
+                    transform: Mat4::from_translation(vec3(0.0, 1.0, 0.0)),
+                    parent_index: Some(0),
+                },
+            ],
+            procedural_bones: Vec::new(),
+        };
+        let file = UsdFile::from_model("model", &[root("a", Some(skeleton))]);
+
+        assert_eq!(9, prim_count(&file.stage));
+        assert!(file.stage.contains("def SkelRoot \"root0\""));
+        assert!(file
+            .stage
+            .contains("uniform token[] joints = [\"root\", \"root/spine_1\"]"));
+    }
+
+    #[test]
+    fn identifier_invalid_characters() {
+        assert_eq!("mat_01", identifier("mat 01"));
+        assert_eq!("_01", identifier("01"));
+        assert_eq!("_", identifier(""));
+    }
+}
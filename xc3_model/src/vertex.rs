@@ -8,10 +8,16 @@
 //! The vertex buffers in game use an interleaved or "array of structs" approach.
 //! This makes rendering each vertex cache friendly.
 //! A collection of [AttributeData] can always be packed into an interleaved form for rendering.
-use std::io::{Cursor, Seek, SeekFrom, Write};
+use std::{
+    collections::{BTreeMap, HashMap},
+    io::{Cursor, Read, Seek, SeekFrom, Write},
+};
 
 use binrw::{BinRead, BinReaderExt, BinResult, BinWrite, Endian};
-use glam::{Vec2, Vec3, Vec4};
+use glam::{Mat4, Vec2, Vec3, Vec4};
+use log::{debug, warn};
+use rayon::prelude::*;
+use thiserror::Error;
 use xc3_lib::vertex::{
     DataType, IndexBufferDescriptor, MorphDescriptor, MorphTargetFlags, OutlineBufferDescriptor,
     Unk, UnkBufferDescriptor, VertexBufferDescriptor, VertexBufferExtInfo,
@@ -20,13 +26,17 @@ use xc3_lib::vertex::{
 
 pub use xc3_lib::vertex::{WeightGroup, WeightLod};
 
-use crate::skinning::{SkinWeights, WeightGroups, Weights};
+use crate::{
+    skinning::{SkinWeights, WeightGroups, Weights},
+    Models,
+};
 
 #[cfg(feature = "arbitrary")]
 use crate::{arbitrary_vec2s, arbitrary_vec3s, arbitrary_vec4s};
 
 /// See [VertexData].
 #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, PartialEq, Clone)]
 pub struct ModelBuffers {
     pub vertex_buffers: Vec<VertexBuffer>,
@@ -38,12 +48,16 @@ pub struct ModelBuffers {
 
 /// See [VertexBufferDescriptor].
 #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, PartialEq, Clone)]
 pub struct VertexBuffer {
     pub attributes: Vec<AttributeData>,
     /// Animation targets for vertex attributes like positions and normals.
     /// The base target is already applied to [attributes](#structfield.attributes).
     pub morph_targets: Vec<MorphTarget>,
+    /// The original bytes for the default morph target if present.
+    /// See [ModelBuffers::to_vertex_data] for writing this data unmodified.
+    pub morph_default_target: Option<Vec<u8>>,
     pub outline_buffer_index: Option<usize>,
 }
 
@@ -51,6 +65,7 @@ pub struct VertexBuffer {
 ///
 /// The final attribute values are simply `base + target * weight`.
 #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, PartialEq, Clone)]
 pub struct MorphTarget {
     /// Index into [morph_controller_names](../struct.Models.html#structfield.morph_controller_names).
@@ -72,8 +87,18 @@ pub struct MorphTarget {
     pub vertex_indices: Vec<u32>,
 }
 
+impl MorphTarget {
+    fn has_same_data(&self, other: &Self) -> bool {
+        self.position_deltas == other.position_deltas
+            && self.normal_deltas == other.normal_deltas
+            && self.tangent_deltas == other.tangent_deltas
+            && self.vertex_indices == other.vertex_indices
+    }
+}
+
 /// See [OutlineBufferDescriptor].
 #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, PartialEq, Clone)]
 pub struct OutlineBuffer {
     pub attributes: Vec<AttributeData>,
@@ -81,24 +106,827 @@ pub struct OutlineBuffer {
 
 /// See [UnkBufferDescriptor].
 #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, PartialEq, Clone)]
 pub struct UnkBuffer {
-    pub attributes: Vec<AttributeData>,
+    /// The model space position stored in the first 12 bytes of each vertex.
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = arbitrary_vec3s))]
+    pub positions: Vec<Vec3>,
+    /// The remaining bytes of each vertex with the same length as [positions](#structfield.positions).
+    pub values: UnkBufferValues,
+}
+
+/// The data following the position for each vertex in an [UnkBuffer].
+///
+/// The meaning of these bytes is unknown,
+/// so the raw bytes are preserved to write the original data unchanged.
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, PartialEq, Clone)]
+pub enum UnkBufferValues {
+    /// 16 byte vertices with 4 bytes after the position.
+    /// This often resembles an RGB color.
+    Size16(Vec<[u8; 4]>),
+    /// 24 byte vertices with 12 bytes after the position.
+    /// The first 8 bytes tend to be two u32 values like 255 and 2
+    /// followed by 4 bytes that resemble an RGB color.
+    Size24(Vec<[u8; 12]>),
 }
 
 /// See [IndexBufferDescriptor].
 #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, PartialEq, Clone)]
 pub struct IndexBuffer {
     // TODO: support u32?
     pub indices: Vec<u16>,
 }
 
+impl IndexBuffer {
+    /// The triangles for this triangle list
+    /// with degenerate triangles that repeat an index removed.
+    /// The winding order of each triangle is preserved.
+    pub fn triangles(&self) -> impl Iterator<Item = [u16; 3]> + '_ {
+        self.indices
+            .chunks_exact(3)
+            .map(|t| [t[0], t[1], t[2]])
+            .filter(|[a, b, c]| a != b && b != c && a != c)
+    }
+
+    /// Flip triangles with a face normal pointing away from the sum of their vertex `normals`
+    /// by swapping the last two indices.
+    ///
+    /// This gives every triangle a counterclockwise front face relative to its vertex normals,
+    /// so backface culling with [CullMode](xc3_lib::mxmd::CullMode) does not render meshes inside out.
+    /// Triangles with out of range indices or normals perpendicular to the face are unchanged.
+    pub fn fix_winding(&mut self, positions: &[Vec3], normals: &[Vec4]) {
+        for face in self.indices.chunks_exact_mut(3) {
+            let [i0, i1, i2] = [face[0] as usize, face[1] as usize, face[2] as usize];
+            if let (Some(p0), Some(p1), Some(p2), Some(n0), Some(n1), Some(n2)) = (
+                positions.get(i0),
+                positions.get(i1),
+                positions.get(i2),
+                normals.get(i0),
+                normals.get(i1),
+                normals.get(i2),
+            ) {
+                let face_normal = (*p1 - *p0).cross(*p2 - *p0);
+                let vertex_normal = n0.truncate() + n1.truncate() + n2.truncate();
+                if face_normal.dot(vertex_normal) < 0.0 {
+                    face.swap(1, 2);
+                }
+            }
+        }
+    }
+
+    /// Find the neighboring triangle across each edge of [triangles](#method.triangles).
+    ///
+    /// Edges shared by more than two triangles are non-manifold and have no neighbor.
+    ///
+    /// # Examples
+    /// ```rust
+    /// use xc3_model::vertex::IndexBuffer;
+    ///
+    /// let buffer = IndexBuffer {
+    ///     indices: vec![0, 1, 2, 2, 1, 3],
+    /// };
+    /// let adjacency = buffer.adjacency();
+    /// assert_eq!([None, Some(1), None], adjacency.neighbors[0]);
+    /// ```
+    pub fn adjacency(&self) -> Adjacency {
+        let triangles: Vec<_> = self.triangles().collect();
+
+        // Map each undirected edge to the triangles and edges that use it.
+        let mut edge_triangles: HashMap<[u16; 2], Vec<(usize, usize)>> = HashMap::new();
+        for (i, triangle) in triangles.iter().enumerate() {
+            for e in 0..3 {
+                edge_triangles
+                    .entry(edge_key(triangle, e))
+                    .or_default()
+                    .push((i, e));
+            }
+        }
+
+        let mut neighbors = vec![[None; 3]; triangles.len()];
+        for shared in edge_triangles.values() {
+            if let [(t0, e0), (t1, e1)] = shared[..] {
+                neighbors[t0][e0] = Some(t1);
+                neighbors[t1][e1] = Some(t0);
+            }
+        }
+
+        Adjacency {
+            triangles,
+            neighbors,
+        }
+    }
+}
+
+fn edge_key(triangle: &[u16; 3], edge: usize) -> [u16; 2] {
+    let a = triangle[edge];
+    let b = triangle[(edge + 1) % 3];
+    [a.min(b), a.max(b)]
+}
+
+/// Edge adjacency for the triangles of an [IndexBuffer] from [IndexBuffer::adjacency].
+#[derive(Debug, PartialEq, Clone)]
+pub struct Adjacency {
+    /// The triangles with degenerate triangles removed.
+    pub triangles: Vec<[u16; 3]>,
+    /// The index in [triangles](#structfield.triangles) of the neighbor across each edge.
+    /// Edge `i` connects vertex `i` and vertex `(i + 1) % 3` of the triangle.
+    /// Boundary edges and non-manifold edges shared by more than two triangles are [None].
+    pub neighbors: Vec<[Option<usize>; 3]>,
+}
+
 impl VertexBuffer {
     pub fn vertex_count(&self) -> usize {
         // TODO: Check all attributes for consistency?
         self.attributes.first().map(|a| a.len()).unwrap_or_default()
     }
+
+    /// The values for [AttributeData::Position] or [None] if not present.
+    ///
+    /// # Examples
+    /// ```rust
+    /// use glam::vec3;
+    /// use xc3_model::vertex::{AttributeData, VertexBuffer};
+    ///
+    /// let buffer = VertexBuffer {
+    ///     attributes: vec![AttributeData::Position(vec![vec3(1.0, 2.0, 3.0)])],
+    ///     morph_targets: Vec::new(),
+    ///     morph_default_target: None,
+    ///     outline_buffer_index: None,
+    /// };
+    /// assert_eq!(Some([vec3(1.0, 2.0, 3.0)].as_slice()), buffer.positions());
+    /// assert_eq!(None, buffer.tex_coords(0));
+    /// ```
+    pub fn positions(&self) -> Option<&[Vec3]> {
+        self.attributes.iter().find_map(|a| match a {
+            AttributeData::Position(values) => Some(values.as_slice()),
+            _ => None,
+        })
+    }
+
+    /// The mutable values for [AttributeData::Position] or [None] if not present.
+    pub fn positions_mut(&mut self) -> Option<&mut [Vec3]> {
+        self.attributes.iter_mut().find_map(|a| match a {
+            AttributeData::Position(values) => Some(values.as_mut_slice()),
+            _ => None,
+        })
+    }
+
+    /// The values for [AttributeData::Normal] or [None] if not present.
+    pub fn normals(&self) -> Option<&[Vec4]> {
+        self.attributes.iter().find_map(|a| match a {
+            AttributeData::Normal(values) => Some(values.as_slice()),
+            _ => None,
+        })
+    }
+
+    /// The mutable values for [AttributeData::Normal] or [None] if not present.
+    pub fn normals_mut(&mut self) -> Option<&mut [Vec4]> {
+        self.attributes.iter_mut().find_map(|a| match a {
+            AttributeData::Normal(values) => Some(values.as_mut_slice()),
+            _ => None,
+        })
+    }
+
+    /// The values for [AttributeData::Tangent] or [None] if not present.
+    pub fn tangents(&self) -> Option<&[Vec4]> {
+        self.attributes.iter().find_map(|a| match a {
+            AttributeData::Tangent(values) => Some(values.as_slice()),
+            _ => None,
+        })
+    }
+
+    /// The mutable values for [AttributeData::Tangent] or [None] if not present.
+    pub fn tangents_mut(&mut self) -> Option<&mut [Vec4]> {
+        self.attributes.iter_mut().find_map(|a| match a {
+            AttributeData::Tangent(values) => Some(values.as_mut_slice()),
+            _ => None,
+        })
+    }
+
+    /// The values for the texture coordinates at `index` or [None] if not present.
+    /// An `index` of `0` returns [AttributeData::TexCoord0], `1` returns [AttributeData::TexCoord1], and so on
+    /// up to [AttributeData::TexCoord8].
+    pub fn tex_coords(&self, index: usize) -> Option<&[Vec2]> {
+        self.attributes.iter().find_map(|a| match (index, a) {
+            (0, AttributeData::TexCoord0(values)) => Some(values.as_slice()),
+            (1, AttributeData::TexCoord1(values)) => Some(values.as_slice()),
+            (2, AttributeData::TexCoord2(values)) => Some(values.as_slice()),
+            (3, AttributeData::TexCoord3(values)) => Some(values.as_slice()),
+            (4, AttributeData::TexCoord4(values)) => Some(values.as_slice()),
+            (5, AttributeData::TexCoord5(values)) => Some(values.as_slice()),
+            (6, AttributeData::TexCoord6(values)) => Some(values.as_slice()),
+            (7, AttributeData::TexCoord7(values)) => Some(values.as_slice()),
+            (8, AttributeData::TexCoord8(values)) => Some(values.as_slice()),
+            _ => None,
+        })
+    }
+
+    /// The mutable values for the texture coordinates at `index` or [None] if not present.
+    /// See [tex_coords](VertexBuffer::tex_coords).
+    pub fn tex_coords_mut(&mut self, index: usize) -> Option<&mut [Vec2]> {
+        self.attributes.iter_mut().find_map(|a| match (index, a) {
+            (0, AttributeData::TexCoord0(values)) => Some(values.as_mut_slice()),
+            (1, AttributeData::TexCoord1(values)) => Some(values.as_mut_slice()),
+            (2, AttributeData::TexCoord2(values)) => Some(values.as_mut_slice()),
+            (3, AttributeData::TexCoord3(values)) => Some(values.as_mut_slice()),
+            (4, AttributeData::TexCoord4(values)) => Some(values.as_mut_slice()),
+            (5, AttributeData::TexCoord5(values)) => Some(values.as_mut_slice()),
+            (6, AttributeData::TexCoord6(values)) => Some(values.as_mut_slice()),
+            (7, AttributeData::TexCoord7(values)) => Some(values.as_mut_slice()),
+            (8, AttributeData::TexCoord8(values)) => Some(values.as_mut_slice()),
+            _ => None,
+        })
+    }
+
+    /// The values for [AttributeData::VertexColor] or [None] if not present.
+    pub fn vertex_colors(&self) -> Option<&[Vec4]> {
+        self.attributes.iter().find_map(|a| match a {
+            AttributeData::VertexColor(values) => Some(values.as_slice()),
+            _ => None,
+        })
+    }
+
+    /// The mutable values for [AttributeData::VertexColor] or [None] if not present.
+    pub fn vertex_colors_mut(&mut self) -> Option<&mut [Vec4]> {
+        self.attributes.iter_mut().find_map(|a| match a {
+            AttributeData::VertexColor(values) => Some(values.as_mut_slice()),
+            _ => None,
+        })
+    }
+
+    /// The values for [AttributeData::Blend] or [None] if not present.
+    pub fn blend_weights(&self) -> Option<&[Vec4]> {
+        self.attributes.iter().find_map(|a| match a {
+            AttributeData::Blend(values) => Some(values.as_slice()),
+            _ => None,
+        })
+    }
+
+    /// Remove morph targets with the same deltas and vertex indices as an earlier target.
+    /// Returns the new [morph_controller_index](struct.MorphTarget.html#structfield.morph_controller_index)
+    /// for the controller of each removed target.
+    ///
+    /// Targets for different controllers with identical data are also collapsed,
+    /// so the removed controllers should be remapped using the returned indices
+    /// when applying morph controller weights or animations.
+    /// See [ModelBuffers::to_vertex_data] for sharing data without removing any targets.
+    pub fn deduplicate_morph_targets(&mut self) -> BTreeMap<usize, usize> {
+        let mut controller_indices = BTreeMap::new();
+
+        let mut morph_targets: Vec<MorphTarget> = Vec::new();
+        for target in std::mem::take(&mut self.morph_targets) {
+            match morph_targets.iter().find(|t| t.has_same_data(&target)) {
+                Some(existing) => {
+                    if existing.morph_controller_index != target.morph_controller_index {
+                        controller_indices.insert(
+                            target.morph_controller_index,
+                            existing.morph_controller_index,
+                        );
+                    }
+                }
+                None => morph_targets.push(target),
+            }
+        }
+        self.morph_targets = morph_targets;
+
+        controller_indices
+    }
+
+    /// Replace or add the [AttributeData::Normal] using [generate_smooth_normals]
+    /// with the [AttributeData::Position] values and triangle list `indices`.
+    ///
+    /// This does nothing if the buffer has no positions.
+    pub fn generate_normals(&mut self, indices: &[u16]) {
+        let normals = self
+            .positions()
+            .map(|positions| generate_smooth_normals(positions, indices));
+
+        if let Some(normals) = normals {
+            match self
+                .attributes
+                .iter_mut()
+                .find(|a| matches!(a, AttributeData::Normal(_)))
+            {
+                Some(attribute) => *attribute = AttributeData::Normal(normals),
+                None => self.attributes.push(AttributeData::Normal(normals)),
+            }
+        }
+    }
+
+    /// Replace or add the [AttributeData::Tangent] using [generate_tangents_split]
+    /// with the [AttributeData::Position], [AttributeData::Normal], and [AttributeData::TexCoord0] values.
+    /// Returns the updated triangle list indices.
+    ///
+    /// Vertices along mirrored UV seams are duplicated,
+    /// so the vertex count may increase after calling this method.
+    /// All attributes, morph targets, and the outline buffer from `outline_buffers`
+    /// are updated to include the duplicated vertices.
+    pub fn generate_tangents_split(
+        &mut self,
+        indices: &[u16],
+        outline_buffers: &mut [OutlineBuffer],
+    ) -> Result<Vec<u16>, GenerateTangentsError> {
+        let positions = self
+            .positions()
+            .ok_or(GenerateTangentsError::MissingAttribute("Position"))?;
+        let normals = self
+            .normals()
+            .ok_or(GenerateTangentsError::MissingAttribute("Normal"))?;
+        let uvs = self
+            .tex_coords(0)
+            .ok_or(GenerateTangentsError::MissingAttribute("TexCoord0"))?;
+
+        let vertex_count = positions.len();
+        let split = generate_tangents_split(positions, normals, uvs, indices)?;
+
+        self.attributes = self
+            .attributes
+            .iter()
+            .filter(|a| !matches!(a, AttributeData::Tangent(_)))
+            .map(|a| a.remap(&split.vertex_indices))
+            .collect();
+        self.attributes.push(AttributeData::Tangent(split.tangents));
+
+        if let Some(outline_buffer) = self
+            .outline_buffer_index
+            .and_then(|i| outline_buffers.get_mut(i))
+        {
+            outline_buffer.attributes = outline_buffer
+                .attributes
+                .iter()
+                .map(|a| a.remap(&split.vertex_indices))
+                .collect();
+        }
+
+        // Morph targets only store affected vertices, so add any duplicated vertices.
+        let mut duplicates = vec![Vec::new(); vertex_count];
+        for (new_index, index) in split.vertex_indices.iter().enumerate().skip(vertex_count) {
+            duplicates[*index].push(new_index as u32);
+        }
+        for target in &mut self.morph_targets {
+            for i in 0..target.vertex_indices.len() {
+                for new_index in &duplicates[target.vertex_indices[i] as usize] {
+                    target.position_deltas.push(target.position_deltas[i]);
+                    target.normal_deltas.push(target.normal_deltas[i]);
+                    target.tangent_deltas.push(target.tangent_deltas[i]);
+                    target.vertex_indices.push(*new_index);
+                }
+            }
+        }
+
+        Ok(split.indices)
+    }
+
+    /// Apply `transform` to the positions, normals, and tangents
+    /// including any morph target deltas.
+    ///
+    /// Normals use the inverse transpose of `transform` to correctly handle non uniform scale.
+    /// The sign in the W component of normals and tangents is preserved.
+    /// This can be used to bake instance transforms or convert between coordinate systems.
+    pub fn transform(&mut self, transform: Mat4) {
+        // Normals need the inverse transpose to handle non uniform scale.
+        let normal_transform = transform.inverse().transpose();
+
+        let transform_normal = |n: Vec4| {
+            normal_transform
+                .transform_vector3(n.truncate())
+                .normalize_or_zero()
+                .extend(n.w)
+        };
+        let transform_tangent = |t: Vec4| {
+            transform
+                .transform_vector3(t.truncate())
+                .normalize_or_zero()
+                .extend(t.w)
+        };
+
+        for attribute in &mut self.attributes {
+            match attribute {
+                AttributeData::Position(values) => {
+                    for v in values {
+                        *v = transform.transform_point3(*v);
+                    }
+                }
+                AttributeData::Normal(values) => {
+                    for v in values {
+                        *v = transform_normal(*v);
+                    }
+                }
+                AttributeData::Tangent(values) => {
+                    for v in values {
+                        *v = transform_tangent(*v);
+                    }
+                }
+                _ => (),
+            }
+        }
+
+        // Deltas are offsets, so don't apply the translation.
+        for target in &mut self.morph_targets {
+            for v in &mut target.position_deltas {
+                *v = transform.transform_vector3(*v);
+            }
+            for v in &mut target.normal_deltas {
+                *v = normal_transform.transform_vector3(v.truncate()).extend(v.w);
+            }
+            for v in &mut target.tangent_deltas {
+                *v = transform.transform_vector3(v.truncate()).extend(v.w);
+            }
+        }
+    }
+
+    /// Create a new buffer with only the vertices referenced by `indices`
+    /// and the indices remapped to the new vertex ordering.
+    /// Triangles with out of range vertex indices are skipped.
+    pub(crate) fn compact(&self, indices: &[u16]) -> (VertexBuffer, IndexBuffer) {
+        let mut new_indices = vec![None; self.vertex_count()];
+        let mut vertex_indices = Vec::new();
+
+        let mut compacted_indices = Vec::with_capacity(indices.len());
+        let mut skipped_count = 0;
+        for face in indices.chunks(3) {
+            if face.iter().any(|i| new_indices.get(*i as usize).is_none()) {
+                skipped_count += 1;
+                continue;
+            }
+
+            for i in face {
+                let new_index = new_indices[*i as usize].get_or_insert_with(|| {
+                    vertex_indices.push(*i as usize);
+                    (vertex_indices.len() - 1) as u16
+                });
+                compacted_indices.push(*new_index);
+            }
+        }
+        if skipped_count > 0 {
+            warn!("Skipping {skipped_count} triangles with out of range vertex indices");
+        }
+
+        // Keep empty targets to preserve the morph controller ordering.
+        let morph_targets = self
+            .morph_targets
+            .iter()
+            .map(|target| {
+                let mut new_target = MorphTarget {
+                    morph_controller_index: target.morph_controller_index,
+                    position_deltas: Vec::new(),
+                    normal_deltas: Vec::new(),
+                    tangent_deltas: Vec::new(),
+                    vertex_indices: Vec::new(),
+                };
+                for (i, vertex_index) in target.vertex_indices.iter().enumerate() {
+                    if let Some(Some(new_index)) = new_indices.get(*vertex_index as usize) {
+                        new_target.position_deltas.push(target.position_deltas[i]);
+                        new_target.normal_deltas.push(target.normal_deltas[i]);
+                        new_target.tangent_deltas.push(target.tangent_deltas[i]);
+                        new_target.vertex_indices.push(*new_index as u32);
+                    }
+                }
+                new_target
+            })
+            .collect();
+
+        (
+            VertexBuffer {
+                attributes: self
+                    .attributes
+                    .iter()
+                    .map(|a| a.remap(&vertex_indices))
+                    .collect(),
+                morph_targets,
+                morph_default_target: None,
+                outline_buffer_index: None,
+            },
+            IndexBuffer {
+                indices: compacted_indices,
+            },
+        )
+    }
+
+    /// Append the vertices from `other` to this buffer and the triangle list `other_indices` to `indices`.
+    /// Returns `false` without modifying anything if the attributes don't match
+    /// or the combined vertices can't be indexed with [u16].
+    ///
+    /// Morph targets and outline buffers are not appended.
+    pub(crate) fn append(
+        &mut self,
+        indices: &mut Vec<u16>,
+        other: &VertexBuffer,
+        other_indices: &[u16],
+    ) -> bool {
+        let same_attributes = self.attributes.len() == other.attributes.len()
+            && self
+                .attributes
+                .iter()
+                .zip(&other.attributes)
+                .all(|(a, b)| std::mem::discriminant(a) == std::mem::discriminant(b));
+
+        let offset = self.vertex_count();
+        if !same_attributes || offset + other.vertex_count() > u16::MAX as usize + 1 {
+            return false;
+        }
+
+        for (a, b) in self.attributes.iter_mut().zip(&other.attributes) {
+            a.extend(b);
+        }
+        indices.extend(other_indices.iter().map(|i| i + offset as u16));
+
+        true
+    }
+}
+
+/// Tangents with vertices duplicated along mirrored UV seams.
+/// See [generate_tangents_split].
+#[derive(Debug, PartialEq, Clone)]
+pub struct SplitTangents {
+    /// The index of the original vertex for each vertex.
+    /// Duplicated vertices are added after the original vertices.
+    pub vertex_indices: Vec<usize>,
+    /// The updated triangle list indices.
+    pub indices: Vec<u16>,
+    /// The tangent for each vertex in [vertex_indices](#structfield.vertex_indices).
+    pub tangents: Vec<Vec4>,
+}
+
+/// Calculate tangents for each vertex from the positions, normals, and UVs
+/// of adjacent faces. The `indices` should be a triangle list.
+///
+/// The W component stores the sign of the bitangent.
+/// Vertices shared by faces with mirrored UVs will have a single averaged tangent,
+/// which can cause shading seams. See [generate_tangents_split].
+pub fn generate_tangents(
+    positions: &[Vec3],
+    normals: &[Vec4],
+    uvs: &[Vec2],
+    indices: &[u16],
+) -> Vec<Vec4> {
+    let mut tangents = vec![Vec3::ZERO; positions.len()];
+    let mut bitangents = vec![Vec3::ZERO; positions.len()];
+
+    for face in indices.chunks_exact(3) {
+        let face = [face[0] as usize, face[1] as usize, face[2] as usize];
+        if let Some((tangent, bitangent)) = face_tangent(positions, uvs, face) {
+            for i in face {
+                tangents[i] += tangent;
+                bitangents[i] += bitangent;
+            }
+        }
+    }
+
+    normals
+        .iter()
+        .zip(tangents)
+        .zip(bitangents)
+        .map(|((n, t), b)| orthonormal_tangent(n.truncate(), t, b))
+        .collect()
+}
+
+/// Calculate tangents like [generate_tangents]
+/// but duplicate vertices shared by faces with mirrored UVs.
+/// Each duplicated vertex has its own tangent with the appropriate sign.
+///
+/// The resulting vertex count will be larger than the original vertex count
+/// if the UVs contain mirrored seams.
+/// Use [SplitTangents::vertex_indices] to update the other vertex attributes.
+/// Returns an error if the duplicated vertices can't be indexed with [u16].
+pub fn generate_tangents_split(
+    positions: &[Vec3],
+    normals: &[Vec4],
+    uvs: &[Vec2],
+    indices: &[u16],
+) -> Result<SplitTangents, GenerateTangentsError> {
+    let mut vertex_indices: Vec<usize> = (0..positions.len()).collect();
+    let mut vertex_signs = vec![None; positions.len()];
+    let mut duplicates = vec![None; positions.len()];
+
+    let mut new_indices = Vec::new();
+    let mut face_tangents = Vec::new();
+
+    for face in indices.chunks_exact(3) {
+        let face = [face[0] as usize, face[1] as usize, face[2] as usize];
+        let tangent_bitangent = face_tangent(positions, uvs, face);
+
+        for i in face {
+            let new_index = match tangent_bitangent {
+                Some((tangent, bitangent)) => {
+                    let face_normal = (positions[face[1]] - positions[face[0]])
+                        .cross(positions[face[2]] - positions[face[0]]);
+                    let sign = tangent_sign(face_normal, tangent, bitangent);
+
+                    // Use a separate vertex for faces with a different tangent sign.
+                    let new_index = match vertex_signs[i] {
+                        Some(vertex_sign) if vertex_sign != sign => *duplicates[i]
+                            .get_or_insert_with(|| {
+                                vertex_indices.push(i);
+                                vertex_indices.len() - 1
+                            }),
+                        _ => {
+                            vertex_signs[i] = Some(sign);
+                            i
+                        }
+                    };
+                    face_tangents.push((new_index, tangent, bitangent));
+                    new_index
+                }
+                None => i,
+            };
+            let new_index =
+                u16::try_from(new_index).map_err(|_| GenerateTangentsError::VertexCount {
+                    vertex_count: new_index + 1,
+                })?;
+            new_indices.push(new_index);
+        }
+    }
+
+    let mut tangents = vec![Vec3::ZERO; vertex_indices.len()];
+    let mut bitangents = vec![Vec3::ZERO; vertex_indices.len()];
+    for (i, tangent, bitangent) in face_tangents {
+        tangents[i] += tangent;
+        bitangents[i] += bitangent;
+    }
+
+    let tangents = vertex_indices
+        .iter()
+        .zip(tangents)
+        .zip(bitangents)
+        .map(|((i, t), b)| orthonormal_tangent(normals[*i].truncate(), t, b))
+        .collect();
+
+    Ok(SplitTangents {
+        vertex_indices,
+        indices: new_indices,
+        tangents,
+    })
+}
+
+fn face_tangent(positions: &[Vec3], uvs: &[Vec2], face: [usize; 3]) -> Option<(Vec3, Vec3)> {
+    let [i0, i1, i2] = face;
+    let (p0, p1, p2) = (positions.get(i0)?, positions.get(i1)?, positions.get(i2)?);
+    let (uv0, uv1, uv2) = (uvs.get(i0)?, uvs.get(i1)?, uvs.get(i2)?);
+
+    let e1 = *p1 - *p0;
+    let e2 = *p2 - *p0;
+    let duv1 = *uv1 - *uv0;
+    let duv2 = *uv2 - *uv0;
+
+    // Skip faces with degenerate UVs.
+    let r = duv1.x * duv2.y - duv2.x * duv1.y;
+    if r.abs() < f32::EPSILON {
+        return None;
+    }
+
+    let tangent = (e1 * duv2.y - e2 * duv1.y) / r;
+    let bitangent = (e2 * duv1.x - e1 * duv2.x) / r;
+    Some((tangent, bitangent))
+}
+
+fn tangent_sign(normal: Vec3, tangent: Vec3, bitangent: Vec3) -> f32 {
+    if normal.cross(tangent).dot(bitangent) < 0.0 {
+        -1.0
+    } else {
+        1.0
+    }
+}
+
+fn orthonormal_tangent(normal: Vec3, tangent: Vec3, bitangent: Vec3) -> Vec4 {
+    // Remove the component along the normal using Gram-Schmidt.
+    let t = (tangent - normal * normal.dot(tangent)).normalize_or_zero();
+    t.extend(tangent_sign(normal, t, bitangent))
+}
+
+/// Calculate smooth normals for each vertex by averaging the normals
+/// of adjacent faces weighted by area.
+/// The `indices` should be a triangle list.
+///
+/// The W component is always `0.0`.
+/// Vertices not used by any face will have a normal of zero.
+pub fn generate_smooth_normals(positions: &[Vec3], indices: &[u16]) -> Vec<Vec4> {
+    let mut normals = vec![Vec3::ZERO; positions.len()];
+
+    for face in indices.chunks_exact(3) {
+        let [i0, i1, i2] = [face[0] as usize, face[1] as usize, face[2] as usize];
+        if let (Some(p0), Some(p1), Some(p2)) =
+            (positions.get(i0), positions.get(i1), positions.get(i2))
+        {
+            // The length of the cross product is proportional to the face area.
+            let normal = (*p1 - *p0).cross(*p2 - *p0);
+            normals[i0] += normal;
+            normals[i1] += normal;
+            normals[i2] += normal;
+        }
+    }
+
+    let isolated_count = normals.iter().filter(|n| **n == Vec3::ZERO).count();
+    if isolated_count > 0 {
+        warn!("{isolated_count} vertices have no adjacent faces and will use a zero normal");
+    }
+
+    normals
+        .into_iter()
+        .map(|n| n.normalize_or_zero().extend(0.0))
+        .collect()
+}
+
+/// Calculate per vertex ambient occlusion for the triangle list `indices`
+/// by casting `sample_count` rays over the hemisphere around each vertex normal.
+///
+/// Values range from `0.0` for fully occluded to `1.0` for fully exposed vertices.
+/// Only the triangles in `indices` are considered as occluders.
+/// Vertices with a zero normal are treated as fully exposed.
+pub fn bake_vertex_ao(
+    positions: &[Vec3],
+    normals: &[Vec4],
+    indices: &[u16],
+    sample_count: usize,
+) -> Vec<f32> {
+    let triangles: Vec<[Vec3; 3]> = indices
+        .chunks_exact(3)
+        .filter_map(|face| {
+            Some([
+                *positions.get(face[0] as usize)?,
+                *positions.get(face[1] as usize)?,
+                *positions.get(face[2] as usize)?,
+            ])
+        })
+        .collect();
+
+    let directions = hemisphere_directions(sample_count);
+
+    // Each ray is tested against every triangle, so process vertices in parallel.
+    positions
+        .par_iter()
+        .enumerate()
+        .map(|(i, position)| {
+            let normal = normals
+                .get(i)
+                .map(|n| n.truncate().normalize_or_zero())
+                .unwrap_or_default();
+            if normal == Vec3::ZERO || directions.is_empty() {
+                return 1.0;
+            }
+
+            // Offset the origin slightly to avoid hitting the vertex's own faces.
+            let origin = *position + normal * 1e-4;
+            let (tangent, bitangent) = normal.any_orthonormal_pair();
+
+            let hit_count = directions
+                .iter()
+                .filter(|d| {
+                    let direction = tangent * d.x + bitangent * d.y + normal * d.z;
+                    triangles
+                        .iter()
+                        .any(|t| ray_intersects_triangle(origin, direction, t))
+                })
+                .count();
+
+            1.0 - hit_count as f32 / directions.len() as f32
+        })
+        .collect()
+}
+
+fn hemisphere_directions(sample_count: usize) -> Vec<Vec3> {
+    // Cosine weighted directions around +Z using a Fibonacci spiral.
+    // This avoids randomness so the results are deterministic.
+    let golden_angle = std::f32::consts::PI * (3.0 - 5.0f32.sqrt());
+    (0..sample_count)
+        .map(|i| {
+            let u = (i as f32 + 0.5) / sample_count as f32;
+            let r = u.sqrt();
+            let phi = i as f32 * golden_angle;
+            Vec3::new(r * phi.cos(), r * phi.sin(), (1.0 - u).sqrt())
+        })
+        .collect()
+}
+
+fn ray_intersects_triangle(origin: Vec3, direction: Vec3, [p0, p1, p2]: &[Vec3; 3]) -> bool {
+    // Möller–Trumbore intersection without backface culling.
+    let e1 = *p1 - *p0;
+    let e2 = *p2 - *p0;
+    let h = direction.cross(e2);
+    let a = e1.dot(h);
+    if a.abs() < 1e-8 {
+        return false;
+    }
+
+    let f = 1.0 / a;
+    let s = origin - *p0;
+    let u = f * s.dot(h);
+    if !(0.0..=1.0).contains(&u) {
+        return false;
+    }
+
+    let q = s.cross(e1);
+    let v = f * direction.dot(q);
+    if v < 0.0 || u + v > 1.0 {
+        return false;
+    }
+
+    f * e2.dot(q) > 1e-5
 }
 
 // TODO: Add an option to convert a collection of these to the vertex above?
@@ -106,6 +934,7 @@ impl VertexBuffer {
 // TODO: Link to appropriate xc3_lib types and fields.
 /// Per vertex values for a vertex attribute.
 #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, PartialEq, Clone)]
 pub enum AttributeData {
     /// Data for [DataType::Position].
@@ -148,16 +977,79 @@ pub enum AttributeData {
     VertexColor(#[cfg_attr(feature = "arbitrary", arbitrary(with = arbitrary_vec4s))] Vec<Vec4>),
 
     /// Data for [DataType::Blend].
+    ///
+    /// The channels control blending between colors or textures for some map shaders.
+    /// The meaning of each channel depends on the shader.
+    /// This is exported to glTF as the custom `_Blend` attribute.
     Blend(#[cfg_attr(feature = "arbitrary", arbitrary(with = arbitrary_vec4s))] Vec<Vec4>),
 
     /// Data for [DataType::WeightIndex].
     WeightIndex(Vec<[u16; 2]>),
 
+    /// Data for [DataType::WeightIndex2].
+    ///
+    /// The meaning of these values is not known yet,
+    /// so they are preserved for writing but not used for skinning.
+    /// This is not a second set of skin influences and does not extend
+    /// [AttributeData::WeightIndex] to more than 4 influences per vertex.
+    WeightIndex2(Vec<[u16; 2]>),
+
     /// Data for [DataType::SkinWeights].
     SkinWeights(#[cfg_attr(feature = "arbitrary", arbitrary(with = arbitrary_vec4s))] Vec<Vec4>),
 
     /// Data for [DataType::BoneIndices].
     BoneIndices(Vec<[u8; 4]>),
+
+    /// Unorm8x4 data for the first attribute in outline buffers with 8 bytes per vertex.
+    /// The second attribute uses the same layout as [AttributeData::VertexColor] for 4 byte outline buffers.
+    // TODO: What is the in game name of this attribute?
+    OutlineUnk(#[cfg_attr(feature = "arbitrary", arbitrary(with = arbitrary_vec4s))] Vec<Vec4>),
+
+    /// The raw bytes for each vertex for data types that are not yet supported.
+    /// This preserves the original data when converting back to xc3_lib types.
+    Unknown {
+        data_type: DataType,
+        bytes: Vec<Vec<u8>>,
+    },
+}
+
+fn first_difference<T>(a: &[T], b: &[T], eq: impl Fn(&T, &T) -> bool) -> Option<usize> {
+    a.iter()
+        .zip(b)
+        .position(|(a, b)| !eq(a, b))
+        .or_else(|| (a.len() != b.len()).then_some(a.len().min(b.len())))
+}
+
+/// The per vertex binary layout of an [AttributeData] in the vertex buffer.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct AttributeFormat {
+    /// The number of components like `3` for XYZ values.
+    pub component_count: usize,
+    pub component_type: ComponentType,
+    /// `true` if integer components map to the range `0.0` to `1.0` for unsigned types
+    /// or `-1.0` to `1.0` for signed types.
+    pub normalized: bool,
+}
+
+/// The scalar type for each component of an [AttributeFormat].
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum ComponentType {
+    U8,
+    I8,
+    U16,
+    F32,
+}
+
+impl AttributeFormat {
+    /// The size in bytes for a single vertex.
+    pub fn size_in_bytes(&self) -> usize {
+        let component_size = match self.component_type {
+            ComponentType::U8 | ComponentType::I8 => 1,
+            ComponentType::U16 => 2,
+            ComponentType::F32 => 4,
+        };
+        self.component_count * component_size
+    }
 }
 
 impl AttributeData {
@@ -178,8 +1070,11 @@ impl AttributeData {
             AttributeData::VertexColor(v) => v.len(),
             AttributeData::Blend(v) => v.len(),
             AttributeData::WeightIndex(v) => v.len(),
+            AttributeData::WeightIndex2(v) => v.len(),
             AttributeData::SkinWeights(v) => v.len(),
             AttributeData::BoneIndices(v) => v.len(),
+            AttributeData::OutlineUnk(v) => v.len(),
+            AttributeData::Unknown { bytes, .. } => bytes.len(),
         }
     }
 
@@ -187,6 +1082,174 @@ impl AttributeData {
         self.len() == 0
     }
 
+    /// The index of the first vertex with a different value in `other`
+    /// or `None` if all values are equal.
+    /// Float components are considered equal if their difference is at most `epsilon`.
+    ///
+    /// Attributes with different types always differ at the first vertex.
+    /// Attributes with different lengths differ at the end of the shorter attribute.
+    pub fn first_difference(&self, other: &Self, epsilon: f32) -> Option<usize> {
+        match (self, other) {
+            (AttributeData::Position(a), AttributeData::Position(b)) => {
+                first_difference(a, b, |a, b| a.abs_diff_eq(*b, epsilon))
+            }
+            (AttributeData::Normal(a), AttributeData::Normal(b))
+            | (AttributeData::Tangent(a), AttributeData::Tangent(b))
+            | (AttributeData::VertexColor(a), AttributeData::VertexColor(b))
+            | (AttributeData::Blend(a), AttributeData::Blend(b))
+            | (AttributeData::SkinWeights(a), AttributeData::SkinWeights(b))
+            | (AttributeData::OutlineUnk(a), AttributeData::OutlineUnk(b)) => {
+                first_difference(a, b, |a, b| a.abs_diff_eq(*b, epsilon))
+            }
+            (AttributeData::TexCoord0(a), AttributeData::TexCoord0(b))
+            | (AttributeData::TexCoord1(a), AttributeData::TexCoord1(b))
+            | (AttributeData::TexCoord2(a), AttributeData::TexCoord2(b))
+            | (AttributeData::TexCoord3(a), AttributeData::TexCoord3(b))
+            | (AttributeData::TexCoord4(a), AttributeData::TexCoord4(b))
+            | (AttributeData::TexCoord5(a), AttributeData::TexCoord5(b))
+            | (AttributeData::TexCoord6(a), AttributeData::TexCoord6(b))
+            | (AttributeData::TexCoord7(a), AttributeData::TexCoord7(b))
+            | (AttributeData::TexCoord8(a), AttributeData::TexCoord8(b)) => {
+                first_difference(a, b, |a, b| a.abs_diff_eq(*b, epsilon))
+            }
+            (AttributeData::WeightIndex(a), AttributeData::WeightIndex(b))
+            | (AttributeData::WeightIndex2(a), AttributeData::WeightIndex2(b)) => {
+                first_difference(a, b, |a, b| a == b)
+            }
+            (AttributeData::BoneIndices(a), AttributeData::BoneIndices(b)) => {
+                first_difference(a, b, |a, b| a == b)
+            }
+            (
+                AttributeData::Unknown {
+                    data_type: a_type,
+                    bytes: a,
+                },
+                AttributeData::Unknown {
+                    data_type: b_type,
+                    bytes: b,
+                },
+            ) if a_type == b_type => first_difference(a, b, |a, b| a == b),
+            _ => Some(0),
+        }
+    }
+
+    /// The binary layout used when writing this attribute to a vertex buffer.
+    ///
+    /// This describes the encoded data in the game's vertex buffers.
+    /// Renderers like xc3_wgpu use the decoded values with their own vertex layouts instead.
+    pub fn format(&self) -> AttributeFormat {
+        let (component_count, component_type, normalized) = match self {
+            AttributeData::Position(_) => (3, ComponentType::F32, false),
+            AttributeData::Normal(_) => (4, ComponentType::I8, true),
+            AttributeData::Tangent(_) => (4, ComponentType::I8, true),
+            AttributeData::TexCoord0(_) => (2, ComponentType::F32, false),
+            AttributeData::TexCoord1(_) => (2, ComponentType::F32, false),
+            AttributeData::TexCoord2(_) => (2, ComponentType::F32, false),
+            AttributeData::TexCoord3(_) => (2, ComponentType::F32, false),
+            AttributeData::TexCoord4(_) => (2, ComponentType::F32, false),
+            AttributeData::TexCoord5(_) => (2, ComponentType::F32, false),
+            AttributeData::TexCoord6(_) => (2, ComponentType::F32, false),
+            AttributeData::TexCoord7(_) => (2, ComponentType::F32, false),
+            AttributeData::TexCoord8(_) => (2, ComponentType::F32, false),
+            AttributeData::VertexColor(_) => (4, ComponentType::U8, true),
+            AttributeData::Blend(_) => (4, ComponentType::U8, true),
+            AttributeData::WeightIndex(_) => (2, ComponentType::U16, false),
+            AttributeData::WeightIndex2(_) => (2, ComponentType::U16, false),
+            AttributeData::SkinWeights(_) => (4, ComponentType::U16, true),
+            AttributeData::BoneIndices(_) => (4, ComponentType::U8, false),
+            AttributeData::OutlineUnk(_) => (4, ComponentType::U8, true),
+            AttributeData::Unknown { bytes, .. } => (
+                bytes.first().map(|b| b.len()).unwrap_or_default(),
+                ComponentType::U8,
+                false,
+            ),
+        };
+        AttributeFormat {
+            component_count,
+            component_type,
+            normalized,
+        }
+    }
+
+    /// Append the values from `other` if both attributes are the same variant.
+    fn extend(&mut self, other: &Self) {
+        match (self, other) {
+            (AttributeData::Position(a), AttributeData::Position(b)) => a.extend_from_slice(b),
+            (AttributeData::Normal(a), AttributeData::Normal(b)) => a.extend_from_slice(b),
+            (AttributeData::Tangent(a), AttributeData::Tangent(b)) => a.extend_from_slice(b),
+            (AttributeData::TexCoord0(a), AttributeData::TexCoord0(b)) => a.extend_from_slice(b),
+            (AttributeData::TexCoord1(a), AttributeData::TexCoord1(b)) => a.extend_from_slice(b),
+            (AttributeData::TexCoord2(a), AttributeData::TexCoord2(b)) => a.extend_from_slice(b),
+            (AttributeData::TexCoord3(a), AttributeData::TexCoord3(b)) => a.extend_from_slice(b),
+            (AttributeData::TexCoord4(a), AttributeData::TexCoord4(b)) => a.extend_from_slice(b),
+            (AttributeData::TexCoord5(a), AttributeData::TexCoord5(b)) => a.extend_from_slice(b),
+            (AttributeData::TexCoord6(a), AttributeData::TexCoord6(b)) => a.extend_from_slice(b),
+            (AttributeData::TexCoord7(a), AttributeData::TexCoord7(b)) => a.extend_from_slice(b),
+            (AttributeData::TexCoord8(a), AttributeData::TexCoord8(b)) => a.extend_from_slice(b),
+            (AttributeData::VertexColor(a), AttributeData::VertexColor(b)) => {
+                a.extend_from_slice(b)
+            }
+            (AttributeData::Blend(a), AttributeData::Blend(b)) => a.extend_from_slice(b),
+            (AttributeData::WeightIndex(a), AttributeData::WeightIndex(b)) => {
+                a.extend_from_slice(b)
+            }
+            (AttributeData::WeightIndex2(a), AttributeData::WeightIndex2(b)) => {
+                a.extend_from_slice(b)
+            }
+            (AttributeData::SkinWeights(a), AttributeData::SkinWeights(b)) => {
+                a.extend_from_slice(b)
+            }
+            (AttributeData::BoneIndices(a), AttributeData::BoneIndices(b)) => {
+                a.extend_from_slice(b)
+            }
+            (AttributeData::OutlineUnk(a), AttributeData::OutlineUnk(b)) => a.extend_from_slice(b),
+            (
+                AttributeData::Unknown {
+                    data_type: a_type,
+                    bytes: a,
+                },
+                AttributeData::Unknown {
+                    data_type: b_type,
+                    bytes: b,
+                },
+            ) if a_type == b_type => a.extend_from_slice(b),
+            _ => (),
+        }
+    }
+
+    /// Create a new attribute with the value at each index in `vertex_indices`.
+    fn remap(&self, vertex_indices: &[usize]) -> Self {
+        fn remap<T: Copy>(values: &[T], vertex_indices: &[usize]) -> Vec<T> {
+            vertex_indices.iter().map(|i| values[*i]).collect()
+        }
+
+        match self {
+            AttributeData::Position(v) => AttributeData::Position(remap(v, vertex_indices)),
+            AttributeData::Normal(v) => AttributeData::Normal(remap(v, vertex_indices)),
+            AttributeData::Tangent(v) => AttributeData::Tangent(remap(v, vertex_indices)),
+            AttributeData::TexCoord0(v) => AttributeData::TexCoord0(remap(v, vertex_indices)),
+            AttributeData::TexCoord1(v) => AttributeData::TexCoord1(remap(v, vertex_indices)),
+            AttributeData::TexCoord2(v) => AttributeData::TexCoord2(remap(v, vertex_indices)),
+            AttributeData::TexCoord3(v) => AttributeData::TexCoord3(remap(v, vertex_indices)),
+            AttributeData::TexCoord4(v) => AttributeData::TexCoord4(remap(v, vertex_indices)),
+            AttributeData::TexCoord5(v) => AttributeData::TexCoord5(remap(v, vertex_indices)),
+            AttributeData::TexCoord6(v) => AttributeData::TexCoord6(remap(v, vertex_indices)),
+            AttributeData::TexCoord7(v) => AttributeData::TexCoord7(remap(v, vertex_indices)),
+            AttributeData::TexCoord8(v) => AttributeData::TexCoord8(remap(v, vertex_indices)),
+            AttributeData::VertexColor(v) => AttributeData::VertexColor(remap(v, vertex_indices)),
+            AttributeData::Blend(v) => AttributeData::Blend(remap(v, vertex_indices)),
+            AttributeData::WeightIndex(v) => AttributeData::WeightIndex(remap(v, vertex_indices)),
+            AttributeData::WeightIndex2(v) => AttributeData::WeightIndex2(remap(v, vertex_indices)),
+            AttributeData::SkinWeights(v) => AttributeData::SkinWeights(remap(v, vertex_indices)),
+            AttributeData::BoneIndices(v) => AttributeData::BoneIndices(remap(v, vertex_indices)),
+            AttributeData::OutlineUnk(v) => AttributeData::OutlineUnk(remap(v, vertex_indices)),
+            AttributeData::Unknown { data_type, bytes } => AttributeData::Unknown {
+                data_type: *data_type,
+                bytes: vertex_indices.iter().map(|i| bytes[*i].clone()).collect(),
+            },
+        }
+    }
+
     fn write<W: Write + Seek>(
         &self,
         writer: &mut W,
@@ -240,12 +1303,23 @@ impl AttributeData {
             AttributeData::WeightIndex(values) => {
                 write_data(writer, values, offset, stride, endian, write_u16x2)
             }
+            AttributeData::WeightIndex2(values) => {
+                write_data(writer, values, offset, stride, endian, write_u16x2)
+            }
             AttributeData::SkinWeights(values) => {
                 write_data(writer, values, offset, stride, endian, write_unorm16x4)
             }
             AttributeData::BoneIndices(values) => {
                 write_data(writer, values, offset, stride, endian, write_u8x4)
             }
+            AttributeData::OutlineUnk(values) => {
+                write_data(writer, values, offset, stride, endian, write_unorm8x4)
+            }
+            AttributeData::Unknown { bytes, .. } => {
+                write_data(writer, bytes, offset, stride, endian, |w, b, _| {
+                    w.write_all(b).map_err(Into::into)
+                })
+            }
         }
     }
 }
@@ -313,6 +1387,10 @@ impl From<&AttributeData> for xc3_lib::vertex::VertexAttribute {
                 data_type: DataType::WeightIndex,
                 data_size: 4,
             },
+            AttributeData::WeightIndex2(_) => xc3_lib::vertex::VertexAttribute {
+                data_type: DataType::WeightIndex2,
+                data_size: 4,
+            },
             AttributeData::SkinWeights(_) => xc3_lib::vertex::VertexAttribute {
                 data_type: DataType::SkinWeights,
                 data_size: 8,
@@ -321,6 +1399,15 @@ impl From<&AttributeData> for xc3_lib::vertex::VertexAttribute {
                 data_type: DataType::BoneIndices,
                 data_size: 4,
             },
+            // Outline buffers don't store attribute types.
+            AttributeData::OutlineUnk(_) => xc3_lib::vertex::VertexAttribute {
+                data_type: DataType::VertexColor,
+                data_size: 4,
+            },
+            AttributeData::Unknown { data_type, bytes } => xc3_lib::vertex::VertexAttribute {
+                data_type: *data_type,
+                data_size: bytes.first().map(|b| b.len()).unwrap_or_default() as u16,
+            },
         }
     }
 }
@@ -328,6 +1415,7 @@ impl From<&AttributeData> for xc3_lib::vertex::VertexAttribute {
 fn read_vertex_buffers(
     vertex_data: &VertexData,
     skinning: Option<&xc3_lib::mxmd::Skinning>,
+    endian: Endian,
 ) -> BinResult<(Vec<VertexBuffer>, Option<Weights>)> {
     // TODO: This skips the weights buffer since it doesn't have ext info?
     // TODO: Save the weights buffer for converting back to xc3_lib types?
@@ -337,12 +1425,12 @@ fn read_vertex_buffers(
         .iter()
         .zip(vertex_data.vertex_buffer_info.iter())
         .map(|(descriptor, ext)| {
-            let attributes =
-                read_vertex_attributes(descriptor, &vertex_data.buffer, Endian::Little);
+            let attributes = read_vertex_attributes(descriptor, &vertex_data.buffer, endian);
 
             VertexBuffer {
                 attributes,
                 morph_targets: Vec::new(),
+                morph_default_target: None,
                 outline_buffer_index: ext
                     .flags
                     .has_outline_buffer()
@@ -354,7 +1442,7 @@ fn read_vertex_buffers(
     // TODO: Get names from the mxmd?
     // TODO: Add better tests for morph target data.
     if let Some(vertex_morphs) = &vertex_data.vertex_morphs {
-        assign_morph_targets(vertex_morphs, &mut buffers, vertex_data)?;
+        assign_morph_targets(vertex_morphs, &mut buffers, vertex_data, endian)?;
     }
 
     // TODO: Is this the best place to do this?
@@ -363,7 +1451,7 @@ fn read_vertex_buffers(
         let weights_index = vertex_weights.vertex_buffer_index as usize;
 
         let descriptor = vertex_data.vertex_buffers.get(weights_index)?;
-        let attributes = read_vertex_attributes(descriptor, &vertex_data.buffer, Endian::Little);
+        let attributes = read_vertex_attributes(descriptor, &vertex_data.buffer, endian);
 
         let (weights, bone_indices) = skin_weights_bone_indices(&attributes)?;
 
@@ -384,10 +1472,14 @@ fn read_vertex_buffers(
     Ok((buffers, skin_weights))
 }
 
-fn outline_buffer(descriptor: &OutlineBufferDescriptor, buffer: &[u8]) -> BinResult<OutlineBuffer> {
+fn outline_buffer(
+    descriptor: &OutlineBufferDescriptor,
+    buffer: &[u8],
+    endian: Endian,
+) -> BinResult<OutlineBuffer> {
     // TODO: This fails for legacy files like xc2 oj108004?
     Ok(OutlineBuffer {
-        attributes: read_outline_buffer(descriptor, buffer)?,
+        attributes: read_outline_buffer(descriptor, buffer, endian)?,
     })
 }
 
@@ -395,21 +1487,31 @@ fn assign_morph_targets(
     vertex_morphs: &xc3_lib::vertex::VertexMorphs,
     buffers: &mut [VertexBuffer],
     vertex_data: &VertexData,
+    endian: Endian,
 ) -> BinResult<()> {
     // TODO: Find a cleaner way to write this.
     for descriptor in &vertex_morphs.descriptors {
         if let Some(buffer) = buffers.get_mut(descriptor.vertex_buffer_index as usize) {
-            if let Some((blend, _default, params)) = split_targets(descriptor, vertex_morphs) {
-                let base = read_morph_blend_target(blend, &vertex_data.buffer)?;
+            if let Some((blend, default, params)) = split_targets(descriptor, vertex_morphs) {
+                let base = read_morph_blend_target(blend, &vertex_data.buffer, endian)?;
 
                 // TODO: What to do with the default target?
+                // Preserve the original bytes to allow writing the data unmodified.
+                let start = default.data_offset as usize;
+                let size = default.vertex_count as usize * default.vertex_size as usize;
+                buffer.morph_default_target = vertex_data
+                    .buffer
+                    .get(start..start + size)
+                    .map(|bytes| bytes.to_vec());
+
                 buffer.morph_targets = params
                     .iter()
                     .zip(descriptor.param_indices.iter())
                     .map(|(target, param_index)| {
                         // Apply remaining targets onto the base target values.
                         // TODO: Lots of morph targets use the exact same bytes?
-                        let vertices = read_morph_buffer_target(target, &vertex_data.buffer)?;
+                        let vertices =
+                            read_morph_buffer_target(target, &vertex_data.buffer, endian)?;
 
                         let mut position_deltas = Vec::new();
                         let mut normal_deltas = Vec::new();
@@ -552,7 +1654,9 @@ fn read_attribute(
         DataType::WeightIndex => Some(AttributeData::WeightIndex(
             read_data(d, relative_offset, buffer, endian, read_u16x2).ok()?,
         )),
-        DataType::WeightIndex2 => None,
+        DataType::WeightIndex2 => Some(AttributeData::WeightIndex2(
+            read_data(d, relative_offset, buffer, endian, read_u16x2).ok()?,
+        )),
         DataType::TexCoord0 => Some(AttributeData::TexCoord0(
             read_data(d, relative_offset, buffer, endian, read_f32x2).ok()?,
         )),
@@ -583,43 +1687,66 @@ fn read_attribute(
         DataType::Blend => Some(AttributeData::Blend(
             read_data(d, relative_offset, buffer, endian, read_unorm8x4).ok()?,
         )),
-        DataType::Unk15 => None,
-        DataType::Unk16 => None,
+        DataType::Unk15 => read_unknown_attribute(a, d, relative_offset, buffer, endian),
+        DataType::Unk16 => read_unknown_attribute(a, d, relative_offset, buffer, endian),
         DataType::VertexColor => Some(AttributeData::VertexColor(
             read_data(d, relative_offset, buffer, endian, read_unorm8x4).ok()?,
         )),
-        DataType::Unk18 => None,
-        DataType::Unk24 => None,
-        DataType::Unk25 => None,
-        DataType::Unk26 => None,
+        DataType::Unk18 => read_unknown_attribute(a, d, relative_offset, buffer, endian),
+        DataType::Unk24 => read_unknown_attribute(a, d, relative_offset, buffer, endian),
+        DataType::Unk25 => read_unknown_attribute(a, d, relative_offset, buffer, endian),
+        DataType::Unk26 => read_unknown_attribute(a, d, relative_offset, buffer, endian),
         DataType::Normal => Some(AttributeData::Normal(
             read_data(d, relative_offset, buffer, endian, read_snorm8x4).ok()?,
         )),
         DataType::Tangent => Some(AttributeData::Tangent(
             read_data(d, relative_offset, buffer, endian, read_snorm8x4).ok()?,
         )),
-        DataType::Unk30 => None,
-        DataType::Unk31 => None,
+        DataType::Unk30 => read_unknown_attribute(a, d, relative_offset, buffer, endian),
+        DataType::Unk31 => read_unknown_attribute(a, d, relative_offset, buffer, endian),
         DataType::Normal2 => Some(AttributeData::Normal(
             read_data(d, relative_offset, buffer, endian, read_snorm8x4).ok()?,
         )),
-        DataType::Unk33 => None,
-        DataType::Normal3 => None,
-        DataType::VertexColor3 => None,
-        DataType::Position2 => None,
-        DataType::Normal4 => None,
-        DataType::OldPosition => None,
-        DataType::Tangent2 => None,
+        DataType::Unk33 => read_unknown_attribute(a, d, relative_offset, buffer, endian),
+        DataType::Normal3 => read_unknown_attribute(a, d, relative_offset, buffer, endian),
+        DataType::VertexColor3 => read_unknown_attribute(a, d, relative_offset, buffer, endian),
+        DataType::Position2 => read_unknown_attribute(a, d, relative_offset, buffer, endian),
+        DataType::Normal4 => read_unknown_attribute(a, d, relative_offset, buffer, endian),
+        DataType::OldPosition => read_unknown_attribute(a, d, relative_offset, buffer, endian),
+        DataType::Tangent2 => read_unknown_attribute(a, d, relative_offset, buffer, endian),
         DataType::SkinWeights => Some(AttributeData::SkinWeights(
             read_data(d, relative_offset, buffer, endian, read_unorm16x4).ok()?,
         )),
         DataType::BoneIndices => Some(AttributeData::BoneIndices(
             read_data(d, relative_offset, buffer, endian, read_u8x4).ok()?,
         )),
-        DataType::Flow => None,
+        DataType::Flow => read_unknown_attribute(a, d, relative_offset, buffer, endian),
     }
 }
 
+fn read_unknown_attribute(
+    a: &xc3_lib::vertex::VertexAttribute,
+    d: &VertexBufferDescriptor,
+    relative_offset: u64,
+    buffer: &[u8],
+    endian: Endian,
+) -> Option<AttributeData> {
+    debug!(
+        "Preserving raw bytes for unsupported vertex attribute {:?}",
+        a.data_type
+    );
+    let size = a.data_size as usize;
+    Some(AttributeData::Unknown {
+        data_type: a.data_type,
+        bytes: read_data(d, relative_offset, buffer, endian, |r, _| {
+            let mut bytes = vec![0u8; size];
+            r.read_exact(&mut bytes)?;
+            Ok(bytes)
+        })
+        .ok()?,
+    })
+}
+
 fn read_data<T, F>(
     descriptor: &VertexBufferDescriptor,
     relative_offset: u64,
@@ -750,6 +1877,7 @@ struct MorphTargetVertex {
 fn read_morph_blend_target(
     base_target: &xc3_lib::vertex::MorphTarget,
     model_bytes: &[u8],
+    endian: Endian,
 ) -> BinResult<MorphBlendTargetAttributes> {
     // Only the base target contains data for all vertices.
     // This includes required position, normal, and tangent attributes.
@@ -764,7 +1892,7 @@ fn read_morph_blend_target(
             base_target.data_offset as u64 + i * base_target.vertex_size as u64,
         ))?;
 
-        let vertex: MorphBufferBlendTargetVertex = reader.read_le()?;
+        let vertex: MorphBufferBlendTargetVertex = reader.read_type(endian)?;
         positions.push(vertex.position1.into());
         normals.push(vertex.normal.map(|u| u as f32 / 255.0 * 2.0 - 1.0).into());
         tangents.push(vertex.tangent.map(|u| u as f32 / 255.0 * 2.0 - 1.0).into());
@@ -780,6 +1908,7 @@ fn read_morph_blend_target(
 fn read_morph_buffer_target(
     morph_target: &xc3_lib::vertex::MorphTarget,
     model_bytes: &[u8],
+    endian: Endian,
 ) -> BinResult<Vec<MorphTargetVertex>> {
     let mut reader = Cursor::new(model_bytes);
 
@@ -790,7 +1919,7 @@ fn read_morph_buffer_target(
                 morph_target.data_offset as u64 + i * morph_target.vertex_size as u64,
             ))?;
 
-            let vertex: MorphBufferTargetVertex = reader.read_le()?;
+            let vertex: MorphBufferTargetVertex = reader.read_type(endian)?;
 
             Ok(MorphTargetVertex {
                 position_delta: vertex.position_delta.into(),
@@ -805,22 +1934,24 @@ fn read_morph_buffer_target(
 fn read_outline_buffer(
     descriptor: &xc3_lib::vertex::OutlineBufferDescriptor,
     buffer: &[u8],
+    endian: Endian,
 ) -> BinResult<Vec<AttributeData>> {
     // TODO: outline buffer normally just has vColor?
-    // TODO: Some buffers have 8 bytes per vertex instead of 4?
-    // TODO: What are the in game names of these attributes?
+    // The second attribute for 8 byte buffers has similar values to vColor for 4 byte buffers.
     if descriptor.vertex_size == 8 {
         Ok(vec![
-            AttributeData::VertexColor(read_outline_attribute(
+            AttributeData::OutlineUnk(read_outline_attribute(
                 descriptor,
                 0,
                 buffer,
+                endian,
                 read_unorm8x4,
             )?),
             AttributeData::VertexColor(read_outline_attribute(
                 descriptor,
                 4,
                 buffer,
+                endian,
                 read_unorm8x4,
             )?),
         ])
@@ -829,6 +1960,7 @@ fn read_outline_buffer(
             descriptor,
             0,
             buffer,
+            endian,
             read_unorm8x4,
         )?)])
     }
@@ -838,6 +1970,7 @@ fn read_outline_attribute<T, F>(
     descriptor: &xc3_lib::vertex::OutlineBufferDescriptor,
     relative_offset: u64,
     buffer: &[u8],
+    endian: Endian,
     read_item: F,
 ) -> BinResult<Vec<T>>
 where
@@ -849,29 +1982,52 @@ where
         descriptor.vertex_size as u64,
         relative_offset,
         buffer,
-        Endian::Little,
+        endian,
         read_item,
     )
 }
 
 impl ModelBuffers {
-    /// Decode all the attributes from `vertex_data`.
+    /// Decode all the attributes from little endian `vertex_data`.
+    /// See [ModelBuffers::from_vertex_data_endian] for other platforms.
     pub fn from_vertex_data(
         vertex_data: &VertexData,
         skinning: Option<&xc3_lib::mxmd::Skinning>,
     ) -> BinResult<Self> {
-        let (vertex_buffers, weights) = read_vertex_buffers(vertex_data, skinning)?;
-        let index_buffers = read_index_buffers(vertex_data, Endian::Little);
+        Self::from_vertex_data_endian(vertex_data, skinning, Endian::Little)
+    }
+
+    /// Decode all the attributes from `vertex_data` with the byte order `endian`.
+    ///
+    /// # Examples
+    /// ```rust no_run
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// use binrw::Endian;
+    /// use xc3_lib::vertex::VertexData;
+    /// use xc3_model::vertex::ModelBuffers;
+    ///
+    /// let vertex_data = VertexData::from_file("vertex_data.bin")?;
+    /// let buffers = ModelBuffers::from_vertex_data_endian(&vertex_data, None, Endian::Big)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn from_vertex_data_endian(
+        vertex_data: &VertexData,
+        skinning: Option<&xc3_lib::mxmd::Skinning>,
+        endian: Endian,
+    ) -> BinResult<Self> {
+        let (vertex_buffers, weights) = read_vertex_buffers(vertex_data, skinning, endian)?;
+        let index_buffers = read_index_buffers(vertex_data, endian);
 
         let outline_buffers = vertex_data
             .outline_buffers
             .iter()
-            .map(|descriptor| outline_buffer(descriptor, &vertex_data.buffer))
+            .map(|descriptor| outline_buffer(descriptor, &vertex_data.buffer, endian))
             .collect::<Result<Vec<_>, _>>()?;
 
         // TODO: Preserve if this is none or not?
         let unk_buffers = match &vertex_data.unk7 {
-            Some(unk) => read_unk_buffers(unk, vertex_data)?,
+            Some(unk) => read_unk_buffers(unk, vertex_data, endian)?,
             None => Vec::new(),
         };
 
@@ -905,9 +2061,167 @@ impl ModelBuffers {
         })
     }
 
+    /// Encode and write all the attributes and indices to a new legacy [VertexData](xc3_lib::mxmd::legacy::VertexData).
+    ///
+    /// Data is written in big endian like the original `vertex_data` from [ModelBuffers::from_vertex_data_legacy].
+    /// Offsets and unknown values are copied from the original buffer at the same index.
+    ///
+    /// Writing legacy files with new offsets is not supported yet,
+    /// so the buffer counts and encoded buffer sizes must match the original `vertex_data`.
+    /// This allows editing attribute and index values but not adding or removing vertices.
+    pub fn to_vertex_data_legacy(
+        &self,
+        vertex_data: &xc3_lib::mxmd::legacy::VertexData,
+    ) -> Result<xc3_lib::mxmd::legacy::VertexData, VertexDataLegacyError> {
+        if self.vertex_buffers.len() != vertex_data.vertex_buffers.len() {
+            return Err(VertexDataLegacyError::VertexBufferCount {
+                expected: vertex_data.vertex_buffers.len(),
+                actual: self.vertex_buffers.len(),
+            });
+        }
+        if self.index_buffers.len() != vertex_data.index_buffers.len() {
+            return Err(VertexDataLegacyError::IndexBufferCount {
+                expected: vertex_data.index_buffers.len(),
+                actual: self.index_buffers.len(),
+            });
+        }
+
+        let vertex_buffers = self
+            .vertex_buffers
+            .iter()
+            .zip(&vertex_data.vertex_buffers)
+            .enumerate()
+            .map(|(i, (buffer, original))| {
+                let mut writer = Cursor::new(Vec::new());
+                let descriptor = write_vertex_buffer(&mut writer, &buffer.attributes, Endian::Big)?;
+                let data = writer.into_inner();
+                if data.len() != original.data.len() {
+                    return Err(VertexDataLegacyError::VertexBufferSize {
+                        index: i,
+                        expected: original.data.len(),
+                        actual: data.len(),
+                    });
+                }
+
+                Ok(xc3_lib::mxmd::legacy::VertexBufferDescriptor {
+                    data_offset: original.data_offset,
+                    vertex_count: descriptor.vertex_count,
+                    vertex_size: descriptor.vertex_size,
+                    attributes: descriptor.attributes,
+                    unk1: original.unk1,
+                    data,
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let index_buffers = self
+            .index_buffers
+            .iter()
+            .zip(&vertex_data.index_buffers)
+            .enumerate()
+            .map(|(i, (buffer, original))| {
+                let mut writer = Cursor::new(Vec::new());
+                let descriptor = write_index_buffer(&mut writer, &buffer.indices, Endian::Big)?;
+                let data = writer.into_inner();
+                if data.len() != original.data.len() {
+                    return Err(VertexDataLegacyError::IndexBufferSize {
+                        index: i,
+                        expected: original.data.len(),
+                        actual: data.len(),
+                    });
+                }
+
+                Ok(xc3_lib::mxmd::legacy::IndexBufferDescriptor {
+                    data_offset: original.data_offset,
+                    index_count: descriptor.index_count,
+                    unk1: original.unk1,
+                    unk2: original.unk2,
+                    data,
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(xc3_lib::mxmd::legacy::VertexData {
+            vertex_buffers,
+            index_buffers,
+            weight_buffer_indices: vertex_data.weight_buffer_indices,
+            unk: vertex_data.unk,
+        })
+    }
+
+    /// Write the buffers from [ModelBuffers::to_vertex_data_legacy] over the original buffer data in `bytes`.
+    ///
+    /// `vertex_data_offset` is the file offset of the original `vertex_data`
+    /// that the buffer data offsets are relative to.
+    /// Since the buffer sizes must match the original data, all other bytes are unchanged.
+    pub fn write_vertex_data_legacy(
+        &self,
+        vertex_data: &xc3_lib::mxmd::legacy::VertexData,
+        vertex_data_offset: usize,
+        bytes: &mut [u8],
+    ) -> Result<(), VertexDataLegacyError> {
+        let new_vertex_data = self.to_vertex_data_legacy(vertex_data)?;
+
+        let vertex_buffers = new_vertex_data
+            .vertex_buffers
+            .iter()
+            .map(|b| (b.data_offset, &b.data));
+        let index_buffers = new_vertex_data
+            .index_buffers
+            .iter()
+            .map(|b| (b.data_offset, &b.data));
+        for (data_offset, data) in vertex_buffers.chain(index_buffers) {
+            let offset = vertex_data_offset + data_offset as usize;
+            let end = offset + data.len();
+            if end > bytes.len() {
+                return Err(VertexDataLegacyError::DataOutOfRange {
+                    offset,
+                    size: data.len(),
+                    len: bytes.len(),
+                });
+            }
+            bytes[offset..end].copy_from_slice(data);
+        }
+
+        Ok(())
+    }
+
+    /// Create standalone vertex and index buffers for each mesh in `models`
+    /// in the same order as the meshes in [models](../struct.Models.html#structfield.models).
+    ///
+    /// Each vertex buffer contains only the vertices referenced by the mesh's indices
+    /// with the indices remapped to this smaller range of vertices.
+    /// Triangles with out of range vertex indices are skipped with a warning.
+    /// All attributes and morph targets are preserved.
+    /// [AttributeData::WeightIndex] values still index into [weights](#structfield.weights).
+    /// The default morph target bytes and outline buffers are not included.
+    pub fn split_per_mesh(&self, models: &Models) -> Vec<(VertexBuffer, IndexBuffer)> {
+        models
+            .models
+            .iter()
+            .flat_map(|model| &model.meshes)
+            .map(|mesh| {
+                let vertex_buffer = &self.vertex_buffers[mesh.vertex_buffer_index];
+                let index_buffer = &self.index_buffers[mesh.index_buffer_index];
+                vertex_buffer.compact(&index_buffer.indices)
+            })
+            .collect()
+    }
+
     // TODO: Test this in xc3_test?
     /// Encode and write all the attributes to a new [VertexData].
-    pub fn to_vertex_data(&self) -> BinResult<VertexData> {
+    ///
+    /// If `keep_morph_default_targets` is `true`, the original bytes in
+    /// [morph_default_target](struct.VertexBuffer.html#structfield.morph_default_target)
+    /// will be written unmodified when present.
+    ///
+    /// If `share_morph_targets` is `true`, morph targets with identical vertex data
+    /// will point to the same bytes instead of writing the data again.
+    pub fn to_vertex_data(
+        &self,
+        keep_morph_default_targets: bool,
+        share_morph_targets: bool,
+    ) -> BinResult<VertexData> {
         // TODO: recreate vertex buffers and match original ordering?
         // TODO: vertex, outline, index, align 256, morph, align 256, unk7
         let mut vertex_buffers = Vec::new();
@@ -955,7 +2269,11 @@ impl ModelBuffers {
             .iter()
             .any(|b| !b.morph_targets.is_empty())
         {
-            Some(self.write_morph_targets(&mut buffer_writer)?)
+            Some(self.write_morph_targets(
+                &mut buffer_writer,
+                keep_morph_default_targets,
+                share_morph_targets,
+            )?)
         } else {
             None
         };
@@ -1033,10 +2351,15 @@ impl ModelBuffers {
     fn write_morph_targets(
         &self,
         writer: &mut Cursor<Vec<u8>>,
+        keep_default_targets: bool,
+        share_targets: bool,
     ) -> BinResult<xc3_lib::vertex::VertexMorphs> {
         let mut targets = Vec::new();
         let mut descriptors = Vec::new();
 
+        // Track the offset for previously written targets to reuse identical data.
+        let mut written_targets: Vec<(&MorphTarget, u32)> = Vec::new();
+
         for (i, buffer) in self
             .vertex_buffers
             .iter()
@@ -1059,22 +2382,59 @@ impl ModelBuffers {
                 vertex_size: 32,
                 flags: MorphTargetFlags::new(0, true, false, false, 0u8.into()),
             });
-            targets.push(xc3_lib::vertex::MorphTarget {
-                data_offset: 0,
-                vertex_count: buffer.vertex_count() as u32,
-                vertex_size: 32,
-                flags: MorphTargetFlags::new(0, false, true, false, 0u8.into()),
-            });
+            let default_flags = MorphTargetFlags::new(0, false, true, false, 0u8.into());
+            match buffer
+                .morph_default_target
+                .as_ref()
+                .filter(|_| keep_default_targets)
+            {
+                Some(bytes) => {
+                    let offset = writer.stream_position()?;
+                    writer.write_all(bytes)?;
+                    targets.push(xc3_lib::vertex::MorphTarget {
+                        data_offset: offset as u32,
+                        vertex_count: bytes.len() as u32 / 32,
+                        vertex_size: 32,
+                        flags: default_flags,
+                    });
+                }
+                None => {
+                    targets.push(xc3_lib::vertex::MorphTarget {
+                        data_offset: 0,
+                        vertex_count: buffer.vertex_count() as u32,
+                        vertex_size: 32,
+                        flags: default_flags,
+                    });
+                }
+            }
 
             for morph_target in &buffer.morph_targets {
+                let flags = MorphTargetFlags::new(0, false, false, true, 0u8.into());
+
+                if share_targets {
+                    if let Some((_, offset)) = written_targets
+                        .iter()
+                        .find(|(t, _)| t.has_same_data(morph_target))
+                    {
+                        targets.push(xc3_lib::vertex::MorphTarget {
+                            data_offset: *offset,
+                            vertex_count: morph_target.position_deltas.len() as u32,
+                            vertex_size: 32,
+                            flags,
+                        });
+                        continue;
+                    }
+                }
+
                 let offset = writer.stream_position()?;
                 let target = xc3_lib::vertex::MorphTarget {
                     data_offset: offset as u32,
                     vertex_count: morph_target.position_deltas.len() as u32,
                     vertex_size: 32,
-                    flags: MorphTargetFlags::new(0, false, false, true, 0u8.into()),
+                    flags,
                 };
                 targets.push(target);
+                written_targets.push((morph_target, offset as u32));
 
                 // TODO: These shouldn't all be deltas.
                 write_data(
@@ -1145,6 +2505,7 @@ fn read_vertex_buffers_legacy(
                 Endian::Big,
             ),
             morph_targets: Vec::new(),
+            morph_default_target: None,
             outline_buffer_index: None,
         })
         .collect()
@@ -1222,18 +2583,47 @@ fn write_unk_buffer<W: Write + Seek>(
     unk2: u16,
     start_index: u32,
 ) -> BinResult<UnkBufferDescriptor> {
-    let buffer = write_vertex_buffer(writer, &buffer.attributes, Endian::Little)?;
+    let offset = writer.stream_position()?;
+
+    let (vertex_size, value_count) = match &buffer.values {
+        UnkBufferValues::Size16(values) => (16, values.len()),
+        UnkBufferValues::Size24(values) => (24, values.len()),
+    };
+    if value_count != buffer.positions.len() {
+        return Err(binrw::Error::AssertFail {
+            pos: offset,
+            message: format!(
+                "expected {} unk buffer values but found {value_count}",
+                buffer.positions.len()
+            ),
+        });
+    }
+    write_data(
+        writer,
+        &buffer.positions,
+        offset,
+        vertex_size,
+        Endian::Little,
+        write_f32x3,
+    )?;
+
+    let (unk1, unk2) = match &buffer.values {
+        UnkBufferValues::Size16(values) => {
+            write_data(writer, values, offset + 12, 16, Endian::Little, write_u8x4)?;
+            (0, unk2)
+        }
+        UnkBufferValues::Size24(values) => {
+            write_data(writer, values, offset + 12, 24, Endian::Little, write_u8x12)?;
+            (1, unk2 + 1)
+        }
+    };
 
     // Offsets are relative to the start of the section.
     Ok(UnkBufferDescriptor {
-        unk1: if buffer.vertex_size == 16 { 0 } else { 1 },
-        unk2: if buffer.vertex_size == 16 {
-            unk2
-        } else {
-            unk2 + 1
-        },
-        count: buffer.vertex_count,
-        offset: buffer.data_offset - data_offset,
+        unk1,
+        unk2,
+        count: buffer.positions.len() as u32,
+        offset: offset as u32 - data_offset,
         unk5: 0,
         start_index,
     })
@@ -1242,10 +2632,11 @@ fn write_unk_buffer<W: Write + Seek>(
 fn read_unk_buffers(
     unk: &xc3_lib::vertex::Unk,
     vertex_data: &VertexData,
+    endian: Endian,
 ) -> BinResult<Vec<UnkBuffer>> {
     unk.buffers
         .iter()
-        .map(|descriptor| read_unk_buffer(descriptor, unk.data_offset, &vertex_data.buffer))
+        .map(|descriptor| read_unk_buffer(descriptor, unk.data_offset, &vertex_data.buffer, endian))
         .collect()
 }
 
@@ -1253,59 +2644,32 @@ fn read_unk_buffer(
     descriptor: &UnkBufferDescriptor,
     data_offset: u32,
     buffer: &[u8],
+    endian: Endian,
 ) -> Result<UnkBuffer, binrw::Error> {
     // TODO: why is this 16 or 24 bytes?
-    Ok(UnkBuffer {
-        attributes: if descriptor.unk1 == 0 {
-            vec![
-                AttributeData::Position(read_unk_buffer_attribute(
-                    descriptor,
-                    data_offset,
-                    0,
-                    buffer,
-                    read_f32x3,
-                )?),
-                AttributeData::VertexColor(read_unk_buffer_attribute(
-                    descriptor,
-                    data_offset,
-                    12,
-                    buffer,
-                    read_unorm8x4,
-                )?),
-            ]
-        } else {
-            vec![
-                AttributeData::Position(read_unk_buffer_attribute(
-                    descriptor,
-                    data_offset,
-                    0,
-                    buffer,
-                    read_f32x3,
-                )?),
-                AttributeData::VertexColor(read_unk_buffer_attribute(
-                    descriptor,
-                    data_offset,
-                    12,
-                    buffer,
-                    read_unorm8x4,
-                )?),
-                AttributeData::VertexColor(read_unk_buffer_attribute(
-                    descriptor,
-                    data_offset,
-                    16,
-                    buffer,
-                    read_unorm8x4,
-                )?),
-                AttributeData::VertexColor(read_unk_buffer_attribute(
-                    descriptor,
-                    data_offset,
-                    20,
-                    buffer,
-                    read_unorm8x4,
-                )?),
-            ]
-        },
-    })
+    let positions =
+        read_unk_buffer_attribute(descriptor, data_offset, 0, buffer, endian, read_f32x3)?;
+    let values = if descriptor.unk1 == 0 {
+        UnkBufferValues::Size16(read_unk_buffer_attribute(
+            descriptor,
+            data_offset,
+            12,
+            buffer,
+            endian,
+            read_u8x4,
+        )?)
+    } else {
+        UnkBufferValues::Size24(read_unk_buffer_attribute(
+            descriptor,
+            data_offset,
+            12,
+            buffer,
+            endian,
+            |r, e| r.read_type(e),
+        )?)
+    };
+
+    Ok(UnkBuffer { positions, values })
 }
 
 fn read_unk_buffer_attribute<T, F>(
@@ -1313,6 +2677,7 @@ fn read_unk_buffer_attribute<T, F>(
     data_offset: u32,
     relative_offset: u64,
     buffer: &[u8],
+    endian: Endian,
     read_item: F,
 ) -> BinResult<Vec<T>>
 where
@@ -1324,7 +2689,7 @@ where
         if descriptor.unk1 == 0 { 16 } else { 24 },
         relative_offset,
         buffer,
-        Endian::Little,
+        endian,
         read_item,
     )
 }
@@ -1356,6 +2721,126 @@ fn write_index_buffer<W: Write + Seek>(
     })
 }
 
+#[derive(Debug, Error)]
+pub enum VertexDataLegacyError {
+    #[error("expected {expected} vertex buffers but found {actual}")]
+    VertexBufferCount { expected: usize, actual: usize },
+
+    #[error("expected {expected} index buffers but found {actual}")]
+    IndexBufferCount { expected: usize, actual: usize },
+
+    #[error("expected {expected} bytes for vertex buffer {index} but found {actual}")]
+    VertexBufferSize {
+        index: usize,
+        expected: usize,
+        actual: usize,
+    },
+
+    #[error("expected {expected} bytes for index buffer {index} but found {actual}")]
+    IndexBufferSize {
+        index: usize,
+        expected: usize,
+        actual: usize,
+    },
+
+    #[error("{size} bytes of buffer data at offset {offset} do not fit in {len} bytes")]
+    DataOutOfRange {
+        offset: usize,
+        size: usize,
+        len: usize,
+    },
+
+    #[error("error writing vertex data")]
+    Binrw(#[from] binrw::Error),
+}
+
+#[derive(Debug, PartialEq, Eq, Error)]
+pub enum GenerateTangentsError {
+    #[error("missing required {0} attribute")]
+    MissingAttribute(&'static str),
+
+    #[error("split vertex count {vertex_count} exceeds the maximum for u16 indices")]
+    VertexCount { vertex_count: usize },
+}
+
+#[derive(Debug, Error)]
+pub enum InterleaveAttributesError {
+    #[error("expected {expected} vertices for attribute {index} but found {actual}")]
+    VertexCount {
+        index: usize,
+        expected: usize,
+        actual: usize,
+    },
+
+    #[error("error writing vertex data")]
+    Binrw(#[from] binrw::Error),
+}
+
+/// Pack `attributes` into a single interleaved or "array of structs" buffer.
+/// Returns the buffer bytes and the layout of each attribute in the buffer.
+///
+/// Attributes use the same formats and ordering as vertex buffers in game.
+/// See [AttributeData::format] for the layout of each attribute.
+///
+/// # Examples
+/// ``` rust
+/// use glam::{vec2, vec3};
+/// use xc3_model::vertex::{interleave_attributes, AttributeData};
+///
+/// let attributes = vec![
+///     AttributeData::Position(vec![vec3(0.0, 1.0, 2.0)]),
+///     AttributeData::TexCoord0(vec![vec2(0.5, 0.5)]),
+/// ];
+/// let (bytes, layout) = interleave_attributes(&attributes, binrw::Endian::Little).unwrap();
+/// assert_eq!(20, bytes.len());
+/// assert_eq!(2, layout.len());
+/// ```
+pub fn interleave_attributes(
+    attributes: &[AttributeData],
+    endian: Endian,
+) -> Result<(Vec<u8>, Vec<xc3_lib::vertex::VertexAttribute>), InterleaveAttributesError> {
+    if attributes.is_empty() {
+        return Ok((Vec::new(), Vec::new()));
+    }
+
+    let expected = attributes[0].len();
+    for (index, a) in attributes.iter().enumerate() {
+        if a.len() != expected {
+            return Err(InterleaveAttributesError::VertexCount {
+                index,
+                expected,
+                actual: a.len(),
+            });
+        }
+    }
+
+    let mut writer = Cursor::new(Vec::new());
+    let descriptor = write_vertex_buffer(&mut writer, attributes, endian)?;
+    Ok((writer.into_inner(), descriptor.attributes))
+}
+
+/// Unpack the interleaved `buffer` with `vertex_count` vertices into separate attributes.
+/// This is the inverse of [interleave_attributes].
+///
+/// Attributes with unsupported data types are skipped.
+pub fn deinterleave_attributes(
+    buffer: &[u8],
+    attributes: &[xc3_lib::vertex::VertexAttribute],
+    vertex_count: usize,
+    endian: Endian,
+) -> Vec<AttributeData> {
+    let descriptor = VertexBufferDescriptor {
+        data_offset: 0,
+        vertex_count: vertex_count as u32,
+        vertex_size: attributes.iter().map(|a| a.data_size as u32).sum(),
+        attributes: attributes.to_vec(),
+        unk1: 0,
+        unk2: 0,
+        unk3: 0,
+    };
+    read_vertex_attributes(&descriptor, buffer, endian)
+}
+
 fn write_vertex_buffer<W: Write + Seek>(
     writer: &mut W,
     attribute_data: &[AttributeData],
@@ -1433,6 +2918,10 @@ fn write_u8x4<W: Write + Seek>(writer: &mut W, value: &[u8; 4], endian: Endian)
     value.write_options(writer, endian, ())
 }
 
+fn write_u8x12<W: Write + Seek>(writer: &mut W, value: &[u8; 12], endian: Endian) -> BinResult<()> {
+    value.write_options(writer, endian, ())
+}
+
 fn write_f32x2<W: Write + Seek>(writer: &mut W, value: &Vec2, endian: Endian) -> BinResult<()> {
     value.to_array().write_options(writer, endian, ())
 }
@@ -1466,7 +2955,7 @@ fn write_snorm8x4<W: Write + Seek>(writer: &mut W, value: &Vec4, endian: Endian)
 mod tests {
     use super::*;
 
-    use crate::assert_hex_eq;
+    use crate::{assert_hex_eq, test_support};
 
     use glam::{vec2, vec3, vec4};
     use hexlit::hex;
@@ -1588,6 +3077,117 @@ mod tests {
         assert_hex_eq!(data, writer.into_inner());
     }
 
+    #[test]
+    fn vertex_buffer_unknown_attribute() {
+        let data = hex!(
+            // vertex 0
+            0000803f 00000040 00004040
+            01020304
+            // vertex 1
+            000080bf 000000c0 000040c0
+            05060708
+        );
+
+        let descriptor = VertexBufferDescriptor {
+            data_offset: 0,
+            vertex_count: 2,
+            vertex_size: 16,
+            attributes: vec![
+                VertexAttribute {
+                    data_type: DataType::Position,
+                    data_size: 12,
+                },
+                VertexAttribute {
+                    data_type: DataType::Unk18,
+                    data_size: 4,
+                },
+            ],
+            unk1: 0,
+            unk2: 0,
+            unk3: 0,
+        };
+
+        // Test read.
+        let attributes = vec![
+            AttributeData::Position(vec![vec3(1.0, 2.0, 3.0), vec3(-1.0, -2.0, -3.0)]),
+            AttributeData::Unknown {
+                data_type: DataType::Unk18,
+                bytes: vec![vec![1, 2, 3, 4], vec![5, 6, 7, 8]],
+            },
+        ];
+        assert_eq!(
+            attributes,
+            read_vertex_attributes(&descriptor, &data, Endian::Little)
+        );
+
+        // Test write.
+        let mut writer = Cursor::new(Vec::new());
+        let new_descriptor = write_vertex_buffer(&mut writer, &attributes, Endian::Little).unwrap();
+        assert_eq!(new_descriptor, descriptor);
+        assert_hex_eq!(data, writer.into_inner());
+    }
+
+    #[test]
+    fn interleave_attributes_position_uv() {
+        // xeno3/chr/ch/ch01012013.wismt, vertex buffer 0 without other attributes
+        let data = hex!(
+            // vertex 0
+            0x459ecd3d 8660673f f2ad923d
+            fd8d423f aea11b3f
+            // vertex 1
+            0x8879143e 81d46a3f 54db4e3d
+            72904a3f 799d193f
+        );
+
+        let attributes = vec![
+            AttributeData::Position(vec![
+                vec3(0.10039953, 0.9038166, 0.07162084),
+                vec3(0.14499485, 0.91730505, 0.050502136),
+            ]),
+            AttributeData::TexCoord0(vec![
+                vec2(0.75997907, 0.6079358),
+                vec2(0.79126656, 0.6000591),
+            ]),
+        ];
+
+        let (bytes, layout) = interleave_attributes(&attributes, Endian::Little).unwrap();
+        assert_hex_eq!(data, bytes);
+        assert_eq!(
+            vec![
+                VertexAttribute {
+                    data_type: DataType::Position,
+                    data_size: 12,
+                },
+                VertexAttribute {
+                    data_type: DataType::TexCoord0,
+                    data_size: 8,
+                },
+            ],
+            layout
+        );
+
+        assert_eq!(
+            attributes,
+            deinterleave_attributes(&data, &layout, 2, Endian::Little)
+        );
+    }
+
+    #[test]
+    fn interleave_attributes_different_lengths() {
+        let attributes = vec![
+            AttributeData::Position(vec![Vec3::ZERO; 3]),
+            AttributeData::TexCoord0(vec![Vec2::ZERO; 2]),
+        ];
+        assert!(matches!(
+            interleave_attributes(&attributes, Endian::Little),
+            Err(InterleaveAttributesError::VertexCount {
+                index: 1,
+                expected: 3,
+                actual: 2
+            })
+        ));
+    }
+
     #[test]
     fn weight_buffer_vertices() {
         // xeno3/chr/ch/ch01012013.wismt, vertex buffer 12
@@ -1846,7 +3446,7 @@ mod tests {
                     vec4(-0.035294116, 0.54509807, -0.827451, 1.0)
                 ]
             },
-            read_morph_blend_target(&target, &data).unwrap()
+            read_morph_blend_target(&target, &data, Endian::Little).unwrap()
         );
     }
 
@@ -1890,7 +3490,7 @@ mod tests {
                     vertex_index: 6
                 }
             ],
-            read_morph_buffer_target(&target, &data).unwrap()
+            read_morph_buffer_target(&target, &data, Endian::Little).unwrap()
         );
     }
 
@@ -1930,7 +3530,7 @@ mod tests {
                     vertex_index: 217
                 }
             ],
-            read_morph_buffer_target(&target, &data).unwrap()
+            read_morph_buffer_target(&target, &data, Endian::Little).unwrap()
         );
     }
 
@@ -1960,27 +3560,17 @@ mod tests {
         };
 
         // Test read.
-        let buffer = read_unk_buffer(&descriptor, 0, &data).unwrap();
+        let buffer = read_unk_buffer(&descriptor, 0, &data, Endian::Little).unwrap();
         assert_eq!(
             UnkBuffer {
-                attributes: vec![
-                    AttributeData::Position(vec![
-                        vec3(-0.038012017, 1.6167967, -0.10723422),
-                        vec3(-0.026746355, 1.6158215, -0.110543534)
-                    ]),
-                    AttributeData::VertexColor(vec![
-                        vec4(1.0, 0.0, 0.0, 0.0),
-                        vec4(1.0, 0.0, 0.0, 0.0)
-                    ]),
-                    AttributeData::VertexColor(vec![
-                        vec4(0.007843138, 0.0, 0.0, 0.0),
-                        vec4(0.007843138, 0.0, 0.0, 0.0)
-                    ]),
-                    AttributeData::VertexColor(vec![
-                        vec4(0.7764706, 0.9019608, 0.5764706, 0.0),
-                        vec4(0.88235295, 0.92941177, 0.5294118, 0.0)
-                    ])
-                ]
+                positions: vec![
+                    vec3(-0.038012017, 1.6167967, -0.10723422),
+                    vec3(-0.026746355, 1.6158215, -0.110543534)
+                ],
+                values: UnkBufferValues::Size24(vec![
+                    [0xff, 0, 0, 0, 2, 0, 0, 0, 0xc6, 0xe6, 0x93, 0],
+                    [0xff, 0, 0, 0, 2, 0, 0, 0, 0xe1, 0xed, 0x87, 0]
+                ])
             },
             buffer
         );
@@ -2014,19 +3604,17 @@ mod tests {
         };
 
         // Test read.
-        let buffer = read_unk_buffer(&descriptor, 0, &data).unwrap();
+        let buffer = read_unk_buffer(&descriptor, 0, &data, Endian::Little).unwrap();
         assert_eq!(
             UnkBuffer {
-                attributes: vec![
-                    AttributeData::Position(vec![
-                        vec3(-0.03853178, 0.01579536, -0.17696129),
-                        vec3(-0.026833333, 0.01425251, -0.17931697)
-                    ]),
-                    AttributeData::VertexColor(vec![
-                        vec4(0.69803923, 0.99607843, 0.6156863, 0.0),
-                        vec4(0.98039216, 0.50980395, 0.0, 0.0)
-                    ])
-                ]
+                positions: vec![
+                    vec3(-0.03853178, 0.01579536, -0.17696129),
+                    vec3(-0.026833333, 0.01425251, -0.17931697)
+                ],
+                values: UnkBufferValues::Size16(vec![
+                    [0xb2, 0xfe, 0x9d, 0x00],
+                    [0xfa, 0x82, 0x00, 0x00]
+                ])
             },
             buffer
         );
@@ -2038,6 +3626,70 @@ mod tests {
         assert_hex_eq!(data, writer.into_inner());
     }
 
+    #[test]
+    fn unk_buffers_size16_size24_round_trip() {
+        // xeno3/chr/ch/ch06002301.wismt and xeno3/chr/ch/ch01011011.wismt unk buffers.
+        let data = hex!(
+            // buffer 0 vertex 0
+            80d31dbd 4565813c 573535be
+            b2fe9d00
+            // buffer 0 vertex 1
+            94d1dbbc 5c83693c de9e37be
+            fa820000
+            // buffer 1 vertex 0
+            7db21bbd 32f3ce3f 9d9ddbbd
+            ff000000
+            02000000
+            c6e69300
+            // buffer 1 vertex 1
+            2c1bdbbc 3dd3ce3f a664e2bd
+            ff000000
+            02000000
+            e1ed8700
+        );
+
+        let descriptors = [
+            xc3_lib::vertex::UnkBufferDescriptor {
+                unk1: 0,
+                unk2: 0,
+                count: 2,
+                offset: 0,
+                unk5: 0,
+                start_index: 0,
+            },
+            xc3_lib::vertex::UnkBufferDescriptor {
+                unk1: 1,
+                unk2: 2,
+                count: 2,
+                offset: 32,
+                unk5: 0,
+                start_index: 2,
+            },
+        ];
+
+        let buffers: Vec<_> = descriptors
+            .iter()
+            .map(|d| read_unk_buffer(d, 0, &data, Endian::Little).unwrap())
+            .collect();
+
+        // Writing should preserve all the bytes after the position.
+        let mut writer = Cursor::new(Vec::new());
+        let unk = write_unk_buffers(&mut writer, &buffers).unwrap();
+        assert_eq!(descriptors.to_vec(), unk.buffers);
+        assert_eq!(data.len() as u32, unk.data_length);
+        assert_hex_eq!(data, writer.into_inner());
+    }
+
+    #[test]
+    fn write_unk_buffer_value_count_mismatch() {
+        let buffer = UnkBuffer {
+            positions: vec![Vec3::ZERO; 2],
+            values: UnkBufferValues::Size16(vec![[0u8; 4]]),
+        };
+        let mut writer = Cursor::new(Vec::new());
+        assert!(write_unk_buffer(&mut writer, &buffer, 0, 0, 0).is_err());
+    }
+
     #[test]
     fn read_outline_buffer_vertices_size4() {
         // xeno3/chr/ch/ch01011011.wismt, outline buffer 0.
@@ -2060,12 +3712,12 @@ mod tests {
                 vec4(0.3647059, 0.18431373, 0.12156863, 0.0),
                 vec4(0.3647059, 0.18431373, 0.12156863, 0.047058824)
             ])],
-            read_outline_buffer(&descriptor, &data).unwrap()
+            read_outline_buffer(&descriptor, &data, Endian::Little).unwrap()
         );
     }
 
     #[test]
-    fn read_outline_buffer_vertices_size8() {
+    fn outline_buffer_vertices_size8() {
         // xeno3/chr/ch/ch01011011.wismt, outline buffer 3.
         let data = hex!(
             // vertex 0
@@ -2083,10 +3735,10 @@ mod tests {
             unk: 0,
         };
 
-        // TODO: What is the second attribute?
+        let attributes = read_outline_buffer(&descriptor, &data, Endian::Little).unwrap();
         assert_eq!(
             vec![
-                AttributeData::VertexColor(vec![
+                AttributeData::OutlineUnk(vec![
                     vec4(0.47843137, 0.8745098, 0.9882353, 0.0),
                     vec4(0.47843137, 0.8745098, 0.9882353, 0.0)
                 ]),
@@ -2095,8 +3747,14 @@ mod tests {
                     vec4(0.29411766, 0.21568628, 0.16078432, 0.29803923)
                 ])
             ],
-            read_outline_buffer(&descriptor, &data).unwrap()
+            attributes
         );
+
+        // Test write.
+        let mut writer = Cursor::new(Vec::new());
+        let new_descriptor = write_outline_buffer(&mut writer, &attributes).unwrap();
+        assert_eq!(new_descriptor, descriptor);
+        assert_hex_eq!(data, writer.into_inner());
     }
 
     #[test]
@@ -2260,4 +3918,869 @@ mod tests {
         assert_eq!(new_descriptor, descriptor);
         assert_hex_eq!(data, writer.into_inner());
     }
+
+    fn morph_buffers(morph_default_target: Option<Vec<u8>>) -> ModelBuffers {
+        ModelBuffers {
+            vertex_buffers: vec![VertexBuffer {
+                attributes: vec![AttributeData::Position(vec![Vec3::ZERO; 2])],
+                morph_targets: vec![MorphTarget {
+                    morph_controller_index: 0,
+                    position_deltas: vec![Vec3::ONE],
+                    normal_deltas: vec![Vec4::ZERO],
+                    tangent_deltas: vec![Vec4::ZERO],
+                    vertex_indices: vec![0],
+                }],
+                morph_default_target,
+                outline_buffer_index: None,
+            }],
+            outline_buffers: Vec::new(),
+            index_buffers: Vec::new(),
+            unk_buffers: Vec::new(),
+            weights: None,
+        }
+    }
+
+    #[test]
+    fn write_morph_default_target_original_bytes() {
+        // xeno3/chr/ch/ch01027000.wismt, "face_D2_shape", target index 325.
+        let data = hex!(
+            8c54023d bc27ac3f 72dd93bc 00000000
+            d6237601
+            a0a90cff
+            00000000
+            04000000
+            2b28153d 27e7ac3f 06d8b2bc 00000000
+            dd2c6b01
+            0x8ead0aff
+            00000000
+            06000000
+        );
+
+        let buffers = morph_buffers(Some(data.to_vec()));
+
+        // The original bytes should be preserved when reading and writing.
+        let vertex_data = buffers.to_vertex_data(true, false).unwrap();
+        let default = &vertex_data.vertex_morphs.as_ref().unwrap().targets[1];
+        assert_eq!(2, default.vertex_count);
+        let start = default.data_offset as usize;
+        assert_hex_eq!(data, &vertex_data.buffer[start..start + data.len()]);
+
+        let new_buffers = ModelBuffers::from_vertex_data(&vertex_data, None).unwrap();
+        assert_eq!(
+            Some(data.to_vec()),
+            new_buffers.vertex_buffers[0].morph_default_target
+        );
+
+        let new_vertex_data = new_buffers.to_vertex_data(true, false).unwrap();
+        let default = &new_vertex_data.vertex_morphs.as_ref().unwrap().targets[1];
+        let start = default.data_offset as usize;
+        assert_hex_eq!(data, &new_vertex_data.buffer[start..start + data.len()]);
+    }
+
+    #[test]
+    fn write_morph_default_target_regenerated() {
+        let buffers = morph_buffers(Some(vec![0u8; 64]));
+
+        let vertex_data = buffers.to_vertex_data(false, false).unwrap();
+        assert_eq!(
+            xc3_lib::vertex::MorphTarget {
+                data_offset: 0,
+                vertex_count: 2,
+                vertex_size: 32,
+                flags: xc3_lib::vertex::MorphTargetFlags::new(0u16, false, true, false, 0u8.into()),
+            },
+            vertex_data.vertex_morphs.unwrap().targets[1]
+        );
+    }
+
+    #[test]
+    fn deduplicate_morph_targets_identical() {
+        let mut buffers = morph_buffers(None);
+        let buffer = &mut buffers.vertex_buffers[0];
+        let target = buffer.morph_targets[0].clone();
+        buffer.morph_targets.push(target.clone());
+        buffer.morph_targets.push(MorphTarget {
+            morph_controller_index: 1,
+            ..target.clone()
+        });
+        buffer.morph_targets.push(MorphTarget {
+            morph_controller_index: 2,
+            position_deltas: vec![Vec3::ONE; target.position_deltas.len()],
+            ..target.clone()
+        });
+
+        // Identical targets collapse to the first target even for different controllers.
+        assert_eq!(
+            BTreeMap::from([(1, target.morph_controller_index)]),
+            buffer.deduplicate_morph_targets()
+        );
+        assert_eq!(2, buffer.morph_targets.len());
+        assert_eq!(target, buffer.morph_targets[0]);
+        assert_eq!(2, buffer.morph_targets[1].morph_controller_index);
+    }
+
+    #[test]
+    fn write_morph_targets_shared() {
+        let mut buffers = morph_buffers(None);
+        let target = buffers.vertex_buffers[0].morph_targets[0].clone();
+        buffers.vertex_buffers[0].morph_targets.push(MorphTarget {
+            morph_controller_index: 1,
+            ..target
+        });
+
+        // Identical targets should only be stored once.
+        let shared = buffers.to_vertex_data(false, true).unwrap();
+        let targets = &shared.vertex_morphs.as_ref().unwrap().targets;
+        assert_eq!(4, targets.len());
+        assert_eq!(targets[2].data_offset, targets[3].data_offset);
+
+        let unshared = buffers.to_vertex_data(false, false).unwrap();
+        let targets = &unshared.vertex_morphs.as_ref().unwrap().targets;
+        assert_ne!(targets[2].data_offset, targets[3].data_offset);
+        assert!(shared.buffer.len() <= unshared.buffer.len());
+
+        // Both targets should still read the same deltas.
+        let new_buffers = ModelBuffers::from_vertex_data(&shared, None).unwrap();
+        let new_targets = &new_buffers.vertex_buffers[0].morph_targets;
+        assert_eq!(2, new_targets.len());
+        assert_eq!(
+            new_targets[0].position_deltas,
+            new_targets[1].position_deltas
+        );
+        assert_eq!(vec![Vec3::ONE], new_targets[1].position_deltas);
+    }
+
+    #[test]
+    fn fix_winding_reversed_triangle() {
+        let positions = vec![
+            vec3(0.0, 0.0, 0.0),
+            vec3(1.0, 0.0, 0.0),
+            vec3(0.0, 1.0, 0.0),
+            vec3(1.0, 1.0, 0.0),
+        ];
+        let normals = vec![vec4(0.0, 0.0, 1.0, 0.0); 4];
+
+        // The second triangle faces -Z and should be flipped.
+        let mut buffer = IndexBuffer {
+            indices: vec![0, 1, 2, 2, 3, 1, 0, 1, 4],
+        };
+        buffer.fix_winding(&positions, &normals);
+        assert_eq!(vec![0, 1, 2, 2, 1, 3, 0, 1, 4], buffer.indices);
+    }
+
+    #[test]
+    fn generate_smooth_normals_flat_quad() {
+        let positions = vec![
+            vec3(0.0, 0.0, 0.0),
+            vec3(1.0, 0.0, 0.0),
+            vec3(0.0, 1.0, 0.0),
+            vec3(1.0, 1.0, 0.0),
+            vec3(5.0, 5.0, 5.0),
+        ];
+        let indices = [0, 1, 2, 2, 1, 3];
+
+        assert_eq!(
+            vec![
+                vec4(0.0, 0.0, 1.0, 0.0),
+                vec4(0.0, 0.0, 1.0, 0.0),
+                vec4(0.0, 0.0, 1.0, 0.0),
+                vec4(0.0, 0.0, 1.0, 0.0),
+                Vec4::ZERO
+            ],
+            generate_smooth_normals(&positions, &indices)
+        );
+
+        let mut buffer = VertexBuffer {
+            attributes: vec![AttributeData::Position(positions)],
+            morph_targets: Vec::new(),
+            morph_default_target: None,
+            outline_buffer_index: None,
+        };
+        buffer.generate_normals(&indices);
+        assert_eq!(
+            AttributeData::Normal(vec![
+                vec4(0.0, 0.0, 1.0, 0.0),
+                vec4(0.0, 0.0, 1.0, 0.0),
+                vec4(0.0, 0.0, 1.0, 0.0),
+                vec4(0.0, 0.0, 1.0, 0.0),
+                Vec4::ZERO
+            ]),
+            buffer.attributes[1]
+        );
+    }
+
+    #[test]
+    fn bake_vertex_ao_crevice() {
+        // A floor fan around vertex 0 enclosed by four walls.
+        let mut positions = vec![
+            vec3(0.0, 0.0, 0.0),
+            vec3(-1.0, -1.0, 0.0),
+            vec3(1.0, -1.0, 0.0),
+            vec3(1.0, 1.0, 0.0),
+            vec3(-1.0, 1.0, 0.0),
+        ];
+        let mut indices = vec![0, 1, 2, 0, 2, 3, 0, 3, 4, 0, 4, 1];
+        for (a, b) in [(1, 2), (2, 3), (3, 4), (4, 1)] {
+            let start = positions.len() as u16;
+            let (pa, pb) = (positions[a], positions[b]);
+            positions.extend([pa, pb, pa + Vec3::Z * 2.0, pb + Vec3::Z * 2.0]);
+            indices.extend([start, start + 1, start + 2, start + 2, start + 1, start + 3]);
+        }
+
+        // An exposed triangle far away from the walls.
+        let exposed = positions.len();
+        positions.extend([
+            vec3(100.0, 0.0, 0.0),
+            vec3(101.0, 0.0, 0.0),
+            vec3(100.0, 1.0, 0.0),
+        ]);
+        indices.extend([exposed as u16, exposed as u16 + 1, exposed as u16 + 2]);
+
+        let normals = generate_smooth_normals(&positions, &indices);
+        let ao = bake_vertex_ao(&positions, &normals, &indices, 64);
+
+        assert_eq!(positions.len(), ao.len());
+        assert_eq!(1.0, ao[exposed]);
+        assert!(ao[0] < 0.5);
+        assert!(ao[0] < ao[exposed]);
+    }
+
+    #[test]
+    fn generate_tangents_split_mirrored_uvs() {
+        // Two quads with UVs mirrored along the shared edge at x=1.
+        let positions = vec![
+            vec3(0.0, 0.0, 0.0),
+            vec3(1.0, 0.0, 0.0),
+            vec3(2.0, 0.0, 0.0),
+            vec3(0.0, 1.0, 0.0),
+            vec3(1.0, 1.0, 0.0),
+            vec3(2.0, 1.0, 0.0),
+        ];
+        let normals = vec![vec4(0.0, 0.0, 1.0, 0.0); 6];
+        let uvs = vec![
+            vec2(0.0, 0.0),
+            vec2(1.0, 0.0),
+            vec2(0.0, 0.0),
+            vec2(0.0, 1.0),
+            vec2(1.0, 1.0),
+            vec2(0.0, 1.0),
+        ];
+        let indices = [0, 1, 3, 3, 1, 4, 1, 2, 4, 4, 2, 5];
+
+        // The seam vertices 1 and 4 are duplicated for the mirrored faces.
+        assert_eq!(
+            Ok(SplitTangents {
+                vertex_indices: vec![0, 1, 2, 3, 4, 5, 1, 4],
+                indices: vec![0, 1, 3, 3, 1, 4, 6, 2, 7, 7, 2, 5],
+                tangents: vec![
+                    vec4(1.0, 0.0, 0.0, 1.0),
+                    vec4(1.0, 0.0, 0.0, 1.0),
+                    vec4(-1.0, 0.0, 0.0, -1.0),
+                    vec4(1.0, 0.0, 0.0, 1.0),
+                    vec4(1.0, 0.0, 0.0, 1.0),
+                    vec4(-1.0, 0.0, 0.0, -1.0),
+                    vec4(-1.0, 0.0, 0.0, -1.0),
+                    vec4(-1.0, 0.0, 0.0, -1.0),
+                ],
+            }),
+            generate_tangents_split(&positions, &normals, &uvs, &indices)
+        );
+
+        let mut buffer = VertexBuffer {
+            attributes: vec![
+                AttributeData::Position(positions),
+                AttributeData::Normal(normals),
+                AttributeData::TexCoord0(uvs),
+            ],
+            morph_targets: vec![MorphTarget {
+                morph_controller_index: 0,
+                position_deltas: vec![vec3(0.0, 0.0, 1.0)],
+                normal_deltas: vec![Vec4::ZERO],
+                tangent_deltas: vec![Vec4::ZERO],
+                vertex_indices: vec![4],
+            }],
+            morph_default_target: None,
+            outline_buffer_index: Some(0),
+        };
+        let mut outline_buffers = vec![OutlineBuffer {
+            attributes: vec![AttributeData::VertexColor(vec![Vec4::ONE; 6])],
+        }];
+        assert_eq!(
+            Ok(vec![0, 1, 3, 3, 1, 4, 6, 2, 7, 7, 2, 5]),
+            buffer.generate_tangents_split(&indices, &mut outline_buffers)
+        );
+        assert!(buffer.attributes.iter().all(|a| a.len() == 8));
+        assert_eq!(8, outline_buffers[0].attributes[0].len());
+        assert_eq!(
+            AttributeData::Position(vec![
+                vec3(0.0, 0.0, 0.0),
+                vec3(1.0, 0.0, 0.0),
+                vec3(2.0, 0.0, 0.0),
+                vec3(0.0, 1.0, 0.0),
+                vec3(1.0, 1.0, 0.0),
+                vec3(2.0, 1.0, 0.0),
+                vec3(1.0, 0.0, 0.0),
+                vec3(1.0, 1.0, 0.0),
+            ]),
+            buffer.attributes[0]
+        );
+        assert_eq!(vec![4, 7], buffer.morph_targets[0].vertex_indices);
+    }
+
+    #[test]
+    fn generate_tangents_split_too_many_vertices() {
+        // Place the mirrored quads at the end so the duplicates don't fit in u16.
+        let offset = u16::MAX as usize - 5;
+        let mut positions = vec![Vec3::ZERO; offset];
+        positions.extend([
+            vec3(0.0, 0.0, 0.0),
+            vec3(1.0, 0.0, 0.0),
+            vec3(2.0, 0.0, 0.0),
+            vec3(0.0, 1.0, 0.0),
+            vec3(1.0, 1.0, 0.0),
+            vec3(2.0, 1.0, 0.0),
+        ]);
+        let normals = vec![vec4(0.0, 0.0, 1.0, 0.0); positions.len()];
+        let mut uvs = vec![Vec2::ZERO; offset];
+        uvs.extend([
+            vec2(0.0, 0.0),
+            vec2(1.0, 0.0),
+            vec2(0.0, 0.0),
+            vec2(0.0, 1.0),
+            vec2(1.0, 1.0),
+            vec2(0.0, 1.0),
+        ]);
+        let indices: Vec<_> = [0, 1, 3, 3, 1, 4, 1, 2, 4, 4, 2, 5]
+            .iter()
+            .map(|i| (offset + i) as u16)
+            .collect();
+
+        assert_eq!(
+            Err(GenerateTangentsError::VertexCount {
+                vertex_count: u16::MAX as usize + 2
+            }),
+            generate_tangents_split(&positions, &normals, &uvs, &indices)
+        );
+    }
+
+    #[test]
+    fn attribute_format_sizes() {
+        let normal = AttributeData::Normal(Vec::new()).format();
+        assert_eq!(
+            AttributeFormat {
+                component_count: 4,
+                component_type: ComponentType::I8,
+                normalized: true
+            },
+            normal
+        );
+        assert_eq!(4, normal.size_in_bytes());
+
+        // The sizes should match the attributes written to the buffer.
+        for attribute in [
+            AttributeData::Position(Vec::new()),
+            AttributeData::TexCoord0(Vec::new()),
+            AttributeData::VertexColor(Vec::new()),
+            AttributeData::WeightIndex(Vec::new()),
+            AttributeData::SkinWeights(Vec::new()),
+            AttributeData::BoneIndices(Vec::new()),
+        ] {
+            assert_eq!(
+                xc3_lib::vertex::VertexAttribute::from(&attribute).data_size as usize,
+                attribute.format().size_in_bytes()
+            );
+        }
+    }
+
+    #[test]
+    fn weight_index2_read_write() {
+        let data = hex!(
+            // vertex 0
+            01000000
+            // vertex 1
+            02000100
+        );
+
+        let descriptor = VertexBufferDescriptor {
+            data_offset: 0,
+            vertex_count: 2,
+            vertex_size: 4,
+            attributes: vec![VertexAttribute {
+                data_type: DataType::WeightIndex2,
+                data_size: 4,
+            }],
+            unk1: 0,
+            unk2: 0,
+            unk3: 0,
+        };
+
+        let attributes = read_vertex_attributes(&descriptor, &data, Endian::Little);
+        assert_eq!(
+            vec![AttributeData::WeightIndex2(vec![[1, 0], [2, 1]])],
+            attributes
+        );
+
+        // Test write.
+        let mut writer = Cursor::new(Vec::new());
+        let new_descriptor = write_vertex_buffer(&mut writer, &attributes, Endian::Little).unwrap();
+        assert_eq!(new_descriptor, descriptor);
+        assert_hex_eq!(data, writer.into_inner());
+    }
+
+    #[test]
+    fn index_buffer_triangles_skip_degenerate() {
+        let buffer = IndexBuffer {
+            indices: vec![0, 1, 2, 2, 2, 3, 3, 2, 1],
+        };
+        assert_eq!(
+            vec![[0, 1, 2], [3, 2, 1]],
+            buffer.triangles().collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn vertex_buffer_tex_coords_index() {
+        let mut buffer = VertexBuffer {
+            attributes: vec![
+                AttributeData::TexCoord1(vec![Vec2::ONE]),
+                AttributeData::TexCoord8(vec![Vec2::ZERO]),
+            ],
+            morph_targets: Vec::new(),
+            morph_default_target: None,
+            outline_buffer_index: None,
+        };
+
+        assert_eq!(None, buffer.tex_coords(0));
+        assert_eq!(Some([Vec2::ONE].as_slice()), buffer.tex_coords(1));
+        assert_eq!(Some([Vec2::ZERO].as_slice()), buffer.tex_coords(8));
+        assert_eq!(None, buffer.tex_coords(9));
+
+        buffer.tex_coords_mut(8).unwrap()[0] = Vec2::X;
+        assert_eq!(Some([Vec2::X].as_slice()), buffer.tex_coords(8));
+    }
+
+    #[test]
+    fn vertex_buffer_transform_translation() {
+        let mut buffer = VertexBuffer {
+            attributes: vec![
+                AttributeData::Position(vec![Vec3::ZERO, Vec3::ONE]),
+                AttributeData::Normal(vec![Vec4::new(0.0, 1.0, 0.0, -1.0)]),
+                AttributeData::Tangent(vec![Vec4::new(1.0, 0.0, 0.0, 1.0)]),
+            ],
+            morph_targets: vec![MorphTarget {
+                morph_controller_index: 0,
+                position_deltas: vec![Vec3::X],
+                normal_deltas: vec![Vec4::Y],
+                tangent_deltas: vec![Vec4::X],
+                vertex_indices: vec![0],
+            }],
+            morph_default_target: None,
+            outline_buffer_index: None,
+        };
+
+        buffer.transform(Mat4::from_translation(Vec3::new(1.0, 2.0, 3.0)));
+
+        assert_eq!(
+            Some([Vec3::new(1.0, 2.0, 3.0), Vec3::new(2.0, 3.0, 4.0)].as_slice()),
+            buffer.positions()
+        );
+        assert_eq!(
+            Some([Vec4::new(0.0, 1.0, 0.0, -1.0)].as_slice()),
+            buffer.normals()
+        );
+        assert_eq!(
+            Some([Vec4::new(1.0, 0.0, 0.0, 1.0)].as_slice()),
+            buffer.tangents()
+        );
+
+        // Deltas are offsets and should ignore translation.
+        assert_eq!(vec![Vec3::X], buffer.morph_targets[0].position_deltas);
+        assert_eq!(vec![Vec4::Y], buffer.morph_targets[0].normal_deltas);
+        assert_eq!(vec![Vec4::X], buffer.morph_targets[0].tangent_deltas);
+    }
+
+    #[test]
+    fn vertex_buffer_transform_non_uniform_scale() {
+        let mut buffer = VertexBuffer {
+            attributes: vec![AttributeData::Normal(vec![Vec4::new(1.0, 1.0, 0.0, 1.0)])],
+            morph_targets: Vec::new(),
+            morph_default_target: None,
+            outline_buffer_index: None,
+        };
+
+        buffer.transform(Mat4::from_scale(Vec3::new(2.0, 1.0, 1.0)));
+
+        // Normals should remain perpendicular to the scaled surface.
+        let expected = Vec3::new(0.5, 1.0, 0.0).normalize().extend(1.0);
+        assert!(buffer.normals().unwrap()[0].abs_diff_eq(expected, 0.0001));
+    }
+
+    #[test]
+    fn index_buffer_adjacency_quad() {
+        // 0---2
+        // | / |
+        // 1---3
+        let buffer = IndexBuffer {
+            indices: vec![0, 1, 2, 2, 1, 3],
+        };
+        assert_eq!(
+            Adjacency {
+                triangles: vec![[0, 1, 2], [2, 1, 3]],
+                neighbors: vec![[None, Some(1), None], [Some(0), None, None]]
+            },
+            buffer.adjacency()
+        );
+    }
+
+    #[test]
+    fn index_buffer_adjacency_non_manifold() {
+        let buffer = IndexBuffer {
+            indices: vec![0, 1, 2, 2, 1, 3, 1, 2, 4],
+        };
+        assert_eq!(vec![[None; 3]; 3], buffer.adjacency().neighbors);
+    }
+
+    #[test]
+    fn write_vertex_data_legacy_big_endian() {
+        let vertex_data = xc3_lib::mxmd::legacy::VertexData {
+            vertex_buffers: vec![xc3_lib::mxmd::legacy::VertexBufferDescriptor {
+                data_offset: 64,
+                vertex_count: 2,
+                vertex_size: 16,
+                attributes: vec![
+                    VertexAttribute {
+                        data_type: DataType::Position,
+                        data_size: 12,
+                    },
+                    VertexAttribute {
+                        data_type: DataType::VertexColor,
+                        data_size: 4,
+                    },
+                ],
+                unk1: 1,
+                data: hex!(
+                    // vertex 0
+                    3f800000 40000000 40400000
+                    ff0000ff
+                    // vertex 1
+                    bf800000 00000000 3f000000
+                    00ff00ff
+                )
+                .to_vec(),
+            }],
+            index_buffers: vec![xc3_lib::mxmd::legacy::IndexBufferDescriptor {
+                data_offset: 96,
+                index_count: 3,
+                unk1: 0,
+                unk2: 0,
+                data: hex!(0000 0001 0001).to_vec(),
+            }],
+            weight_buffer_indices: [0; 6],
+            unk: [0; 5],
+        };
+
+        let buffers = ModelBuffers {
+            vertex_buffers: read_vertex_buffers_legacy(&vertex_data),
+            outline_buffers: Vec::new(),
+            index_buffers: read_index_buffers_legacy(&vertex_data),
+            unk_buffers: Vec::new(),
+            weights: None,
+        };
+        assert_eq!(
+            Some([vec3(1.0, 2.0, 3.0), vec3(-1.0, 0.0, 0.5)].as_slice()),
+            buffers.vertex_buffers[0].positions()
+        );
+        assert_eq!(vec![0, 1, 1], buffers.index_buffers[0].indices);
+
+        assert_eq!(
+            vertex_data,
+            buffers.to_vertex_data_legacy(&vertex_data).unwrap()
+        );
+    }
+
+    #[test]
+    fn write_vertex_data_legacy_in_place() {
+        let vertex_data = xc3_lib::mxmd::legacy::VertexData {
+            vertex_buffers: vec![xc3_lib::mxmd::legacy::VertexBufferDescriptor {
+                data_offset: 0,
+                vertex_count: 1,
+                vertex_size: 12,
+                attributes: vec![VertexAttribute {
+                    data_type: DataType::Position,
+                    data_size: 12,
+                }],
+                unk1: 0,
+                data: hex!(3f800000 40000000 40400000).to_vec(),
+            }],
+            index_buffers: vec![xc3_lib::mxmd::legacy::IndexBufferDescriptor {
+                data_offset: 16,
+                index_count: 3,
+                unk1: 0,
+                unk2: 0,
+                data: hex!(0000 0000 0000).to_vec(),
+            }],
+            weight_buffer_indices: [0; 6],
+            unk: [0; 5],
+        };
+
+        let buffers = ModelBuffers {
+            vertex_buffers: vec![VertexBuffer {
+                attributes: vec![AttributeData::Position(vec![vec3(-1.0, 0.0, 0.5)])],
+                morph_targets: Vec::new(),
+                morph_default_target: None,
+                outline_buffer_index: None,
+            }],
+            outline_buffers: Vec::new(),
+            index_buffers: vec![IndexBuffer {
+                indices: vec![0, 0, 0],
+            }],
+            unk_buffers: Vec::new(),
+            weights: None,
+        };
+
+        // Only the buffer data relative to the vertex data offset should change.
+        let mut bytes = vec![0xAA; 40];
+        buffers
+            .write_vertex_data_legacy(&vertex_data, 8, &mut bytes)
+            .unwrap();
+        assert_hex_eq!(
+            hex!(
+                aaaaaaaa aaaaaaaa
+                bf800000 00000000 3f000000
+                aaaaaaaa
+                0000 0000 0000
+                aaaa aaaaaaaa aaaaaaaa
+            ),
+            bytes
+        );
+
+        let mut bytes = vec![0xAA; 24];
+        assert!(matches!(
+            buffers.write_vertex_data_legacy(&vertex_data, 8, &mut bytes),
+            Err(VertexDataLegacyError::DataOutOfRange {
+                offset: 24,
+                size: 6,
+                len: 24
+            })
+        ));
+    }
+
+    #[test]
+    fn write_vertex_data_legacy_size_changed() {
+        let vertex_data = xc3_lib::mxmd::legacy::VertexData {
+            vertex_buffers: Vec::new(),
+            index_buffers: vec![xc3_lib::mxmd::legacy::IndexBufferDescriptor {
+                data_offset: 96,
+                index_count: 3,
+                unk1: 0,
+                unk2: 0,
+                data: hex!(0000 0001 0002).to_vec(),
+            }],
+            weight_buffer_indices: [0; 6],
+            unk: [0; 5],
+        };
+
+        // Adding indices would overlap the data at the original offsets.
+        let buffers = ModelBuffers {
+            vertex_buffers: Vec::new(),
+            outline_buffers: Vec::new(),
+            index_buffers: vec![IndexBuffer {
+                indices: vec![0, 1, 2, 2, 1, 0],
+            }],
+            unk_buffers: Vec::new(),
+            weights: None,
+        };
+        assert!(matches!(
+            buffers.to_vertex_data_legacy(&vertex_data),
+            Err(VertexDataLegacyError::IndexBufferSize {
+                index: 0,
+                expected: 6,
+                actual: 12
+            })
+        ));
+
+        let buffers = ModelBuffers {
+            index_buffers: Vec::new(),
+            ..buffers
+        };
+        assert!(matches!(
+            buffers.to_vertex_data_legacy(&vertex_data),
+            Err(VertexDataLegacyError::IndexBufferCount {
+                expected: 1,
+                actual: 0
+            })
+        ));
+    }
+
+    #[test]
+    fn vertex_data_little_big_endian() {
+        let vertex_data = VertexData {
+            vertex_buffers: vec![VertexBufferDescriptor {
+                data_offset: 0,
+                vertex_count: 1,
+                vertex_size: 12,
+                attributes: vec![VertexAttribute {
+                    data_type: DataType::Position,
+                    data_size: 12,
+                }],
+                unk1: 0,
+                unk2: 0,
+                unk3: 0,
+            }],
+            index_buffers: vec![IndexBufferDescriptor {
+                data_offset: 12,
+                index_count: 3,
+                unk1: xc3_lib::vertex::Unk1::Unk0,
+                unk2: xc3_lib::vertex::Unk2::Unk0,
+                unk3: 0,
+                unk4: 0,
+            }],
+            unk0: 0,
+            unk1: 0,
+            unk2: 0,
+            vertex_buffer_info: vec![xc3_lib::vertex::VertexBufferExtInfo {
+                flags: 0u16.into(),
+                outline_buffer_index: 0,
+                morph_target_start_index: 0,
+                morph_target_count: 0,
+                unk: 0,
+            }],
+            outline_buffers: Vec::new(),
+            vertex_morphs: None,
+            buffer: hex!(
+                // vertex 0
+                3f800000 40000000 00000000
+                // indices
+                0001 0002 0003
+            )
+            .to_vec(),
+            unk_data: None,
+            weights: None,
+            unk7: None,
+            unks: [0; 5],
+        };
+
+        let little =
+            ModelBuffers::from_vertex_data_endian(&vertex_data, None, Endian::Little).unwrap();
+        assert_eq!(
+            Some(
+                [vec3(
+                    f32::from_bits(0x0000803f),
+                    f32::from_bits(0x00000040),
+                    0.0
+                )]
+                .as_slice()
+            ),
+            little.vertex_buffers[0].positions()
+        );
+        assert_eq!(vec![256, 512, 768], little.index_buffers[0].indices);
+        assert_eq!(
+            little,
+            ModelBuffers::from_vertex_data(&vertex_data, None).unwrap()
+        );
+
+        let big = ModelBuffers::from_vertex_data_endian(&vertex_data, None, Endian::Big).unwrap();
+        assert_eq!(
+            Some([vec3(1.0, 2.0, 0.0)].as_slice()),
+            big.vertex_buffers[0].positions()
+        );
+        assert_eq!(vec![1, 2, 3], big.index_buffers[0].indices);
+    }
+
+    #[test]
+    fn compact_out_of_range_indices() {
+        let buffer = VertexBuffer {
+            attributes: vec![AttributeData::Position(vec![
+                vec3(0.0, 0.0, 0.0),
+                vec3(1.0, 0.0, 0.0),
+                vec3(2.0, 0.0, 0.0),
+                vec3(3.0, 0.0, 0.0),
+            ])],
+            morph_targets: Vec::new(),
+            morph_default_target: None,
+            outline_buffer_index: None,
+        };
+
+        // The second triangle references a vertex that doesn't exist.
+        let (new_buffer, new_indices) = buffer.compact(&[3, 1, 2, 1, 2, 5]);
+        assert_eq!(vec![0, 1, 2], new_indices.indices);
+        assert_eq!(
+            Some(
+                &[
+                    vec3(3.0, 0.0, 0.0),
+                    vec3(1.0, 0.0, 0.0),
+                    vec3(2.0, 0.0, 0.0)
+                ][..]
+            ),
+            new_buffer.positions()
+        );
+    }
+
+    #[test]
+    fn split_per_mesh_shared_buffer() {
+        let models = test_support::models(vec![test_support::model(vec![
+            test_support::mesh(0, 0, 1),
+            test_support::mesh(0, 1, 1),
+        ])]);
+
+        let buffers = test_support::model_buffers(
+            vec![VertexBuffer {
+                attributes: vec![
+                    AttributeData::Position(vec![
+                        vec3(0.0, 0.0, 0.0),
+                        vec3(1.0, 0.0, 0.0),
+                        vec3(2.0, 0.0, 0.0),
+                        vec3(3.0, 0.0, 0.0),
+                        vec3(4.0, 0.0, 0.0),
+                    ]),
+                    AttributeData::WeightIndex(vec![[0, 0], [1, 0], [2, 0], [3, 0], [4, 0]]),
+                ],
+                morph_targets: vec![MorphTarget {
+                    morph_controller_index: 0,
+                    position_deltas: vec![Vec3::ONE, Vec3::ONE],
+                    normal_deltas: vec![Vec4::ZERO, Vec4::ZERO],
+                    tangent_deltas: vec![Vec4::ZERO, Vec4::ZERO],
+                    vertex_indices: vec![1, 4],
+                }],
+                morph_default_target: None,
+                outline_buffer_index: None,
+            }],
+            vec![
+                IndexBuffer {
+                    indices: vec![0, 1, 2],
+                },
+                IndexBuffer {
+                    indices: vec![4, 2, 3],
+                },
+            ],
+        );
+
+        let split = buffers.split_per_mesh(&models);
+        assert_eq!(2, split.len());
+
+        let (vertex_buffer, index_buffer) = &split[0];
+        assert_eq!(vec![0, 1, 2], index_buffer.indices);
+        assert_eq!(3, vertex_buffer.vertex_count());
+        assert_eq!(
+            AttributeData::WeightIndex(vec![[0, 0], [1, 0], [2, 0]]),
+            vertex_buffer.attributes[1]
+        );
+        assert_eq!(vec![1], vertex_buffer.morph_targets[0].vertex_indices);
+
+        let (vertex_buffer, index_buffer) = &split[1];
+        assert_eq!(vec![0, 1, 2], index_buffer.indices);
+        assert_eq!(
+            AttributeData::Position(vec![
+                vec3(4.0, 0.0, 0.0),
+                vec3(2.0, 0.0, 0.0),
+                vec3(3.0, 0.0, 0.0),
+            ]),
+            vertex_buffer.attributes[0]
+        );
+        assert_eq!(
+            AttributeData::WeightIndex(vec![[4, 0], [2, 0], [3, 0]]),
+            vertex_buffer.attributes[1]
+        );
+        assert_eq!(vec![0], vertex_buffer.morph_targets[0].vertex_indices);
+    }
 }
@@ -1,4 +1,5 @@
 use std::{
+    collections::BTreeMap,
     io::{BufReader, Cursor},
     path::Path,
 };
@@ -6,6 +7,7 @@ use std::{
 use binrw::{BinRead, BinReaderExt, Endian};
 use clap::Parser;
 use rayon::prelude::*;
+use serde::Serialize;
 use xc3_lib::{
     apmd::Apmd,
     bc::Bc,
@@ -89,7 +91,7 @@ struct Cli {
     #[arg(long)]
     bmn: bool,
 
-    /// Process all file types except gltf and wimdo-model.
+    /// Process all file types except gltf, wimdo-model, and animation.
     #[arg(long)]
     all: bool,
 
@@ -101,9 +103,17 @@ struct Cli {
     #[arg(long)]
     wimdo_model: bool,
 
+    /// Load animations from .anm, .mot, and .motstm_data to xc3_model types.
+    #[arg(long)]
+    animation: bool,
+
     /// Check that read/write is 1:1 for all files and embedded files.
     #[arg(long)]
     rw: bool,
+
+    /// Save statistics for all wimdo models to the specified JSON file.
+    #[arg(long)]
+    stats: Option<String>,
 }
 
 fn main() {
@@ -226,6 +236,16 @@ fn main() {
         check_all_wimdo_model(root, cli.rw);
     }
 
+    if cli.animation {
+        println!("Checking animations ...");
+        check_all_animations(root);
+    }
+
+    if let Some(stats) = &cli.stats {
+        println!("Saving model statistics to {stats:?} ...");
+        save_model_stats(root, stats);
+    }
+
     println!("Finished in {:?}", start.elapsed());
 }
 
@@ -313,6 +333,25 @@ fn check_msrd(msrd: Msrd, path: &Path, original_bytes: &[u8], check_read_write:
                 .decompress_stream_entry(0, data.shader_entry_index)
                 .unwrap();
             check_spch(spch, path, &spch_bytes, check_read_write);
+
+            // Textures should match the low textures even without the vertex data.
+            let texture_count = data
+                .texture_resources
+                .low_textures
+                .as_ref()
+                .map(|t| t.textures.len())
+                .unwrap_or_default();
+            match xc3_model::extract_textures_from_msrd(&msrd) {
+                Ok(image_textures) => {
+                    if image_textures.len() != texture_count {
+                        println!(
+                            "Expected {texture_count} textures but found {} for {path:?}",
+                            image_textures.len()
+                        );
+                    }
+                }
+                Err(e) => println!("Error extracting textures for {path:?}: {e}"),
+            }
         }
     }
 
@@ -779,12 +818,37 @@ fn check_beb(beb: Beb, path: &Path, original_bytes: &[u8], check_read_write: boo
     }
 }
 
-fn check_mxmd_legacy(
-    mxmd: MxmdLegacy,
-    path: &Path,
-    _original_bytes: &[u8],
-    check_read_write: bool,
-) {
+fn check_mxmd_legacy(mxmd: MxmdLegacy, path: &Path, original_bytes: &[u8], check_read_write: bool) {
+    if check_read_write {
+        match xc3_model::vertex::ModelBuffers::from_vertex_data_legacy(&mxmd.vertex, &mxmd.models) {
+            Ok(buffers) => {
+                match buffers.to_vertex_data_legacy(&mxmd.vertex) {
+                    Ok(new_vertex) => {
+                        if new_vertex != mxmd.vertex {
+                            println!("VertexData legacy not 1:1 for {path:?}");
+                        }
+                    }
+                    Err(e) => println!("Error writing legacy VertexData for {path:?}: {e}"),
+                }
+
+                // Patching the original file should recreate the original bytes.
+                let vertex_offset =
+                    u32::from_be_bytes(original_bytes[20..24].try_into().unwrap()) as usize;
+                let mut new_bytes = original_bytes.to_vec();
+                match buffers.write_vertex_data_legacy(&mxmd.vertex, vertex_offset, &mut new_bytes)
+                {
+                    Ok(()) => {
+                        if new_bytes != original_bytes {
+                            println!("Camdo vertex data patching not 1:1 for {path:?}");
+                        }
+                    }
+                    Err(e) => println!("Error patching legacy VertexData for {path:?}: {e}"),
+                }
+            }
+            Err(e) => println!("Error reading legacy VertexData for {path:?}: {e}"),
+        }
+    }
+
     if let Some(textures) = mxmd.packed_textures {
         for texture in textures.textures {
             match Mtxt::from_bytes(&texture.mtxt_data) {
@@ -870,8 +934,20 @@ fn check_all_gltf<P: AsRef<Path>>(root: P) {
         .unwrap()
         .for_each(|entry| {
             let path = entry.as_ref().unwrap().path();
-            match xc3_model::load_map(path, None) {
+            let mut progress = Vec::new();
+            match xc3_model::load_map_with_progress(path, None, |p| progress.push(p)) {
                 Ok(roots) => {
+                    check_map_progress(&progress, path);
+
+                    match xc3_model::load_map_mmap(path, None) {
+                        Ok(mmap_roots) => {
+                            if mmap_roots != roots {
+                                println!("Memory mapped roots not equal for {path:?}");
+                            }
+                        }
+                        Err(e) => println!("Error loading memory mapped {path:?}: {e}"),
+                    }
+
                     if let Err(e) = xc3_model::gltf::GltfFile::from_map("model", &roots) {
                         println!("Error converting {path:?}: {e}");
                     }
@@ -881,6 +957,37 @@ fn check_all_gltf<P: AsRef<Path>>(root: P) {
         });
 }
 
+fn check_map_progress(progress: &[xc3_model::MapLoadProgress], path: &Path) {
+    use xc3_model::MapLoadPhase;
+
+    // Each phase should count up from 0 to the total in order.
+    let mut remaining = progress;
+    for phase in [
+        MapLoadPhase::Env,
+        MapLoadPhase::Map,
+        MapLoadPhase::Props,
+        MapLoadPhase::Textures,
+    ] {
+        let count = remaining.iter().take_while(|p| p.phase == phase).count();
+        let (reported, rest) = remaining.split_at(count);
+        remaining = rest;
+
+        let is_valid = reported.first().is_some_and(|p| {
+            reported.len() == p.total + 1
+                && reported
+                    .iter()
+                    .enumerate()
+                    .all(|(i, r)| r.completed == i && r.total == p.total)
+        });
+        if !is_valid {
+            println!("Unexpected {phase:?} progress {reported:?} for {path:?}");
+        }
+    }
+    if !remaining.is_empty() {
+        println!("Unexpected progress {remaining:?} for {path:?}");
+    }
+}
+
 fn check_all_wimdo_model<P: AsRef<Path>>(root: P, check_read_write: bool) {
     globwalk::GlobWalkerBuilder::from_patterns(root.as_ref(), &["*.{wimdo}"])
         .build()
@@ -890,7 +997,8 @@ fn check_all_wimdo_model<P: AsRef<Path>>(root: P, check_read_write: bool) {
             let path = entry.as_ref().unwrap().path();
 
             // Test reimporting models without any changes.
-            let mxmd = Mxmd::from_file(path).unwrap();
+            // Map and object models may store the Mxmd in an Apmd.
+            let mxmd = xc3_model::load_mxmd(path).unwrap();
             let msrd = mxmd
                 .streaming
                 .is_some()
@@ -901,17 +1009,131 @@ fn check_all_wimdo_model<P: AsRef<Path>>(root: P, check_read_write: bool) {
 
             match xc3_model::ModelRoot::from_mxmd_model(&mxmd, None, &streaming_data, None) {
                 Ok(root) => {
+                    if let Some(spch) = &mxmd.spch {
+                        match xc3_model::ShaderInfo::from_spch(spch) {
+                            Ok(info) => {
+                                if info.programs.is_empty() {
+                                    println!("Missing embedded shader info for {path:?}");
+                                }
+                            }
+                            Err(e) => {
+                                println!("Error reading embedded shader info for {path:?}: {e}")
+                            }
+                        }
+                    }
+
+                    check_morph_controller_animation_indices(&root.models, path);
+
+                    if let Some(skinning) = &mxmd.models.skinning {
+                        if let Some(skeleton) =
+                            xc3_model::load_skeleton(path, Some(skinning), false)
+                        {
+                            check_inverse_bind_matrices(&skeleton, skinning, path);
+                        }
+                    }
+
                     // TODO: Create a function that loads files from wimdo path?
                     // TODO: Should this take the msrd or streaming?
                     // TODO: Is it worth being able to test this without compression?
                     if check_read_write {
                         // TODO: Should to_mxmd_model make the msrd optional?
                         if let Some(msrd) = msrd {
-                            let (_new_mxmd, new_msrd) = root.to_mxmd_model(&mxmd, &msrd);
-                            let (new_vertex, _, _) = new_msrd.extract_files(None).unwrap();
+                            let (wimdo, wismt) = root.write_wimdo_wismt(&mxmd, &msrd).unwrap();
+                            let new_mxmd = Mxmd::from_bytes(wimdo).unwrap();
+                            let new_msrd = Msrd::from_bytes(wismt).unwrap();
+
+                            let (new_vertex, _, new_textures) =
+                                new_msrd.extract_files(None).unwrap();
                             if &new_vertex != streaming_data.vertex.as_ref() {
                                 println!("VertexData not 1:1 for {path:?}")
                             }
+
+                            // Reloading the written files should produce the same model.
+                            let new_streaming_data = xc3_model::StreamingData {
+                                vertex: std::borrow::Cow::Owned(new_vertex),
+                                textures: xc3_model::ExtractedTextures::Switch(new_textures),
+                            };
+                            match xc3_model::ModelRoot::from_mxmd_model(
+                                &new_mxmd,
+                                None,
+                                &new_streaming_data,
+                                None,
+                            ) {
+                                Ok(new_root) => {
+                                    if new_root.buffers != root.buffers {
+                                        println!("ModelBuffers not 1:1 for {path:?}")
+                                    }
+                                }
+                                Err(e) => println!("Error reloading {path:?}: {e}"),
+                            }
+                        }
+                    }
+                }
+                Err(e) => println!("Error loading {path:?}: {e}"),
+            }
+        });
+}
+
+fn check_morph_controller_animation_indices(models: &xc3_model::Models, path: &Path) {
+    // Each controller should map to an existing animation morph.
+    for (name, i) in models
+        .morph_controller_names
+        .iter()
+        .zip(&models.morph_controller_animation_indices)
+    {
+        if *i >= models.animation_morph_names.len() {
+            println!(
+                "Morph controller {name:?} animation index {i} out of range for {} animation morphs for {path:?}",
+                models.animation_morph_names.len()
+            );
+        }
+    }
+}
+
+fn check_inverse_bind_matrices(
+    skeleton: &xc3_model::Skeleton,
+    skinning: &xc3_lib::mxmd::Skinning,
+    path: &Path,
+) {
+    // The computed values should match the values stored in the model.
+    let inverse_bind_matrices = skeleton.inverse_bind_matrices();
+    for (bone, transform) in skinning.bones.iter().zip(&skinning.inverse_bind_transforms) {
+        if let Some(index) = skeleton.bones.iter().position(|b| b.name == bone.name) {
+            let actual = inverse_bind_matrices[index].to_cols_array_2d();
+            if actual
+                .iter()
+                .flatten()
+                .zip(transform.iter().flatten())
+                .any(|(a, b)| (a - b).abs() > 0.001)
+            {
+                println!(
+                    "Inverse bind matrix not 1:1 for bone {:?} for {path:?}",
+                    bone.name
+                );
+            }
+        }
+    }
+}
+
+fn check_all_animations<P: AsRef<Path>>(root: P) {
+    globwalk::GlobWalkerBuilder::from_patterns(root.as_ref(), &["*.{anm,mot,motstm_data}"])
+        .build()
+        .unwrap()
+        .par_bridge()
+        .for_each(|entry| {
+            let path = entry.as_ref().unwrap().path();
+            match xc3_model::load_named_animations(path) {
+                Ok(animations) => {
+                    // Standalone BC files should always contain an animation.
+                    if animations.is_empty() && path.extension().is_some_and(|e| e == "motstm_data")
+                    {
+                        println!("No animations for {path:?}");
+                    }
+
+                    // Names are needed to select a specific animation.
+                    for (i, (name, _)) in animations.iter().enumerate() {
+                        if name.is_empty() {
+                            println!("Empty name for animation {i} in {path:?}");
                         }
                     }
                 }
@@ -919,3 +1141,82 @@ fn check_all_wimdo_model<P: AsRef<Path>>(root: P, check_read_write: bool) {
             }
         });
 }
+
+#[derive(Serialize)]
+struct ModelStats {
+    vertex_count: usize,
+    index_count: usize,
+    material_count: usize,
+    texture_count: usize,
+    texture_formats: BTreeMap<String, usize>,
+    bone_count: usize,
+    morph_target_count: usize,
+}
+
+impl ModelStats {
+    fn new(root: &xc3_model::ModelRoot) -> Self {
+        let mut texture_formats = BTreeMap::new();
+        for texture in &root.image_textures {
+            *texture_formats
+                .entry(format!("{:?}", texture.image_format))
+                .or_default() += 1;
+        }
+
+        Self {
+            vertex_count: root
+                .buffers
+                .vertex_buffers
+                .iter()
+                .map(|b| b.vertex_count())
+                .sum(),
+            index_count: root
+                .buffers
+                .index_buffers
+                .iter()
+                .map(|b| b.indices.len())
+                .sum(),
+            material_count: root.models.materials.len(),
+            texture_count: root.image_textures.len(),
+            texture_formats,
+            bone_count: root
+                .skeleton
+                .as_ref()
+                .map(|s| s.bones.len())
+                .unwrap_or_default(),
+            morph_target_count: root
+                .buffers
+                .vertex_buffers
+                .iter()
+                .map(|b| b.morph_targets.len())
+                .sum(),
+        }
+    }
+}
+
+fn save_model_stats(root: &Path, output: &str) {
+    // Sort by path to make the output easier to compare.
+    let stats: BTreeMap<_, _> = globwalk::GlobWalkerBuilder::from_patterns(root, &["*.{wimdo}"])
+        .build()
+        .unwrap()
+        .par_bridge()
+        .filter_map(|entry| {
+            let path = entry.as_ref().unwrap().path();
+            match xc3_model::load_model(path, None) {
+                Ok(model) => {
+                    let name = path.strip_prefix(root).unwrap_or(path);
+                    Some((
+                        name.to_string_lossy().replace('\\', "/"),
+                        ModelStats::new(&model),
+                    ))
+                }
+                Err(e) => {
+                    println!("Error loading {path:?}: {e}");
+                    None
+                }
+            }
+        })
+        .collect();
+
+    let json = serde_json::to_string_pretty(&stats).unwrap();
+    std::fs::write(output, json).unwrap();
+}
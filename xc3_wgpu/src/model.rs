@@ -377,8 +377,12 @@ impl ModelGroup {
         current_time_seconds: f32,
     ) {
         // TODO: Tests for this?
-        let morph_controller_names = &self.models[0].morph_controller_names;
-        let animation_morph_names = &self.models[0].animation_morph_names;
+        // Morph targets are only used for model roots with a single Models.
+        let Some(models) = self.models.first() else {
+            return;
+        };
+        let morph_controller_names = &models.morph_controller_names;
+        let animation_morph_names = &models.animation_morph_names;
         // TODO: interpolate between frames?
         let frame = animation.current_frame(current_time_seconds);
         let frame_index = frame as usize;